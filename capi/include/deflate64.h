@@ -0,0 +1,118 @@
+#ifndef DEFLATE64_H
+#define DEFLATE64_H
+
+/* This file is generated by cbindgen from src/lib.rs. Do not edit it manually. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+// The call succeeded, and the end of the deflate64 stream was not reached yet.
+#define DEFLATE64_OK 0
+
+// The end of the deflate64 stream was reached and all output was returned.
+#define DEFLATE64_STREAM_END 1
+
+// The deflate64 stream is invalid. See `deflate64_error_code`.
+#define DEFLATE64_DATA_ERROR -1
+
+// A required pointer argument was null.
+#define DEFLATE64_INVALID_ARGUMENT -2
+
+// The checkpoint data is corrupt, or was created by an incompatible version.
+#define DEFLATE64_INVALID_CHECKPOINT -3
+
+// The maximum size of a serialized checkpoint.
+#define DEFLATE64_MAX_CHECKPOINT_SIZE 131686
+
+// An incremental deflate64 decompressor.
+//
+// Created with `deflate64_new` and destroyed with `deflate64_free`.
+typedef struct Deflate64Inflater Deflate64Inflater;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// Creates an inflater for a new deflate64 stream.
+struct Deflate64Inflater *deflate64_new(void);
+
+// Destroys an inflater. Does nothing if `inflater` is null.
+//
+// # Safety
+//
+// `inflater` must be null or returned by `deflate64_new` and not freed yet.
+void deflate64_free(struct Deflate64Inflater *inflater);
+
+// Decompresses from `input` to `output`.
+//
+// This decompresses until `output` is full, `input` is empty, the end of the stream is
+// reached, or an error is found. The counts of bytes consumed from `input` and written to
+// `output` are stored to `bytes_consumed` and `bytes_written`, even if an error is found.
+//
+// Returns `DEFLATE64_STREAM_END` once all output of the stream was returned,
+// `DEFLATE64_OK` if more input or output space is needed, or a negative error.
+//
+// # Safety
+//
+// `inflater` must be a valid inflater. `input` must point to `input_len` readable bytes,
+// and `output` to `output_len` writable bytes; they may be null if their length is 0.
+// `bytes_consumed` and `bytes_written` must be valid for writes.
+int32_t deflate64_inflate(struct Deflate64Inflater *inflater,
+                          const uint8_t *input,
+                          size_t input_len,
+                          uint8_t *output,
+                          size_t output_len,
+                          size_t *bytes_consumed,
+                          size_t *bytes_written);
+
+// Returns the stable numeric code of the error which stopped decoding, or 0 if there is
+// no error. The codes are the ones of `InflateErrorKind::code` in the deflate64 crate.
+//
+// # Safety
+//
+// `inflater` must be null or a valid inflater.
+uint32_t deflate64_error_code(const struct Deflate64Inflater *inflater);
+
+// Serializes the most recent checkpoint of the inflater into `out`.
+//
+// Returns the length of the checkpoint, or 0 if there is no checkpoint or `out_len` is too
+// small for it. A buffer of `DEFLATE64_MAX_CHECKPOINT_SIZE` bytes always fits. On success,
+// the input and output positions of the checkpoint are stored to `input_bytes_to_skip`
+// and `output_bytes_already_returned`.
+//
+// # Safety
+//
+// `inflater` must be a valid inflater, and `out` must point to `out_len` writable bytes.
+// `input_bytes_to_skip` and `output_bytes_already_returned` must be valid for writes.
+size_t deflate64_checkpoint(const struct Deflate64Inflater *inflater,
+                            uint8_t *out,
+                            size_t out_len,
+                            uint64_t *input_bytes_to_skip,
+                            uint64_t *output_bytes_already_returned);
+
+// Restores the inflater from a checkpoint serialized by `deflate64_checkpoint`.
+//
+// On success, returns `DEFLATE64_OK` and stores the positions the input and output must be
+// moved to before decompression continues. Returns `DEFLATE64_INVALID_CHECKPOINT` and
+// leaves the inflater unchanged if the checkpoint can't be restored.
+//
+// Checkpoint data is not fully validated; do not restore checkpoints from untrusted sources.
+//
+// # Safety
+//
+// `inflater` must be a valid inflater, and `data` must point to `data_len` readable bytes.
+// `input_bytes_to_skip` and `output_bytes_already_returned` must be valid for writes.
+int32_t deflate64_restore(struct Deflate64Inflater *inflater,
+                          const uint8_t *data,
+                          size_t data_len,
+                          uint64_t *input_bytes_to_skip,
+                          uint64_t *output_bytes_already_returned);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* DEFLATE64_H */
@@ -0,0 +1,231 @@
+//! C API for the [deflate64] crate.
+//!
+//! The header `include/deflate64.h` declares the functions of this library, and is generated
+//! with [cbindgen] from this file:
+//!
+//! ```sh
+//! cbindgen --config cbindgen.toml --output include/deflate64.h
+//! ```
+//!
+//! [deflate64]: https://crates.io/crates/deflate64
+//! [cbindgen]: https://github.com/mozilla/cbindgen
+
+#![deny(rust_2018_idioms, nonstandard_style, future_incompatible)]
+
+use deflate64::{CheckpointStreamPositions, Checkpointing, InflaterManaged};
+use std::slice;
+
+/// The call succeeded, and the end of the deflate64 stream was not reached yet.
+pub const DEFLATE64_OK: i32 = 0;
+/// The end of the deflate64 stream was reached and all output was returned.
+pub const DEFLATE64_STREAM_END: i32 = 1;
+/// The deflate64 stream is invalid. See `deflate64_error_code`.
+pub const DEFLATE64_DATA_ERROR: i32 = -1;
+/// A required pointer argument was null.
+pub const DEFLATE64_INVALID_ARGUMENT: i32 = -2;
+/// The checkpoint data is corrupt, or was created by an incompatible version.
+pub const DEFLATE64_INVALID_CHECKPOINT: i32 = -3;
+
+/// The maximum size of a serialized checkpoint.
+// spelled out so that cbindgen can evaluate it
+pub const DEFLATE64_MAX_CHECKPOINT_SIZE: usize = 131686;
+const _: () = assert!(DEFLATE64_MAX_CHECKPOINT_SIZE == deflate64::MAX_CHECKPOINT_SIZE);
+
+/// An incremental deflate64 decompressor.
+///
+/// Created with `deflate64_new` and destroyed with `deflate64_free`.
+pub struct Deflate64Inflater {
+    inflater: InflaterManaged<Checkpointing>,
+}
+
+/// Creates an inflater for a new deflate64 stream.
+#[no_mangle]
+pub extern "C" fn deflate64_new() -> *mut Deflate64Inflater {
+    Box::into_raw(Box::new(Deflate64Inflater {
+        inflater: InflaterManaged::with_checkpoints(),
+    }))
+}
+
+/// Destroys an inflater. Does nothing if `inflater` is null.
+///
+/// # Safety
+///
+/// `inflater` must be null or returned by `deflate64_new` and not freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn deflate64_free(inflater: *mut Deflate64Inflater) {
+    if !inflater.is_null() {
+        drop(Box::from_raw(inflater));
+    }
+}
+
+/// Decompresses from `input` to `output`.
+///
+/// This decompresses until `output` is full, `input` is empty, the end of the stream is
+/// reached, or an error is found. The counts of bytes consumed from `input` and written to
+/// `output` are stored to `bytes_consumed` and `bytes_written`, even if an error is found.
+///
+/// Returns `DEFLATE64_STREAM_END` once all output of the stream was returned,
+/// `DEFLATE64_OK` if more input or output space is needed, or a negative error.
+///
+/// # Safety
+///
+/// `inflater` must be a valid inflater. `input` must point to `input_len` readable bytes,
+/// and `output` to `output_len` writable bytes; they may be null if their length is 0.
+/// `bytes_consumed` and `bytes_written` must be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn deflate64_inflate(
+    inflater: *mut Deflate64Inflater,
+    input: *const u8,
+    input_len: usize,
+    output: *mut u8,
+    output_len: usize,
+    bytes_consumed: *mut usize,
+    bytes_written: *mut usize,
+) -> i32 {
+    let Some(inflater) = inflater.as_mut() else {
+        return DEFLATE64_INVALID_ARGUMENT;
+    };
+    if bytes_consumed.is_null() || bytes_written.is_null() {
+        return DEFLATE64_INVALID_ARGUMENT;
+    }
+    let (Some(input), Some(output)) = (
+        input_slice(input, input_len),
+        output_slice(output, output_len),
+    ) else {
+        return DEFLATE64_INVALID_ARGUMENT;
+    };
+
+    let result = inflater.inflater.inflate(input, output);
+    *bytes_consumed = result.bytes_consumed;
+    *bytes_written = result.bytes_written;
+    if result.data_error {
+        DEFLATE64_DATA_ERROR
+    } else if inflater.inflater.finished() {
+        DEFLATE64_STREAM_END
+    } else {
+        DEFLATE64_OK
+    }
+}
+
+/// Returns the stable numeric code of the error which stopped decoding, or 0 if there is
+/// no error. The codes are the ones of `InflateErrorKind::code` in the deflate64 crate.
+///
+/// # Safety
+///
+/// `inflater` must be null or a valid inflater.
+#[no_mangle]
+pub unsafe extern "C" fn deflate64_error_code(inflater: *const Deflate64Inflater) -> u32 {
+    inflater
+        .as_ref()
+        .and_then(|inflater| inflater.inflater.error())
+        .map_or(0, |error| error.code())
+}
+
+/// Serializes the most recent checkpoint of the inflater into `out`.
+///
+/// Returns the length of the checkpoint, or 0 if there is no checkpoint or `out_len` is too
+/// small for it. A buffer of `DEFLATE64_MAX_CHECKPOINT_SIZE` bytes always fits. On success,
+/// the input and output positions of the checkpoint are stored to `input_bytes_to_skip`
+/// and `output_bytes_already_returned`.
+///
+/// # Safety
+///
+/// `inflater` must be a valid inflater, and `out` must point to `out_len` writable bytes.
+/// `input_bytes_to_skip` and `output_bytes_already_returned` must be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn deflate64_checkpoint(
+    inflater: *const Deflate64Inflater,
+    out: *mut u8,
+    out_len: usize,
+    input_bytes_to_skip: *mut u64,
+    output_bytes_already_returned: *mut u64,
+) -> usize {
+    let Some(inflater) = inflater.as_ref() else {
+        return 0;
+    };
+    let Some(out) = output_slice(out, out_len) else {
+        return 0;
+    };
+    if input_bytes_to_skip.is_null() || output_bytes_already_returned.is_null() {
+        return 0;
+    }
+    match inflater.inflater.checkpoint_into(out) {
+        Some((len, positions)) => {
+            store_positions(
+                &positions,
+                input_bytes_to_skip,
+                output_bytes_already_returned,
+            );
+            len
+        }
+        None => 0,
+    }
+}
+
+/// Restores the inflater from a checkpoint serialized by `deflate64_checkpoint`.
+///
+/// On success, returns `DEFLATE64_OK` and stores the positions the input and output must be
+/// moved to before decompression continues. Returns `DEFLATE64_INVALID_CHECKPOINT` and
+/// leaves the inflater unchanged if the checkpoint can't be restored.
+///
+/// Checkpoint data is not fully validated; do not restore checkpoints from untrusted sources.
+///
+/// # Safety
+///
+/// `inflater` must be a valid inflater, and `data` must point to `data_len` readable bytes.
+/// `input_bytes_to_skip` and `output_bytes_already_returned` must be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn deflate64_restore(
+    inflater: *mut Deflate64Inflater,
+    data: *const u8,
+    data_len: usize,
+    input_bytes_to_skip: *mut u64,
+    output_bytes_already_returned: *mut u64,
+) -> i32 {
+    let Some(inflater) = inflater.as_mut() else {
+        return DEFLATE64_INVALID_ARGUMENT;
+    };
+    let Some(data) = input_slice(data, data_len) else {
+        return DEFLATE64_INVALID_ARGUMENT;
+    };
+    if input_bytes_to_skip.is_null() || output_bytes_already_returned.is_null() {
+        return DEFLATE64_INVALID_ARGUMENT;
+    }
+    match inflater.inflater.restore_from_checkpoint(data) {
+        Some(positions) => {
+            store_positions(
+                &positions,
+                input_bytes_to_skip,
+                output_bytes_already_returned,
+            );
+            DEFLATE64_OK
+        }
+        None => DEFLATE64_INVALID_CHECKPOINT,
+    }
+}
+
+unsafe fn store_positions(
+    positions: &CheckpointStreamPositions,
+    input_bytes_to_skip: *mut u64,
+    output_bytes_already_returned: *mut u64,
+) {
+    *input_bytes_to_skip = positions.input_bytes_to_skip;
+    *output_bytes_already_returned = positions.output_bytes_already_returned;
+}
+
+// null is allowed for empty buffers, as C callers often pass NULL with length 0
+unsafe fn input_slice<'a>(ptr: *const u8, len: usize) -> Option<&'a [u8]> {
+    match (ptr.is_null(), len) {
+        (true, 0) => Some(&[]),
+        (true, _) => None,
+        (false, _) => Some(slice::from_raw_parts(ptr, len)),
+    }
+}
+
+unsafe fn output_slice<'a>(ptr: *mut u8, len: usize) -> Option<&'a mut [u8]> {
+    match (ptr.is_null(), len) {
+        (true, 0) => Some(&mut []),
+        (true, _) => None,
+        (false, _) => Some(slice::from_raw_parts_mut(ptr, len)),
+    }
+}
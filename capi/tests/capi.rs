@@ -0,0 +1,159 @@
+use deflate64_capi::*;
+use std::ptr;
+
+const BINARY_WAV_DATA_OFFSET: usize = 40;
+const BINARY_WAV_COMPRESSED_SIZE: usize = 2669743;
+
+static ZIP_FILE_DATA: &[u8] = include_bytes!("../../test-assets/deflate64.zip");
+static BINARY_WAV_DATA: &[u8] = include_bytes!("../../test-assets/folder/binary.wmv");
+
+fn compressed_data() -> &'static [u8] {
+    &ZIP_FILE_DATA[BINARY_WAV_DATA_OFFSET..][..BINARY_WAV_COMPRESSED_SIZE]
+}
+
+/// Decompresses `input` in chunks until the end of the stream or an error
+unsafe fn inflate(inflater: *mut Deflate64Inflater, mut input: &[u8]) -> (i32, Vec<u8>) {
+    let mut output = vec![];
+    let mut chunk = [0u8; 4096];
+    loop {
+        let (mut consumed, mut written) = (0, 0);
+        let status = deflate64_inflate(
+            inflater,
+            input.as_ptr(),
+            input.len(),
+            chunk.as_mut_ptr(),
+            chunk.len(),
+            &mut consumed,
+            &mut written,
+        );
+        input = &input[consumed..];
+        output.extend_from_slice(&chunk[..written]);
+        if status != DEFLATE64_OK || (consumed == 0 && written == 0) {
+            return (status, output);
+        }
+    }
+}
+
+#[test]
+fn inflate_whole_stream() {
+    unsafe {
+        let inflater = deflate64_new();
+        let (status, output) = inflate(inflater, compressed_data());
+        assert_eq!(status, DEFLATE64_STREAM_END);
+        assert_eq!(output, BINARY_WAV_DATA);
+        assert_eq!(deflate64_error_code(inflater), 0);
+        deflate64_free(inflater);
+    }
+}
+
+#[test]
+fn inflate_invalid_stream() {
+    unsafe {
+        let inflater = deflate64_new();
+        let (status, _) = inflate(inflater, &[0b111]);
+        assert_eq!(status, DEFLATE64_DATA_ERROR);
+        assert_eq!(
+            deflate64_error_code(inflater),
+            deflate64::InflateErrorKind::InvalidBlockType.code()
+        );
+        deflate64_free(inflater);
+    }
+}
+
+#[test]
+fn null_arguments() {
+    unsafe {
+        let inflater = deflate64_new();
+        let (mut consumed, mut written) = (0, 0);
+        let status = deflate64_inflate(
+            inflater,
+            ptr::null(),
+            0,
+            ptr::null_mut(),
+            0,
+            &mut consumed,
+            &mut written,
+        );
+        assert_eq!(status, DEFLATE64_OK);
+        let status = deflate64_inflate(
+            inflater,
+            ptr::null(),
+            1,
+            ptr::null_mut(),
+            0,
+            &mut consumed,
+            &mut written,
+        );
+        assert_eq!(status, DEFLATE64_INVALID_ARGUMENT);
+        let status = deflate64_inflate(
+            ptr::null_mut(),
+            ptr::null(),
+            0,
+            ptr::null_mut(),
+            0,
+            &mut consumed,
+            &mut written,
+        );
+        assert_eq!(status, DEFLATE64_INVALID_ARGUMENT);
+        deflate64_free(inflater);
+        deflate64_free(ptr::null_mut());
+    }
+}
+
+#[test]
+fn checkpoint_and_restore() {
+    unsafe {
+        let inflater = deflate64_new();
+        let mut output = vec![0u8; 100000];
+        let (mut consumed, mut written) = (0, 0);
+        deflate64_inflate(
+            inflater,
+            compressed_data().as_ptr(),
+            50000,
+            output.as_mut_ptr(),
+            output.len(),
+            &mut consumed,
+            &mut written,
+        );
+
+        let mut checkpoint = vec![0u8; DEFLATE64_MAX_CHECKPOINT_SIZE];
+        let (mut input_skip, mut output_skip) = (0, 0);
+        let len = deflate64_checkpoint(
+            inflater,
+            checkpoint.as_mut_ptr(),
+            checkpoint.len(),
+            &mut input_skip,
+            &mut output_skip,
+        );
+        assert_ne!(len, 0);
+        deflate64_free(inflater);
+
+        let restored = deflate64_new();
+        let (mut restored_input_skip, mut restored_output_skip) = (0, 0);
+        let status = deflate64_restore(
+            restored,
+            checkpoint.as_ptr(),
+            len,
+            &mut restored_input_skip,
+            &mut restored_output_skip,
+        );
+        assert_eq!(status, DEFLATE64_OK);
+        assert_eq!(restored_input_skip, input_skip);
+        assert_eq!(restored_output_skip, output_skip);
+
+        let (status, output) = inflate(restored, &compressed_data()[input_skip as usize..]);
+        assert_eq!(status, DEFLATE64_STREAM_END);
+        assert_eq!(output, BINARY_WAV_DATA[output_skip as usize..]);
+
+        checkpoint[0] ^= 1;
+        let status = deflate64_restore(
+            restored,
+            checkpoint.as_ptr(),
+            len,
+            &mut restored_input_skip,
+            &mut restored_output_skip,
+        );
+        assert_eq!(status, DEFLATE64_INVALID_CHECKPOINT);
+        deflate64_free(restored);
+    }
+}
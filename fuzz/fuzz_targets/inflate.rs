@@ -0,0 +1,50 @@
+#![no_main]
+
+use deflate64::{InflaterManaged, InflaterOptions, StreamCarver};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    // the first byte selects the options and the output chunk size
+    let Some((&selector, data)) = data.split_first() else {
+        return;
+    };
+    let options = match selector & 0b11 {
+        0 => InflaterOptions::new(),
+        1 => InflaterOptions::new().strict(true),
+        2 => InflaterOptions::new().fill_missing_history(true),
+        _ => InflaterOptions::new()
+            .uncompressed_size(data.len() * 4)
+            .exact_uncompressed_size(true)
+            .max_expansion_ratio(2)
+            .collect_stats(true),
+    };
+    let chunk_size = 1 + (selector >> 2) as usize * 1024;
+
+    let mut inflater = InflaterManaged::boxed_with_options(options);
+    let mut output = vec![0u8; chunk_size];
+    let mut consumed = 0;
+    loop {
+        let result = inflater.inflate(&data[consumed..], &mut output);
+        consumed += result.bytes_consumed;
+        if result.data_error {
+            match inflater.resync(&data[consumed..]) {
+                Some(gap) => consumed += gap.bytes_consumed,
+                None => break,
+            }
+        }
+        if inflater.finished() || result.is_stalled() {
+            break;
+        }
+    }
+    let _ = inflater.finish();
+
+    let _ = InflaterManaged::measure(data);
+    let _ = InflaterManaged::enumerate_blocks(data);
+    for _ in StreamCarver::new(data).min_output(1).take(4) {}
+
+    let mut inflater = Box::new(InflaterManaged::with_checkpoints());
+    inflater.inflate(data, &mut output);
+    if let Some((checkpoint, _)) = inflater.checkpoint() {
+        Box::new(InflaterManaged::with_checkpoints()).restore_from_checkpoint(&checkpoint);
+    }
+});
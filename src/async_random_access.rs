@@ -0,0 +1,284 @@
+use crate::{Checkpointing, Deflate64Index, InflaterManaged};
+use std::io::{self, SeekFrom};
+use std::ops::Range;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+const BUFFER_SIZE: usize = 64 * 1024;
+
+/// The compressed data source of an [`AsyncRandomAccessReader`], which reads at any offset,
+/// such as an object in an object store read with range requests.
+pub trait AsyncReadAt {
+    /// Attempts to read compressed bytes at `offset` into `buf`, and returns the count of
+    /// bytes read, which is 0 at the end of the data or if `buf` is empty.
+    ///
+    /// If no data is available, returns `Poll::Pending` and arranges for the current task
+    /// to be woken up when it is, like `AsyncRead::poll_read`.
+    fn poll_read_at(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        offset: u64,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>>;
+}
+
+impl AsyncReadAt for &[u8] {
+    fn poll_read_at(
+        self: Pin<&mut Self>,
+        _: &mut Context<'_>,
+        offset: u64,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let data = usize::try_from(offset)
+            .ok()
+            .and_then(|offset| self.get(offset..))
+            .unwrap_or_default();
+        let len = buf.len().min(data.len());
+        buf[..len].copy_from_slice(&data[..len]);
+        Poll::Ready(Ok(len))
+    }
+}
+
+impl AsyncReadAt for Vec<u8> {
+    fn poll_read_at(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        offset: u64,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut &self.get_mut()[..]).poll_read_at(cx, offset, buf)
+    }
+}
+
+impl<S: AsyncReadAt + Unpin + ?Sized> AsyncReadAt for &mut S {
+    fn poll_read_at(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        offset: u64,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut **self.get_mut()).poll_read_at(cx, offset, buf)
+    }
+}
+
+impl<S: AsyncReadAt + Unpin + ?Sized> AsyncReadAt for Box<S> {
+    fn poll_read_at(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        offset: u64,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut **self.get_mut()).poll_read_at(cx, offset, buf)
+    }
+}
+
+/// The async reader that decompresses a deflate64 stream from arbitrary output offsets,
+/// using a [`Deflate64Index`] of the stream. This is the async counterpart of
+/// [`RandomAccessInflater`](crate::RandomAccessInflater).
+///
+/// This implements `AsyncRead` and `AsyncSeek` of the `futures-io` crate with the
+/// `futures-io` feature, and those of the `tokio` crate with the `tokio` feature. Seeking
+/// restores the nearest checkpoint before the target from the index without reading, and
+/// the following reads decompress and discard the data up to the target first.
+///
+/// The compressed stream is read from `source` at offsets relative to the start of the
+/// stream.
+pub struct AsyncRandomAccessReader<S> {
+    index: Arc<Deflate64Index>,
+    source: S,
+    inflater: Box<InflaterManaged<Checkpointing>>,
+    buffer: Box<[u8]>,
+    input: Range<usize>, // compressed bytes in buffer not consumed yet
+    input_offset: u64,   // offset in source of the next read
+    eof: bool,
+    decoded: u64, // count of bytes decompressed, less than position while skipping to it
+    position: u64,
+}
+
+impl<S: AsyncReadAt + Unpin> AsyncRandomAccessReader<S> {
+    /// Creates AsyncRandomAccessReader with the index of a deflate64 stream, which may be
+    /// shared with other readers, and the source of the stream
+    pub fn new(index: impl Into<Arc<Deflate64Index>>, source: S) -> Self {
+        Self {
+            index: index.into(),
+            source,
+            inflater: Box::new(InflaterManaged::with_checkpoints()),
+            buffer: vec![0u8; BUFFER_SIZE].into_boxed_slice(),
+            input: 0..0,
+            input_offset: 0,
+            eof: false,
+            decoded: 0,
+            position: 0,
+        }
+    }
+
+    /// Returns the source of the stream
+    pub fn into_inner(self) -> S {
+        self.source
+    }
+
+    /// Returns reference to the source of the stream
+    pub fn get_ref(&self) -> &S {
+        &self.source
+    }
+
+    /// Returns the index of the stream
+    pub fn index(&self) -> &Arc<Deflate64Index> {
+        &self.index
+    }
+
+    /// Returns the output offset the next read decompresses from
+    pub fn position(&self) -> u64 {
+        self.position
+    }
+
+    // Moves the position to `pos`, restoring the nearest checkpoint before it if that's
+    // closer than the decompressed data.
+    fn start_seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let (base, offset) = match pos {
+            SeekFrom::Start(offset) => (offset, 0),
+            SeekFrom::Current(offset) => (self.position, offset),
+            SeekFrom::End(offset) => (self.index.uncompressed_size(), offset),
+        };
+        let target = base.checked_add_signed(offset).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "invalid seek to a negative or overflowing position",
+            )
+        })?;
+        if target > self.index.uncompressed_size() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "seek past the end of the deflate64 stream",
+            ));
+        }
+
+        let entry_offset = self
+            .index
+            .entry_before(target)
+            .map_or(0, |entry| entry.output_offset);
+        if target < self.decoded || entry_offset > self.decoded {
+            let positions = self.index.restore_before(&mut self.inflater, target)?;
+            self.input = 0..0;
+            self.input_offset = positions.input_bytes_to_skip;
+            self.eof = false;
+            self.decoded = positions.output_bytes_already_returned;
+        }
+        self.position = target;
+        Ok(target)
+    }
+
+    // Decompresses to `output` from the position, first decompressing and discarding
+    // output up to it.
+    fn poll_decode(&mut self, cx: &mut Context<'_>, output: &mut [u8]) -> Poll<io::Result<usize>> {
+        let mut scratch = [0u8; 8 * 1024];
+        while self.decoded < self.position {
+            let len = (self.position - self.decoded).min(scratch.len() as u64) as usize;
+            match self.poll_inflate(cx, &mut scratch[..len]) {
+                Poll::Ready(Ok(_)) => {}
+                other => return other,
+            }
+        }
+        let written = match self.poll_inflate(cx, output) {
+            Poll::Ready(Ok(written)) => written,
+            other => return other,
+        };
+        self.position = self.decoded;
+        Poll::Ready(Ok(written))
+    }
+
+    fn poll_inflate(&mut self, cx: &mut Context<'_>, output: &mut [u8]) -> Poll<io::Result<usize>> {
+        if output.is_empty() || self.decoded == self.index.uncompressed_size() {
+            return Poll::Ready(Ok(0));
+        }
+
+        loop {
+            let result = self
+                .inflater
+                .inflate(&self.buffer[self.input.clone()], output);
+            self.input.start += result.bytes_consumed;
+            self.decoded += result.bytes_written as u64;
+
+            if let Some(&error) = self.inflater.error() {
+                return Poll::Ready(Err(error.into()));
+            }
+
+            if result.bytes_written != 0 {
+                return Poll::Ready(Ok(result.bytes_written));
+            }
+
+            if self.eof || self.inflater.finished() {
+                // the stream ended before the uncompressed size of the index
+                return Poll::Ready(Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    "incomplete deflate64",
+                )));
+            }
+
+            if self.input.is_empty() {
+                let read = match Pin::new(&mut self.source).poll_read_at(
+                    cx,
+                    self.input_offset,
+                    &mut self.buffer,
+                ) {
+                    Poll::Ready(Ok(read)) => read,
+                    Poll::Ready(Err(e)) => return Poll::Ready(Err(e)),
+                    Poll::Pending => return Poll::Pending,
+                };
+                self.input = 0..read;
+                self.input_offset += read as u64;
+                self.eof = read == 0;
+            }
+        }
+    }
+}
+
+#[cfg(feature = "futures-io")]
+#[cfg_attr(docsrs, doc(cfg(feature = "futures-io")))]
+impl<S: AsyncReadAt + Unpin> futures_io::AsyncRead for AsyncRandomAccessReader<S> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        self.get_mut().poll_decode(cx, buf)
+    }
+}
+
+#[cfg(feature = "futures-io")]
+#[cfg_attr(docsrs, doc(cfg(feature = "futures-io")))]
+impl<S: AsyncReadAt + Unpin> futures_io::AsyncSeek for AsyncRandomAccessReader<S> {
+    fn poll_seek(
+        self: Pin<&mut Self>,
+        _: &mut Context<'_>,
+        pos: SeekFrom,
+    ) -> Poll<io::Result<u64>> {
+        Poll::Ready(self.get_mut().start_seek(pos))
+    }
+}
+
+#[cfg(feature = "tokio")]
+#[cfg_attr(docsrs, doc(cfg(feature = "tokio")))]
+impl<S: AsyncReadAt + Unpin> tokio::io::AsyncRead for AsyncRandomAccessReader<S> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut tokio::io::ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let written = self.get_mut().poll_decode(cx, buf.initialize_unfilled());
+        written.map_ok(|written| buf.advance(written))
+    }
+}
+
+#[cfg(feature = "tokio")]
+#[cfg_attr(docsrs, doc(cfg(feature = "tokio")))]
+impl<S: AsyncReadAt + Unpin> tokio::io::AsyncSeek for AsyncRandomAccessReader<S> {
+    fn start_seek(self: Pin<&mut Self>, pos: SeekFrom) -> io::Result<()> {
+        self.get_mut().start_seek(pos).map(|_| ())
+    }
+
+    fn poll_complete(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<io::Result<u64>> {
+        Poll::Ready(Ok(self.position))
+    }
+}
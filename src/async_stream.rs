@@ -0,0 +1,136 @@
+use crate::InflaterManaged;
+use std::io;
+use std::ops::Range;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+const DEFAULT_BUFFER_SIZE: usize = 8 * 1024;
+
+/// The async reader that decompresses deflate64 from another async reader.
+///
+/// This implements `AsyncRead` of the `futures-io` crate for inner readers implementing it
+/// with the `futures-io` feature, and `AsyncRead` of the `tokio` crate for inner readers
+/// implementing it with the `tokio` feature.
+///
+/// Compressed data is read from the inner reader into an internal buffer, so the inner
+/// reader may have been read past the end of the deflate64 stream.
+pub struct AsyncDeflate64Decoder<R> {
+    inner: R,
+    inflater: Box<InflaterManaged>,
+    buffer: Box<[u8]>,
+    input: Range<usize>, // compressed bytes in buffer not consumed yet
+    eof: bool,
+}
+
+impl<R> AsyncDeflate64Decoder<R> {
+    /// Creates AsyncDeflate64Decoder with an async reader
+    pub fn new(inner: R) -> Self {
+        Self::with_capacity(DEFAULT_BUFFER_SIZE, inner)
+    }
+
+    /// Creates AsyncDeflate64Decoder with an async reader, buffering `capacity` bytes of
+    /// compressed data
+    ///
+    /// # Panics
+    ///
+    /// Panics if `capacity` is zero.
+    pub fn with_capacity(capacity: usize, inner: R) -> Self {
+        assert!(capacity > 0, "capacity must not be zero");
+        Self {
+            inner,
+            inflater: InflaterManaged::boxed(),
+            buffer: vec![0u8; capacity].into_boxed_slice(),
+            input: 0..0,
+            eof: false,
+        }
+    }
+
+    /// Returns inner async reader
+    ///
+    /// Compressed data which was read from it but not decompressed yet is lost.
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    /// Returns reference to innner async reader
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    /// Returns mutable reference to innner async reader
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.inner
+    }
+
+    /// Decompresses to `output`, polling the inner reader with `poll_fill` for more input.
+    fn poll_inflate(
+        &mut self,
+        output: &mut [u8],
+        mut poll_fill: impl FnMut(&mut R, &mut [u8]) -> Poll<io::Result<usize>>,
+    ) -> Poll<io::Result<usize>> {
+        if output.is_empty() {
+            // we received empty buffer, so it won't be possible to write anything
+            return Poll::Ready(Ok(0));
+        }
+
+        loop {
+            // the inflater may have output left from the previous call, so inflate
+            // before polling the inner reader, which may be pending
+            let result = self
+                .inflater
+                .inflate(&self.buffer[self.input.clone()], output);
+            self.input.start += result.bytes_consumed;
+
+            if let Some(&error) = self.inflater.error() {
+                return Poll::Ready(Err(error.into()));
+            }
+
+            if result.bytes_written != 0 || self.eof || self.inflater.finished() {
+                return Poll::Ready(Ok(result.bytes_written));
+            }
+
+            if self.input.is_empty() {
+                let read = match poll_fill(&mut self.inner, &mut self.buffer) {
+                    Poll::Ready(Ok(read)) => read,
+                    Poll::Ready(Err(e)) => return Poll::Ready(Err(e)),
+                    Poll::Pending => return Poll::Pending,
+                };
+                self.input = 0..read;
+                self.eof = read == 0;
+            }
+        }
+    }
+}
+
+#[cfg(feature = "futures-io")]
+#[cfg_attr(docsrs, doc(cfg(feature = "futures-io")))]
+impl<R: futures_io::AsyncRead + Unpin> futures_io::AsyncRead for AsyncDeflate64Decoder<R> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        self.get_mut()
+            .poll_inflate(buf, |inner, input| Pin::new(inner).poll_read(cx, input))
+    }
+}
+
+#[cfg(feature = "tokio")]
+#[cfg_attr(docsrs, doc(cfg(feature = "tokio")))]
+impl<R: tokio::io::AsyncRead + Unpin> tokio::io::AsyncRead for AsyncDeflate64Decoder<R> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut tokio::io::ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let written = self
+            .get_mut()
+            .poll_inflate(buf.initialize_unfilled(), |inner, input| {
+                let mut input = tokio::io::ReadBuf::new(input);
+                Pin::new(inner)
+                    .poll_read(cx, &mut input)
+                    .map_ok(|()| input.filled().len())
+            });
+        written.map_ok(|written| buf.advance(written))
+    }
+}
@@ -0,0 +1,127 @@
+use crate::InflaterManaged;
+use bytes::{Buf, Bytes, BytesMut};
+use futures_core::Stream;
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+const DEFAULT_CHUNK_SIZE: usize = 64 * 1024;
+
+/// The stream that decompresses deflate64 from a stream of [`Bytes`] chunks.
+///
+/// This is made for network pipelines such as HTTP bodies, where compressed data arrives
+/// in chunks of arbitrary sizes. Each chunk is decompressed as far as it goes, and the rest
+/// of it is kept until the inflater needs more input, so input chunks don't have to align
+/// with anything in the deflate64 stream. The inner stream is only polled when all of the
+/// previous chunk was consumed, so a slow consumer of the output slows down reading the
+/// input as well.
+///
+/// Each item is a chunk of at most `chunk_size` decompressed bytes. If the inner stream ends
+/// before the end of the deflate64 stream, an [`io::ErrorKind::UnexpectedEof`] error is
+/// yielded. Data after the end of the deflate64 stream is ignored. After an error, this
+/// stream ends.
+pub struct Deflate64Stream<S> {
+    inner: S,
+    inflater: Box<InflaterManaged>,
+    input: Bytes,
+    output: BytesMut,
+    chunk_size: usize,
+    eof: bool,
+    done: bool,
+}
+
+impl<S> Deflate64Stream<S> {
+    /// Creates Deflate64Stream with a stream of compressed chunks, yielding chunks of
+    /// at most 64 KiB
+    pub fn new(inner: S) -> Self {
+        Self::with_chunk_size(DEFAULT_CHUNK_SIZE, inner)
+    }
+
+    /// Creates Deflate64Stream with a stream of compressed chunks, yielding chunks of
+    /// at most `chunk_size` bytes
+    ///
+    /// # Panics
+    ///
+    /// Panics if `chunk_size` is zero.
+    pub fn with_chunk_size(chunk_size: usize, inner: S) -> Self {
+        assert!(chunk_size > 0, "chunk_size must not be zero");
+        Self {
+            inner,
+            inflater: InflaterManaged::boxed(),
+            input: Bytes::new(),
+            output: BytesMut::new(),
+            chunk_size,
+            eof: false,
+            done: false,
+        }
+    }
+
+    /// Returns inner stream
+    ///
+    /// Compressed data which was received from it but not decompressed yet is lost.
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+
+    /// Returns reference to innner stream
+    pub fn get_ref(&self) -> &S {
+        &self.inner
+    }
+
+    /// Returns mutable reference to innner stream
+    pub fn get_mut(&mut self) -> &mut S {
+        &mut self.inner
+    }
+}
+
+impl<S: Stream<Item = io::Result<Bytes>> + Unpin> Stream for Deflate64Stream<S> {
+    type Item = io::Result<Bytes>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        loop {
+            if this.done {
+                return Poll::Ready(None);
+            }
+
+            this.output.resize(this.chunk_size, 0);
+            let result = this.inflater.inflate(&this.input, &mut this.output);
+            this.input.advance(result.bytes_consumed);
+
+            if let Some(&error) = this.inflater.error() {
+                this.done = true;
+                return Poll::Ready(Some(Err(error.into())));
+            }
+
+            if result.bytes_written != 0 {
+                let chunk = this.output.split_to(result.bytes_written).freeze();
+                return Poll::Ready(Some(Ok(chunk)));
+            }
+
+            if this.inflater.finished() {
+                this.done = true;
+                return Poll::Ready(None);
+            }
+
+            if this.eof {
+                this.done = true;
+                return Poll::Ready(Some(Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    "deflate64 stream is incomplete",
+                ))));
+            }
+
+            if this.input.is_empty() {
+                match Pin::new(&mut this.inner).poll_next(cx) {
+                    Poll::Ready(Some(Ok(chunk))) => this.input = chunk,
+                    Poll::Ready(Some(Err(e))) => {
+                        this.done = true;
+                        return Poll::Ready(Some(Err(e)));
+                    }
+                    Poll::Ready(None) => this.eof = true,
+                    Poll::Pending => return Poll::Pending,
+                }
+            }
+        }
+    }
+}
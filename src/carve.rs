@@ -0,0 +1,157 @@
+use crate::{InflateErrorKind, InflaterManaged, InflaterOptions};
+
+/// How likely a [`CarvedStream`] is the start of a real deflate64 stream.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+#[non_exhaustive]
+pub enum CarveConfidence {
+    /// Decoding failed, but only after producing enough output.
+    Low,
+    /// Decoding reached the end of the data, or the trial output limit, without error.
+    Medium,
+    /// The stream was decoded to the end of its final block without error.
+    High,
+}
+
+/// A candidate deflate64 stream found by [`StreamCarver`].
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[non_exhaustive]
+pub struct CarvedStream {
+    /// The offset of the first byte of the stream in the scanned data.
+    pub offset: usize,
+    /// The count of bytes decoded by the trial. This is the length of the stream if the
+    /// confidence is [`CarveConfidence::High`].
+    pub input_len: usize,
+    /// The count of bytes the trial decoded to.
+    pub output_len: u64,
+    /// How likely this is a real stream.
+    pub confidence: CarveConfidence,
+}
+
+/// The iterator over candidate deflate64 streams in arbitrary data, such as unallocated
+/// space of a disk image.
+///
+/// Every byte offset is tried as the start of a stream by decoding from it in
+/// [strict mode](InflaterOptions::strict), without keeping the output. Candidates which
+/// decode to less than [`Self::min_output`] bytes are not reported, since random data
+/// often decodes to a few bytes. After a candidate which decoded without error, the scan
+/// continues after the input it decoded; after a candidate which failed, it continues
+/// with the next byte.
+///
+/// ```
+/// # use deflate64::{CarveConfidence, StreamCarver};
+/// let mut data = vec![0x55; 100];
+/// // a stored final block of 64 bytes
+/// data.extend_from_slice(&[0b001, 64, 0, !64, !0]);
+/// data.extend_from_slice(&[b'x'; 64]);
+///
+/// let found: Vec<_> = StreamCarver::new(&data).collect();
+/// assert_eq!(found.len(), 1);
+/// assert_eq!(found[0].offset, 100);
+/// assert_eq!(found[0].input_len, 69);
+/// assert_eq!(found[0].confidence, CarveConfidence::High);
+/// ```
+pub struct StreamCarver<'a> {
+    data: &'a [u8],
+    offset: usize,
+    min_output: u64,
+    max_trial_output: u64,
+    inflater: Box<InflaterManaged>,
+}
+
+impl<'a> StreamCarver<'a> {
+    /// Creates a carver which scans `data` from the start.
+    pub fn new(data: &'a [u8]) -> Self {
+        Self {
+            data,
+            offset: 0,
+            min_output: 64,
+            max_trial_output: 1024 * 1024,
+            inflater: InflaterManaged::boxed(),
+        }
+    }
+
+    /// Sets the count of output bytes a candidate must decode to before it is reported.
+    ///
+    /// Defaults to 64.
+    pub fn min_output(mut self, min_output: u64) -> Self {
+        self.min_output = min_output;
+        self
+    }
+
+    /// Sets the count of output bytes after which a trial stops. Larger limits tell more
+    /// streams apart from random data, but make the trials of long streams slower.
+    ///
+    /// Defaults to 1 MiB.
+    pub fn max_trial_output(mut self, max_trial_output: u64) -> Self {
+        self.max_trial_output = max_trial_output;
+        self
+    }
+
+    /// Returns the offset of the next byte to be tried.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    fn trial(&mut self, offset: usize) -> CarvedStream {
+        self.inflater.reset_with_options(
+            InflaterOptions::new()
+                .strict(true)
+                .max_output(self.max_trial_output),
+        );
+        let (input_len, output_len, confidence) = match self.inflater.validate(&self.data[offset..])
+        {
+            Ok(summary) => match self.inflater.stream_len() {
+                Some(len) => (
+                    len as usize,
+                    summary.bytes_written as u64,
+                    CarveConfidence::High,
+                ),
+                None => (
+                    summary.bytes_consumed,
+                    summary.bytes_written as u64,
+                    CarveConfidence::Medium,
+                ),
+            },
+            Err(error) => (
+                (error.input_bit_offset() / 8) as usize,
+                error.output_offset(),
+                if error.kind() == InflateErrorKind::OutputLimitExceeded {
+                    CarveConfidence::Medium
+                } else {
+                    CarveConfidence::Low
+                },
+            ),
+        };
+        CarvedStream {
+            offset,
+            input_len,
+            output_len,
+            confidence,
+        }
+    }
+}
+
+impl Iterator for StreamCarver<'_> {
+    type Item = CarvedStream;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.offset < self.data.len() {
+            let offset = self.offset;
+            self.offset += 1;
+            // BTYPE=11 is invalid
+            if self.data[offset] & 0b110 == 0b110 {
+                continue;
+            }
+
+            let found = self.trial(offset);
+            if found.output_len < self.min_output {
+                continue;
+            }
+            if found.confidence != CarveConfidence::Low {
+                self.offset = offset + found.input_len.max(1);
+            }
+            return Some(found);
+        }
+        None
+    }
+}
@@ -0,0 +1,207 @@
+use crate::{CheckpointStreamPositions, Checkpointing, InflateResult, InflaterManaged};
+use std::cmp::min;
+use std::collections::VecDeque;
+
+/// When [`InflaterManaged::inflate_with_policy`] takes checkpoints.
+///
+/// A checkpoint is taken whenever `every_n_output_bytes` bytes of output were returned
+/// since the last one, or since the start of the stream. The policy keeps the buffer
+/// checkpoints are serialized into, so taking them doesn't allocate after the first one.
+/// It can also keep the most recent checkpoints, see [`Self::retain_last`], to rewind a
+/// short distance without an external index.
+///
+/// ```
+/// # use deflate64::{CheckpointPolicy, InflaterManaged};
+/// # let compressed = [0x4b, 0x04, 0x02, 0x00];
+/// let mut inflater = InflaterManaged::with_checkpoints();
+/// let mut policy = CheckpointPolicy::new(1024 * 1024);
+/// let mut output = vec![0; 4096];
+/// let result = inflater.inflate_with_policy(&compressed, &mut output, &mut policy, |data, _| {
+///     // persist the checkpoint
+/// #   let _ = data;
+/// });
+/// assert_eq!(result.bytes_written, 4);
+/// ```
+#[cfg_attr(docsrs, doc(cfg(feature = "checkpoint")))]
+#[derive(Debug, Clone)]
+pub struct CheckpointPolicy {
+    every_n_output_bytes: u64,
+    at_block_boundaries_only: bool,
+    next_checkpoint_at: u64,
+    buffer: Vec<u8>,
+    retain: usize,
+    retained: VecDeque<(Vec<u8>, CheckpointStreamPositions)>,
+}
+
+impl CheckpointPolicy {
+    /// Creates a policy which takes a checkpoint every `every_n_output_bytes` bytes of
+    /// output.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `every_n_output_bytes` is 0.
+    pub fn new(every_n_output_bytes: u64) -> Self {
+        assert!(
+            every_n_output_bytes > 0,
+            "every_n_output_bytes must be non-zero"
+        );
+        Self {
+            every_n_output_bytes,
+            at_block_boundaries_only: false,
+            next_checkpoint_at: every_n_output_bytes,
+            buffer: Vec::new(),
+            retain: 0,
+            retained: VecDeque::new(),
+        }
+    }
+
+    /// Sets whether checkpoints are only taken at the end of deflate64 blocks. The
+    /// checkpoint is then taken at the first block boundary after `every_n_output_bytes`
+    /// bytes of output.
+    ///
+    /// Checkpoints at block boundaries don't contain Huffman code lengths, and they are
+    /// the positions [`InflaterManaged::enumerate_blocks`] reports.
+    pub fn at_block_boundaries_only(mut self, at_block_boundaries_only: bool) -> Self {
+        self.at_block_boundaries_only = at_block_boundaries_only;
+        self
+    }
+
+    /// Sets how many of the most recent checkpoints the policy keeps, which
+    /// [`Self::latest_checkpoint`] and [`Self::checkpoint_at_or_before`] return. The
+    /// oldest checkpoint is dropped when a checkpoint is taken while `count` are kept.
+    /// Defaults to 0, which keeps none.
+    ///
+    /// Each checkpoint takes up to [`MAX_CHECKPOINT_SIZE`](crate::MAX_CHECKPOINT_SIZE)
+    /// bytes, and the memory of dropped checkpoints is reused.
+    ///
+    /// ```
+    /// # use deflate64::{CheckpointPolicy, InflaterManaged};
+    /// # let compressed = [0x4b, 0x04, 0x02, 0x00];
+    /// let mut inflater = InflaterManaged::with_checkpoints();
+    /// let mut policy = CheckpointPolicy::new(1).retain_last(2);
+    /// let mut output = vec![0; 4096];
+    /// inflater.inflate_with_policy(&compressed, &mut output, &mut policy, |_, _| {});
+    ///
+    /// // rewind to the output offset 2
+    /// let (data, _) = policy.checkpoint_at_or_before(2).unwrap();
+    /// let positions = inflater.restore_from_checkpoint(data);
+    /// assert_eq!(positions.unwrap().output_bytes_already_returned, 2);
+    /// ```
+    pub fn retain_last(mut self, count: usize) -> Self {
+        self.retain = count;
+        let dropped = self.retained.len().saturating_sub(count);
+        self.retained.drain(..dropped);
+        self
+    }
+
+    /// Returns the most recent checkpoint the policy keeps, with its positions, or None if
+    /// it keeps none.
+    pub fn latest_checkpoint(&self) -> Option<(&[u8], &CheckpointStreamPositions)> {
+        self.retained
+            .back()
+            .map(|(data, positions)| (&data[..], positions))
+    }
+
+    /// Returns the kept checkpoint with the largest output offset not after
+    /// `output_offset`, with its positions, or None if there is none.
+    pub fn checkpoint_at_or_before(
+        &self,
+        output_offset: u64,
+    ) -> Option<(&[u8], &CheckpointStreamPositions)> {
+        let index = self.retained.partition_point(|(_, positions)| {
+            positions.output_bytes_already_returned <= output_offset
+        });
+        let (data, positions) = self.retained.get(index.checked_sub(1)?)?;
+        Some((data, positions))
+    }
+
+    // keeps a copy of the checkpoint which was just taken, dropping the oldest one
+    fn retain_checkpoint(&mut self, len: usize, positions: &CheckpointStreamPositions) {
+        if self.retain == 0 {
+            return;
+        }
+        let mut data = if self.retained.len() == self.retain {
+            self.retained
+                .pop_front()
+                .map(|(data, _)| data)
+                .unwrap_or_default()
+        } else {
+            Vec::new()
+        };
+        data.clear();
+        data.extend_from_slice(&self.buffer[..len]);
+        self.retained.push_back((data, positions.clone()));
+    }
+
+    /// Returns the count of output bytes between checkpoints.
+    pub fn every_n_output_bytes(&self) -> u64 {
+        self.every_n_output_bytes
+    }
+
+    /// Returns the output offset from which the next checkpoint is taken.
+    pub fn next_checkpoint_at(&self) -> u64 {
+        self.next_checkpoint_at
+    }
+}
+
+impl InflaterManaged<Checkpointing> {
+    /// Same as [`Self::inflate`] but also takes checkpoints as `policy` tells, and passes
+    /// each of them to `on_checkpoint` with its positions, as returned by
+    /// [`Self::checkpoint`].
+    ///
+    /// To take checkpoints at the exact output offsets the policy tells, the output is
+    /// decoded in parts which end at these offsets. Use the same policy for all calls of
+    /// a stream. If a checkpoint is not available when it is due, such as before the end
+    /// of the first block header, it is taken as soon as it is.
+    #[cfg_attr(docsrs, doc(cfg(feature = "checkpoint")))]
+    pub fn inflate_with_policy(
+        &mut self,
+        input: &[u8],
+        output: &mut [u8],
+        policy: &mut CheckpointPolicy,
+        mut on_checkpoint: impl FnMut(&[u8], CheckpointStreamPositions),
+    ) -> InflateResult {
+        let mut result = InflateResult::new();
+        let output_len = output.len();
+        loop {
+            let input = &input[result.bytes_consumed..];
+            let output = &mut output[result.bytes_written..];
+            let due = policy.next_checkpoint_at.saturating_sub(self.total_out());
+            let (part_result, at_boundary) = if policy.at_block_boundaries_only {
+                let (part_result, boundary) = self.inflate_until_block_boundary(input, output);
+                (part_result, boundary.is_some())
+            } else if due > 0 {
+                let len = min(output.len() as u64, due) as usize;
+                (self.inflate(input, &mut output[..len]), true)
+            } else {
+                (self.inflate(input, output), true)
+            };
+            result.bytes_consumed += part_result.bytes_consumed;
+            result.bytes_written += part_result.bytes_written;
+            result.data_error = part_result.data_error;
+            result.history_filled |= part_result.history_filled;
+            result.status = part_result.status;
+
+            if at_boundary && self.total_out() >= policy.next_checkpoint_at {
+                if policy.buffer.is_empty() {
+                    policy.buffer = vec![0; Self::checkpoint_max_len()];
+                }
+                if let Some((len, positions)) = self.checkpoint_into(&mut policy.buffer) {
+                    policy.next_checkpoint_at = self.total_out() + policy.every_n_output_bytes;
+                    policy.retain_checkpoint(len, &positions);
+                    on_checkpoint(&policy.buffer[..len], positions);
+                }
+            }
+
+            if part_result.data_error
+                || part_result.is_stalled()
+                || self.finished()
+                || result.bytes_written == output_len
+            {
+                // there is an error, the stream ended, input is exhausted or output is full
+                break;
+            }
+        }
+        result
+    }
+}
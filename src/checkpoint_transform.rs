@@ -0,0 +1,93 @@
+use crate::{CheckpointStreamPositions, Checkpointing, InflaterManaged};
+use std::io;
+
+/// Transforms serialized checkpoints before they are persisted and back before they are
+/// restored, such as by encrypting them, see [`InflaterManaged::checkpoint_transformed`].
+///
+/// The history window in a checkpoint holds up to 128KB of recently decompressed data, so
+/// checkpoints of sensitive data should not be stored in plaintext.
+///
+/// ```
+/// # use deflate64::{CheckpointTransform, InflaterManaged};
+/// # use std::io;
+/// struct Xor(u8);
+///
+/// impl CheckpointTransform for Xor {
+///     fn encode(&mut self, checkpoint: &[u8]) -> io::Result<Vec<u8>> {
+///         Ok(checkpoint.iter().map(|byte| byte ^ self.0).collect())
+///     }
+///
+///     fn decode(&mut self, encoded: &[u8]) -> io::Result<Vec<u8>> {
+///         self.encode(encoded)
+///     }
+/// }
+///
+/// # let compressed = [0x4b, 0x04, 0x02, 0x00];
+/// let mut inflater = InflaterManaged::with_checkpoints();
+/// inflater.inflate(&compressed, &mut [0; 2]);
+/// let (encoded, positions) = inflater.checkpoint_transformed(&mut Xor(0x5A))?.unwrap();
+///
+/// let mut restored = InflaterManaged::with_checkpoints();
+/// let restored_positions = restored.restore_transformed(&encoded, &mut Xor(0x5A))?;
+/// assert_eq!(restored_positions, Some(positions));
+/// # Ok::<(), io::Error>(())
+/// ```
+#[cfg_attr(docsrs, doc(cfg(feature = "checkpoint")))]
+pub trait CheckpointTransform {
+    /// Transforms the serialized `checkpoint` into the bytes to persist.
+    fn encode(&mut self, checkpoint: &[u8]) -> io::Result<Vec<u8>>;
+
+    /// Reverses [`encode`](Self::encode), returning the serialized checkpoint. Returns an
+    /// error if `encoded` can't be decoded, such as if it fails authentication.
+    fn decode(&mut self, encoded: &[u8]) -> io::Result<Vec<u8>>;
+}
+
+impl<T: CheckpointTransform + ?Sized> CheckpointTransform for &mut T {
+    fn encode(&mut self, checkpoint: &[u8]) -> io::Result<Vec<u8>> {
+        (**self).encode(checkpoint)
+    }
+
+    fn decode(&mut self, encoded: &[u8]) -> io::Result<Vec<u8>> {
+        (**self).decode(encoded)
+    }
+}
+
+impl<T: CheckpointTransform + ?Sized> CheckpointTransform for Box<T> {
+    fn encode(&mut self, checkpoint: &[u8]) -> io::Result<Vec<u8>> {
+        (**self).encode(checkpoint)
+    }
+
+    fn decode(&mut self, encoded: &[u8]) -> io::Result<Vec<u8>> {
+        (**self).decode(encoded)
+    }
+}
+
+impl InflaterManaged<Checkpointing> {
+    /// Same as [`Self::checkpoint`] but passes the serialized checkpoint through
+    /// `transform`, and returns the encoded bytes. Returns Ok(None) if there is no
+    /// checkpoint, and the error of `transform` if it fails.
+    #[cfg_attr(docsrs, doc(cfg(feature = "checkpoint")))]
+    pub fn checkpoint_transformed(
+        &self,
+        mut transform: impl CheckpointTransform,
+    ) -> io::Result<Option<(Vec<u8>, CheckpointStreamPositions)>> {
+        let Some((checkpoint, positions)) = self.checkpoint() else {
+            return Ok(None);
+        };
+        Ok(Some((transform.encode(&checkpoint)?, positions)))
+    }
+
+    /// Same as [`Self::restore_from_checkpoint`] but decodes `encoded`, returned by
+    /// [`Self::checkpoint_transformed`], with `transform` first. Returns Ok(None) if the
+    /// decoded checkpoint is corrupt, and the error of `transform` if it fails, in which
+    /// case the inflater is not modified.
+    #[cfg_attr(docsrs, doc(cfg(feature = "checkpoint")))]
+    pub fn restore_transformed(
+        &mut self,
+        encoded: &[u8],
+        mut transform: impl CheckpointTransform,
+    ) -> io::Result<Option<CheckpointStreamPositions>> {
+        let checkpoint = transform.decode(encoded)?;
+        Ok(self.restore_from_checkpoint(&checkpoint))
+    }
+}
@@ -0,0 +1,65 @@
+use crate::{CheckpointTracking, InflateError, InflaterManaged};
+
+/// The iterator over decompressed chunks, returned by [`InflaterManaged::chunks`].
+///
+/// Each item is a chunk of at most `chunk_size` decompressed bytes, and nothing is
+/// decompressed before the next chunk is requested. When the end of the deflate64 stream
+/// is reached, the stream is verified with [`InflaterManaged::finish`], so input which ends
+/// before the end of the stream is an error. After an error, the iterator ends.
+pub struct InflateChunks<'a, T: CheckpointTracking> {
+    inflater: &'a mut InflaterManaged<T>,
+    input: &'a [u8],
+    chunk_size: usize,
+    done: bool,
+}
+
+impl<'a, T: CheckpointTracking> InflateChunks<'a, T> {
+    pub(crate) fn new(
+        inflater: &'a mut InflaterManaged<T>,
+        input: &'a [u8],
+        chunk_size: usize,
+    ) -> Self {
+        assert!(chunk_size > 0, "chunk_size must not be zero");
+        Self {
+            inflater,
+            input,
+            chunk_size,
+            done: false,
+        }
+    }
+
+    /// Returns the input which was not consumed yet.
+    pub fn remaining_input(&self) -> &'a [u8] {
+        self.input
+    }
+}
+
+impl<T: CheckpointTracking> Iterator for InflateChunks<'_, T> {
+    type Item = Result<Vec<u8>, InflateError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        let mut chunk = vec![0u8; self.chunk_size];
+        let mut written = 0;
+        while written < chunk.len() && !self.inflater.errored() {
+            let result = self.inflater.inflate(self.input, &mut chunk[written..]);
+            self.input = &self.input[result.bytes_consumed..];
+            written += result.bytes_written;
+            if result.is_stalled() {
+                break;
+            }
+        }
+
+        if written == 0 {
+            // nothing more to output: the stream ended, the input ended, or there is an error
+            self.done = true;
+            return self.inflater.finish().err().map(Err);
+        }
+        // an error found while filling this chunk is returned with the next call
+        chunk.truncate(written);
+        Some(Ok(chunk))
+    }
+}
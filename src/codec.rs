@@ -0,0 +1,89 @@
+use crate::InflaterManaged;
+use bytes::{Buf, Bytes, BytesMut};
+use std::io;
+use tokio_util::codec::Decoder;
+
+const DEFAULT_MAX_FRAME_SIZE: usize = 64 * 1024;
+
+/// The [`Decoder`] that decompresses deflate64 for framed transports.
+///
+/// With [`FramedRead`](tokio_util::codec::FramedRead), each frame is a chunk of at most
+/// `max_frame_size` decompressed bytes. Compressed bytes which the inflater did not consume
+/// yet stay in the read buffer of the framed transport until the next call.
+///
+/// If the transport ends before the end of the deflate64 stream, an
+/// [`io::ErrorKind::UnexpectedEof`] error is returned. Data after the end of the deflate64
+/// stream is left in the read buffer.
+pub struct Deflate64Codec {
+    inflater: Box<InflaterManaged>,
+    output: BytesMut,
+    max_frame_size: usize,
+}
+
+impl Deflate64Codec {
+    /// Creates Deflate64Codec emitting frames of at most 64 KiB
+    pub fn new() -> Self {
+        Self::with_max_frame_size(DEFAULT_MAX_FRAME_SIZE)
+    }
+
+    /// Creates Deflate64Codec emitting frames of at most `max_frame_size` bytes
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max_frame_size` is zero.
+    pub fn with_max_frame_size(max_frame_size: usize) -> Self {
+        assert!(max_frame_size > 0, "max_frame_size must not be zero");
+        Self {
+            inflater: InflaterManaged::boxed(),
+            output: BytesMut::new(),
+            max_frame_size,
+        }
+    }
+
+    /// Returns the maximum count of decompressed bytes in a frame
+    pub fn max_frame_size(&self) -> usize {
+        self.max_frame_size
+    }
+}
+
+impl Default for Deflate64Codec {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Decoder for Deflate64Codec {
+    type Item = Bytes;
+    type Error = io::Error;
+
+    fn decode(&mut self, src: &mut BytesMut) -> io::Result<Option<Bytes>> {
+        if self.inflater.finished() {
+            return Ok(None);
+        }
+
+        self.output.resize(self.max_frame_size, 0);
+        let result = self.inflater.inflate(src, &mut self.output);
+        src.advance(result.bytes_consumed);
+
+        if let Some(&error) = self.inflater.error() {
+            return Err(error.into());
+        }
+
+        if result.bytes_written == 0 {
+            // all input is consumed, so wait for more
+            return Ok(None);
+        }
+        Ok(Some(self.output.split_to(result.bytes_written).freeze()))
+    }
+
+    fn decode_eof(&mut self, src: &mut BytesMut) -> io::Result<Option<Bytes>> {
+        match self.decode(src)? {
+            Some(frame) => Ok(Some(frame)),
+            None if self.inflater.finished() => Ok(None),
+            None => Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "deflate64 stream is incomplete",
+            )),
+        }
+    }
+}
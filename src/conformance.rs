@@ -0,0 +1,231 @@
+//! Conformance test vectors for deflate64 decoders.
+//!
+//! These are the streams this crate is tested with: real-world zip entries, streams from
+//! reported issues, and hand-built streams for edge cases such as the longest match.
+//! Wrappers around this crate, like zip readers or FFI bindings, can run them through
+//! their own integration layer to check it decodes and reports errors the same way.
+
+use crate::crc32::Crc32;
+use crate::InflateErrorKind;
+use std::borrow::Cow;
+
+static DEFLATE64_ZIP: &[u8] = include_bytes!("../test-assets/deflate64.zip");
+static ISSUE_13_ZIP: &[u8] = include_bytes!("../test-assets/issue-13/unitwf-1.5.0.minimized.zip");
+static ISSUE_23_DATA: &[u8] =
+    include_bytes!("../test-assets/issue-23/raw_deflate64_index_out_of_bounds");
+static ISSUE_25_ZIP: &[u8] =
+    include_bytes!("../test-assets/issue-25/deflate64_not_enough_space.zip");
+static ISSUE_29_ZIP: &[u8] = include_bytes!("../test-assets/issue-29/raw.zip");
+
+/// The expected result of decoding a [`TestVector`].
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum Expected {
+    /// The stream decodes to `len` bytes with the CRC-32 `crc32`.
+    Output {
+        /// The length of the decoded output.
+        len: u64,
+        /// The CRC-32 of the decoded output.
+        crc32: u32,
+    },
+    /// Decoding fails with an error of this kind.
+    Error(InflateErrorKind),
+}
+
+/// A deflate64 stream and the expected result of decoding it.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct TestVector {
+    /// A short name which identifies this vector in test output.
+    pub name: &'static str,
+    /// The raw deflate64 stream.
+    pub input: Cow<'static, [u8]>,
+    /// The expected result of decoding `input`.
+    pub expected: Expected,
+}
+
+impl TestVector {
+    fn output(name: &'static str, input: impl Into<Cow<'static, [u8]>>, output: &[u8]) -> Self {
+        let mut crc32 = Crc32::new();
+        crc32.update(output);
+        Self::output_crc32(name, input, output.len() as u64, crc32.value())
+    }
+
+    fn output_crc32(
+        name: &'static str,
+        input: impl Into<Cow<'static, [u8]>>,
+        len: u64,
+        crc32: u32,
+    ) -> Self {
+        Self {
+            name,
+            input: input.into(),
+            expected: Expected::Output { len, crc32 },
+        }
+    }
+
+    fn error(
+        name: &'static str,
+        input: impl Into<Cow<'static, [u8]>>,
+        kind: InflateErrorKind,
+    ) -> Self {
+        Self {
+            name,
+            input: input.into(),
+            expected: Expected::Error(kind),
+        }
+    }
+
+    /// Returns true if `output` is the expected output of this vector.
+    ///
+    /// Always returns false for vectors which are expected to fail.
+    pub fn output_matches(&self, output: &[u8]) -> bool {
+        match self.expected {
+            Expected::Output { len, crc32 } => {
+                let mut actual = Crc32::new();
+                actual.update(output);
+                output.len() as u64 == len && actual.value() == crc32
+            }
+            Expected::Error(_) => false,
+        }
+    }
+}
+
+/// Returns all conformance test vectors.
+pub fn test_vectors() -> Vec<TestVector> {
+    vec![
+        TestVector::output_crc32(
+            "deflate64.zip binary.wmv",
+            &DEFLATE64_ZIP[40..][..2669743],
+            2703788,
+            0x2c1e17a6,
+        ),
+        TestVector::output_crc32(
+            "issue-13 logo.png",
+            &ISSUE_13_ZIP[1182..][..34919],
+            42826,
+            0x6911c296,
+        ),
+        TestVector::error(
+            "issue-23 index out of bounds",
+            ISSUE_23_DATA,
+            InflateErrorKind::InvalidHuffmanCode,
+        ),
+        TestVector::error(
+            "issue-25 not enough space",
+            &ISSUE_25_ZIP[30..],
+            InflateErrorKind::DistanceTooFarBack,
+        ),
+        TestVector::error(
+            "issue-29 too long lookup",
+            &ISSUE_29_ZIP[121..],
+            InflateErrorKind::InvalidHuffmanCode,
+        ),
+        TestVector::output("empty stored block", &[0b001, 0, 0, 0xFF, 0xFF][..], b""),
+        TestVector::output(
+            "stored block",
+            &[0b001, 3, 0, !3, !0, b'a', b'b', b'c'][..],
+            b"abc",
+        ),
+        longest_match(65536),
+        TestVector::error(
+            "too long match",
+            longest_match(65537).input,
+            InflateErrorKind::InvalidLength,
+        ),
+        farthest_match(),
+        TestVector::error(
+            "invalid block type",
+            &[0b111][..],
+            InflateErrorKind::InvalidBlockType,
+        ),
+        TestVector::error(
+            "stored block length mismatch",
+            &[0b001, 5, 0, 5, 0][..],
+            InflateErrorKind::InvalidStoredBlockLength,
+        ),
+    ]
+}
+
+// A literal followed by a match at distance 1. The deflate64 maximum length is 65536.
+fn longest_match(length: u32) -> TestVector {
+    let mut writer = BitWriter::default();
+    writer.write_bits(1, 1); // BFINAL
+    writer.write_bits(1, 2); // BTYPE: static
+    writer.write_static_literal(b'a');
+    writer.write_code(0b1100_0101, 8); // length code 285
+    writer.write_bits(length - 3, 16);
+    writer.write_code(0, 5); // distance code 0: distance 1
+    writer.write_code(0, 7); // end of block
+    let output = vec![b'a'; length as usize + 1];
+    TestVector::output("longest match", writer.finish(), &output)
+}
+
+// A match at the deflate64 maximum distance 65536.
+fn farthest_match() -> TestVector {
+    let stored = (0..65535).map(|i| (i % 251) as u8).collect::<Vec<_>>();
+    let mut writer = BitWriter::default();
+    writer.write_bits(0, 1); // BFINAL
+    writer.write_bits(0, 2); // BTYPE: stored
+    writer.align();
+    writer.write_bits(0xFFFF, 16);
+    writer.write_bits(0, 16);
+    writer.bytes.extend_from_slice(&stored);
+    writer.write_bits(1, 1); // BFINAL
+    writer.write_bits(1, 2); // BTYPE: static
+    writer.write_static_literal(b'z');
+    writer.write_code(0b000_0001, 7); // length code 257: length 3
+    writer.write_code(0b11111, 5); // distance code 31
+    writer.write_bits(65536 - 49153, 14);
+    writer.write_code(0, 7); // end of block
+
+    let mut output = stored.clone();
+    output.push(b'z');
+    output.extend_from_slice(&stored[..3]);
+    TestVector::output("farthest match", writer.finish(), &output)
+}
+
+#[derive(Default)]
+struct BitWriter {
+    bytes: Vec<u8>,
+    bits: u32,
+    bit_count: u32,
+}
+
+impl BitWriter {
+    // Writes the value LSB first, like header fields and extra bits.
+    fn write_bits(&mut self, value: u32, count: u32) {
+        for i in 0..count {
+            self.bits |= ((value >> i) & 1) << self.bit_count;
+            self.bit_count += 1;
+            if self.bit_count == 8 {
+                self.bytes.push(self.bits as u8);
+                self.bits = 0;
+                self.bit_count = 0;
+            }
+        }
+    }
+
+    // Writes the Huffman code MSB first.
+    fn write_code(&mut self, code: u32, length: u32) {
+        for i in (0..length).rev() {
+            self.write_bits(code >> i, 1);
+        }
+    }
+
+    fn write_static_literal(&mut self, literal: u8) {
+        assert!(literal < 144);
+        self.write_code(0b0011_0000 + literal as u32, 8);
+    }
+
+    fn align(&mut self) {
+        if self.bit_count > 0 {
+            self.write_bits(0, 8 - self.bit_count);
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        self.align();
+        self.bytes
+    }
+}
@@ -0,0 +1,84 @@
+// CRC-32 (ISO-HDLC, the one used by zip and gzip) with the usual 256 entry lookup table.
+// CRC-32C (Castagnoli) only differs in the polynomial, and is used for checkpoints.
+
+const POLYNOMIAL: u32 = 0xEDB88320;
+#[cfg(feature = "checkpoint")]
+const CASTAGNOLI_POLYNOMIAL: u32 = 0x82F63B78;
+
+static TABLE: [u32; 256] = make_table(POLYNOMIAL);
+#[cfg(feature = "checkpoint")]
+static CASTAGNOLI_TABLE: [u32; 256] = make_table(CASTAGNOLI_POLYNOMIAL);
+
+const fn make_table(polynomial: u32) -> [u32; 256] {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut value = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            value = if value & 1 != 0 {
+                (value >> 1) ^ polynomial
+            } else {
+                value >> 1
+            };
+            bit += 1;
+        }
+        table[i] = value;
+        i += 1;
+    }
+    table
+}
+
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub(crate) struct Crc32 {
+    state: u32,
+}
+
+impl Crc32 {
+    pub fn new() -> Self {
+        Self { state: !0 }
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        self.state = update(&TABLE, self.state, data);
+    }
+
+    pub fn value(&self) -> u32 {
+        !self.state
+    }
+
+    // continues the CRC-32 whose value() was `value`
+    #[cfg(feature = "checkpoint")]
+    pub fn from_value(value: u32) -> Self {
+        Self { state: !value }
+    }
+}
+
+#[cfg(feature = "checkpoint")]
+#[derive(Debug, Copy, Clone)]
+pub(crate) struct Crc32c {
+    state: u32,
+}
+
+#[cfg(feature = "checkpoint")]
+impl Crc32c {
+    pub fn new() -> Self {
+        Self { state: !0 }
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        self.state = update(&CASTAGNOLI_TABLE, self.state, data);
+    }
+
+    pub fn value(&self) -> u32 {
+        !self.state
+    }
+}
+
+fn update(table: &[u32; 256], mut state: u32, data: &[u8]) -> u32 {
+    for &byte in data {
+        state = table[((state ^ byte as u32) & 0xFF) as usize] ^ (state >> 8);
+    }
+    state
+}
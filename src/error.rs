@@ -0,0 +1,262 @@
+use crate::BlockType;
+use std::{fmt, io};
+
+/// The kind of error found while decoding a deflate64 stream.
+///
+/// Every kind has a stable numeric code, see [`InflateErrorKind::code`].
+/// Codes are never renumbered or reused, so they are safe to store in logs
+/// or pass through FFI boundaries, while the order of the variants in this
+/// enum carries no meaning.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub enum InflateErrorKind {
+    /// The block type bits had the reserved value `11`.
+    InvalidBlockType,
+    /// LEN and NLEN of an uncompressed block are not one's complement of each other.
+    InvalidStoredBlockLength,
+    /// The input does not correspond to any code of the current Huffman tree.
+    InvalidHuffmanCode,
+    /// A dynamic block header uses code length repeat code 16 before any code length.
+    InvalidCodeLengthRepeat,
+    /// The literal/length tree of a dynamic block has no end-of-block code.
+    MissingEndOfBlockCode,
+    /// A literal/length or distance symbol which is not assigned any meaning.
+    InvalidSymbol,
+    /// A match length longer than deflate64 allows.
+    InvalidLength,
+    /// A match distance longer than deflate64 allows.
+    InvalidDistance,
+    /// A code length repeat code in a dynamic block header repeats past the
+    /// declared number of literal/length and distance code lengths.
+    CodeLengthRepeatOverflow,
+    /// The code lengths for the code length alphabet of a dynamic block header
+    /// do not describe a valid Huffman tree.
+    InvalidCodeLengthTree,
+    /// The literal/length code lengths of a dynamic block header do not describe
+    /// a valid Huffman tree.
+    InvalidLiteralLengthTree,
+    /// The distance code lengths of a dynamic block header do not describe
+    /// a valid Huffman tree.
+    InvalidDistanceTree,
+    /// The input ended before the end of the deflate64 stream.
+    IncompleteStream,
+    /// The length of the deflate64 stream differs from the expected compressed size.
+    CompressedSizeMismatch,
+    /// The decompressed size differs from the expected uncompressed size.
+    UncompressedSizeMismatch,
+    /// The CRC-32 of the decompressed data differs from the expected CRC-32.
+    Crc32Mismatch,
+    /// The decompressed data exceeds the maximum output size.
+    OutputLimitExceeded,
+    /// A match distance reaches back before the start of the decompressed data.
+    DistanceTooFarBack,
+    /// The bits skipped to align an uncompressed block to a byte boundary are not zero.
+    ///
+    /// This is only an error with [`InflaterOptions::strict`](crate::InflaterOptions::strict).
+    InvalidStoredBlockPadding,
+    /// The decompressed data exceeds the maximum expansion ratio of the input consumed.
+    ///
+    /// See [`InflaterOptions::max_expansion_ratio`](crate::InflaterOptions::max_expansion_ratio).
+    ExpansionRatioExceeded,
+    /// The inflater is in a state it can not decode from, such as one restored from a
+    /// corrupted checkpoint or deserialized from inconsistent data.
+    InvalidState,
+}
+
+impl InflateErrorKind {
+    /// Returns the stable numeric code of this kind.
+    ///
+    /// Code `0` is never assigned, so it can be used to represent "no error".
+    pub const fn code(self) -> u32 {
+        match self {
+            Self::InvalidBlockType => 1,
+            Self::InvalidStoredBlockLength => 2,
+            Self::InvalidHuffmanCode => 3,
+            Self::InvalidCodeLengthRepeat => 4,
+            Self::MissingEndOfBlockCode => 5,
+            Self::InvalidSymbol => 6,
+            Self::InvalidLength => 7,
+            Self::InvalidDistance => 8,
+            Self::CodeLengthRepeatOverflow => 9,
+            Self::InvalidCodeLengthTree => 10,
+            Self::InvalidLiteralLengthTree => 11,
+            Self::InvalidDistanceTree => 12,
+            Self::IncompleteStream => 13,
+            Self::CompressedSizeMismatch => 14,
+            Self::UncompressedSizeMismatch => 15,
+            Self::Crc32Mismatch => 16,
+            Self::OutputLimitExceeded => 17,
+            Self::DistanceTooFarBack => 18,
+            Self::InvalidStoredBlockPadding => 19,
+            Self::ExpansionRatioExceeded => 20,
+            Self::InvalidState => 21,
+        }
+    }
+
+    /// Returns the kind with the numeric code, or `None` if the code is not assigned.
+    pub const fn from_code(code: u32) -> Option<Self> {
+        Some(match code {
+            1 => Self::InvalidBlockType,
+            2 => Self::InvalidStoredBlockLength,
+            3 => Self::InvalidHuffmanCode,
+            4 => Self::InvalidCodeLengthRepeat,
+            5 => Self::MissingEndOfBlockCode,
+            6 => Self::InvalidSymbol,
+            7 => Self::InvalidLength,
+            8 => Self::InvalidDistance,
+            9 => Self::CodeLengthRepeatOverflow,
+            10 => Self::InvalidCodeLengthTree,
+            11 => Self::InvalidLiteralLengthTree,
+            12 => Self::InvalidDistanceTree,
+            13 => Self::IncompleteStream,
+            14 => Self::CompressedSizeMismatch,
+            15 => Self::UncompressedSizeMismatch,
+            16 => Self::Crc32Mismatch,
+            17 => Self::OutputLimitExceeded,
+            18 => Self::DistanceTooFarBack,
+            19 => Self::InvalidStoredBlockPadding,
+            20 => Self::ExpansionRatioExceeded,
+            21 => Self::InvalidState,
+            _ => return None,
+        })
+    }
+}
+
+impl fmt::Display for InflateErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::InvalidBlockType => "invalid block type",
+            Self::InvalidStoredBlockLength => "stored block length does not match its complement",
+            Self::InvalidHuffmanCode => "invalid Huffman code",
+            Self::InvalidCodeLengthRepeat => "code length repeat without previous code length",
+            Self::MissingEndOfBlockCode => "missing end-of-block code",
+            Self::InvalidSymbol => "invalid literal/length or distance symbol",
+            Self::InvalidLength => "invalid match length",
+            Self::InvalidDistance => "invalid match distance",
+            Self::CodeLengthRepeatOverflow => "code length repeat exceeds the number of codes",
+            Self::InvalidCodeLengthTree => "invalid code length tree",
+            Self::InvalidLiteralLengthTree => "invalid literal/length tree",
+            Self::InvalidDistanceTree => "invalid distance tree",
+            Self::IncompleteStream => "unexpected end of input",
+            Self::CompressedSizeMismatch => "compressed size mismatch",
+            Self::UncompressedSizeMismatch => "uncompressed size mismatch",
+            Self::Crc32Mismatch => "CRC-32 mismatch",
+            Self::OutputLimitExceeded => "output limit exceeded",
+            Self::DistanceTooFarBack => "match distance too far back",
+            Self::InvalidStoredBlockPadding => "non-zero stored block padding",
+            Self::ExpansionRatioExceeded => "expansion ratio exceeded",
+            Self::InvalidState => "invalid inflater state",
+        })
+    }
+}
+
+/// The error which stopped decoding of a deflate64 stream.
+///
+/// In addition to the [kind](InflateErrorKind) of the error, this records where in the
+/// stream the error was found. The input and output offsets are positions in the whole
+/// stream, also after the inflater was restored from a checkpoint. The block index is
+/// counted from the point the inflater started decoding, which is the start of the stream
+/// unless it was restored from a checkpoint.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct InflateError {
+    kind: InflateErrorKind,
+    block_index: u64,
+    block_type: Option<BlockType>,
+    input_bits: u64,
+    output_offset: u64,
+}
+
+impl InflateError {
+    pub(crate) fn new(
+        kind: InflateErrorKind,
+        block_index: u64,
+        block_type: Option<BlockType>,
+        input_bits: u64,
+        output_offset: u64,
+    ) -> Self {
+        Self {
+            kind,
+            block_index,
+            block_type,
+            input_bits,
+            output_offset,
+        }
+    }
+
+    /// Returns the kind of this error.
+    pub fn kind(&self) -> InflateErrorKind {
+        self.kind
+    }
+
+    /// Returns the stable numeric code of this error. Shorthand for `self.kind().code()`.
+    pub fn code(&self) -> u32 {
+        self.kind.code()
+    }
+
+    /// Returns the zero-based index of the block in which the error was found.
+    pub fn block_index(&self) -> u64 {
+        self.block_index
+    }
+
+    /// Returns the type of the block in which the error was found,
+    /// or `None` if the block type itself could not be read.
+    pub fn block_type(&self) -> Option<BlockType> {
+        self.block_type
+    }
+
+    /// Returns the offset of the input byte at which the error was found.
+    pub fn input_offset(&self) -> u64 {
+        self.input_bits / 8
+    }
+
+    /// Returns the offset of the input bit at which the error was found, which is just
+    /// after the bits read for the invalid field.
+    ///
+    /// Blocks and codes are not byte aligned, so this locates the error more exactly than
+    /// [`Self::input_offset`].
+    pub fn input_bit_offset(&self) -> u64 {
+        self.input_bits
+    }
+
+    /// Returns the count of bytes decompressed before the error was found.
+    pub fn output_offset(&self) -> u64 {
+        self.output_offset
+    }
+}
+
+impl fmt::Display for InflateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} in block {}", self.kind, self.block_index)?;
+        if let Some(block_type) = self.block_type {
+            let block_type = match block_type {
+                BlockType::Uncompressed => "uncompressed",
+                BlockType::Static => "static",
+                BlockType::Dynamic => "dynamic",
+            };
+            write!(f, " ({block_type})")?;
+        }
+        write!(
+            f,
+            " at input byte {}, output byte {}",
+            self.input_offset(),
+            self.output_offset
+        )
+    }
+}
+
+impl std::error::Error for InflateError {}
+
+impl From<InflateError> for io::Error {
+    /// Converts to [`io::ErrorKind::UnexpectedEof`] for incomplete streams, and to
+    /// [`io::ErrorKind::InvalidInput`] otherwise, like the readers and writers of this
+    /// crate do.
+    fn from(error: InflateError) -> Self {
+        let kind = match error.kind {
+            InflateErrorKind::IncompleteStream => io::ErrorKind::UnexpectedEof,
+            _ => io::ErrorKind::InvalidInput,
+        };
+        io::Error::new(kind, error)
+    }
+}
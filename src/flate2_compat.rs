@@ -0,0 +1,137 @@
+//! An API mirroring [`flate2::Decompress`].
+//!
+//! Code written against the low-level raw deflate API of flate2 can switch to deflate64,
+//! for example for zip entries with compression method 9, by replacing the imports of
+//! `Decompress`, `FlushDecompress`, and `Status` with the ones of this module.
+//! The zlib header is not supported, since deflate64 streams are always raw.
+//!
+//! [`flate2::Decompress`]: https://docs.rs/flate2/1/flate2/struct.Decompress.html
+
+use crate::{InflateError, InflaterManaged};
+use std::fmt;
+
+/// Decompresses a raw deflate64 stream, like `flate2::Decompress` does for deflate.
+pub struct Decompress {
+    inflater: Box<InflaterManaged>,
+    total_in: u64,
+    total_out: u64,
+}
+
+/// Flush values of [`Decompress::decompress`], same as `flate2::FlushDecompress`.
+///
+/// The inflater always returns as much output as possible, so these don't change the
+/// result and are only there for compatibility.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum FlushDecompress {
+    /// No flushing.
+    None,
+    /// Flush as much output as possible.
+    Sync,
+    /// All input was provided.
+    Finish,
+}
+
+/// The status of a successful [`Decompress::decompress`] call, same as `flate2::Status`.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Status {
+    /// Input was consumed or output was written, and the stream did not end yet.
+    Ok,
+    /// No progress was possible, since `input` is empty or `output` is full.
+    BufError,
+    /// The end of the stream was reached and all output was returned.
+    StreamEnd,
+}
+
+/// The error returned when the deflate64 stream is invalid.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct DecompressError(InflateError);
+
+impl DecompressError {
+    /// Returns the error which stopped decoding.
+    pub fn inflate_error(&self) -> &InflateError {
+        &self.0
+    }
+}
+
+impl fmt::Display for DecompressError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "deflate64 decompression error: {}", self.0)
+    }
+}
+
+impl std::error::Error for DecompressError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.0)
+    }
+}
+
+impl Decompress {
+    /// Creates Decompress for a new raw deflate64 stream
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> Self {
+        Self {
+            inflater: InflaterManaged::boxed(),
+            total_in: 0,
+            total_out: 0,
+        }
+    }
+
+    /// Returns the total count of input bytes consumed
+    pub fn total_in(&self) -> u64 {
+        self.total_in
+    }
+
+    /// Returns the total count of output bytes written
+    pub fn total_out(&self) -> u64 {
+        self.total_out
+    }
+
+    /// Decompresses from `input` to `output`
+    ///
+    /// Use [`Self::total_in`] and [`Self::total_out`] before and after the call to know how
+    /// much input was consumed and how much output was written.
+    pub fn decompress(
+        &mut self,
+        input: &[u8],
+        output: &mut [u8],
+        _flush: FlushDecompress,
+    ) -> Result<Status, DecompressError> {
+        let result = self.inflater.inflate(input, output);
+        self.total_in += result.bytes_consumed as u64;
+        self.total_out += result.bytes_written as u64;
+        if let Some(error) = self.inflater.error() {
+            return Err(DecompressError(*error));
+        }
+        Ok(if self.inflater.finished() {
+            Status::StreamEnd
+        } else if result.is_stalled() {
+            Status::BufError
+        } else {
+            Status::Ok
+        })
+    }
+
+    /// Same as [`Self::decompress`] but writes to the spare capacity of `output`, which
+    /// is not grown
+    pub fn decompress_vec(
+        &mut self,
+        input: &[u8],
+        output: &mut Vec<u8>,
+        flush: FlushDecompress,
+    ) -> Result<Status, DecompressError> {
+        let len = output.len();
+        output.resize(output.capacity(), 0);
+        let total_out = self.total_out;
+        let status = self.decompress(input, &mut output[len..], flush);
+        output.truncate(len + (self.total_out - total_out) as usize);
+        status
+    }
+
+    /// Resets this to decompress a new stream
+    pub fn reset(&mut self) {
+        self.inflater.reset();
+        self.total_in = 0;
+        self.total_out = 0;
+    }
+}
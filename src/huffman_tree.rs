@@ -1,5 +1,5 @@
 use crate::input_buffer::InputBuffer;
-use crate::InternalErr;
+use crate::{InflateErrorKind, InternalErr};
 
 // Packing: bits 0-8 = symbol (0-288), bits 9-13 = code length (1-16), bits 14+ = zero
 const SYMBOL_BITS: u8 = 9;
@@ -9,29 +9,64 @@ fn pack(symbol: i16, code_len: u8) -> i16 {
     symbol | ((code_len as i16) << SYMBOL_BITS)
 }
 
+// Subtable pointer packing: the negated value of the subtable offset shifted left by
+// SUBTABLE_BITS_WIDTH, or'ed with the number of bits indexing the subtable (1-7)
+const SUBTABLE_BITS_WIDTH: u8 = 3;
+
+fn pack_subtable(offset: usize, sub_bits: u8) -> i16 {
+    -(((offset as i16) << SUBTABLE_BITS_WIDTH) | sub_bits as i16)
+}
+
+fn unpack_subtable(entry: i16) -> (usize, u8) {
+    let value = -entry as usize;
+    (
+        value >> SUBTABLE_BITS_WIDTH,
+        (value & ((1 << SUBTABLE_BITS_WIDTH) - 1)) as u8,
+    )
+}
+
 pub(crate) fn unpack(entry: i16) -> (u16, i32) {
     ((entry & SYMBOL_MASK) as u16, (entry >> SYMBOL_BITS) as i32)
 }
 
-#[derive(Debug)]
+// Literal pair packing: bits 0-7 = first literal, bits 8-15 = second literal,
+// bits 16-20 = total code length (2-9), zero if the entry holds no pair
+fn pack_pair(first: u16, second: u16, code_len: i32) -> u32 {
+    first as u32 | (second as u32) << 8 | (code_len as u32) << 16
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub(crate) struct HuffmanTree {
     code_lengths_length: u16,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_array"))]
     table: [i16; 1 << Self::TABLE_BITS],
     // Table stores positive or negative numbers. Positive numbers are packed symbols
-    // and code lengths (see pack/unpack above). Negative values are indexes into a
-    // binary tree of array nodes; consume additional bits for left/right navagation
-    // until a positive packed value is reached. Note, the original implementation had
-    // separate "left" and "right" tables, we have interleaved these tables to enable
-    // branchless left/right navigation with simple math. Left and right nodes come in
-    // pairs, where N*2 is a left node and N*2+1 is a right node.
-    nodes: [i16; Self::MAX_CODE_LENGTHS * 4],
+    // and code lengths (see pack/unpack above). Negative values point to a subtable for
+    // the codes longer than TABLE_BITS which start with the entry's index (see
+    // pack_subtable/unpack_subtable above). The subtable is indexed by the bits after
+    // the first TABLE_BITS and holds packed symbols and code lengths, so that a long
+    // code is decoded with a second lookup.
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_array"))]
+    subtables: [i16; Self::SUBTABLES_SIZE],
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_array"))]
     code_length_array: [u8; Self::MAX_CODE_LENGTHS],
+    // For literal/length trees, the table index of two literals whose codes together fit
+    // in TABLE_BITS, packed by pack_pair, so that both are decoded with one lookup.
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_array"))]
+    literal_pairs: [u32; 1 << Self::TABLE_BITS],
 }
 
 impl HuffmanTree {
     pub(crate) const MAX_CODE_LENGTHS: usize = 288;
     pub(crate) const TABLE_BITS: u8 = 9;
     pub(crate) const TABLE_BITS_MASK: usize = (1 << Self::TABLE_BITS) - 1;
+    // Canonical codes are assigned in order of length, so each subtable only holds codes
+    // of one length (and fills its entries with them) except the ones where the length
+    // changes and the last one of an incomplete code. With codes of at most 15 bits,
+    // there are at most six of these, with up to 1 << 6 entries each. Over-subscribed
+    // codes which don't fit are rejected as invalid.
+    const SUBTABLES_SIZE: usize = Self::MAX_CODE_LENGTHS + 6 * (1 << 6);
 
     pub(crate) const MAX_LITERAL_TREE_ELEMENTS: usize = 288;
     pub(crate) const MAX_DIST_TREE_ELEMENTS: usize = 32;
@@ -42,8 +77,9 @@ impl HuffmanTree {
         HuffmanTree {
             code_lengths_length: Default::default(),
             table: [0i16; 1 << Self::TABLE_BITS],
-            nodes: [0i16; Self::MAX_CODE_LENGTHS * 4],
+            subtables: [0i16; Self::SUBTABLES_SIZE],
             code_length_array: [0u8; Self::MAX_CODE_LENGTHS],
+            literal_pairs: [0u32; 1 << Self::TABLE_BITS],
         }
     }
 
@@ -68,18 +104,16 @@ impl HuffmanTree {
         Self::assert_code_lengths_len(code_lengths.len());
         let code_lengths_length = code_lengths.len();
 
-        // I need to find proof that left and right array will always be
-        // enough. I think they are.
-
         let mut instance = Self {
             table: [0; 1 << Self::TABLE_BITS],
-            nodes: [0; Self::MAX_CODE_LENGTHS * 4],
+            subtables: [0; Self::SUBTABLES_SIZE],
             code_lengths_length: code_lengths_length as u16,
             code_length_array: {
                 let mut buffer = [0u8; Self::MAX_CODE_LENGTHS];
                 buffer[..code_lengths.len()].copy_from_slice(code_lengths);
                 buffer
             },
+            literal_pairs: [0; 1 << Self::TABLE_BITS],
         };
 
         instance.create_table()?;
@@ -90,10 +124,11 @@ impl HuffmanTree {
     pub fn new_in_place(&mut self, code_lengths: &[u8]) -> Result<(), InternalErr> {
         Self::assert_code_lengths_len(code_lengths.len());
         self.table.fill(0);
-        self.nodes.fill(0);
+        self.subtables.fill(0);
         self.code_lengths_length = code_lengths.len() as u16;
         self.code_length_array[..code_lengths.len()].copy_from_slice(code_lengths);
         self.code_length_array[code_lengths.len()..].fill(0);
+        self.literal_pairs.fill(0);
 
         self.create_table()
     }
@@ -150,7 +185,8 @@ impl HuffmanTree {
         let code_array = self.calculate_huffman_code();
         let code_lengths_len = self.code_lengths_length as usize;
 
-        let mut avail = 1; // skip 0 because -0 is still 0, can't distinguish by sign
+        // the number of bits indexing the subtable of each table entry, zero if none
+        let mut subtable_bits = [0u8; 1 << Self::TABLE_BITS];
 
         for (ch, &len) in self.code_length_array[..code_lengths_len]
             .iter()
@@ -183,7 +219,7 @@ impl HuffmanTree {
                     //
                     let increment = 1 << len;
                     if start >= increment {
-                        return Err(InternalErr::DataError); // InvalidHuffmanData
+                        return Err(InternalErr::DataError(InflateErrorKind::InvalidHuffmanCode));
                     }
 
                     // Note the bits in the table are reverted.
@@ -193,56 +229,84 @@ impl HuffmanTree {
                         start += increment;
                     }
                 } else {
-                    // For any code which has length longer than num_elements,
-                    // build a binary tree.
-
-                    let mut overflow_bits = len - Self::TABLE_BITS; // the nodes we need to represent the data.
-                    let mut code_bit_mask = 1 << Self::TABLE_BITS; // mask to get current bit (the bits can't fit in the table)
-
-                    // the left, right table is used to represent the
-                    // the rest bits. When we got the first part (number bits.) and look at
-                    // tbe table, we will need to follow the tree to find the real character.
-                    // This is in place to avoid bloating the table if there are
-                    // a few ones with long code.
-                    // As an optimization, we now store left/right together at N*2 and N*2+1.
-                    // We store (-left_index) as a pointer to newly allocated node pairs; the
-                    // get_symbol logic increments the negated left_index to get right_index.
-                    let mut index = start & Self::TABLE_BITS_MASK;
-                    let mut value: &mut i16 = &mut self.table[index];
-
-                    while {
-                        if *value == 0 {
-                            // set up next pointer if this node is not used before.
-                            // store -left_index directly (avail * 2)
-                            *value = -(avail * 2);
-                            avail += 1;
-                        }
-
-                        if *value > 0 {
-                            // prevent an IndexOutOfRangeException from array[index]
-                            return Err(InternalErr::DataError); // InvalidHuffmanData
-                        }
-
-                        // left child at -value, right child at -value+1
-                        let left_index = (-*value) as usize;
-                        index = left_index + ((start & code_bit_mask) != 0) as usize;
-
-                        value = self.nodes.get_mut(index).ok_or(InternalErr::DataError)?; // InvalidHuffmanData
-
-                        code_bit_mask <<= 1;
-                        overflow_bits -= 1;
-
-                        overflow_bits != 0
-                    } {}
-
-                    *value = pack(ch as i16, len);
+                    // For any code which has length longer than num_elements, the
+                    // table entry of its first bits points to a subtable, which needs
+                    // enough bits for the longest code starting with these bits.
+                    let index = start & Self::TABLE_BITS_MASK;
+                    subtable_bits[index] = subtable_bits[index].max(len - Self::TABLE_BITS);
+                }
+            }
+        }
+
+        // Allocate the subtables in the order of their table entries.
+        let mut avail = 0;
+        for (index, &sub_bits) in subtable_bits.iter().enumerate() {
+            if sub_bits == 0 {
+                continue;
+            }
+            let size = 1 << sub_bits;
+            if self.table[index] != 0 || avail + size > Self::SUBTABLES_SIZE {
+                // a shorter code has the same first bits, or the code is over-subscribed
+                return Err(InternalErr::DataError(InflateErrorKind::InvalidHuffmanCode));
+            }
+            self.table[index] = pack_subtable(avail, sub_bits);
+            avail += size;
+        }
+
+        // Fill the subtables the same way as the table: a code which is shorter than
+        // its subtable's bits is duplicated in all entries starting with its remaining
+        // bits.
+        for (ch, &len) in self.code_length_array[..code_lengths_len]
+            .iter()
+            .enumerate()
+        {
+            if len > Self::TABLE_BITS {
+                let code = code_array[ch] as usize;
+                let (offset, sub_bits) = unpack_subtable(self.table[code & Self::TABLE_BITS_MASK]);
+                let increment = 1 << (len - Self::TABLE_BITS);
+                let mut sub_index = code >> Self::TABLE_BITS;
+                while sub_index < 1 << sub_bits {
+                    let entry = &mut self.subtables[offset + sub_index];
+                    if *entry != 0 {
+                        return Err(InternalErr::DataError(InflateErrorKind::InvalidHuffmanCode));
+                    }
+                    *entry = pack(ch as i16, len);
+                    sub_index += increment;
                 }
             }
         }
 
+        if code_lengths_len == Self::MAX_LITERAL_TREE_ELEMENTS {
+            self.create_literal_pairs();
+        }
         Ok(())
     }
 
+    // Finds the table indexes which start with the codes of two literals. The second code
+    // only has the bits left after the first one, but codes which fit in them have the
+    // same entry for all values of the missing high bits.
+    fn create_literal_pairs(&mut self) {
+        for (index, pair) in self.literal_pairs.iter_mut().enumerate() {
+            let entry = self.table[index];
+            if entry <= 0 {
+                continue;
+            }
+            let (first, first_len) = unpack(entry);
+            let rest_len = Self::TABLE_BITS as i32 - first_len;
+            if first >= 256 || rest_len == 0 {
+                continue;
+            }
+            let next = self.table[index >> first_len];
+            if next <= 0 {
+                continue;
+            }
+            let (second, second_len) = unpack(next);
+            if second < 256 && second_len <= rest_len {
+                *pair = pack_pair(first, second, first_len + second_len);
+            }
+        }
+    }
+
     pub fn get_next_symbol(&self, input: &mut InputBuffer<'_>) -> Result<u16, InternalErr> {
         debug_assert_ne!(self.code_lengths_length, 0, "invalid table");
         // Try to load 16 bits into input buffer if possible and get the bit_buffer value.
@@ -256,19 +320,15 @@ impl HuffmanTree {
 
         // decode an element
         let mut entry = self.table[bit_buffer as usize & Self::TABLE_BITS_MASK];
-        let mut bits = bit_buffer >> Self::TABLE_BITS;
-        while entry < 0 {
-            // navigate the tree: left child at -entry, right at -entry+1
-            let child_index = ((-entry) as usize) + (bits & 1) as usize;
-            entry = self.nodes[child_index];
-            // shift bits down and mask for branchless left/right indexing
-            bits >>= 1;
+        if entry < 0 {
+            // look up the remaining bits of a long code in its subtable
+            entry = self.subtable_entry(entry, bit_buffer);
         }
 
         let (symbol, code_length) = unpack(entry);
 
         if code_length <= 0 || code_length > 16 {
-            return Err(InternalErr::DataError); // InvalidHuffmanData
+            return Err(InternalErr::DataError(InflateErrorKind::InvalidHuffmanCode));
         }
 
         // If this code is longer than the # bits we had in the bit buffer (i.e.
@@ -285,6 +345,15 @@ impl HuffmanTree {
         Ok(symbol)
     }
 
+    // returns the subtable entry for the bits after the first TABLE_BITS, given the
+    // table entry pointing to the subtable
+    #[inline(always)]
+    fn subtable_entry(&self, entry: i16, bit_buffer: u64) -> i16 {
+        let (offset, sub_bits) = unpack_subtable(entry);
+        let sub_index = (bit_buffer >> Self::TABLE_BITS) as usize & ((1 << sub_bits) - 1);
+        self.subtables[offset + sub_index]
+    }
+
     // get_next_symbol_assume_input is an optimization of get_next_symbol when the caller
     // knows that 16 bits exist in the bit buffer or are available as input bytes. It is
     // meant for use in an optimized decode loop that strictly verifies this precondition.
@@ -298,22 +367,63 @@ impl HuffmanTree {
         debug_assert_ne!(self.code_lengths_length, 0, "invalid table");
         let bit_buffer = input.load_16bits_assume_input();
         let mut entry = self.table[bit_buffer as usize & Self::TABLE_BITS_MASK];
-        let mut bits = bit_buffer >> Self::TABLE_BITS;
-        while entry < 0 {
-            let child_index = ((-entry) as usize) + (bits & 1) as usize;
-            entry = self.nodes[child_index];
-            bits >>= 1;
+        if entry < 0 {
+            entry = self.subtable_entry(entry, bit_buffer);
         }
         let (symbol, code_length) = unpack(entry);
         if code_length == 0 {
-            return Err(InternalErr::DataError);
+            return Err(InternalErr::DataError(InflateErrorKind::InvalidHuffmanCode));
         }
         input.skip_bits(code_length);
         Ok(symbol)
     }
 
+    // decodes two literals at once if the next bits start with the codes of two literals
+    // which fit in the table, see create_literal_pairs(). Otherwise, returns None without
+    // consuming any bits. Has the same precondition as get_next_symbol_assume_input.
+    #[inline(always)]
+    pub fn get_literal_pair_assume_input(&self, input: &mut InputBuffer<'_>) -> Option<[u8; 2]> {
+        let bit_buffer = input.load_16bits_assume_input();
+        let pair = self.literal_pairs[bit_buffer as usize & Self::TABLE_BITS_MASK];
+        if pair == 0 {
+            return None;
+        }
+        input.skip_bits((pair >> 16) as i32);
+        Some([pair as u8, (pair >> 8) as u8])
+    }
+
+    /// Returns the part of the code space used by the code lengths, in units of 2^-16.
+    /// A complete code uses exactly `1 << 16`, an incomplete code uses less and an
+    /// over-subscribed code uses more.
+    pub fn code_space_used(code_lengths: &[u8]) -> u32 {
+        code_lengths
+            .iter()
+            .filter(|&&len| len != 0)
+            .map(|&len| 1 << (16 - len as u32))
+            .sum()
+    }
+
     #[allow(dead_code)]
     pub fn code_lengths(&self) -> &[u8] {
         &self.code_length_array[..self.code_lengths_length as usize]
     }
+
+    // whether the tables are the ones built from the code lengths, which a deserialized
+    // tree needs before decoding looks up symbols and subtables in them
+    #[cfg(feature = "serde")]
+    pub(crate) fn is_valid(&self) -> bool {
+        let len = self.code_lengths_length as usize;
+        let rebuilt = match len {
+            0 => Ok(Self::invalid()),
+            Self::MAX_LITERAL_TREE_ELEMENTS
+            | Self::MAX_DIST_TREE_ELEMENTS
+            | Self::NUMBER_OF_CODE_LENGTH_TREE_ELEMENTS
+                if self.code_length_array[..len].iter().all(|&len| len <= 15) =>
+            {
+                Self::new(&self.code_length_array[..len])
+            }
+            _ => return false,
+        };
+        rebuilt.is_ok_and(|tree| tree == *self)
+    }
 }
@@ -0,0 +1,359 @@
+// Indexes are saved by Deflate64Index::save() in version 1 as follows:
+//
+//   Offset  Size  Field
+//   0       8     magic (b"D64INDEX")
+//   8       2     version (1)
+//   10      2     flags
+//   12      8     interval
+//   20      8     compressed_size
+//   28      8     uncompressed_size
+//   36      8     entry_count
+//   44      var   entries
+//   end-4   4     CRC-32 of all preceding bytes
+//
+// Each entry is:
+//
+//   0       8     output_offset
+//   8       8     input_offset
+//   16      4     checkpoint_len
+//   20      var   checkpoint
+//
+// Flags follow the same rules as checkpoint flags: unknown flags in the low byte are
+// rejected, and those in the high byte are ignored. See CHECKPOINT.md.
+
+use crate::crc32::Crc32;
+use crate::{
+    CheckpointInfo, CheckpointPolicy, CheckpointStreamPositions, Checkpointing, InflaterManaged,
+    MAX_CHECKPOINT_SIZE,
+};
+use std::io::{self, Read, Write};
+
+const BUFFER_SIZE: usize = 64 * 1024;
+const INDEX_MAGIC: &[u8; 8] = b"D64INDEX";
+const INDEX_VERSION_1: u16 = 1;
+const INDEX_REQUIRED_FLAGS: u16 = 0x00FF;
+
+/// A checkpoint recorded in a [`Deflate64Index`].
+#[cfg_attr(docsrs, doc(cfg(feature = "checkpoint")))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Deflate64IndexEntry {
+    /// Count of output bytes before the checkpoint.
+    pub output_offset: u64,
+    /// Count of input bytes to skip before resuming from the checkpoint.
+    pub input_offset: u64,
+    /// The serialized checkpoint, restored with
+    /// [`InflaterManaged::restore_from_checkpoint`].
+    pub checkpoint: Vec<u8>,
+}
+
+/// An index of checkpoints into a deflate64 stream, which allows decompressing from the
+/// middle of the stream.
+///
+/// The index is built by decompressing the whole stream once, and records a checkpoint
+/// every `interval` bytes of output. Each checkpoint takes up to
+/// [`MAX_CHECKPOINT_SIZE`](crate::MAX_CHECKPOINT_SIZE) bytes, so smaller intervals make
+/// random access faster at the cost of a larger index.
+///
+/// The index is `Send` and `Sync`, so one index in an [`Arc`](std::sync::Arc) can serve
+/// many threads, each decompressing with its own
+/// [`RandomAccessInflater`](crate::RandomAccessInflater) or an inflater from
+/// [`spawn_inflater`](Self::spawn_inflater).
+///
+/// ```
+/// # use deflate64::Deflate64Index;
+/// # let compressed: &[u8] = &[0x4b, 0x04, 0x02, 0x00];
+/// let index = Deflate64Index::build(compressed, 1024 * 1024)?;
+/// assert_eq!(index.uncompressed_size(), 4);
+/// // decompression can be resumed from the nearest checkpoint before an output offset
+/// assert!(index.entry_before(2).is_none());
+/// # Ok::<(), std::io::Error>(())
+/// ```
+#[cfg_attr(docsrs, doc(cfg(feature = "checkpoint")))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Deflate64Index {
+    interval: u64,
+    compressed_size: u64,
+    uncompressed_size: u64,
+    entries: Vec<Deflate64IndexEntry>,
+}
+
+impl Deflate64Index {
+    /// Builds the index by decompressing the deflate64 stream read from `reader`, recording
+    /// a checkpoint every `interval` bytes of output. The reader is read from its current
+    /// position, and may be read past the end of the stream.
+    ///
+    /// Returns an [`io::ErrorKind::InvalidInput`] error if the stream is invalid, and an
+    /// [`io::ErrorKind::UnexpectedEof`] error if it is truncated.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `interval` is zero.
+    pub fn build(mut reader: impl Read, interval: u64) -> io::Result<Self> {
+        assert!(interval > 0, "interval must not be zero");
+        let mut inflater = Box::new(InflaterManaged::with_checkpoints());
+        let mut policy = CheckpointPolicy::new(interval);
+        let mut input = vec![0u8; BUFFER_SIZE];
+        let mut output = vec![0u8; BUFFER_SIZE];
+        let mut entries = Vec::new();
+        let mut compressed_size = 0;
+        let (mut start, mut end) = (0, 0);
+        let mut eof = false;
+
+        while !inflater.finished() {
+            if start == end && !eof {
+                end = reader.read(&mut input)?;
+                start = 0;
+                eof = end == 0;
+            }
+            let result = inflater.inflate_with_policy(
+                &input[start..end],
+                &mut output,
+                &mut policy,
+                |checkpoint, positions| {
+                    entries.push(Deflate64IndexEntry {
+                        output_offset: positions.output_bytes_already_returned,
+                        input_offset: positions.input_bytes_to_skip,
+                        checkpoint: checkpoint.to_vec(),
+                    })
+                },
+            );
+            start += result.bytes_consumed;
+            compressed_size += result.bytes_consumed as u64;
+            if let Some(&error) = inflater.error() {
+                return Err(error.into());
+            }
+            if eof && result.bytes_written == 0 && !inflater.finished() {
+                return Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    "incomplete deflate64",
+                ));
+            }
+        }
+
+        Ok(Self {
+            interval,
+            compressed_size,
+            uncompressed_size: inflater.total_out(),
+            entries,
+        })
+    }
+
+    /// Returns the count of output bytes between checkpoints.
+    pub fn interval(&self) -> u64 {
+        self.interval
+    }
+
+    /// Returns the length of the deflate64 stream.
+    pub fn compressed_size(&self) -> u64 {
+        self.compressed_size
+    }
+
+    /// Returns the length of the decompressed data.
+    pub fn uncompressed_size(&self) -> u64 {
+        self.uncompressed_size
+    }
+
+    /// Returns the checkpoints, ordered by output offset.
+    pub fn entries(&self) -> &[Deflate64IndexEntry] {
+        &self.entries
+    }
+
+    /// Returns the checkpoint with the largest output offset not after `output_offset`, or
+    /// None if decompression has to start from the beginning of the stream.
+    pub fn entry_before(&self, output_offset: u64) -> Option<&Deflate64IndexEntry> {
+        let index = self
+            .entries
+            .partition_point(|entry| entry.output_offset <= output_offset);
+        self.entries.get(index.checked_sub(1)?)
+    }
+
+    /// Resets `inflater` and restores the checkpoint with the largest output offset not
+    /// after `output_offset` into it, and returns the positions to resume decompressing
+    /// from. Without such a checkpoint, the inflater is only reset and the positions are
+    /// the start of the stream. Output between the returned position and `output_offset`
+    /// has to be decompressed and discarded.
+    ///
+    /// Returns an [`io::ErrorKind::InvalidData`] error if the checkpoint can't be restored.
+    pub fn restore_before(
+        &self,
+        inflater: &mut InflaterManaged<Checkpointing>,
+        output_offset: u64,
+    ) -> io::Result<CheckpointStreamPositions> {
+        inflater.reset();
+        let Some(entry) = self.entry_before(output_offset) else {
+            return Ok(CheckpointStreamPositions {
+                input_bytes_to_skip: 0,
+                output_bytes_already_returned: 0,
+            });
+        };
+        match inflater.restore_from_checkpoint(&entry.checkpoint) {
+            Some(positions)
+                if positions.output_bytes_already_returned == entry.output_offset
+                    && positions.input_bytes_to_skip == entry.input_offset =>
+            {
+                Ok(positions)
+            }
+            _ => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "invalid deflate64 index",
+            )),
+        }
+    }
+
+    /// Same as [`restore_before`](Self::restore_before) but restores into a new inflater,
+    /// which is independent of the index and can be moved to another thread.
+    pub fn spawn_inflater(
+        &self,
+        output_offset: u64,
+    ) -> io::Result<(
+        Box<InflaterManaged<Checkpointing>>,
+        CheckpointStreamPositions,
+    )> {
+        let mut inflater = Box::new(InflaterManaged::with_checkpoints());
+        let positions = self.restore_before(&mut inflater, output_offset)?;
+        Ok((inflater, positions))
+    }
+
+    /// Saves the index to `writer` in the format documented in CHECKPOINT.md, so it can be
+    /// built once and shipped alongside the archive. The index is written in many small
+    /// parts, so `writer` should be buffered.
+    pub fn save(&self, writer: impl Write) -> io::Result<()> {
+        let mut writer = Checksummed::new(writer);
+        writer.write_all(INDEX_MAGIC)?;
+        writer.write_all(&INDEX_VERSION_1.to_le_bytes())?;
+        writer.write_all(&0u16.to_le_bytes())?; // flags
+        writer.write_all(&self.interval.to_le_bytes())?;
+        writer.write_all(&self.compressed_size.to_le_bytes())?;
+        writer.write_all(&self.uncompressed_size.to_le_bytes())?;
+        writer.write_all(&(self.entries.len() as u64).to_le_bytes())?;
+        for entry in &self.entries {
+            writer.write_all(&entry.output_offset.to_le_bytes())?;
+            writer.write_all(&entry.input_offset.to_le_bytes())?;
+            writer.write_all(&(entry.checkpoint.len() as u32).to_le_bytes())?;
+            writer.write_all(&entry.checkpoint)?;
+        }
+        let crc = writer.crc.value();
+        writer.inner.write_all(&crc.to_le_bytes())
+    }
+
+    /// Loads an index saved by [`Self::save`] from `reader`, which is read up to the end of
+    /// the index. Indexes saved by all previous versions of this crate are loaded.
+    ///
+    /// Returns an [`io::ErrorKind::InvalidData`] error if the index is corrupt or was saved
+    /// by an incompatible version, and an [`io::ErrorKind::UnexpectedEof`] error if it is
+    /// truncated. The reader should be buffered.
+    pub fn load(reader: impl Read) -> io::Result<Self> {
+        fn invalid() -> io::Error {
+            io::Error::new(io::ErrorKind::InvalidData, "invalid deflate64 index")
+        }
+        fn read_u64(reader: &mut impl Read) -> io::Result<u64> {
+            let mut bytes = [0; 8];
+            reader.read_exact(&mut bytes)?;
+            Ok(u64::from_le_bytes(bytes))
+        }
+        let mut reader = Checksummed::new(reader);
+
+        let mut header = [0; 12];
+        reader.read_exact(&mut header)?;
+        let version = u16::from_le_bytes([header[8], header[9]]);
+        let flags = u16::from_le_bytes([header[10], header[11]]);
+        if &header[..8] != INDEX_MAGIC
+            || version != INDEX_VERSION_1
+            || flags & INDEX_REQUIRED_FLAGS != 0
+        {
+            return Err(invalid());
+        }
+        let interval = read_u64(&mut reader)?;
+        let compressed_size = read_u64(&mut reader)?;
+        let uncompressed_size = read_u64(&mut reader)?;
+        let entry_count = read_u64(&mut reader)?;
+        if interval == 0 {
+            return Err(invalid());
+        }
+
+        // the entry count is not trusted for allocating
+        let mut entries = Vec::new();
+        let mut last_output_offset = 0;
+        for _ in 0..entry_count {
+            let output_offset = read_u64(&mut reader)?;
+            let input_offset = read_u64(&mut reader)?;
+            let mut len = [0; 4];
+            reader.read_exact(&mut len)?;
+            let len = u32::from_le_bytes(len) as usize;
+            if len > MAX_CHECKPOINT_SIZE
+                || output_offset <= last_output_offset
+                || output_offset > uncompressed_size
+                || input_offset > compressed_size
+            {
+                return Err(invalid());
+            }
+            let mut checkpoint = vec![0; len];
+            reader.read_exact(&mut checkpoint)?;
+
+            // the checkpoint must be resumable at the offsets of the entry
+            let info = CheckpointInfo::parse(&checkpoint).ok_or_else(invalid)?;
+            let positions = info.positions();
+            if info.delta
+                || positions.output_bytes_already_returned != output_offset
+                || positions.input_bytes_to_skip != input_offset
+            {
+                return Err(invalid());
+            }
+            last_output_offset = output_offset;
+            entries.push(Deflate64IndexEntry {
+                output_offset,
+                input_offset,
+                checkpoint,
+            });
+        }
+
+        let crc = reader.crc.value();
+        let mut stored_crc = [0; 4];
+        reader.inner.read_exact(&mut stored_crc)?;
+        if crc != u32::from_le_bytes(stored_crc) {
+            return Err(invalid());
+        }
+        Ok(Self {
+            interval,
+            compressed_size,
+            uncompressed_size,
+            entries,
+        })
+    }
+}
+
+/// A reader or writer which computes the CRC-32 of the data passing through it.
+struct Checksummed<T> {
+    inner: T,
+    crc: Crc32,
+}
+
+impl<T> Checksummed<T> {
+    fn new(inner: T) -> Self {
+        Self {
+            inner,
+            crc: Crc32::new(),
+        }
+    }
+}
+
+impl<R: Read> Read for Checksummed<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        self.crc.update(&buf[..read]);
+        Ok(read)
+    }
+}
+
+impl<W: Write> Write for Checksummed<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.crc.update(&buf[..written]);
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
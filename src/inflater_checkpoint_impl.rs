@@ -5,36 +5,715 @@
 // after every output window write when in DecodeTop or DecodingUncompressed
 // states, or when in between deflate blocks. See CHECKPOINT.md for details.
 //
-// Checkpoints are serialized by the checkpoint() function as follows:
+// Checkpoints are serialized by the checkpoint() function in version 2 as follows:
 //
 //   Offset  Size  Field
-//   0       2     version (0x1001)
-//   2       8     input_bits
-//   10      1     buffered_value (masked on restore by from_bits)
-//   11      1     bfinal_block_type
-//   12      2     uncompressed_remaining
-//   14      288   lit_code_lengths
-//   302     32    dist_code_lengths
-//   334     8     output_bytes_written
-//   342     4     output_bytes_unread
-//   346     var   window_data
-//   end-4   4     fletcher32 checksum
-
-const CHECKPOINT_HEADER_SIZE: usize = 346;
-
-fn fletcher32_checksum(data: &[u8]) -> u32 {
-    let (mut a, mut b) = (0u32, 0u32);
-    for &byte in data {
-        a = a.wrapping_add(byte as u32);
-        b = b.wrapping_add(a);
-    }
-    (b << 16) | (a & 0xFFFF)
+//   0       2     version (0x1002)
+//   2       2     flags
+//   4       2     header_len
+//   6       8     input_bits
+//   14      1     buffered_value (masked on restore by from_bits)
+//   15      1     bfinal_block_type
+//   16      2     uncompressed_remaining
+//   18      288   lit_code_lengths
+//   306     32    dist_code_lengths
+//   338     8     output_bytes_written
+//   346     4     output_bytes_unread
+//   350     var   extension fields, up to header_len
+//   hdr_len var   window_data
+//   end-4   4     checksum
+//
+// The checksum is Fletcher-32, or CRC-32 with the CRC32 flag, or CRC-32C with the CRC32C
+// flag. With the RLE_WINDOW flag, window_data is run-length encoded by rle_encode(). Its
+// decoded length is the same as without the flag.
+//
+// With the DELTA flag, the checkpoint is a delta against a base checkpoint, and has these
+// extension fields:
+//
+//   350     4     base_checksum, the stored checksum of the base checkpoint
+//   354     4     copy_source, the offset of the copied bytes in the base window data
+//   358     4     copy_dest, the offset of the copied bytes in the window data
+//   362     4     copy_len
+//
+// The window data is then the window with the copy_len bytes at copy_dest left out.
+//
+// With the OUTPUT_CRC32 flag, the CRC-32 of the output returned before the checkpoint
+// follows the other extension fields, at offset 350 or 366 for deltas:
+//
+//   350     4     output_crc32
+//
+// With the METADATA flag, the payload set by set_checkpoint_metadata() follows them:
+//
+//   350     1     metadata_len
+//   351     var   metadata
+//
+// Version 1 (0x1001) lacks the flags and header_len fields, so its fields start at
+// offset 2 and the window data at offset 346. Restoring always supports all previous
+// versions. Flags in the low byte change the meaning of the data, and checkpoints with
+// unknown ones are rejected; flags in the high byte may be ignored. Fields added to a
+// later version 2 go before header_len, so older readers skip them. See CHECKPOINT.md.
+
+const CHECKPOINT_VERSION_1: u16 = 0x1001;
+const CHECKPOINT_VERSION_2: u16 = 0x1002;
+const CHECKPOINT_V1_HEADER_SIZE: usize = 346;
+const CHECKPOINT_HEADER_SIZE: usize = 350;
+const CHECKPOINT_DELTA_HEADER_SIZE: usize = 366;
+const CHECKPOINT_MAX_HEADER_SIZE: usize =
+    CHECKPOINT_DELTA_HEADER_SIZE + 4 + 1 + MAX_CHECKPOINT_METADATA_LEN;
+const CHECKPOINT_REQUIRED_FLAGS: u16 = 0x00FF;
+const CHECKPOINT_FLAG_RLE_WINDOW: u16 = 0x0001;
+const CHECKPOINT_FLAG_CRC32: u16 = 0x0002;
+const CHECKPOINT_FLAG_CRC32C: u16 = 0x0004;
+const CHECKPOINT_FLAG_DELTA: u16 = 0x0008;
+const CHECKPOINT_FLAG_OUTPUT_CRC32: u16 = 0x0100;
+const CHECKPOINT_FLAG_METADATA: u16 = 0x0200;
+const CHECKPOINT_KNOWN_FLAGS: u16 = CHECKPOINT_FLAG_RLE_WINDOW
+    | CHECKPOINT_FLAG_CRC32
+    | CHECKPOINT_FLAG_CRC32C
+    | CHECKPOINT_FLAG_DELTA;
+
+/// The maximum size of a serialized checkpoint, see [`InflaterManaged::checkpoint_into`].
+pub const MAX_CHECKPOINT_SIZE: usize = CHECKPOINT_HEADER_SIZE
+    + 4
+    + 1
+    + MAX_CHECKPOINT_METADATA_LEN
+    + crate::output_window::WINDOW_SIZE
+    + 4;
+
+/// The maximum length of the metadata payload of a checkpoint, see
+/// [`InflaterManaged::set_checkpoint_metadata`].
+pub const MAX_CHECKPOINT_METADATA_LEN: usize = 255;
+
+/// The fields of a serialized checkpoint of any version, after verifying its checksum.
+struct CheckpointFields<'a> {
+    version: u16,
+    flags: u16,
+    checksum: CheckpointChecksum,
+    stored_checksum: u32,
+    delta: Option<CheckpointDelta>,
+    output_crc32: Option<u32>,
+    metadata: &'a [u8],
+    input_bits: u64,
+    buffered_value: u8,
+    bfinal_block_type: u8,
+    uncompressed_remaining: u16,
+    lit_codes: &'a [u8],
+    dist_codes: &'a [u8],
+    output_bytes_written: u64,
+    output_bytes_unread: u32,
+    window_data: &'a [u8],
+}
+
+/// The inflater state of a checkpoint, as validated before its window is restored.
+struct RestoredState {
+    bits: BitsBuffer,
+    bfinal: bool,
+    block_type: BlockType,
+    lit_tree: HuffmanTree,
+    dist_tree: HuffmanTree,
+}
+
+/// The extension fields of a delta checkpoint.
+struct CheckpointDelta {
+    base_checksum: u32,
+    copy_source: usize,
+    copy_dest: usize,
+    copy_len: usize,
+}
+
+impl<'a> CheckpointFields<'a> {
+    fn parse(checkpoint_data: &'a [u8]) -> Option<Self> {
+        if checkpoint_data.len() < CHECKPOINT_V1_HEADER_SIZE + 4 {
+            return None;
+        }
+        let (data, checksum_bytes) = checkpoint_data.split_at(checkpoint_data.len() - 4);
+        let (mut fields, header_len) = Self::parse_header(data)?;
+        fields.stored_checksum = u32::from_le_bytes(checksum_bytes.try_into().ok()?);
+        if fields.checksum.compute(data) != fields.stored_checksum {
+            return None;
+        }
+        fields.window_data = data.get(header_len..)?;
+        Some(fields)
+    }
+
+    // parses the header fields at the start of `data` without verifying the checksum, and
+    // returns them with an empty window and the header length
+    fn parse_header(data: &'a [u8]) -> Option<(Self, usize)> {
+        let mut cursor = data;
+        let mut read = |n: usize| -> Option<&'a [u8]> {
+            if cursor.len() < n {
+                return None;
+            }
+            let (head, tail) = cursor.split_at(n);
+            cursor = tail;
+            Some(head)
+        };
+
+        let version = u16::from_le_bytes(read(2)?.try_into().ok()?);
+        let (flags, header_len) = match version {
+            CHECKPOINT_VERSION_1 => (0, CHECKPOINT_V1_HEADER_SIZE),
+            CHECKPOINT_VERSION_2 => {
+                let flags = u16::from_le_bytes(read(2)?.try_into().ok()?);
+                let header_len = u16::from_le_bytes(read(2)?.try_into().ok()?) as usize;
+                if flags & CHECKPOINT_REQUIRED_FLAGS & !CHECKPOINT_KNOWN_FLAGS != 0
+                    || header_len < CHECKPOINT_HEADER_SIZE
+                {
+                    return None;
+                }
+                (flags, header_len)
+            }
+            _ => return None, // unsupported version
+        };
+        let mut fields = Self {
+            version,
+            flags,
+            checksum: CheckpointChecksum::from_flags(flags)?,
+            stored_checksum: 0,
+            delta: None,
+            output_crc32: None,
+            metadata: &[],
+            input_bits: u64::from_le_bytes(read(8)?.try_into().ok()?),
+            buffered_value: read(1)?[0],
+            bfinal_block_type: read(1)?[0],
+            uncompressed_remaining: u16::from_le_bytes(read(2)?.try_into().ok()?),
+            lit_codes: read(HuffmanTree::MAX_LITERAL_TREE_ELEMENTS)?,
+            dist_codes: read(HuffmanTree::MAX_DIST_TREE_ELEMENTS)?,
+            output_bytes_written: u64::from_le_bytes(read(8)?.try_into().ok()?),
+            output_bytes_unread: u32::from_le_bytes(read(4)?.try_into().ok()?),
+            window_data: &[],
+        };
+
+        if flags & CHECKPOINT_FLAG_DELTA != 0 {
+            if flags & CHECKPOINT_FLAG_RLE_WINDOW != 0 || header_len < CHECKPOINT_DELTA_HEADER_SIZE
+            {
+                return None;
+            }
+            let mut read_u32 = || Some(u32::from_le_bytes(read(4)?.try_into().ok()?));
+            fields.delta = Some(CheckpointDelta {
+                base_checksum: read_u32()?,
+                copy_source: read_u32()? as usize,
+                copy_dest: read_u32()? as usize,
+                copy_len: read_u32()? as usize,
+            });
+        }
+        let mut offset = CHECKPOINT_HEADER_SIZE + if fields.delta.is_some() { 16 } else { 0 };
+        if flags & CHECKPOINT_FLAG_OUTPUT_CRC32 != 0 {
+            offset += 4;
+            if header_len < offset {
+                return None;
+            }
+            fields.output_crc32 = Some(u32::from_le_bytes(read(4)?.try_into().ok()?));
+        }
+        if flags & CHECKPOINT_FLAG_METADATA != 0 {
+            let metadata_len = read(1)?[0] as usize;
+            offset += 1 + metadata_len;
+            if header_len < offset {
+                return None;
+            }
+            fields.metadata = read(metadata_len)?;
+        }
+
+        // Unread output must have been written
+        if fields.output_bytes_unread as u64 > fields.output_bytes_written {
+            return None;
+        }
+        Some((fields, header_len))
+    }
+
+    // the length of the window data after decoding, or None if it is invalid
+    fn window_len(&self) -> Option<usize> {
+        match &self.delta {
+            Some(delta) if delta.copy_dest > self.window_data.len() => None,
+            Some(delta) => Some(self.window_data.len() + delta.copy_len),
+            None if self.flags & CHECKPOINT_FLAG_RLE_WINDOW != 0 => {
+                rle_decode(self.window_data, None)
+            }
+            None => Some(self.window_data.len()),
+        }
+    }
+
+    // decodes the window data of a checkpoint which is not a delta into `window`, which
+    // has the length returned by window_len()
+    fn fill_window(&self, window: &mut [u8]) {
+        debug_assert!(self.delta.is_none());
+        if self.flags & CHECKPOINT_FLAG_RLE_WINDOW != 0 {
+            // can't fail, the decoded length was checked by window_len()
+            rle_decode(self.window_data, Some(window));
+        } else {
+            window.copy_from_slice(self.window_data);
+        }
+    }
+
+    // the length of the window implied by the output offsets of the checkpoint
+    fn expected_window_len(&self) -> usize {
+        (self.output_bytes_written.min(TABLE_LOOKUP_DISTANCE_MAX as u64) as u32)
+            .max(self.output_bytes_unread) as usize
+    }
+
+    // checks that the header fields with a decoded window of `window_len` bytes are
+    // consistent, and returns the inflater state to restore
+    fn validate(&self, window_len: usize) -> Option<RestoredState> {
+        let &CheckpointFields {
+            input_bits,
+            buffered_value,
+            bfinal_block_type,
+            uncompressed_remaining: remaining_uncompressed,
+            lit_codes,
+            dist_codes,
+            ..
+        } = self;
+
+        // from_bits masks off invalid high bits
+        let num_buffered_bits = (8 - (input_bits & 7)) as i32 & 7;
+        let bits = BitsBuffer::from_bits(buffered_value as u32, num_buffered_bits);
+
+        // Check window data length validity
+        if window_len != self.expected_window_len()
+            || window_len > crate::output_window::WINDOW_SIZE
+        {
+            return None;
+        }
+
+        let bfinal = (bfinal_block_type & 128) != 0;
+        let block_type_val = bfinal_block_type % 128;
+        let block_type = BlockType::from_int(block_type_val.into())?;
+
+        let mut lit_tree = HuffmanTree::invalid();
+        let mut dist_tree = HuffmanTree::invalid();
+        if block_type == BlockType::Dynamic {
+            if lit_codes.iter().any(|x| *x > 16) || dist_codes.iter().any(|x| *x > 16) {
+                return None;
+            }
+            lit_tree.new_in_place(lit_codes).ok()?;
+            dist_tree.new_in_place(dist_codes).ok()?;
+        } else if block_type == BlockType::Uncompressed && remaining_uncompressed > 0 {
+            // Uncompressed blocks with remaining bytes must be byte-aligned
+            if bits.bits_in_buffer != 0 {
+                return None;
+            }
+        }
+
+        Some(RestoredState {
+            bits,
+            bfinal,
+            block_type,
+            lit_tree,
+            dist_tree,
+        })
+    }
+
+    fn positions(&self) -> CheckpointStreamPositions {
+        CheckpointStreamPositions {
+            // round up; partial input byte is already stored in checkpoint
+            input_bytes_to_skip: self.input_bits.div_ceil(8),
+            output_bytes_already_returned: self.output_bytes_written
+                - self.output_bytes_unread as u64,
+        }
+    }
+}
+
+// Run-length encodes `data` like PackBits: a control byte n < 128 is followed by n + 1
+// literal bytes, and a control byte n >= 128 is followed by one byte repeated n - 126
+// times. Runs of 2 bytes are only encoded as runs at the start of a literal sequence.
+fn rle_encode(data: &[u8], mut emit: impl FnMut(&[u8])) {
+    let mut i = 0;
+    while i < data.len() {
+        let run = data[i..]
+            .iter()
+            .take(129)
+            .take_while(|&&byte| byte == data[i])
+            .count();
+        if run >= 2 {
+            emit(&[(run + 126) as u8, data[i]]);
+            i += run;
+            continue;
+        }
+        let start = i;
+        while i < data.len() && i - start < 128 {
+            if data[i..].len() >= 3 && data[i] == data[i + 1] && data[i] == data[i + 2] {
+                break;
+            }
+            i += 1;
+        }
+        emit(&[(i - start - 1) as u8]);
+        emit(&data[start..i]);
+    }
+}
+
+fn rle_encoded_len(data: &[u8]) -> usize {
+    let mut len = 0;
+    rle_encode(data, |chunk| len += chunk.len());
+    len
+}
+
+// Decodes rle_encode() output into `out`, or only returns its decoded length if `out` is
+// None. Returns None if the data is truncated or doesn't fit `out`.
+fn rle_decode(mut data: &[u8], mut out: Option<&mut [u8]>) -> Option<usize> {
+    let mut len = 0;
+    while let Some((&control, rest)) = data.split_first() {
+        let (count, rest) = if control < 128 {
+            let count = control as usize + 1;
+            let (literals, rest) = rest.split_at_checked(count)?;
+            if let Some(out) = out.as_deref_mut() {
+                out.get_mut(len..len + count)?.copy_from_slice(literals);
+            }
+            (count, rest)
+        } else {
+            let count = control as usize - 126;
+            let (&byte, rest) = rest.split_first()?;
+            if let Some(out) = out.as_deref_mut() {
+                out.get_mut(len..len + count)?.fill(byte);
+            }
+            (count, rest)
+        };
+        len += count;
+        data = rest;
+    }
+    Some(len)
+}
+
+impl CheckpointChecksum {
+    fn flags(self) -> u16 {
+        match self {
+            Self::Fletcher32 => 0,
+            Self::Crc32 => CHECKPOINT_FLAG_CRC32,
+            Self::Crc32c => CHECKPOINT_FLAG_CRC32C,
+        }
+    }
+
+    fn from_flags(flags: u16) -> Option<Self> {
+        match flags & (CHECKPOINT_FLAG_CRC32 | CHECKPOINT_FLAG_CRC32C) {
+            0 => Some(Self::Fletcher32),
+            CHECKPOINT_FLAG_CRC32 => Some(Self::Crc32),
+            CHECKPOINT_FLAG_CRC32C => Some(Self::Crc32c),
+            _ => None,
+        }
+    }
+
+    fn start(self) -> ChecksumState {
+        match self {
+            Self::Fletcher32 => ChecksumState::Fletcher32(0, 0),
+            Self::Crc32 => ChecksumState::Crc32(Crc32::new()),
+            Self::Crc32c => ChecksumState::Crc32c(crate::crc32::Crc32c::new()),
+        }
+    }
+
+    fn compute(self, data: &[u8]) -> u32 {
+        let mut state = self.start();
+        state.update(data);
+        state.value()
+    }
+}
+
+/// A checkpoint checksum being computed over data passed in parts.
+enum ChecksumState {
+    Fletcher32(u32, u32),
+    Crc32(Crc32),
+    Crc32c(crate::crc32::Crc32c),
+}
+
+impl ChecksumState {
+    fn update(&mut self, data: &[u8]) {
+        match self {
+            Self::Fletcher32(a, b) => {
+                for &byte in data {
+                    *a = a.wrapping_add(byte as u32);
+                    *b = b.wrapping_add(*a);
+                }
+            }
+            Self::Crc32(crc) => crc.update(data),
+            Self::Crc32c(crc) => crc.update(data),
+        }
+    }
+
+    fn value(&self) -> u32 {
+        match *self {
+            Self::Fletcher32(a, b) => (b << 16) | (a & 0xFFFF),
+            Self::Crc32(crc) => crc.value(),
+            Self::Crc32c(crc) => crc.value(),
+        }
+    }
+}
+
+// Reads rle_encode() output from `reader` until `out` is full, and adds the encoded bytes
+// to `checksum`. Returns false if a run doesn't fit `out`.
+fn rle_decode_from(
+    reader: &mut impl io::Read,
+    out: &mut [u8],
+    checksum: &mut ChecksumState,
+) -> io::Result<bool> {
+    let mut len = 0;
+    while len < out.len() {
+        let mut control = [0];
+        reader.read_exact(&mut control)?;
+        checksum.update(&control);
+        if control[0] < 128 {
+            let count = control[0] as usize + 1;
+            let Some(literals) = out.get_mut(len..len + count) else {
+                return Ok(false);
+            };
+            reader.read_exact(literals)?;
+            checksum.update(literals);
+            len += count;
+        } else {
+            let count = control[0] as usize - 126;
+            let mut byte = [0];
+            reader.read_exact(&mut byte)?;
+            checksum.update(&byte);
+            let Some(run) = out.get_mut(len..len + count) else {
+                return Ok(false);
+            };
+            run.fill(byte[0]);
+            len += count;
+        }
+    }
+    Ok(true)
+}
+
+/// Buffers the small writes of a checkpoint being serialized to an io::Write on the
+/// stack, and keeps the first error.
+struct CheckpointWriter<W> {
+    writer: W,
+    buffer: [u8; 4096],
+    len: usize,
+    result: io::Result<()>,
+}
+
+impl<W: Write> CheckpointWriter<W> {
+    fn new(writer: W) -> Self {
+        Self {
+            writer,
+            buffer: [0; 4096],
+            len: 0,
+            result: Ok(()),
+        }
+    }
+
+    fn write(&mut self, data: &[u8]) {
+        if self.result.is_err() {
+            return;
+        }
+        if self.len + data.len() > self.buffer.len() {
+            self.flush_buffer();
+        }
+        if data.len() >= self.buffer.len() {
+            if self.result.is_ok() {
+                self.result = self.writer.write_all(data);
+            }
+        } else {
+            self.buffer[self.len..self.len + data.len()].copy_from_slice(data);
+            self.len += data.len();
+        }
+    }
+
+    fn flush_buffer(&mut self) {
+        if self.result.is_ok() {
+            self.result = self.writer.write_all(&self.buffer[..self.len]);
+        }
+        self.len = 0;
+    }
+
+    fn finish(mut self) -> io::Result<()> {
+        self.flush_buffer();
+        self.result
+    }
+}
+
+impl CheckpointInfo {
+    /// Parses the header of a serialized checkpoint without restoring it. Returns None if
+    /// the data is corrupt or was serialized by an incompatible library version. A
+    /// checkpoint which parses may still be rejected by restore_from_checkpoint(), for
+    /// example if its Huffman code lengths are invalid; validate_checkpoint() checks those.
+    pub fn parse(checkpoint_data: &[u8]) -> Option<Self> {
+        let fields = CheckpointFields::parse(checkpoint_data)?;
+        Some(Self {
+            version: fields.version,
+            flags: fields.flags,
+            input_bits: fields.input_bits,
+            output_bytes_written: fields.output_bytes_written,
+            output_bytes_unread: fields.output_bytes_unread,
+            block_type: BlockType::from_int((fields.bfinal_block_type & 0x7F) as u16)?,
+            final_block: fields.bfinal_block_type & 0x80 != 0,
+            checksum: fields.checksum,
+            delta: fields.delta.is_some(),
+            output_crc32: fields.output_crc32,
+        })
+    }
+
+    /// Returns the input and output byte offsets restore_from_checkpoint() would return
+    /// for this checkpoint.
+    pub fn positions(&self) -> CheckpointStreamPositions {
+        CheckpointStreamPositions {
+            input_bytes_to_skip: self.input_bits.div_ceil(8),
+            output_bytes_already_returned: self.output_bytes_written
+                - self.output_bytes_unread as u64,
+        }
+    }
+}
+
+// the most output zlib handoffs include as the dictionary
+const ZLIB_DICTIONARY_MAX: usize = 64 * 1024;
+
+impl ZlibHandoff {
+    /// Describes the state of a serialized checkpoint for zlib. Returns None if the
+    /// checkpoint is corrupt or a delta, if it is not at a block boundary, such as
+    /// checkpoints taken while stopped by inflate_until_block_boundary(), or if the stream
+    /// ended at it.
+    ///
+    /// Output between the positions of the checkpoint and `output_offset` was not returned
+    /// when the checkpoint was taken; it is at the end of the dictionary.
+    pub fn from_checkpoint(checkpoint_data: &[u8]) -> Option<Self> {
+        let fields = CheckpointFields::parse(checkpoint_data)?;
+        if fields.delta.is_some() {
+            return None;
+        }
+        let window_len = fields.window_len()?;
+        fields.validate(window_len)?;
+        let mut window = vec![0; window_len];
+        fields.fill_window(&mut window);
+        Self::at_boundary(
+            fields.input_bits,
+            fields.buffered_value,
+            fields.bfinal_block_type,
+            fields.uncompressed_remaining,
+            fields.output_bytes_written,
+            [&window, &[]],
+        )
+    }
+
+    // describes a checkpoint with the given fields and window data ending at
+    // `output_bytes_written`, if it is at a block boundary before the end of the stream
+    fn at_boundary(
+        input_bits: u64,
+        buffered_value: u8,
+        bfinal_block_type: u8,
+        uncompressed_remaining: u16,
+        output_bytes_written: u64,
+        window: [&[u8]; 2],
+    ) -> Option<Self> {
+        // the end of a block is stored as an uncompressed block with no bytes remaining
+        if bfinal_block_type != BlockType::Uncompressed as u8 || uncompressed_remaining != 0 {
+            return None;
+        }
+        let prime_bits = ((8 - (input_bits & 7)) & 7) as u8;
+        let [first, second] = window;
+        let skip = (first.len() + second.len()).saturating_sub(ZLIB_DICTIONARY_MAX);
+        let dictionary = if skip < first.len() {
+            [&first[skip..], second].concat()
+        } else {
+            second[skip - first.len()..].to_vec()
+        };
+        Some(Self {
+            input_byte_offset: input_bits.div_ceil(8),
+            prime_bits,
+            prime_value: buffered_value & ((1u16 << prime_bits) - 1) as u8,
+            output_offset: output_bytes_written,
+            dictionary,
+        })
+    }
+}
+
+/// Checks a serialized checkpoint without restoring it, including its checksum, version,
+/// window length and Huffman code lengths. Returns the offsets restore_from_checkpoint()
+/// would return, or None if it would reject the checkpoint for any inflater. Delta
+/// checkpoints are rejected, as restore_from_checkpoint() does. An inflater with an output
+/// limit from with_uncompressed_size() may still reject a checkpoint past the limit.
+#[cfg_attr(docsrs, doc(cfg(feature = "checkpoint")))]
+pub fn validate_checkpoint(checkpoint_data: &[u8]) -> Option<CheckpointStreamPositions> {
+    let fields = CheckpointFields::parse(checkpoint_data)?;
+    if fields.delta.is_some() {
+        return None;
+    }
+    fields.validate(fields.window_len()?)?;
+    Some(fields.positions())
 }
 
-impl InflaterManaged {
+/// Checks that `checkpoints`, ordered by offset, resume the deflate64 stream `compressed`
+/// consistently, such as the checkpoints of a resume index. The stream is decoded from its
+/// start up to the first checkpoint, and from each checkpoint up to the next one, which
+/// must be reached at its output offset with the history and output CRC-32 it stores; the
+/// CRC-32 is compared when both checkpoints store one. The stream is then decoded from the
+/// last checkpoint to its end. This decodes the whole stream once, so checkpoints which
+/// diverge from the stream or are stale are found before resuming from them. Without
+/// CRC-32s, only the last 64KB of output before each checkpoint is compared.
+#[cfg_attr(docsrs, doc(cfg(feature = "checkpoint")))]
+pub fn verify_checkpoint_chain<C: AsRef<[u8]>>(
+    compressed: &[u8],
+    checkpoints: &[C],
+) -> Result<(), CheckpointChainError> {
+    let options = InflaterOptions::new().track_crc32(true);
+    let mut inflater = Box::new(InflaterManaged::with_checkpoints_and_options(options));
+    let mut output = vec![0; 64 * 1024];
+    let mut consumed = 0;
+    let mut crc32_known = true;
+    for (index, checkpoint) in checkpoints.iter().enumerate() {
+        let checkpoint = checkpoint.as_ref();
+        let fields = CheckpointFields::parse(checkpoint)
+            .filter(|fields| fields.delta.is_none())
+            .ok_or(CheckpointChainError::Invalid(index))?;
+        let window_len = fields.window_len();
+        let positions = fields.positions();
+        if window_len.and_then(|len| fields.validate(len)).is_none()
+            || positions.input_bytes_to_skip < consumed as u64
+            || positions.output_bytes_already_returned < inflater.total_out()
+        {
+            return Err(CheckpointChainError::Invalid(index));
+        }
+
+        while inflater.total_out() < positions.output_bytes_already_returned {
+            let remaining = positions.output_bytes_already_returned - inflater.total_out();
+            let len = min(output.len() as u64, remaining) as usize;
+            let result = inflater.inflate(&compressed[consumed..], &mut output[..len]);
+            consumed += result.bytes_consumed;
+            if result.data_error || (result.bytes_consumed == 0 && result.bytes_written == 0) {
+                return Err(CheckpointChainError::Mismatch(index));
+            }
+        }
+        let mut window = vec![0; window_len.unwrap_or_default()];
+        fields.fill_window(&mut window);
+        let history = &window[..window.len() - fields.output_bytes_unread as usize];
+        if positions.input_bytes_to_skip > inflater.total_in()
+            || !inflater.returned_history_matches(history)
+            || (crc32_known
+                && fields
+                    .output_crc32
+                    .is_some_and(|crc32| inflater.output_crc32() != Some(crc32)))
+        {
+            return Err(CheckpointChainError::Mismatch(index));
+        }
+
+        inflater.reset();
+        inflater
+            .restore_from_checkpoint(checkpoint)
+            .ok_or(CheckpointChainError::Invalid(index))?;
+        consumed = min(positions.input_bytes_to_skip, compressed.len() as u64) as usize;
+        crc32_known = fields.output_crc32.is_some();
+    }
+
+    let last = checkpoints.len().saturating_sub(1);
+    while !inflater.finished() {
+        let result = inflater.inflate(&compressed[consumed..], &mut output);
+        consumed += result.bytes_consumed;
+        if result.data_error || (result.bytes_consumed == 0 && result.bytes_written == 0) {
+            return Err(CheckpointChainError::Incomplete(last));
+        }
+    }
+    Ok(())
+}
+
+impl<T: CheckpointTracking> InflaterManaged<T> {
     #[inline(always)]
     fn update_checkpoint_after_write_or_eob(&mut self, input: &InputBuffer<'_>, end_of_block: bool) {
-        debug_assert!(input.available_bits() >= 0 && input.available_bits() <= 32);
+        if !T::ENABLED {
+            // compiled out for inflaters which don't track checkpoints
+            return;
+        }
+        if !end_of_block && self.options.checkpoints_at_block_boundaries_only {
+            // the checkpoint stays at the last block boundary until has_checkpoint() finds
+            // output written after it
+            return;
+        }
+        debug_assert!(input.available_bits() >= 0 && input.available_bits() <= 64);
         // checkpoint_input_bits tracks the number of input bits which have been consumed up to
         // the moment of the checkpoint. It does not count unconsumed bits still in the buffer.
         self.checkpoint_input_bits = (self.total_input_loaded + input.read_bytes as u64) * 8
@@ -49,6 +728,7 @@ impl InflaterManaged {
         if end_of_block {
             debug_assert!(matches!(self.state, InflaterState::ReadingBFinal | InflaterState::Done));
             self.checkpoint_bfinal_block_type = BlockType::Uncompressed as u8 | bfinal_flag;
+            self.checkpoint_history_len = self.history_len();
         } else {
             match self.block_type {
                 BlockType::Uncompressed => {
@@ -61,6 +741,9 @@ impl InflaterManaged {
             self.checkpoint_bfinal_block_type = self.block_type as u8 | bfinal_flag;
         }
     }
+}
+
+impl InflaterManaged<Checkpointing> {
 
     /// Serialize the most recent inflater checkpoint for use with restore_from_checkpoint().
     /// A serialized checkpoint can contain up to 129KB of data representing the state of the
@@ -69,13 +752,292 @@ impl InflaterManaged {
     /// input and output byte offsets which correspond to the checkpoint being serialized.
     #[cfg_attr(docsrs, doc(cfg(feature = "checkpoint")))]
     pub fn checkpoint(&self) -> Option<(Vec<u8>, CheckpointStreamPositions)> {
-        if self.checkpoint_input_bits == 0
+        let mut out = vec![0; self.checkpoint_len()?];
+        let (len, positions) = self.checkpoint_into(&mut out)?;
+        debug_assert_eq!(len, out.len());
+        Some((out, positions))
+    }
+
+    /// Arms the inflater to stop at the next block boundary, so that a checkpoint at the
+    /// boundary can be taken. The inflate() call which reaches the boundary stops there
+    /// once all output of the block was returned, with status InflateStatus::BlockBoundary,
+    /// and checkpoint() then returns the checkpoint at the boundary until the next call.
+    /// Boundary checkpoints don't contain Huffman code lengths. The request is cleared when
+    /// it is satisfied, and by reset().
+    #[cfg_attr(docsrs, doc(cfg(feature = "checkpoint")))]
+    pub fn request_boundary_checkpoint(&mut self) {
+        self.boundary_checkpoint_requested = true;
+    }
+
+    /// Describes the most recent checkpoint for zlib, as ZlibHandoff::from_checkpoint()
+    /// does without serializing it. Returns None if there is no checkpoint, if it is not at
+    /// a block boundary, or if the stream ended at it, so call this while stopped at a
+    /// block boundary. Output before `output_offset` which was not returned yet is still
+    /// returned by inflate().
+    #[cfg_attr(docsrs, doc(cfg(feature = "checkpoint")))]
+    pub fn zlib_handoff(&self) -> Option<ZlibHandoff> {
+        if !self.has_checkpoint() {
+            return None;
+        }
+        let num_buffered_bits = (8 - (self.checkpoint_input_bits & 7)) as u32 & 7;
+        let (window_a, window_b) = self.output.get_checkpoint_data(self.history_len());
+        ZlibHandoff::at_boundary(
+            self.checkpoint_input_bits,
+            self.checkpoint_bit_buffer & ((1u16 << num_buffered_bits) - 1) as u8,
+            self.checkpoint_bfinal_block_type,
+            self.block_length as u16,
+            self.history_len(),
+            [window_a, window_b],
+        )
+    }
+
+    /// Returns true if request_boundary_checkpoint() was called, and the inflater has not
+    /// stopped at a block boundary since.
+    #[cfg_attr(docsrs, doc(cfg(feature = "checkpoint")))]
+    pub fn boundary_checkpoint_requested(&self) -> bool {
+        self.boundary_checkpoint_requested
+    }
+
+    /// Sets a payload of up to MAX_CHECKPOINT_METADATA_LEN bytes which is stored in the
+    /// checkpoints serialized from now on, such as the ID of the source file or a hash of
+    /// the compressed stream. The payload is covered by the checksum of the checkpoint, and
+    /// restoring a checkpoint replaces the payload of the inflater with the one it stores,
+    /// or clears it. The payload is cleared by reset().
+    ///
+    /// # Panics
+    ///
+    /// Panics if `metadata` is longer than MAX_CHECKPOINT_METADATA_LEN.
+    #[cfg_attr(docsrs, doc(cfg(feature = "checkpoint")))]
+    pub fn set_checkpoint_metadata(&mut self, metadata: &[u8]) {
+        assert!(
+            metadata.len() <= MAX_CHECKPOINT_METADATA_LEN,
+            "checkpoint metadata longer than MAX_CHECKPOINT_METADATA_LEN"
+        );
+        self.checkpoint_metadata[..metadata.len()].copy_from_slice(metadata);
+        self.checkpoint_metadata_len = metadata.len() as u8;
+    }
+
+    /// Returns the payload set by set_checkpoint_metadata() or restored from a checkpoint,
+    /// which is empty if there is none.
+    #[cfg_attr(docsrs, doc(cfg(feature = "checkpoint")))]
+    pub fn checkpoint_metadata(&self) -> &[u8] {
+        &self.checkpoint_metadata[..self.checkpoint_metadata_len as usize]
+    }
+
+    /// Returns the maximum length of a serialized checkpoint, which is MAX_CHECKPOINT_SIZE.
+    /// A buffer of this length always fits the output of checkpoint_into().
+    #[cfg_attr(docsrs, doc(cfg(feature = "checkpoint")))]
+    pub const fn checkpoint_max_len() -> usize {
+        MAX_CHECKPOINT_SIZE
+    }
+
+    /// Returns the length checkpoint_into() would serialize the most recent checkpoint to,
+    /// or None if there is no checkpoint. The length is smaller than checkpoint_max_len()
+    /// while less than a full window of output has been written.
+    #[cfg_attr(docsrs, doc(cfg(feature = "checkpoint")))]
+    pub fn checkpoint_len(&self) -> Option<usize> {
+        if !self.has_checkpoint() {
+            return None;
+        }
+        let (_, _, window_len, _) = self.checkpoint_window();
+        Some(CHECKPOINT_HEADER_SIZE + self.extension_len() + window_len + 4)
+    }
+
+    // the length of the output CRC-32 and metadata extension fields of checkpoints
+    fn extension_len(&self) -> usize {
+        let crc_len = if self.options.tracks_crc32() { 4 } else { 0 };
+        let metadata_len = match self.checkpoint_metadata_len {
+            0 => 0,
+            len => 1 + len as usize,
+        };
+        crc_len + metadata_len
+    }
+
+    // returns the window data of the checkpoint, which may be split due to the circular
+    // buffer, the length it is serialized to, and whether it is run-length encoded
+    fn checkpoint_window(&self) -> (&[u8], &[u8], usize, bool) {
+        let output_bytes_written =
+            self.total_output_consumed + self.output.available_bytes() as u64;
+        let (window_a, window_b) = self.output.get_checkpoint_data(output_bytes_written);
+        let raw_len = window_a.len() + window_b.len();
+        if self.options.compress_checkpoints {
+            let rle_len = rle_encoded_len(window_a) + rle_encoded_len(window_b);
+            if rle_len < raw_len {
+                return (window_a, window_b, rle_len, true);
+            }
+        }
+        (window_a, window_b, raw_len, false)
+    }
+
+    /// Returns the input and output byte offsets checkpoint() would report for the most
+    /// recent checkpoint, without serializing it, or None if there is no checkpoint.
+    #[cfg_attr(docsrs, doc(cfg(feature = "checkpoint")))]
+    pub fn checkpoint_positions(&self) -> Option<CheckpointStreamPositions> {
+        if !self.has_checkpoint() {
+            return None;
+        }
+        Some(CheckpointStreamPositions {
+            // round up; partial input byte is already stored in checkpoint
+            input_bytes_to_skip: self.checkpoint_input_bits.div_ceil(8),
+            output_bytes_already_returned: self.total_output_consumed,
+        })
+    }
+
+    // whether the output returned so far ends with `history`, as far as the window still
+    // holds it
+    fn returned_history_matches(&self, history: &[u8]) -> bool {
+        let len = history
+            .len()
+            .min(crate::output_window::WINDOW_SIZE - self.output.available_bytes());
+        let (first, second) = self.output.recently_copied(len);
+        let (history_first, history_second) = history[history.len() - len..].split_at(first.len());
+        history_first == first && history_second == second
+    }
+
+    fn has_checkpoint(&self) -> bool {
+        !(self.checkpoint_input_bits == 0
             || self.errored()
             || (self.output.available_bytes() == 0 && self.state == InflaterState::Done)
+            || (self.options.checkpoints_at_block_boundaries_only
+                && self.history_len() != self.checkpoint_history_len))
+    }
+
+    /// Same as checkpoint() but serializes the checkpoint into `out` without allocating,
+    /// and returns the length of the serialized checkpoint. Returns None if there is no
+    /// checkpoint or if `out` is too small for it; a buffer of MAX_CHECKPOINT_SIZE bytes
+    /// always fits.
+    #[cfg_attr(docsrs, doc(cfg(feature = "checkpoint")))]
+    pub fn checkpoint_into(&self, out: &mut [u8]) -> Option<(usize, CheckpointStreamPositions)> {
+        let mut cursor = &mut out[..];
+        let mut fits = true;
+        let result = self.serialize_full_checkpoint(&mut |data| {
+            match std::mem::take(&mut cursor).split_at_mut_checked(data.len()) {
+                Some((head, tail)) => {
+                    head.copy_from_slice(data);
+                    cursor = tail;
+                }
+                None => fits = false,
+            }
+        });
+        result.filter(|_| fits)
+    }
+
+    /// Same as checkpoint_into() but writes the checkpoint to `writer` without allocating,
+    /// such as to a file or a network stream, and returns the number of bytes written.
+    /// Returns Ok(None) without writing anything if there is no checkpoint. The checkpoint
+    /// is written in parts of up to 4KB; `writer` is not flushed.
+    #[cfg_attr(docsrs, doc(cfg(feature = "checkpoint")))]
+    pub fn checkpoint_to_writer(
+        &self,
+        writer: impl Write,
+    ) -> io::Result<Option<(usize, CheckpointStreamPositions)>> {
+        if !self.has_checkpoint() {
+            return Ok(None);
+        }
+        let mut writer = CheckpointWriter::new(writer);
+        let positions = self.serialize_full_checkpoint(&mut |data| writer.write(data));
+        writer.finish()?;
+        Ok(positions)
+    }
+
+    // serializes the most recent checkpoint, which is not a delta, to `out`
+    fn serialize_full_checkpoint(
+        &self,
+        out: &mut dyn FnMut(&[u8]),
+    ) -> Option<(usize, CheckpointStreamPositions)> {
+        if !self.has_checkpoint() {
+            return None;
+        }
+        let (window_a, window_b, window_len, rle) = self.checkpoint_window();
+        let flags = if rle { CHECKPOINT_FLAG_RLE_WINDOW } else { 0 };
+        self.serialize_checkpoint(out, flags, &[], window_len, |write| {
+            if rle {
+                rle_encode(window_a, &mut *write);
+                rle_encode(window_b, &mut *write);
+            } else {
+                write(window_a);
+                write(window_b);
+            }
+        })
+    }
+
+    /// Same as checkpoint() but serializes the checkpoint as a delta against `base`, a
+    /// checkpoint previously taken from the same stream, and restored with
+    /// restore_from_delta(). The delta only stores the part of the history window which
+    /// is not in the window of `base`, so it is smaller than a full checkpoint when less
+    /// than 64KB of output was written since `base`, such as for checkpoints taken at every
+    /// block boundary. Returns None if there is no checkpoint, or if `base` is corrupt, is
+    /// itself a delta, or is not from this stream.
+    #[cfg_attr(docsrs, doc(cfg(feature = "checkpoint")))]
+    pub fn checkpoint_delta(&self, base: &[u8]) -> Option<(Vec<u8>, CheckpointStreamPositions)> {
+        if !self.has_checkpoint() {
+            return None;
+        }
+        let base_fields = CheckpointFields::parse(base)?;
+        if base_fields.delta.is_some() {
+            return None;
+        }
+        let base_len = base_fields.window_len()?;
+        let mut base_window = vec![0; base_len];
+        base_fields.fill_window(&mut base_window);
+
+        let output_bytes_written =
+            self.total_output_consumed + self.output.available_bytes() as u64;
+        let (window_a, window_b) = self.output.get_checkpoint_data(output_bytes_written);
+        let window = [window_a, window_b].concat();
+
+        // the range of output offsets which is in both windows
+        let start = output_bytes_written - window.len() as u64;
+        let base_start = base_fields.output_bytes_written - base_len as u64;
+        let overlap_start = start.max(base_start);
+        let overlap_end = output_bytes_written.min(base_fields.output_bytes_written);
+        let copy_len = overlap_end.saturating_sub(overlap_start) as usize;
+        let (copy_source, copy_dest) = if copy_len == 0 {
+            (0, 0)
+        } else {
+            (
+                (overlap_start - base_start) as usize,
+                (overlap_start - start) as usize,
+            )
+        };
+        if window[copy_dest..copy_dest + copy_len]
+            != base_window[copy_source..copy_source + copy_len]
         {
             return None;
         }
 
+        let mut extension = [0; CHECKPOINT_DELTA_HEADER_SIZE - CHECKPOINT_HEADER_SIZE];
+        extension[0..4].copy_from_slice(&base_fields.stored_checksum.to_le_bytes());
+        extension[4..8].copy_from_slice(&(copy_source as u32).to_le_bytes());
+        extension[8..12].copy_from_slice(&(copy_dest as u32).to_le_bytes());
+        extension[12..16].copy_from_slice(&(copy_len as u32).to_le_bytes());
+        let literals_len = window.len() - copy_len;
+        let mut out = Vec::with_capacity(CHECKPOINT_DELTA_HEADER_SIZE + literals_len + 4);
+        let (len, positions) = self.serialize_checkpoint(
+            &mut |data| out.extend_from_slice(data),
+            CHECKPOINT_FLAG_DELTA,
+            &extension,
+            literals_len,
+            |write| {
+                write(&window[..copy_dest]);
+                write(&window[copy_dest + copy_len..]);
+            },
+        )?;
+        debug_assert_eq!(len, out.len());
+        Some((out, positions))
+    }
+
+    // serializes the checkpoint with `flags` in addition to those of the checksum, the
+    // output CRC-32 and the metadata, the `extension` header fields, and the `window_len` bytes of window
+    // data written by `write_window`, to `out`
+    fn serialize_checkpoint(
+        &self,
+        out: &mut dyn FnMut(&[u8]),
+        flags: u16,
+        extension: &[u8],
+        window_len: usize,
+        write_window: impl FnOnce(&mut dyn FnMut(&[u8])),
+    ) -> Option<(usize, CheckpointStreamPositions)> {
         let checkpoint_block_type =
             BlockType::from_int((self.checkpoint_bfinal_block_type & 0x7F) as u16)?;
         let uncompressed_remaining = match checkpoint_block_type {
@@ -92,40 +1054,62 @@ impl InflaterManaged {
             dist_codes[..lens.len()].copy_from_slice(lens);
         }
 
-        // window data slices may be split due to circular buffer
         let output_bytes_written =
             self.total_output_consumed + self.output.available_bytes() as u64;
         let bytes_unread = self.output.available_bytes() as u32;
-        let (window_a, window_b) = self.output.get_checkpoint_data(output_bytes_written);
+        let checksum = self.options.checkpoint_checksum;
+        let output_crc32 = self.output_crc32();
+        let metadata = self.checkpoint_metadata();
+        let mut flags = flags | checksum.flags();
+        if output_crc32.is_some() {
+            flags |= CHECKPOINT_FLAG_OUTPUT_CRC32;
+        }
+        if !metadata.is_empty() {
+            flags |= CHECKPOINT_FLAG_METADATA;
+        }
+        let header_len = CHECKPOINT_HEADER_SIZE + extension.len() + self.extension_len();
 
         let bfinal_block_type = self.checkpoint_bfinal_block_type;
+        let positions = self.checkpoint_positions()?;
+        debug_assert_eq!(
+            positions.output_bytes_already_returned,
+            output_bytes_written - bytes_unread as u64
+        );
 
         // Mask unrefereenced high bits in buffered byte for deterministic serialization
         let num_buffered_bits = (8 - (self.checkpoint_input_bits & 7)) as u32 & 7;
         let buffered_value = self.checkpoint_bit_buffer & ((1 << num_buffered_bits) - 1);
 
-        let mut out = Vec::with_capacity(CHECKPOINT_HEADER_SIZE + window_a.len() + window_b.len());
-        out.extend_from_slice(&0x1001u16.to_le_bytes()); // 2 - version
-        out.extend_from_slice(&self.checkpoint_input_bits.to_le_bytes()); // 8
-        out.push(buffered_value); // 1
-        out.push(bfinal_block_type); // 1
-        out.extend_from_slice(&(uncompressed_remaining as u16).to_le_bytes()); // 2
-        out.extend_from_slice(&lit_codes); // 288
-        out.extend_from_slice(&dist_codes); // 32
-        out.extend_from_slice(&output_bytes_written.to_le_bytes()); // 8
-        out.extend_from_slice(&bytes_unread.to_le_bytes()); // 4
-        debug_assert_eq!(out.len(), CHECKPOINT_HEADER_SIZE);
-        out.extend_from_slice(window_a);
-        out.extend_from_slice(window_b);
-        let checksum = fletcher32_checksum(&out);
-        out.extend_from_slice(&checksum.to_le_bytes());
-
-        let positions = CheckpointStreamPositions {
-            // round up; partial input byte is already stored in checkpoint
-            input_bytes_to_skip: self.checkpoint_input_bits.div_ceil(8),
-            output_bytes_already_returned: output_bytes_written - bytes_unread as u64,
+        let mut checksum = checksum.start();
+        let mut len = 0;
+        let mut write = |data: &[u8]| {
+            checksum.update(data);
+            len += data.len();
+            out(data);
         };
-        Some((out, positions))
+        write(&CHECKPOINT_VERSION_2.to_le_bytes()); // 2 - version
+        write(&flags.to_le_bytes()); // 2 - flags
+        write(&(header_len as u16).to_le_bytes()); // 2 - header_len
+        write(&self.checkpoint_input_bits.to_le_bytes()); // 8
+        write(&[buffered_value]); // 1
+        write(&[bfinal_block_type]); // 1
+        write(&(uncompressed_remaining as u16).to_le_bytes()); // 2
+        write(&lit_codes); // 288
+        write(&dist_codes); // 32
+        write(&output_bytes_written.to_le_bytes()); // 8
+        write(&bytes_unread.to_le_bytes()); // 4
+        write(extension);
+        if let Some(output_crc32) = output_crc32 {
+            write(&output_crc32.to_le_bytes());
+        }
+        if !metadata.is_empty() {
+            write(&[metadata.len() as u8]);
+            write(metadata);
+        }
+        write_window(&mut write);
+        debug_assert_eq!(len, header_len + window_len);
+        out(&checksum.value().to_le_bytes());
+        Some((len + 4, positions))
     }
 
     /// Restore inflater state from a previous serialized checkpoint. Returns None if data
@@ -133,103 +1117,233 @@ impl InflaterManaged {
     /// success, the inflater's internal state is overwritten and the caller must adjust the
     /// input/output of the next inflate() operation according to the returned offsets. The
     /// offsets will be the same as those originally returned by the checkpoint() function.
+    /// This also recovers an inflater after a decoding error: the error, warnings and
+    /// statistics of the data decoded before are cleared.
     /// Note, if the inflater has a total output byte limit due to with_uncompressed_size(),
     /// the output byte limit will be retained after restoration and adjusted to account for
     /// output bytes already written before the checkpoint. Any checkpoint which has already
     /// exceeded the current output byte limit will not be restored and instead return None.
+    /// The same applies to the limits of InflaterOptions::max_output() and the uncompressed
+    /// size of InflaterOptions::entry_metadata().
+    ///
+    /// Checkpoints taken by inflaters which compute the CRC-32 of the output, see
+    /// InflaterOptions::track_crc32(), store it, and restoring continues it, so finish()
+    /// verifies the CRC-32 of InflaterOptions::entry_metadata() over the whole output. If
+    /// the checkpoint doesn't store it, the CRC-32 only covers the output after it.
+    /// Restoring also restores the payload of set_checkpoint_metadata().
     #[cfg_attr(docsrs, doc(cfg(feature = "checkpoint")))]
     #[must_use]
     pub fn restore_from_checkpoint(
         &mut self,
         checkpoint_data: &[u8],
     ) -> Option<CheckpointStreamPositions> {
-        if checkpoint_data.len() < CHECKPOINT_HEADER_SIZE + 4 {
+        // Parse all fields of any supported version
+        let fields = CheckpointFields::parse(checkpoint_data)?;
+        if fields.delta.is_some() {
             return None;
         }
-        let (data, checksum_bytes) = checkpoint_data.split_at(checkpoint_data.len() - 4);
-        let stored_checksum = u32::from_le_bytes(checksum_bytes.try_into().ok()?);
-        if fletcher32_checksum(data) != stored_checksum {
+        self.restore_fields(&fields, None)
+    }
+
+    /// Restore inflater state from a delta checkpoint serialized by checkpoint_delta(), and
+    /// the `base` checkpoint it was serialized against. Returns None if either checkpoint is
+    /// corrupt, or if `base` is not the base of `delta`; see restore_from_checkpoint() for
+    /// the other cases and how to continue after restoring.
+    #[cfg_attr(docsrs, doc(cfg(feature = "checkpoint")))]
+    #[must_use]
+    pub fn restore_from_delta(
+        &mut self,
+        base: &[u8],
+        delta: &[u8],
+    ) -> Option<CheckpointStreamPositions> {
+        let base = CheckpointFields::parse(base)?;
+        let fields = CheckpointFields::parse(delta)?;
+        let delta = fields.delta.as_ref()?;
+        if base.delta.is_some() || base.stored_checksum != delta.base_checksum {
             return None;
         }
-        let mut cursor = data;
-        let mut read = |n: usize| -> Option<&[u8]> {
-            if cursor.len() < n {
-                return None;
+        let base_len = base.window_len()?;
+        if base_len > crate::output_window::WINDOW_SIZE
+            || delta.copy_source.checked_add(delta.copy_len)? > base_len
+        {
+            return None;
+        }
+        self.restore_fields(&fields, Some((&base, base_len)))
+    }
+
+    /// Same as restore_from_checkpoint() but reads the checkpoint from `reader` without
+    /// allocating, such as from a file or a network stream. Reads exactly the bytes of the
+    /// checkpoint, so more data may follow it. Returns Ok(None) if the data is corrupt, and
+    /// an error if reading fails, including an io::ErrorKind::UnexpectedEof error if the
+    /// checkpoint is truncated. Delta checkpoints can't be restored from a reader.
+    ///
+    /// The history window is read in place, so if the checkpoint is found to be corrupt
+    /// or reading fails after the header, the inflater is reset as if by reset(). Reading
+    /// a run-length encoded checkpoint issues many small reads, so `reader` should be
+    /// buffered.
+    #[cfg_attr(docsrs, doc(cfg(feature = "checkpoint")))]
+    pub fn restore_from_reader(
+        &mut self,
+        mut reader: impl io::Read,
+    ) -> io::Result<Option<CheckpointStreamPositions>> {
+        // Read the known header fields, up to all known extension fields
+        let mut header = [0; CHECKPOINT_MAX_HEADER_SIZE];
+        reader.read_exact(&mut header[..2])?;
+        let (header_len, start) = match u16::from_le_bytes([header[0], header[1]]) {
+            CHECKPOINT_VERSION_1 => (CHECKPOINT_V1_HEADER_SIZE, 2),
+            CHECKPOINT_VERSION_2 => {
+                reader.read_exact(&mut header[2..6])?;
+                let header_len = u16::from_le_bytes([header[4], header[5]]) as usize;
+                if header_len < CHECKPOINT_HEADER_SIZE {
+                    return Ok(None);
+                }
+                (header_len, 6)
             }
-            let (head, tail) = cursor.split_at(n);
-            cursor = tail;
-            Some(head)
+            _ => return Ok(None), // unsupported version
+        };
+        let known_len = header_len.min(CHECKPOINT_MAX_HEADER_SIZE);
+        reader.read_exact(&mut header[start..known_len])?;
+        let Some((fields, _)) = CheckpointFields::parse_header(&header[..known_len]) else {
+            return Ok(None);
         };
+        if fields.delta.is_some() {
+            return Ok(None);
+        }
+        let mut checksum = fields.checksum.start();
+        checksum.update(&header[..known_len]);
 
-        // Parse all fields
-        let version: u16 = u16::from_le_bytes(read(2)?.try_into().ok()?);
-        if version != 0x1001 {
-            return None; // unsupported version
-        }
-        let input_bits: u64 = u64::from_le_bytes(read(8)?.try_into().ok()?);
-        let buffered_value: u8 = read(1)?[0];
-        let bfinal_block_type: u8 = read(1)?[0];
-        let remaining_uncompressed: u16 = u16::from_le_bytes(read(2)?.try_into().ok()?);
-        let lit_codes: &[u8] = read(HuffmanTree::MAX_LITERAL_TREE_ELEMENTS)?;
-        let dist_codes: &[u8] = read(HuffmanTree::MAX_DIST_TREE_ELEMENTS)?;
-        let output_bytes_written: u64 = u64::from_le_bytes(read(8)?.try_into().ok()?);
-        let output_bytes_unread: u32 = u32::from_le_bytes(read(4)?.try_into().ok()?);
-        let window_data: &[u8] = cursor; // remaining bytes
+        // Skip unknown extension fields
+        let mut remaining = header_len - known_len;
+        let mut skipped = [0; 256];
+        while remaining > 0 {
+            let skipped = &mut skipped[..remaining.min(256)];
+            reader.read_exact(skipped)?;
+            checksum.update(skipped);
+            remaining -= skipped.len();
+        }
 
-        // from_bits masks off invalid high bits
-        let num_buffered_bits = (8 - (input_bits & 7)) as i32 & 7;
-        let bits = BitsBuffer::from_bits(buffered_value as u32, num_buffered_bits);
+        let window_len = fields.expected_window_len();
+        let Some(state) = self.validate_fields(&fields, window_len) else {
+            return Ok(None);
+        };
 
-        // Check window data length validity
-        let expected_window_len = (output_bytes_written.min(TABLE_LOOKUP_DISTANCE_MAX as u64)
-            as u32)
-            .max(output_bytes_unread) as usize;
-        if window_data.len() != expected_window_len
-            || window_data.len() > crate::output_window::WINDOW_SIZE
-        {
-            return None;
+        let window = self
+            .output
+            .restore_from_checkpoint(window_len, fields.output_bytes_unread as usize);
+        let window = &mut window[..window_len];
+        let mut read_window = || -> io::Result<bool> {
+            if fields.flags & CHECKPOINT_FLAG_RLE_WINDOW != 0 {
+                if !rle_decode_from(&mut reader, window, &mut checksum)? {
+                    return Ok(false);
+                }
+            } else {
+                reader.read_exact(window)?;
+                checksum.update(window);
+            }
+            let mut stored_checksum = [0; 4];
+            reader.read_exact(&mut stored_checksum)?;
+            Ok(checksum.value() == u32::from_le_bytes(stored_checksum))
+        };
+        match read_window() {
+            Ok(true) => Ok(Some(self.apply_fields(&fields, state))),
+            result => {
+                self.reset();
+                result.map(|_| None)
+            }
         }
+    }
+
+    // restores the parsed checkpoint `fields`, and the window of `base` with its length if
+    // they are a delta
+    fn restore_fields(
+        &mut self,
+        fields: &CheckpointFields<'_>,
+        base: Option<(&CheckpointFields<'_>, usize)>,
+    ) -> Option<CheckpointStreamPositions> {
+        let window_len = fields.window_len()?;
+        let state = self.validate_fields(fields, window_len)?;
 
+        let window = self
+            .output
+            .restore_from_checkpoint(window_len, fields.output_bytes_unread as usize);
+        match (&fields.delta, base) {
+            (Some(delta), Some((base, base_len))) => {
+                // the copied bytes are moved into place before the literals overwrite them
+                base.fill_window(&mut window[..base_len]);
+                let copy_end = delta.copy_dest + delta.copy_len;
+                window.copy_within(
+                    delta.copy_source..delta.copy_source + delta.copy_len,
+                    delta.copy_dest,
+                );
+                let (prefix, suffix) = fields.window_data.split_at(delta.copy_dest);
+                window[..delta.copy_dest].copy_from_slice(prefix);
+                window[copy_end..window_len].copy_from_slice(suffix);
+            }
+            _ => fields.fill_window(&mut window[..window_len]),
+        }
+
+        Some(self.apply_fields(fields, state))
+    }
+
+    // checks that the header `fields` with a decoded window of `window_len` bytes can be
+    // restored by this inflater, and returns the state to restore, without modifying self
+    fn validate_fields(
+        &self,
+        fields: &CheckpointFields<'_>,
+        window_len: usize,
+    ) -> Option<RestoredState> {
         // Check uncompressed_size limit if set
-        let output_already_returned = output_bytes_written - output_bytes_unread as u64;
-        if self.uncompressed_size != usize::MAX
-            && output_already_returned > self.uncompressed_size as u64
+        let output_already_returned =
+            fields.output_bytes_written - fields.output_bytes_unread as u64;
+        if self.options.uncompressed_size != usize::MAX
+            && output_already_returned > self.options.uncompressed_size as u64
         {
             return None;
         }
-
-        let bfinal = (bfinal_block_type & 128) != 0;
-        let block_type_val = bfinal_block_type % 128;
-        let block_type = BlockType::from_int(block_type_val.into())?;
-
-        let mut lit_tree = HuffmanTree::invalid();
-        let mut dist_tree = HuffmanTree::invalid();
-        if block_type == BlockType::Dynamic {
-            if lit_codes.iter().any(|x| *x > 16) || dist_codes.iter().any(|x| *x > 16) {
-                return None;
-            }
-            lit_tree.new_in_place(lit_codes).ok()?;
-            dist_tree.new_in_place(dist_codes).ok()?;
-        } else if block_type == BlockType::Uncompressed && remaining_uncompressed > 0 {
-            // Uncompressed blocks with remaining bytes must be byte-aligned
-            if bits.bits_in_buffer != 0 {
+        // Returning more output is an error, and the output returned so far can't be undone
+        if let Some((limit, _)) = self.options.fixed_output_limit() {
+            if output_already_returned > limit {
                 return None;
             }
         }
+        fields.validate(window_len)
+    }
+
+    // restores the state returned by validate_fields() for `fields`, after the window was
+    // restored
+    fn apply_fields(
+        &mut self,
+        fields: &CheckpointFields<'_>,
+        state: RestoredState,
+    ) -> CheckpointStreamPositions {
+        let RestoredState {
+            bits,
+            bfinal,
+            block_type,
+            lit_tree,
+            dist_tree,
+        } = state;
+        let remaining_uncompressed = fields.uncompressed_remaining;
 
-        // All validation passed - modify self
         // Pre-load buffered bits into bit buffer
         self.bits = bits;
-        self.checkpoint_input_bits = input_bits;
-        self.checkpoint_bit_buffer = buffered_value;
-        self.total_output_consumed = output_bytes_written - output_bytes_unread as u64;
+        self.checkpoint_input_bits = fields.input_bits;
+        self.checkpoint_history_len = fields.output_bytes_written;
+        self.checkpoint_bit_buffer = fields.buffered_value;
+        self.total_output_consumed =
+            fields.output_bytes_written - fields.output_bytes_unread as u64;
         self.current_inflated_count = self.total_output_consumed as usize;
-        self.total_input_loaded = input_bits.div_ceil(8); // caller will provide input starting at input_bytes_to_skip
-
-        self.output
-            .restore_from_checkpoint(window_data, output_bytes_unread as usize);
+        self.total_input_loaded = fields.input_bits.div_ceil(8); // caller will provide input starting at input_bytes_to_skip
 
-        self.checkpoint_bfinal_block_type = bfinal_block_type;
+        self.output_crc32 = fields.output_crc32.map_or_else(Crc32::new, Crc32::from_value);
+        // what was found decoding before is not part of the restored stream
+        self.error = None;
+        self.warning_count = 0;
+        self.stats = DecodeStats::default();
+        self.block_count = 0;
+        self.filled_history = 0;
+        self.set_checkpoint_metadata(fields.metadata);
+        self.checkpoint_bfinal_block_type = fields.bfinal_block_type;
         match block_type {
             BlockType::Uncompressed => {
                 self.bfinal = bfinal;
@@ -259,10 +1373,6 @@ impl InflaterManaged {
             }
         }
 
-        Some(CheckpointStreamPositions {
-            // round up; partial input byte is already stored in checkpoint
-            input_bytes_to_skip: input_bits.div_ceil(8),
-            output_bytes_already_returned: output_bytes_written - output_bytes_unread as u64,
-        })
+        fields.positions()
     }
 }
@@ -0,0 +1,83 @@
+// Debug state dump for InflaterManaged. This file is included into
+// inflater_managed.rs only when the "debug-dump" feature is enabled.
+
+/// The count of the most recent output bytes shown by dump_state().
+const DUMP_OUTPUT_BYTES: usize = 64;
+
+impl<T: CheckpointTracking> InflaterManaged<T> {
+    /// Renders the current decoder state as text, for bug reports about streams which
+    /// decode wrongly.
+    ///
+    /// The dump shows the state machine position, the current block, the input bit
+    /// position, the code lengths of the current Huffman codes, the fill level of the
+    /// history window, and the last bytes written to it. The format is meant for humans
+    /// and may change in any release.
+    #[cfg_attr(docsrs, doc(cfg(feature = "debug-dump")))]
+    pub fn dump_state(&self) -> String {
+        use std::fmt::Write;
+
+        let mut dump = String::new();
+        let input_bits = self.input_bits(&InputBuffer::new(self.bits, &[]));
+        // writing to a String never fails
+        let _ = writeln!(dump, "state: {:?}", self.state);
+        let _ = writeln!(
+            dump,
+            "block: {} ({:?}{})",
+            self.block_count,
+            self.block_type,
+            if self.bfinal { ", final" } else { "" }
+        );
+        let _ = writeln!(
+            dump,
+            "input: bit {} (byte {} + {} bits), {} bits buffered",
+            input_bits,
+            input_bits / 8,
+            input_bits % 8,
+            self.bits.bits_in_buffer
+        );
+        if self.block_type == BlockType::Uncompressed {
+            let _ = writeln!(dump, "stored bytes remaining: {}", self.block_length);
+        } else {
+            dump_code_lengths(
+                &mut dump,
+                "literal/length",
+                self.literal_length_tree.code_lengths(),
+            );
+            dump_code_lengths(&mut dump, "distance", self.distance_tree.code_lengths());
+        }
+        let _ = writeln!(
+            dump,
+            "window: {} bytes decoded, {} returned, {} pending",
+            self.history_len(),
+            self.total_output_consumed,
+            self.output.available_bytes()
+        );
+        let length = min(self.history_len(), DUMP_OUTPUT_BYTES as u64) as usize;
+        let (first, second) = self.output.last_written(length);
+        let _ = write!(dump, "last {length} bytes:");
+        for byte in first.iter().chain(second) {
+            let _ = write!(dump, " {byte:02x}");
+        }
+        dump.push('\n');
+        if let Some(error) = &self.error {
+            let _ = writeln!(dump, "error: {error}");
+        }
+        for warning in self.warnings() {
+            let _ = writeln!(dump, "warning: {warning}");
+        }
+        dump
+    }
+}
+
+fn dump_code_lengths(dump: &mut String, name: &str, code_lengths: &[u8]) {
+    use std::fmt::Write;
+
+    let _ = write!(dump, "{name} code lengths ({}):", code_lengths.len());
+    for (index, length) in code_lengths.iter().enumerate() {
+        if index % 32 == 0 {
+            dump.push_str("\n ");
+        }
+        let _ = write!(dump, " {length:2}");
+    }
+    dump.push('\n');
+}
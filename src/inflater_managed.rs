@@ -1,12 +1,31 @@
 use crate::buffer::Buffer;
+use crate::chunks::InflateChunks;
+use crate::crc32::Crc32;
 use crate::huffman_tree::HuffmanTree;
 use crate::input_buffer::{BitsBuffer, InputBuffer};
+use crate::observer::{BlockInfo, BlockRecorder, InflateObserver, NoObserver};
 use crate::output_window::OutputWindow;
+#[cfg(feature = "serde")]
+use crate::serde_in_place::{assign, assign_array, deserialize_in_place};
+use crate::sink::{FnSink, OutputSink, SinkError, WriteSink};
+use crate::{
+    array_copy, array_copy1, BlockBoundary, BlockType, CheckpointTracking, DataGap, DecodeStats,
+    EntryMetadata, InflateError, InflateErrorKind, InflateResult, InflateStatus, InflateSummary,
+    InflateWarning, InflateWarningKind, InflaterOptions, InflaterState, InternalErr, MemoryUsage,
+    NoCheckpoint, StreamMeasurements, StreamScan,
+};
 #[cfg(feature = "checkpoint")]
-use crate::CheckpointStreamPositions;
-use crate::{array_copy, array_copy1, BlockType, InflateResult, InflaterState, InternalErr};
+use crate::{
+    CheckpointChainError, CheckpointChecksum, CheckpointInfo, CheckpointStreamPositions,
+    Checkpointing, ZlibHandoff,
+};
 use std::cmp::min;
+use std::io::{self, BufRead, IoSliceMut, Write};
+use std::marker::PhantomData;
 use std::mem::MaybeUninit;
+use std::ops::ControlFlow;
+
+pub(crate) const SCRATCH_BUFFER_SIZE: usize = 64 * 1024;
 
 // Extra bits for length code 257 - 285.
 static EXTRA_LENGTH_BITS: [u8; 29] = [
@@ -41,11 +60,32 @@ static STATIC_DISTANCE_TREE_TABLE: [u8; 32] = [
 const TABLE_LOOKUP_LENGTH_MAX: usize = 65536;
 const TABLE_LOOKUP_DISTANCE_MAX: usize = 65538;
 
+// the longest codes of the literal/length and distance trees, and of the code length tree
+const MAX_CODE_BITS: usize = 15;
+const MAX_CODE_LENGTH_CODE_BITS: usize = 7;
+
+// the code space of a complete Huffman code, see HuffmanTree::code_space_used
+const COMPLETE_CODE_SPACE: u32 = 1 << 16;
+
+// Only the first warnings are kept so that a stream full of anomalies can't grow the inflater.
+const MAX_WARNINGS: usize = 16;
+
 /// The streaming Inflater for deflate64
 ///
 /// This struct has big buffer so It's not recommended to move this struct.
-#[derive(Debug)]
-pub struct InflaterManaged {
+///
+/// The type parameter selects whether checkpoints are tracked, see [`CheckpointTracking`].
+///
+/// Cloning an inflater snapshots its whole state, including the history window, so a clone
+/// can decode ahead speculatively while the original continues from the same position.
+///
+/// With the `serde` feature, the whole state can be serialized, and deserialized as
+/// `Box<InflaterManaged>`, as part of another snapshot format. Unlike checkpoints, this
+/// works at any point of the stream, but the serialized form is not stable across versions
+/// of this crate. Deserializing fails for a state which no inflater could have reached.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct InflaterManaged<T: CheckpointTracking = NoCheckpoint> {
     output: OutputWindow,
     bits: BitsBuffer,
     literal_length_tree: HuffmanTree,
@@ -54,6 +94,8 @@ pub struct InflaterManaged {
     state: InflaterState,
     bfinal: bool,
     block_type: BlockType,
+    block_count: u64,        // number of blocks started so far
+    at_block_boundary: bool, // nothing was decoded since the end of the last block
 
     // uncompressed block
     block_length_buffer: [u8; 4],
@@ -71,12 +113,22 @@ pub struct InflaterManaged {
     code_array_size: u32,
     length_code: u16,
 
+    #[cfg_attr(
+        feature = "serde",
+        serde(serialize_with = "crate::serde_array::serialize")
+    )]
     code_list: [u8; HuffmanTree::MAX_LITERAL_TREE_ELEMENTS + HuffmanTree::MAX_DIST_TREE_ELEMENTS], // temporary array to store the code length for literal/Length and distance
     code_length_tree_code_length: [u8; HuffmanTree::NUMBER_OF_CODE_LENGTH_TREE_ELEMENTS],
     deflate64: bool,
     code_length_tree: HuffmanTree,
-    uncompressed_size: usize,
+    options: InflaterOptions,
     current_inflated_count: usize,
+    output_crc32: Crc32, // only updated if options.tracks_crc32()
+    error: Option<InflateError>,
+    warnings: [InflateWarning; MAX_WARNINGS],
+    warning_count: usize,
+    stats: DecodeStats,  // only updated if options.collect_stats
+    filled_history: u64, // zeros copied for matches before the start of the stream
 
     // Cumulative counters updated once per inflate call
     total_input_loaded: u64, // total bytes loaded into bit reader, only updated after decode()
@@ -86,9 +138,24 @@ pub struct InflaterManaged {
     #[cfg(feature = "checkpoint")]
     checkpoint_input_bits: u64, // exact input bit position of checkpoint
     #[cfg(feature = "checkpoint")]
+    checkpoint_history_len: u64, // history_len() at the last block boundary checkpoint
+    #[cfg(feature = "checkpoint")]
     checkpoint_bit_buffer: u8, // low byte of input bit_buffer (future bits)
     #[cfg(feature = "checkpoint")]
     checkpoint_bfinal_block_type: u8, // (bfinal << 7) | block_type
+    #[cfg(feature = "checkpoint")]
+    boundary_checkpoint_requested: bool, // stop at the next block boundary
+    #[cfg(feature = "checkpoint")]
+    #[cfg_attr(
+        feature = "serde",
+        serde(serialize_with = "crate::serde_array::serialize")
+    )]
+    checkpoint_metadata: [u8; MAX_CHECKPOINT_METADATA_LEN], // caller payload of checkpoints
+    #[cfg(feature = "checkpoint")]
+    checkpoint_metadata_len: u8,
+
+    #[cfg_attr(feature = "serde", serde(skip))]
+    tracking: PhantomData<T>,
 }
 
 impl InflaterManaged {
@@ -96,11 +163,205 @@ impl InflaterManaged {
     #[allow(clippy::new_without_default)]
     #[inline]
     pub fn new() -> Self {
-        Self::with_uncompressed_size(usize::MAX)
+        Self::with_options(InflaterOptions::new())
     }
 
     /// Initializes Inflater with expected uncompressed size.
+    ///
+    /// This is the same as [`InflaterOptions::uncompressed_size`].
     pub fn with_uncompressed_size(uncompressed_size: usize) -> Self {
+        Self::with_options(InflaterOptions::new().uncompressed_size(uncompressed_size))
+    }
+
+    /// Initializes Inflater with the sizes and CRC-32 of a zip entry.
+    ///
+    /// This is the same as [`InflaterOptions::entry_metadata`]: the inflater consumes at
+    /// most `compressed_size` bytes of input, and producing more than `uncompressed_size`
+    /// bytes of output is a data error. Call [`Self::finish`] after the end of the stream
+    /// to verify both sizes and the CRC-32 of the output.
+    pub fn with_entry_metadata(metadata: EntryMetadata) -> Self {
+        Self::with_options(InflaterOptions::new().entry_metadata(metadata))
+    }
+
+    /// Initializes Inflater with options.
+    pub fn with_options(options: InflaterOptions) -> Self {
+        Self::init(options)
+    }
+
+    /// Initializes Inflater on the heap.
+    ///
+    /// The inflater is more than 140 KiB large, mostly for its history window, so threads
+    /// with small stacks should keep it in a box. In optimized builds, this constructs the
+    /// inflater directly on the heap without a temporary on the stack. Debug builds may
+    /// still construct it on the stack first, since that can't be avoided without unsafe
+    /// code.
+    #[inline(always)]
+    pub fn boxed() -> Box<Self> {
+        Self::boxed_with_options(InflaterOptions::new())
+    }
+
+    /// Initializes Inflater with options on the heap, see [`Self::boxed`].
+    #[inline(always)]
+    pub fn boxed_with_options(options: InflaterOptions) -> Box<Self> {
+        Box::new(Self::init(options))
+    }
+
+    /// Decompresses the whole deflate64 stream in `input` without keeping the output,
+    /// and returns its sizes and block count.
+    ///
+    /// This is faster than [`Self::inflate`] into a scratch buffer since the output is
+    /// never copied out of the history window. Bytes after the end of the stream are
+    /// ignored, and input which ends before the end of the stream is an error.
+    pub fn measure(input: &[u8]) -> Result<StreamMeasurements, InflateError> {
+        let mut inflater = Self::boxed();
+        inflater.discard_all(input, &mut NoObserver)?;
+        let position = InputBuffer::new(inflater.bits, &[]);
+        Ok(StreamMeasurements {
+            compressed_size: inflater.input_bits(&position).div_ceil(8),
+            uncompressed_size: inflater.total_output_consumed,
+            block_count: inflater.block_count,
+        })
+    }
+
+    /// Decompresses the whole deflate64 stream in `input` without keeping the output,
+    /// and returns the input and output ranges of each block.
+    ///
+    /// Like [`Self::measure`], bytes after the end of the stream are ignored, and input
+    /// which ends before the end of the stream is an error. To keep the blocks found
+    /// before an error, for example in damaged archives, pass a [`BlockRecorder`] to
+    /// [`Self::inflate_observed`] instead.
+    pub fn enumerate_blocks(input: &[u8]) -> Result<Vec<BlockInfo>, InflateError> {
+        let mut recorder = BlockRecorder::new();
+        Self::boxed().discard_all(input, &mut recorder)?;
+        Ok(recorder.into_blocks())
+    }
+
+    /// Reads a whole deflate64 stream from `reader` without keeping the output, and returns
+    /// its compressed and uncompressed sizes, and its CRC-32 if `track_crc32` is true.
+    ///
+    /// This is useful for zip entries whose local header doesn't record the sizes, when the
+    /// central directory is not available. Only the bytes of the stream are consumed from
+    /// `reader`, so it is positioned right after the stream on success. A reader which ends
+    /// before the stream is an [`io::ErrorKind::UnexpectedEof`] error.
+    pub fn scan_uncompressed_size(
+        reader: &mut impl BufRead,
+        track_crc32: bool,
+    ) -> io::Result<StreamScan> {
+        let mut inflater =
+            Self::boxed_with_options(InflaterOptions::new().track_crc32(track_crc32));
+        let mut compressed_size = 0;
+        let mut uncompressed_size = 0;
+        loop {
+            let input = reader.fill_buf()?;
+            let eof = input.is_empty();
+            let summary = inflater.validate(input)?;
+            reader.consume(summary.bytes_consumed);
+            compressed_size += summary.bytes_consumed as u64;
+            uncompressed_size += summary.bytes_written as u64;
+            if summary.status == InflateStatus::Finished {
+                break;
+            }
+            if eof {
+                let position = InputBuffer::new(inflater.bits, &[]);
+                return Err(inflater
+                    .new_error(InflateErrorKind::IncompleteStream, &position)
+                    .into());
+            }
+        }
+        Ok(StreamScan {
+            compressed_size,
+            uncompressed_size,
+            crc32: inflater.output_crc32(),
+        })
+    }
+
+    // decompresses the whole stream without keeping the output
+    fn discard_all(
+        &mut self,
+        input: &[u8],
+        observer: &mut impl InflateObserver,
+    ) -> Result<(), InflateError> {
+        let mut consumed = 0;
+        loop {
+            let (result, _) = self.inflate_internal(
+                &input[consumed..],
+                Buffer::Discard(usize::MAX),
+                false,
+                observer,
+            );
+            consumed += result.bytes_consumed;
+            if let Some(error) = self.error {
+                return Err(error);
+            }
+            if self.finished() {
+                return Ok(());
+            }
+            if result.is_stalled() {
+                return Err(self.new_error(
+                    InflateErrorKind::IncompleteStream,
+                    &InputBuffer::new(self.bits, &[]),
+                ));
+            }
+        }
+    }
+
+    /// Decompresses the whole deflate64 stream in `input` into `output`, which must have
+    /// exactly the uncompressed size of the stream, and returns the count of bytes
+    /// consumed from `input`.
+    ///
+    /// This fails with [`InflateErrorKind::UncompressedSizeMismatch`] if the stream
+    /// decompresses to more or fewer bytes than `output.len()`, and with
+    /// [`InflateErrorKind::IncompleteStream`] if `input` ends before the end of the stream.
+    /// Bytes after the end of the stream are not consumed.
+    pub fn decompress_into(input: &[u8], output: &mut [u8]) -> Result<usize, InflateError> {
+        let mut inflater = Self::boxed();
+        let (mut consumed, written) = inflater.inflate_all(input, output)?;
+        if written == output.len() && !inflater.finished() {
+            // check whether the stream has more output than fits
+            let result = inflater.inflate(&input[consumed..], &mut [0u8; 1]);
+            consumed += result.bytes_consumed;
+            if result.bytes_written != 0 {
+                return Err(inflater.new_error(
+                    InflateErrorKind::UncompressedSizeMismatch,
+                    &InputBuffer::new(inflater.bits, &[]),
+                ));
+            }
+        }
+        inflater.finish()?;
+        if written != output.len() {
+            return Err(inflater.new_error(
+                InflateErrorKind::UncompressedSizeMismatch,
+                &InputBuffer::new(inflater.bits, &[]),
+            ));
+        }
+        Ok(consumed)
+    }
+}
+
+#[cfg(feature = "checkpoint")]
+#[cfg_attr(docsrs, doc(cfg(feature = "checkpoint")))]
+impl InflaterManaged<Checkpointing> {
+    /// Initializes Inflater which keeps track of checkpoints, see [`Self::checkpoint`].
+    pub fn with_checkpoints() -> Self {
+        Self::with_checkpoints_and_options(InflaterOptions::new())
+    }
+
+    /// Initializes Inflater which keeps track of checkpoints with expected uncompressed size.
+    pub fn with_checkpoints_and_uncompressed_size(uncompressed_size: usize) -> Self {
+        Self::with_checkpoints_and_options(
+            InflaterOptions::new().uncompressed_size(uncompressed_size),
+        )
+    }
+
+    /// Initializes Inflater which keeps track of checkpoints with options.
+    pub fn with_checkpoints_and_options(options: InflaterOptions) -> Self {
+        Self::init(options)
+    }
+}
+
+impl<T: CheckpointTracking> InflaterManaged<T> {
+    #[inline(always)]
+    fn init(options: InflaterOptions) -> Self {
         Self {
             output: OutputWindow::new(),
             bits: BitsBuffer::new(),
@@ -111,10 +372,12 @@ impl InflaterManaged {
             code_length_tree_code_length: [0u8; HuffmanTree::NUMBER_OF_CODE_LENGTH_TREE_ELEMENTS],
             deflate64: true,
             code_length_tree: HuffmanTree::invalid(),
-            uncompressed_size,
+            options,
             state: InflaterState::ReadingBFinal, // start by reading BFinal bit
             bfinal: false,
             block_type: BlockType::Uncompressed,
+            block_count: 0,
+            at_block_boundary: false,
             block_length_buffer: [0u8; 4],
             block_length: 0,
             length: 0,
@@ -128,14 +391,149 @@ impl InflaterManaged {
             distance_tree: HuffmanTree::invalid(),
             length_code: 0,
             current_inflated_count: 0,
+            output_crc32: Crc32::new(),
+            error: None,
+            warnings: [InflateWarning::new(InflateWarningKind::EmptyStoredBlock, 0, 0);
+                MAX_WARNINGS],
+            warning_count: 0,
+            stats: DecodeStats::default(),
+            filled_history: 0,
             total_input_loaded: 0,
             total_output_consumed: 0,
             #[cfg(feature = "checkpoint")]
             checkpoint_input_bits: 0,
             #[cfg(feature = "checkpoint")]
+            checkpoint_history_len: 0,
+            #[cfg(feature = "checkpoint")]
             checkpoint_bit_buffer: 0,
             #[cfg(feature = "checkpoint")]
             checkpoint_bfinal_block_type: 0,
+            #[cfg(feature = "checkpoint")]
+            boundary_checkpoint_requested: false,
+            #[cfg(feature = "checkpoint")]
+            checkpoint_metadata: [0; MAX_CHECKPOINT_METADATA_LEN],
+            #[cfg(feature = "checkpoint")]
+            checkpoint_metadata_len: 0,
+            tracking: PhantomData,
+        }
+    }
+
+    /// Resets this inflater to decompress a new deflate64 stream.
+    ///
+    /// The options this inflater was created with are kept. This is useful to reuse a boxed
+    /// inflater, for example when a codec is reinitialized between streams.
+    pub fn reset(&mut self) {
+        self.reset_with_options(self.options);
+    }
+
+    /// Resets this inflater to decompress a new deflate64 stream, as if it was created with
+    /// [`InflaterManaged::with_uncompressed_size`].
+    pub fn reset_with_uncompressed_size(&mut self, uncompressed_size: usize) {
+        self.reset_with_options(InflaterOptions::new().uncompressed_size(uncompressed_size));
+    }
+
+    /// Resets this inflater to decompress a new deflate64 stream, as if it was created with
+    /// [`InflaterManaged::with_options`].
+    ///
+    /// This is cheaper than creating a new inflater: the state is reset in place, and only
+    /// the history window is cleared, so extracting many small zip entries with one
+    /// inflater doesn't pay for constructing and moving the large inflater for each entry.
+    pub fn reset_with_options(&mut self, options: InflaterOptions) {
+        // same as init(), but without a temporary copy of the window
+        let Self {
+            output,
+            bits,
+            literal_length_tree,
+            distance_tree,
+            state,
+            bfinal,
+            block_type,
+            block_count,
+            at_block_boundary,
+            block_length_buffer,
+            block_length,
+            length,
+            distance_code,
+            extra_bits,
+            loop_counter,
+            literal_length_code_count,
+            distance_code_count,
+            code_length_code_count,
+            code_array_size,
+            length_code,
+            code_list,
+            code_length_tree_code_length,
+            deflate64,
+            code_length_tree,
+            options: old_options,
+            current_inflated_count,
+            output_crc32,
+            error,
+            warnings,
+            warning_count,
+            stats,
+            filled_history,
+            total_input_loaded,
+            total_output_consumed,
+            #[cfg(feature = "checkpoint")]
+            checkpoint_input_bits,
+            #[cfg(feature = "checkpoint")]
+            checkpoint_history_len,
+            #[cfg(feature = "checkpoint")]
+            checkpoint_bit_buffer,
+            #[cfg(feature = "checkpoint")]
+            checkpoint_bfinal_block_type,
+            #[cfg(feature = "checkpoint")]
+            boundary_checkpoint_requested,
+            #[cfg(feature = "checkpoint")]
+            checkpoint_metadata,
+            #[cfg(feature = "checkpoint")]
+            checkpoint_metadata_len,
+            tracking: _,
+        } = self;
+        output.reset();
+        *bits = BitsBuffer::new();
+        *literal_length_tree = HuffmanTree::invalid();
+        *distance_tree = HuffmanTree::invalid();
+        *state = InflaterState::ReadingBFinal;
+        *bfinal = false;
+        *block_type = BlockType::Uncompressed;
+        *block_count = 0;
+        *at_block_boundary = false;
+        *block_length_buffer = [0u8; 4];
+        *block_length = 0;
+        *length = 0;
+        *distance_code = 0;
+        *extra_bits = 0;
+        *loop_counter = 0;
+        *literal_length_code_count = 0;
+        *distance_code_count = 0;
+        *code_length_code_count = 0;
+        *code_array_size = 0;
+        *length_code = 0;
+        code_list.fill(0);
+        code_length_tree_code_length.fill(0);
+        *deflate64 = true;
+        *code_length_tree = HuffmanTree::invalid();
+        *old_options = options;
+        *current_inflated_count = 0;
+        *output_crc32 = Crc32::new();
+        *error = None;
+        *warnings = [InflateWarning::new(InflateWarningKind::EmptyStoredBlock, 0, 0); MAX_WARNINGS];
+        *warning_count = 0;
+        *stats = DecodeStats::default();
+        *filled_history = 0;
+        *total_input_loaded = 0;
+        *total_output_consumed = 0;
+        #[cfg(feature = "checkpoint")]
+        {
+            *checkpoint_input_bits = 0;
+            *checkpoint_history_len = 0;
+            *checkpoint_bit_buffer = 0;
+            *checkpoint_bfinal_block_type = 0;
+            *boundary_checkpoint_requested = false;
+            checkpoint_metadata.fill(0);
+            *checkpoint_metadata_len = 0;
         }
     }
 
@@ -159,18 +557,384 @@ impl InflaterManaged {
         self.state == InflaterState::DataErrored
     }
 
+    /// Returns the type of the block being decoded, or `None` between blocks, before the
+    /// first block, after the end of the stream, and after an error.
+    pub fn current_block_type(&self) -> Option<BlockType> {
+        match self.state {
+            InflaterState::ReadingBFinal
+            | InflaterState::ReadingBType
+            | InflaterState::Done
+            | InflaterState::DataErrored => None,
+            _ => Some(self.block_type),
+        }
+    }
+
+    /// Returns true if the input consumed so far ends exactly at the end of a block, or at
+    /// the start of the stream, so that no part of a block was decoded yet.
+    ///
+    /// Output of the previous blocks may still be pending, see [`Self::available_output`].
+    pub fn is_at_block_boundary(&self) -> bool {
+        matches!(
+            self.state,
+            InflaterState::ReadingBFinal | InflaterState::Done
+        )
+    }
+
+    /// Returns true if the block being decoded, or the last block decoded, is the final
+    /// block of the stream.
+    pub fn is_final_block(&self) -> bool {
+        self.bfinal && !matches!(self.state, InflaterState::ReadingBFinal)
+    }
+
+    /// Skips damaged input after an error, and prepares to continue decoding with the next
+    /// plausible block.
+    ///
+    /// `input` is the input after the bytes consumed so far. It is searched bit by bit for
+    /// the header of an uncompressed block or a dynamic block which would be valid in
+    /// [strict mode](InflaterOptions::strict); static blocks are not searched for, since
+    /// any 3 bits look like their header. If a header is found, the error is cleared and
+    /// the returned [`DataGap`] tells how much of `input` was skipped. The output returned
+    /// afterwards is decoded from the block found. Returns `None` if this inflater has no
+    /// decoding error, or if no header was found in `input`; call this again with more
+    /// input to search further.
+    ///
+    /// The data lost in the gap is missing from the history window, so matches which
+    /// reach back across the gap copy wrong bytes. A header found in damaged data may be
+    /// a false positive and fail to decode again.
+    pub fn resync(&mut self, input: &[u8]) -> Option<DataGap> {
+        let error = self.error?;
+        if matches!(
+            error.kind(),
+            InflateErrorKind::OutputLimitExceeded | InflateErrorKind::UncompressedSizeMismatch
+        ) {
+            // not damage of the stream
+            return None;
+        }
+        let input = self.entry_input(input);
+        let input_start = self.total_input_loaded * 8;
+        let mut trial = InflaterManaged::<NoCheckpoint>::boxed_with_options(
+            InflaterOptions::new().strict(true),
+        );
+        let start_bit = (error.input_bit_offset() + 1).max(input_start) - input_start;
+        for bit in start_bit..input.len() as u64 * 8 {
+            let (byte, shift) = ((bit / 8) as usize, (bit % 8) as u32);
+            // BTYPE is the two bits after BFINAL
+            let header =
+                (input[byte] as u32 | (*input.get(byte + 1).unwrap_or(&0) as u32) << 8) >> shift;
+            if (header >> 1) & 0b11 != BlockType::Uncompressed as u32
+                && (header >> 1) & 0b11 != BlockType::Dynamic as u32
+            {
+                continue;
+            }
+
+            let bits = BitsBuffer::from_bits(input[byte] as u32 >> shift, 8 - shift as i32);
+            let bytes_consumed = byte + 1;
+            trial.total_input_loaded = self.total_input_loaded + bytes_consumed as u64;
+            match trial.is_block_header(bits, &input[bytes_consumed..]) {
+                Some(true) => {}
+                Some(false) => continue,
+                // a header may continue after the end of input
+                None => return None,
+            }
+
+            self.bits = bits;
+            self.total_input_loaded += bytes_consumed as u64;
+            self.state = InflaterState::ReadingBFinal;
+            self.error = None;
+            return Some(DataGap {
+                bytes_consumed,
+                input_bits: error.input_bit_offset()..input_start + bit,
+                output_offset: self.history_len(),
+            });
+        }
+        None
+    }
+
+    // truncates `input` to the rest of the compressed size of the entry metadata, if any
+    fn entry_input<'a>(&self, input: &'a [u8]) -> &'a [u8] {
+        match self.options.entry_metadata {
+            Some(metadata) => {
+                let remaining = metadata
+                    .compressed_size
+                    .saturating_sub(self.total_input_loaded);
+                &input[..min(input.len() as u64, remaining) as usize]
+            }
+            None => input,
+        }
+    }
+
+    // decodes the block header at the start of `bits` and `input`, and returns if it is
+    // valid, or None if the input ends in the header
+    fn is_block_header(&mut self, bits: BitsBuffer, input: &[u8]) -> Option<bool> {
+        self.state = InflaterState::ReadingBFinal;
+        self.error = None;
+        self.output.clear_bytes_used();
+        let mut input = InputBuffer::new(bits, input);
+        match self.decode(&mut input, &mut NoObserver) {
+            // the data of an uncompressed block is copied right after its header, and
+            // decoding stops right after the header of a dynamic block
+            Ok(()) => Some(match self.block_type {
+                BlockType::Uncompressed => true,
+                BlockType::Dynamic => self.state == InflaterState::DecodeTop,
+                BlockType::Static => false,
+            }),
+            Err(InternalErr::DataNeeded) => match self.state {
+                InflaterState::DecodingUncompressed => Some(true),
+                _ if self.state >= InflaterState::DecodeTop
+                    && self.state < InflaterState::UncompressedAligning =>
+                {
+                    // in the data of a static block
+                    Some(false)
+                }
+                _ => None,
+            },
+            Err(InternalErr::DataError(_)) => Some(false),
+        }
+    }
+
+    /// Returns the exact length of the deflate64 stream in bits, once its final block was
+    /// decoded.
+    ///
+    /// The stream ends in the middle of its last byte unless this is a multiple of 8. The
+    /// inflater never consumes input bytes after that byte, so once this returns `Some`,
+    /// the input after `bytes_consumed` of the last call is data following the stream.
+    pub fn stream_end_bits(&self) -> Option<u64> {
+        (self.state == InflaterState::Done)
+            .then(|| self.input_bits(&InputBuffer::new(self.bits, &[])))
+    }
+
+    /// Returns the length of the deflate64 stream in bytes, including the padding bits of
+    /// its last byte, once its final block was decoded. See [`Self::stream_end_bits`].
+    pub fn stream_len(&self) -> Option<u64> {
+        self.stream_end_bits().map(|bits| bits.div_ceil(8))
+    }
+
+    /// Returns the error which stopped decompression, if this inflater is in error state
+    pub fn error(&self) -> Option<&InflateError> {
+        self.error.as_ref()
+    }
+
+    /// Returns the CRC-32 of the output returned so far, if it is computed, see
+    /// [`InflaterOptions::track_crc32`].
+    pub fn output_crc32(&self) -> Option<u32> {
+        self.options
+            .tracks_crc32()
+            .then(|| self.output_crc32.value())
+    }
+
+    /// Returns the non-fatal anomalies found so far.
+    ///
+    /// Only the first 16 anomalies are recorded.
+    pub fn warnings(&self) -> &[InflateWarning] {
+        &self.warnings[..self.warning_count]
+    }
+
+    /// Returns the statistics of the stream decoded so far, if they are collected, see
+    /// [`InflaterOptions::collect_stats`].
+    ///
+    /// After restoring a checkpoint, the statistics only cover the part of the stream
+    /// decoded since.
+    pub fn stats(&self) -> Option<&DecodeStats> {
+        self.options.collect_stats.then_some(&self.stats)
+    }
+
+    /// Returns the count of zeros copied for matches which reach back before the start of
+    /// the stream, see [`InflaterOptions::fill_missing_history`].
+    pub fn filled_history_bytes(&self) -> u64 {
+        self.filled_history
+    }
+
+    /// Verifies that the deflate64 stream ended and all output was returned.
+    ///
+    /// If this inflater was created with [`InflaterOptions::entry_metadata`], this also verifies
+    /// the compressed size, the uncompressed size, and the CRC-32 of the output.
+    pub fn finish(&self) -> Result<(), InflateError> {
+        if let Some(error) = self.error {
+            return Err(error);
+        }
+        let position = InputBuffer::new(self.bits, &[]);
+        if !self.finished() {
+            return Err(self.new_error(InflateErrorKind::IncompleteStream, &position));
+        }
+        if let Some(metadata) = self.options.entry_metadata {
+            let stream_length = self.input_bits(&position).div_ceil(8);
+            let mismatch = if stream_length != metadata.compressed_size {
+                Some(InflateErrorKind::CompressedSizeMismatch)
+            } else if self.total_output_consumed != metadata.uncompressed_size {
+                Some(InflateErrorKind::UncompressedSizeMismatch)
+            } else if self.output_crc32.value() != metadata.crc32 {
+                Some(InflateErrorKind::Crc32Mismatch)
+            } else {
+                None
+            };
+            if let Some(kind) = mismatch {
+                return Err(self.new_error(kind, &position));
+            }
+        }
+        if self.options.enforces_uncompressed_size()
+            && self.total_output_consumed != self.options.uncompressed_size as u64
+        {
+            return Err(self.new_error(InflateErrorKind::UncompressedSizeMismatch, &position));
+        }
+        Ok(())
+    }
+
     /// The count of bytes currently inflater has in internal output buffer
     #[allow(dead_code)]
     pub fn available_output(&self) -> usize {
         self.output.available_bytes()
     }
 
+    /// Returns the memory used by this inflater.
+    ///
+    /// All state is kept inline, so the total is the size of `InflaterManaged` and doesn't
+    /// change while decoding; a `Box`ed inflater uses this much heap memory.
+    pub fn memory_usage(&self) -> MemoryUsage {
+        MemoryUsage {
+            total: std::mem::size_of_val(self),
+            window: std::mem::size_of::<OutputWindow>(),
+            huffman_trees: 3 * std::mem::size_of::<HuffmanTree>(),
+            heap: 0,
+        }
+    }
+
+    /// Returns the count of output bytes still expected, if the uncompressed size was set
+    /// with [`InflaterOptions::uncompressed_size`] or [`InflaterOptions::entry_metadata`].
+    ///
+    /// This is the declared size minus the output returned so far. If the stream ends while
+    /// this is not zero, the stream is shorter than declared.
+    pub fn remaining_expected_output(&self) -> Option<u64> {
+        let declared = (self.options.uncompressed_size != usize::MAX)
+            .then_some(self.options.uncompressed_size as u64);
+        let metadata = self
+            .options
+            .entry_metadata
+            .map(|metadata| metadata.uncompressed_size);
+        let expected = match (declared, metadata) {
+            (Some(declared), Some(metadata)) => Some(min(declared, metadata)),
+            (expected, None) | (None, expected) => expected,
+        };
+        expected.map(|expected| expected.saturating_sub(self.total_output_consumed))
+    }
+
+    /// Returns the count of input bytes consumed since the start of the stream, which is
+    /// the sum of `bytes_consumed` of all calls.
+    ///
+    /// After restoring a checkpoint, this still counts from the start of the stream, so it
+    /// starts at `input_bytes_to_skip` of the restored positions.
+    pub fn total_in(&self) -> u64 {
+        self.total_input_loaded
+    }
+
+    /// Returns the count of output bytes returned since the start of the stream, which is
+    /// the sum of `bytes_written` of all calls.
+    ///
+    /// After restoring a checkpoint, this still counts from the start of the stream, so it
+    /// starts at `output_bytes_already_returned` of the restored positions.
+    pub fn total_out(&self) -> u64 {
+        self.total_output_consumed
+    }
+
+    /// Returns the count of additional input bytes which lets the inflater complete its
+    /// current step, such as a block header field, a Huffman symbol with its extra bits,
+    /// or the rest of a stored block.
+    ///
+    /// This is 0 if progress is possible without more input, because output is available
+    /// or the stream ended, and a single byte may be enough for short Huffman codes.
+    /// Network callers can use it to size their next read.
+    pub fn min_input_hint(&self) -> usize {
+        if self.input_finished() || self.output.available_bytes() > 0 {
+            return 0;
+        }
+        let buffered_bits = self.bits.bits_in_buffer as usize;
+        let needed_bits = match self.state {
+            InflaterState::ReadingBFinal => 1,
+            InflaterState::ReadingBType => 2,
+            InflaterState::ReadingNumLitCodes | InflaterState::ReadingNumDistCodes => 5,
+            InflaterState::ReadingNumCodeLengthCodes => 4,
+            InflaterState::ReadingCodeLengthCodes => 3,
+            InflaterState::ReadingTreeCodesBefore => MAX_CODE_LENGTH_CODE_BITS,
+            InflaterState::ReadingTreeCodesAfter => match self.length_code {
+                16 => 2,
+                17 => 3,
+                _ => 7,
+            },
+            InflaterState::DecodeTop => MAX_CODE_BITS,
+            InflaterState::HaveInitialLength => self.extra_bits as usize,
+            InflaterState::HaveFullLength if self.block_type == BlockType::Dynamic => MAX_CODE_BITS,
+            InflaterState::HaveFullLength => 5,
+            InflaterState::HaveDistCode if self.distance_code > 3 => {
+                ((self.distance_code - 2) >> 1) as usize
+            }
+            InflaterState::HaveDistCode => 0,
+            // the partial byte is skipped before the length is read
+            InflaterState::UncompressedAligning => buffered_bits % 8 + 8,
+            InflaterState::UncompressedByte1
+            | InflaterState::UncompressedByte2
+            | InflaterState::UncompressedByte3
+            | InflaterState::UncompressedByte4 => 8,
+            InflaterState::DecodingUncompressed => self.block_length * 8,
+            InflaterState::Done | InflaterState::DataErrored => 0,
+        };
+        let hint = needed_bits.saturating_sub(buffered_bits).div_ceil(8);
+        match self.options.entry_metadata {
+            Some(metadata) => {
+                let remaining = metadata
+                    .compressed_size
+                    .saturating_sub(self.total_input_loaded);
+                min(hint as u64, remaining) as usize
+            }
+            None => hint,
+        }
+    }
+
     /// Try to decompress from `input` to `output`.
     ///
     /// This will decompress data until `output` is full, `input` is empty,
     /// the end if the deflate64 stream is hit, or there is error data in the deflate64 stream.
     pub fn inflate(&mut self, input: &[u8], output: &mut [u8]) -> InflateResult {
-        self.inflate_internal(input, Buffer::Init(output))
+        self.inflate_internal(input, Buffer::Init(output), false, &mut NoObserver)
+            .0
+    }
+
+    /// Same as [`Self::inflate`] but returns the error if the stream is invalid.
+    ///
+    /// Some output decompressed before the error was found may already be written to
+    /// `output`; the error records where it was found in the stream.
+    pub fn inflate_checked(
+        &mut self,
+        input: &[u8],
+        output: &mut [u8],
+    ) -> Result<InflateSummary, InflateError> {
+        let result = self.inflate(input, output);
+        match self.error {
+            Some(error) => Err(error),
+            None => Ok(InflateSummary {
+                bytes_consumed: result.bytes_consumed,
+                bytes_written: result.bytes_written,
+                status: result.status,
+            }),
+        }
+    }
+
+    /// Decodes and validates `input` without writing the output anywhere.
+    ///
+    /// This checks everything [`Self::inflate_checked`] checks, including the sizes and
+    /// CRC-32 of [`InflaterOptions::entry_metadata`] when [`Self::finish`] is called, but
+    /// the output is never copied out of the history window. `bytes_written` of the
+    /// summary is the count of bytes which would have been written. Call this with
+    /// consecutive parts of the stream to validate it incrementally.
+    pub fn validate(&mut self, input: &[u8]) -> Result<InflateSummary, InflateError> {
+        let (result, _) =
+            self.inflate_internal(input, Buffer::Discard(usize::MAX), false, &mut NoObserver);
+        match self.error {
+            Some(error) => Err(error),
+            None => Ok(InflateSummary {
+                bytes_consumed: result.bytes_consumed,
+                bytes_written: result.bytes_written,
+                status: result.status,
+            }),
+        }
     }
 
     /// Same as [`Self::inflate`] but accepts uninitialized buffer
@@ -179,26 +943,240 @@ impl InflaterManaged {
         input: &[u8],
         output: &mut [MaybeUninit<u8>],
     ) -> InflateResult {
-        self.inflate_internal(input, Buffer::Uninit(output))
+        self.inflate_internal(input, Buffer::Uninit(output), false, &mut NoObserver)
+            .0
+    }
+
+    /// Same as [`Self::inflate`] but reads the input from a [`bytes::Buf`], which may
+    /// consist of multiple non-contiguous chunks.
+    ///
+    /// `input` is advanced by the count of bytes consumed.
+    #[cfg(feature = "bytes")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "bytes")))]
+    pub fn inflate_buf(&mut self, input: &mut impl bytes::Buf, output: &mut [u8]) -> InflateResult {
+        let mut result = InflateResult::new();
+        loop {
+            let chunk = input.chunk();
+            let chunk_result = self.inflate(chunk, &mut output[result.bytes_written..]);
+            let chunk_consumed = chunk_result.bytes_consumed == chunk.len();
+            input.advance(chunk_result.bytes_consumed);
+            result.bytes_consumed += chunk_result.bytes_consumed;
+            result.bytes_written += chunk_result.bytes_written;
+            result.data_error = chunk_result.data_error;
+            result.history_filled |= chunk_result.history_filled;
+            result.status = chunk_result.status;
+            if chunk_result.data_error || !chunk_consumed || !input.has_remaining() {
+                // output is full, the stream ended, there is an error, or input is exhausted
+                break;
+            }
+        }
+        result
+    }
+
+    /// Same as [`Self::inflate`] but writes the output to multiple buffers.
+    ///
+    /// The buffers are filled in order, and a buffer is only written to after all previous
+    /// buffers are full, so `bytes_written` of the result tells how far they were filled.
+    pub fn inflate_vectored(
+        &mut self,
+        input: &[u8],
+        output: &mut [IoSliceMut<'_>],
+    ) -> InflateResult {
+        if output.iter().all(|buffer| buffer.is_empty()) {
+            return self.inflate(input, &mut []);
+        }
+        let mut result = InflateResult::new();
+        for buffer in output.iter_mut().filter(|buffer| !buffer.is_empty()) {
+            let buffer_result = self.inflate(&input[result.bytes_consumed..], buffer);
+            result.bytes_consumed += buffer_result.bytes_consumed;
+            result.bytes_written += buffer_result.bytes_written;
+            result.data_error = buffer_result.data_error;
+            result.history_filled |= buffer_result.history_filled;
+            result.status = buffer_result.status;
+            if buffer_result.data_error || buffer_result.bytes_written < buffer.len() {
+                // input is exhausted, the stream ended, or there is an error
+                break;
+            }
+        }
+        result
+    }
+
+    /// Same as [`Self::inflate`] but also stops at the end of each deflate64 block.
+    ///
+    /// When the end of a block is reached and all output of the block has been written
+    /// to `output`, this returns the position of the block boundary. The input consumed
+    /// so far then ends with the byte containing the last bit of the block, so the next
+    /// call continues with the following block.
+    pub fn inflate_until_block_boundary(
+        &mut self,
+        input: &[u8],
+        output: &mut [u8],
+    ) -> (InflateResult, Option<BlockBoundary>) {
+        self.inflate_internal(input, Buffer::Init(output), true, &mut NoObserver)
     }
 
-    fn inflate_internal(&mut self, input: &[u8], mut output: Buffer<'_>) -> InflateResult {
+    /// Same as [`Self::inflate`] but reports the start and end of each block and the error
+    /// which stops decompression to `observer`.
+    ///
+    /// Events are reported as the input is decoded, so the end of a block may be reported
+    /// before all of its output was returned.
+    pub fn inflate_observed(
+        &mut self,
+        input: &[u8],
+        output: &mut [u8],
+        observer: &mut impl InflateObserver,
+    ) -> InflateResult {
+        self.inflate_internal(input, Buffer::Init(output), false, observer)
+            .0
+    }
+
+    /// Decompresses from `input` to `output` until `input` is exhausted, `output` is full,
+    /// or the end of the deflate64 stream is reached.
+    ///
+    /// This calls [`Self::inflate`] repeatedly while it makes progress, and returns the
+    /// total count of bytes consumed from `input` and written to `output`.
+    pub fn inflate_all(
+        &mut self,
+        input: &[u8],
+        output: &mut [u8],
+    ) -> Result<(usize, usize), InflateError> {
+        let mut consumed = 0;
+        let mut written = 0;
+        loop {
+            let result = self.inflate(&input[consumed..], &mut output[written..]);
+            consumed += result.bytes_consumed;
+            written += result.bytes_written;
+            if let Some(error) = self.error {
+                return Err(error);
+            }
+            if result.is_stalled() {
+                return Ok((consumed, written));
+            }
+        }
+    }
+
+    /// Decompresses from `input` into `sink` until `input` is exhausted and all output was
+    /// taken, `sink` is full, or the end of the deflate64 stream is reached, and returns the
+    /// count of bytes consumed from `input` and the count of bytes passed to `sink`.
+    ///
+    /// This can be called repeatedly with consecutive parts of the stream, and bytes after
+    /// the end of the stream are not consumed. Output decompressed before an error is found
+    /// is still passed to `sink`.
+    pub fn inflate_into_sink<S: OutputSink + ?Sized>(
+        &mut self,
+        input: &[u8],
+        sink: &mut S,
+    ) -> Result<(usize, u64), SinkError<S::Error>> {
+        let mut consumed = 0;
+        let mut written = 0;
+        loop {
+            let buffer = sink.buffer();
+            let len = buffer.len();
+            if len == 0 {
+                return Ok((consumed, written));
+            }
+            let result = self.inflate(&input[consumed..], buffer);
+            consumed += result.bytes_consumed;
+            written += result.bytes_written as u64;
+            sink.commit(result.bytes_written).map_err(SinkError::Sink)?;
+
+            if let Some(error) = self.error {
+                return Err(SinkError::Inflate(error));
+            }
+            if result.bytes_written < len {
+                // all input consumed and all output taken, or the end of the stream
+                return Ok((consumed, written));
+            }
+        }
+    }
+
+    /// Decompresses from `input` into `writer` until `input` is exhausted and all output
+    /// was written, or the end of the deflate64 stream is reached, and returns the count
+    /// of bytes consumed from `input` and the count of bytes written to `writer`.
+    ///
+    /// This is [`Self::inflate_into_sink`] with a [`WriteSink`], which writes the output with
+    /// [`Write::write_all`] in chunks of up to 64 KiB. To decompress from a reader, use
+    /// [`Deflate64Decoder`](crate::Deflate64Decoder) with [`io::copy`]. Errors in the
+    /// stream are converted from [`InflateError`], which can be recovered with
+    /// [`io::Error::downcast`].
+    pub fn inflate_to_writer(
+        &mut self,
+        input: &[u8],
+        writer: &mut impl Write,
+    ) -> io::Result<(usize, u64)> {
+        self.inflate_into_sink(input, &mut WriteSink::new(writer))
+            .map_err(|error| match error {
+                SinkError::Inflate(error) => error.into(),
+                SinkError::Sink(error) => error,
+            })
+    }
+
+    /// Decompresses from `input` and passes the output to `sink` in chunks, until `input`
+    /// is exhausted and all output was passed, the end of the deflate64 stream is reached,
+    /// or `sink` returns [`ControlFlow::Break`].
+    ///
+    /// Returns `Continue` with the count of bytes consumed from `input`, or the `Break`
+    /// value returned by `sink`. After a break, the chunk passed to `sink` is not output
+    /// again. This is [`Self::inflate_into_sink`] with a [`FnSink`], so chunks are at most
+    /// 64 KiB long.
+    pub fn inflate_with_sink<B>(
+        &mut self,
+        input: &[u8],
+        mut sink: impl FnMut(&[u8]) -> ControlFlow<B>,
+    ) -> Result<ControlFlow<B, usize>, InflateError> {
+        let mut sink = FnSink::new(|chunk: &[u8]| match sink(chunk) {
+            ControlFlow::Continue(()) => Ok(()),
+            ControlFlow::Break(value) => Err(value),
+        });
+        match self.inflate_into_sink(input, &mut sink) {
+            Ok((consumed, _)) => Ok(ControlFlow::Continue(consumed)),
+            Err(SinkError::Sink(value)) => Ok(ControlFlow::Break(value)),
+            Err(SinkError::Inflate(error)) => Err(error),
+        }
+    }
+
+    /// Returns an iterator which decompresses `input` lazily, in chunks of at most
+    /// `chunk_size` bytes.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `chunk_size` is zero.
+    pub fn chunks<'a>(&'a mut self, input: &'a [u8], chunk_size: usize) -> InflateChunks<'a, T> {
+        InflateChunks::new(self, input, chunk_size)
+    }
+
+    fn inflate_internal<O: InflateObserver + ?Sized>(
+        &mut self,
+        input: &[u8],
+        mut output: Buffer<'_>,
+        stop_at_block_boundary: bool,
+        observer: &mut O,
+    ) -> (InflateResult, Option<BlockBoundary>) {
         // copy bytes from output to outputbytes if we have available bytes
         // if buffer is not filled up. keep decoding until no input are available
         // if decodeBlock returns false. Throw an exception.
         let mut result = InflateResult::new();
+        let filled_history = self.filled_history;
+        let input = self.entry_input(input);
         let mut input = InputBuffer::new(self.bits, input);
+        let mut data_needed = false;
+        let mut block_boundary = None;
         while 'while_loop: {
+            let output_limit = self
+                .options
+                .output_limit(self.input_bits(&input).div_ceil(8));
             let mut copied = 0;
-            if self.uncompressed_size == usize::MAX {
-                copied = self.output.copy_to(output.reborrow());
-            } else if self.uncompressed_size > self.current_inflated_count {
+            if self.options.uncompressed_size == usize::MAX
+                || self.options.enforces_uncompressed_size()
+            {
+                copied = self.copy_output(output.reborrow(), output_limit);
+            } else if self.options.uncompressed_size > self.current_inflated_count {
                 let len = min(
                     output.len(),
-                    self.uncompressed_size - self.current_inflated_count,
+                    self.options.uncompressed_size - self.current_inflated_count,
                 );
                 output = output.index_mut(..len);
-                copied = self.output.copy_to(output.reborrow());
+                copied = self.copy_output(output.reborrow(), output_limit);
                 self.current_inflated_count += copied;
             } else {
                 self.state = InflaterState::Done;
@@ -208,6 +1186,44 @@ impl InflaterManaged {
                 output = output.index_mut(copied..);
                 result.bytes_written += copied;
                 self.total_output_consumed += copied as u64;
+                let interval = self.options.progress_interval;
+                if interval > 0
+                    && (self.total_output_consumed - copied as u64) / interval
+                        != self.total_output_consumed / interval
+                {
+                    observer.on_progress(self.input_bits(&input), self.total_output_consumed);
+                }
+            }
+
+            if let Some((limit, kind)) = output_limit {
+                // a restored checkpoint may already be past the limit of the expansion ratio
+                let exceeded = self.total_output_consumed > limit
+                    || (self.total_output_consumed == limit && self.output.available_bytes() > 0);
+                if exceeded && !self.errored() {
+                    // the stream has more output than the entry declares or the limit allows
+                    let error = self.new_error(kind, &input);
+                    observer.on_error(&error);
+                    self.error = Some(error);
+                    self.state = InflaterState::DataErrored;
+                }
+            }
+
+            if (stop_at_block_boundary || self.stops_for_boundary_checkpoint())
+                && self.at_block_boundary
+                && self.output.available_bytes() == 0
+                && !self.errored()
+            {
+                self.at_block_boundary = false;
+                #[cfg(feature = "checkpoint")]
+                {
+                    self.boundary_checkpoint_requested = false;
+                }
+                block_boundary = Some(BlockBoundary {
+                    input_bits: self.input_bits(&input),
+                    output_bytes: self.total_output_consumed,
+                    end_of_stream: self.state == InflaterState::Done,
+                });
+                break 'while_loop false;
             }
 
             if output.is_empty() {
@@ -221,10 +1237,16 @@ impl InflaterManaged {
             } else if self.input_finished() {
                 break 'while_loop false;
             }
-            match self.decode(&mut input) {
+            match self.decode(&mut input, observer) {
                 Ok(()) => true,
-                Err(InternalErr::DataNeeded) => false,
-                Err(InternalErr::DataError) => {
+                Err(InternalErr::DataNeeded) => {
+                    data_needed = true;
+                    false
+                }
+                Err(InternalErr::DataError(kind)) => {
+                    let error = self.new_error(kind, &input);
+                    observer.on_error(&error);
+                    self.error = Some(error);
                     self.state = InflaterState::DataErrored;
                     result.data_error = true;
                     false
@@ -232,18 +1254,135 @@ impl InflaterManaged {
             }
         } {}
 
+        if !data_needed {
+            // Give back whole bytes loaded ahead into the bit buffer, so at the end of
+            // the stream bytes_consumed stops exactly after its last byte.
+            // When more data is needed, every buffered bit belongs to the pending read.
+            input.unread_whole_bytes();
+        }
+
         self.bits = input.bits;
         self.total_input_loaded += input.read_bytes as u64;
         result.bytes_consumed = input.read_bytes;
-        result
+        result.history_filled = self.filled_history != filled_history;
+        result.status = if self.errored() {
+            InflateStatus::DataError
+        } else if self.finished() {
+            InflateStatus::Finished
+        } else if block_boundary.is_some() {
+            InflateStatus::BlockBoundary
+        } else if output.is_empty() {
+            InflateStatus::NeedsOutput
+        } else {
+            InflateStatus::NeedsInput
+        };
+        (result, block_boundary)
+    }
+
+    #[inline(always)]
+    fn stops_for_boundary_checkpoint(&self) -> bool {
+        #[cfg(feature = "checkpoint")]
+        {
+            T::ENABLED && self.boundary_checkpoint_requested
+        }
+        #[cfg(not(feature = "checkpoint"))]
+        {
+            false
+        }
     }
 
-    fn decode(&mut self, input: &mut InputBuffer<'_>) -> Result<(), InternalErr> {
+    fn copy_output(
+        &mut self,
+        mut output: Buffer<'_>,
+        limit: Option<(u64, InflateErrorKind)>,
+    ) -> usize {
+        if let Some((limit, _)) = limit {
+            let remaining = limit.saturating_sub(self.total_output_consumed);
+            let len = min(output.len() as u64, remaining) as usize;
+            output = output.index_mut(..len);
+        }
+        let copied = self.output.copy_to(output);
+        if self.options.tracks_crc32() {
+            let (first, second) = self.output.recently_copied(copied);
+            self.output_crc32.update(first);
+            self.output_crc32.update(second);
+        }
+        copied
+    }
+
+    // count of bytes written to the window since the start of the stream
+    fn history_len(&self) -> u64 {
+        self.total_output_consumed + self.output.available_bytes() as u64
+    }
+
+    fn input_bits(&self, input: &InputBuffer<'_>) -> u64 {
+        (self.total_input_loaded + input.read_bytes as u64) * 8 - input.available_bits() as u64
+    }
+
+    fn new_error(&self, kind: InflateErrorKind, input: &InputBuffer<'_>) -> InflateError {
+        // the block type is not known yet if we failed reading it
+        let block_type = (self.state != InflaterState::ReadingBType).then_some(self.block_type);
+        InflateError::new(
+            kind,
+            self.block_count.saturating_sub(1),
+            block_type,
+            self.input_bits(input),
+            self.total_output_consumed + self.output.available_bytes() as u64,
+        )
+    }
+
+    fn warn(
+        &mut self,
+        kind: InflateWarningKind,
+        input: &InputBuffer<'_>,
+    ) -> Result<(), InternalErr> {
+        if let Some(error) = kind.strict_error().filter(|_| self.options.strict) {
+            return Err(InternalErr::DataError(error));
+        }
+        if self.warning_count < MAX_WARNINGS {
+            self.warnings[self.warning_count] = InflateWarning::new(
+                kind,
+                self.block_count.saturating_sub(1),
+                self.input_bits(input) / 8,
+            );
+            self.warning_count += 1;
+        }
+        Ok(())
+    }
+
+    // copies a match which reaches back before the start of the stream, with zeros for the
+    // missing history if the options allow it
+    #[cold]
+    fn fill_missing_history(
+        &mut self,
+        length: usize,
+        distance: usize,
+        input: &InputBuffer<'_>,
+    ) -> Result<(), InternalErr> {
+        if !self.options.fill_missing_history {
+            return Err(InternalErr::DataError(InflateErrorKind::DistanceTooFarBack));
+        }
+        self.warn(InflateWarningKind::MissingHistory, input)?;
+        let missing = min(length as u64, distance as u64 - self.history_len()) as usize;
+        for _ in 0..missing {
+            self.output.write(0);
+        }
+        self.output
+            .write_length_distance(length - missing, distance);
+        self.filled_history += missing as u64;
+        Ok(())
+    }
+
+    fn decode<O: InflateObserver + ?Sized>(
+        &mut self,
+        input: &mut InputBuffer<'_>,
+        observer: &mut O,
+    ) -> Result<(), InternalErr> {
         let mut eob = false;
         let result;
 
-        if self.errored() {
-            return Err(InternalErr::DataError);
+        if let Some(error) = self.error {
+            return Err(InternalErr::DataError(error.kind()));
         } else if self.input_finished() {
             return Ok(());
         }
@@ -252,6 +1391,7 @@ impl InflaterManaged {
             // reading bfinal bit
             // Need 1 bit
             self.bfinal = input.get_bits(1)? != 0;
+            self.block_count += 1;
             self.state = InflaterState::ReadingBType;
         }
 
@@ -260,7 +1400,14 @@ impl InflaterManaged {
             self.state = InflaterState::ReadingBType;
             let bits = input.get_bits(2)?;
 
-            self.block_type = BlockType::from_int(bits).ok_or(InternalErr::DataError)?;
+            self.block_type = BlockType::from_int(bits)
+                .ok_or(InternalErr::DataError(InflateErrorKind::InvalidBlockType))?;
+            // BFINAL and BTYPE are the 3 bits just read
+            observer.on_block_start(self.block_type, self.bfinal, self.input_bits(input) - 3);
+            if self.options.collect_stats {
+                let output_offset = self.history_len();
+                self.stats.block_start(self.block_type, output_offset);
+            }
             match self.block_type {
                 BlockType::Dynamic => {
                     self.state = InflaterState::ReadingNumLitCodes;
@@ -288,16 +1435,24 @@ impl InflaterManaged {
         } else if self.block_type == BlockType::Uncompressed {
             result = self.decode_uncompressed_block(input, &mut eob);
         } else {
-            result = Err(InternalErr::DataError); // UnknownBlockType
+            result = Err(InternalErr::DataError(InflateErrorKind::InvalidBlockType));
         }
 
         //
         // If we reached the end of the block and the block we were decoding had
         // bfinal=1 (final block)
         //
-        if eob && self.bfinal {
-            self.state = InflaterState::Done;
+        if eob {
+            observer.on_block_end(self.input_bits(input), self.history_len());
+            if self.options.collect_stats {
+                let output_offset = self.history_len();
+                self.stats.block_end(output_offset);
+            }
+            if self.bfinal {
+                self.state = InflaterState::Done;
+            }
         }
+        self.at_block_boundary = eob;
         result
     }
 
@@ -310,7 +1465,9 @@ impl InflaterManaged {
         loop {
             match self.state {
                 InflaterState::UncompressedAligning => {
-                    input.skip_to_byte_boundary();
+                    if input.skip_to_byte_boundary() != 0 {
+                        self.warn(InflateWarningKind::NonZeroStoredBlockPadding, input)?;
+                    }
                     self.state = InflaterState::UncompressedByte1;
                     continue; //goto case InflaterState.UncompressedByte1;
                 }
@@ -329,7 +1486,12 @@ impl InflaterManaged {
 
                         // make sure complement matches
                         if self.block_length as u16 != !block_length_complement as u16 {
-                            return Err(InternalErr::DataError); // InvalidBlockLength
+                            return Err(InternalErr::DataError(
+                                InflateErrorKind::InvalidStoredBlockLength,
+                            ));
+                        }
+                        if self.block_length == 0 {
+                            self.warn(InflateWarningKind::EmptyStoredBlock, input)?;
                         }
                     }
 
@@ -368,7 +1530,7 @@ impl InflaterManaged {
                     return Err(InternalErr::DataNeeded);
                 }
                 _ => {
-                    panic!("UnknownState");
+                    return Err(InternalErr::DataError(InflateErrorKind::InvalidState));
                 }
             }
         }
@@ -405,8 +1567,8 @@ impl InflaterManaged {
                     self.update_checkpoint_after_write_or_eob(input, false);
                     return Ok(());
                 }
-                Err(InternalErr::DataError) => {
-                    return Err(InternalErr::DataError);
+                Err(InternalErr::DataError(kind)) => {
+                    return Err(InternalErr::DataError(kind));
                 }
                 Err(InternalErr::DataNeeded) => {
                     unreachable!("fast inner loop never returns DataNeeded")
@@ -433,6 +1595,9 @@ impl InflaterManaged {
                         // literal
                         self.output.write(symbol as u8);
                         free_bytes -= 1;
+                        if self.options.collect_stats {
+                            self.stats.literal();
+                        }
                         #[cfg(feature = "checkpoint")]
                         self.update_checkpoint_after_write_or_eob(input, false);
                     } else if symbol == 256 {
@@ -446,6 +1611,7 @@ impl InflaterManaged {
                     } else {
                         // length/distance pair
                         symbol -= 257; // length code started at 257
+                        let length_index = symbol as usize;
                         if symbol < 8 {
                             symbol += 3; // match length = 3,4,5,6,7,8,9,10
                             self.extra_bits = 0;
@@ -455,12 +1621,17 @@ impl InflaterManaged {
                             self.extra_bits = 0;
                         } else {
                             if symbol as usize >= EXTRA_LENGTH_BITS.len() {
-                                return Err(InternalErr::DataError); // GenericInvalidData
+                                return Err(InternalErr::DataError(
+                                    InflateErrorKind::InvalidSymbol,
+                                ));
                             }
                             self.extra_bits = EXTRA_LENGTH_BITS[symbol as usize] as i32;
                             assert_ne!(self.extra_bits, 0, "We handle other cases separately!");
                         }
                         self.length = symbol as usize;
+                        if self.options.collect_stats {
+                            self.stats.length_code(length_index);
+                        }
 
                         self.state = InflaterState::HaveInitialLength;
                         continue; //goto case InflaterState::HaveInitialLength;
@@ -472,7 +1643,7 @@ impl InflaterManaged {
                         let bits = input.get_bits(self.extra_bits)?;
 
                         if self.length >= LENGTH_BASE.len() {
-                            return Err(InternalErr::DataError); // GenericInvalidData
+                            return Err(InternalErr::DataError(InflateErrorKind::InvalidSymbol));
                         }
                         self.length = LENGTH_BASE[self.length] as usize + bits as usize;
                     }
@@ -488,6 +1659,9 @@ impl InflaterManaged {
                         let bits = input.get_bits(5)?;
                         self.distance_code = STATIC_DISTANCE_TREE_TABLE[bits as usize] as u16;
                     }
+                    if self.options.collect_stats {
+                        self.stats.distance_code(self.distance_code as usize);
+                    }
 
                     self.state = InflaterState::HaveDistCode;
                     continue; //goto case InflaterState.HaveDistCode;
@@ -506,20 +1680,28 @@ impl InflaterManaged {
                         offset = (self.distance_code + 1) as usize;
                     }
 
-                    if self.length > TABLE_LOOKUP_LENGTH_MAX || offset > TABLE_LOOKUP_DISTANCE_MAX {
-                        return Err(InternalErr::DataError);
+                    if self.length > TABLE_LOOKUP_LENGTH_MAX {
+                        return Err(InternalErr::DataError(InflateErrorKind::InvalidLength));
+                    }
+                    if offset > TABLE_LOOKUP_DISTANCE_MAX {
+                        return Err(InternalErr::DataError(InflateErrorKind::InvalidDistance));
+                    }
+                    if offset as u64 > self.history_len() {
+                        self.fill_missing_history(self.length, offset, input)?;
+                    } else {
+                        self.output.write_length_distance(self.length, offset);
                     }
-
-                    self.output.write_length_distance(self.length, offset);
                     free_bytes -= self.length;
+                    if self.options.collect_stats {
+                        self.stats.match_copied(self.length, offset);
+                    }
                     self.state = InflaterState::DecodeTop;
                     #[cfg(feature = "checkpoint")]
                     self.update_checkpoint_after_write_or_eob(input, false);
                 }
 
                 _ => {
-                    //Debug.Fail("check why we are here!");
-                    panic!("UnknownState");
+                    return Err(InternalErr::DataError(InflateErrorKind::InvalidState));
                 }
             }
         }
@@ -546,7 +1728,24 @@ impl InflaterManaged {
             if self.output.free_bytes() < TABLE_LOOKUP_LENGTH_MAX || input.available_bytes() < 8 {
                 return Ok((initial_free - self.output.free_bytes(), false));
             }
+            // Refill with up to 8 bytes at once, so that the loads of a literal, or of a
+            // length with its extra bits, don't have to read input
+            if input.available_bits() < 32 {
+                input.refill();
+            }
 
+            if let Some(literals) = self
+                .literal_length_tree
+                .get_literal_pair_assume_input(input)
+            {
+                self.output.write(literals[0]);
+                self.output.write(literals[1]);
+                if self.options.collect_stats {
+                    self.stats.literal();
+                    self.stats.literal();
+                }
+                continue;
+            }
             let symbol = self
                 .literal_length_tree
                 .get_next_symbol_assume_input(input)?;
@@ -554,6 +1753,9 @@ impl InflaterManaged {
                 0..=255 => {
                     // Literal byte
                     self.output.write(symbol as u8);
+                    if self.options.collect_stats {
+                        self.stats.literal();
+                    }
                 }
                 256 => {
                     // End of block
@@ -583,18 +1785,31 @@ impl InflaterManaged {
                         let bits = input.get_bits_assume_input(extra_bits);
                         *DISTANCE_BASE_POSITION
                             .get(distance_code)
-                            .ok_or(InternalErr::DataError)? as usize
+                            .ok_or(InternalErr::DataError(InflateErrorKind::InvalidSymbol))?
+                            as usize
                             + bits as usize
                     };
 
-                    if length > TABLE_LOOKUP_LENGTH_MAX || offset > TABLE_LOOKUP_DISTANCE_MAX {
-                        return Err(InternalErr::DataError);
+                    if length > TABLE_LOOKUP_LENGTH_MAX {
+                        return Err(InternalErr::DataError(InflateErrorKind::InvalidLength));
+                    }
+                    if offset > TABLE_LOOKUP_DISTANCE_MAX {
+                        return Err(InternalErr::DataError(InflateErrorKind::InvalidDistance));
+                    }
+                    if offset as u64 > self.history_len() {
+                        self.fill_missing_history(length, offset, input)?;
+                    } else {
+                        self.output.write_length_distance(length, offset);
+                    }
+                    if self.options.collect_stats {
+                        self.stats.length_code(length_index);
+                        self.stats.distance_code(distance_code);
+                        self.stats.match_copied(length, offset);
                     }
-                    self.output.write_length_distance(length, offset);
                 }
                 _ => {
                     // Symbol out of range
-                    return Err(InternalErr::DataError);
+                    return Err(InternalErr::DataError(InflateErrorKind::InvalidSymbol));
                 }
             }
         }
@@ -632,6 +1847,12 @@ impl InflaterManaged {
                 InflaterState::ReadingNumLitCodes => {
                     let bits = input.get_bits(5)?;
                     self.literal_length_code_count = bits as u32 + 257;
+                    if self.options.strict && self.literal_length_code_count > 286 {
+                        // symbols 286 and 287 never occur in a valid stream
+                        return Err(InternalErr::DataError(
+                            InflateErrorKind::InvalidLiteralLengthTree,
+                        ));
+                    }
                     self.state = InflaterState::ReadingNumDistCodes;
                     continue 'switch; //goto case InflaterState::ReadingNumDistCodes;
                 }
@@ -660,9 +1881,21 @@ impl InflaterManaged {
                         self.code_length_tree_code_length[code_oder as usize] = 0;
                     }
 
+                    if self.options.strict
+                        && HuffmanTree::code_space_used(&self.code_length_tree_code_length)
+                            != COMPLETE_CODE_SPACE
+                    {
+                        return Err(InternalErr::DataError(
+                            InflateErrorKind::InvalidCodeLengthTree,
+                        ));
+                    }
+
                     // create huffman tree for code length
                     self.code_length_tree
-                        .new_in_place(&self.code_length_tree_code_length)?;
+                        .new_in_place(&self.code_length_tree_code_length)
+                        .map_err(|_| {
+                            InternalErr::DataError(InflateErrorKind::InvalidCodeLengthTree)
+                        })?;
                     self.code_array_size =
                         self.literal_length_code_count + self.distance_code_count;
                     self.loop_counter = 0; // reset loop count
@@ -698,7 +1931,9 @@ impl InflaterManaged {
 
                                 if self.loop_counter == 0 {
                                     // can't have "prev code" on first code
-                                    return Err(InternalErr::DataError);
+                                    return Err(InternalErr::DataError(
+                                        InflateErrorKind::InvalidCodeLengthRepeat,
+                                    ));
                                 }
 
                                 let bits = input.get_bits(2)?;
@@ -708,7 +1943,9 @@ impl InflaterManaged {
 
                                 if self.loop_counter + repeat_count > self.code_array_size {
                                     //throw new InvalidDataException();
-                                    return Err(InternalErr::DataError);
+                                    return Err(InternalErr::DataError(
+                                        InflateErrorKind::CodeLengthRepeatOverflow,
+                                    ));
                                 }
 
                                 for _ in 0..repeat_count {
@@ -723,7 +1960,9 @@ impl InflaterManaged {
 
                                 if self.loop_counter + repeat_count > self.code_array_size {
                                     //throw new InvalidDataException();
-                                    return Err(InternalErr::DataError);
+                                    return Err(InternalErr::DataError(
+                                        InflateErrorKind::CodeLengthRepeatOverflow,
+                                    ));
                                 }
 
                                 for _ in 0..repeat_count {
@@ -739,7 +1978,9 @@ impl InflaterManaged {
 
                                 if self.loop_counter + repeat_count > self.code_array_size {
                                     //throw new InvalidDataException();
-                                    return Err(InternalErr::DataError);
+                                    return Err(InternalErr::DataError(
+                                        InflateErrorKind::CodeLengthRepeatOverflow,
+                                    ));
                                 }
 
                                 for _ in 0..repeat_count {
@@ -753,7 +1994,7 @@ impl InflaterManaged {
                     break 'switch;
                 }
                 _ => {
-                    panic!("InvalidDataException: UnknownState");
+                    return Err(InternalErr::DataError(InflateErrorKind::InvalidState));
                 }
             }
         }
@@ -777,17 +2018,196 @@ impl InflaterManaged {
 
         // Make sure there is an end-of-block code, otherwise how could we ever end?
         if literal_tree_code_length[HuffmanTree::END_OF_BLOCK_CODE] == 0 {
-            return Err(InternalErr::DataError); // InvalidDataException
+            return Err(InternalErr::DataError(
+                InflateErrorKind::MissingEndOfBlockCode,
+            ));
+        }
+
+        match HuffmanTree::code_space_used(&literal_tree_code_length) {
+            COMPLETE_CODE_SPACE => {}
+            used if used < COMPLETE_CODE_SPACE => {
+                self.warn(InflateWarningKind::IncompleteLiteralLengthTree, input)?
+            }
+            _ => self.warn(InflateWarningKind::OverSubscribedLiteralLengthTree, input)?,
+        }
+        // RFC 1951 allows no distance codes, or a single distance code of one bit
+        let single_one_bit_code = distance_tree_code_length
+            .iter()
+            .filter(|&&len| len != 0)
+            .eq(&[1]);
+        match HuffmanTree::code_space_used(&distance_tree_code_length) {
+            COMPLETE_CODE_SPACE | 0 => {}
+            _ if single_one_bit_code => {}
+            used if used < COMPLETE_CODE_SPACE => {
+                self.warn(InflateWarningKind::IncompleteDistanceTree, input)?
+            }
+            _ => self.warn(InflateWarningKind::OverSubscribedDistanceTree, input)?,
         }
 
         self.literal_length_tree
-            .new_in_place(&literal_tree_code_length)?;
+            .new_in_place(&literal_tree_code_length)
+            .map_err(|_| InternalErr::DataError(InflateErrorKind::InvalidLiteralLengthTree))?;
         self.distance_tree
-            .new_in_place(&distance_tree_code_length)?;
+            .new_in_place(&distance_tree_code_length)
+            .map_err(|_| InternalErr::DataError(InflateErrorKind::InvalidDistanceTree))?;
         self.state = InflaterState::DecodeTop;
         Ok(())
     }
 }
 
+#[cfg(feature = "serde")]
+crate::serde_in_place::impl_deserialize_in_place! {
+    impl<T: CheckpointTracking> DeserializeInPlace for InflaterManaged<T> as "InflaterManaged" {
+        output: deserialize_in_place,
+        bits: assign,
+        literal_length_tree: assign,
+        distance_tree: assign,
+        state: assign,
+        bfinal: assign,
+        block_type: assign,
+        block_count: assign,
+        at_block_boundary: assign,
+        block_length_buffer: assign,
+        block_length: assign,
+        length: assign,
+        distance_code: assign,
+        extra_bits: assign,
+        loop_counter: assign,
+        literal_length_code_count: assign,
+        distance_code_count: assign,
+        code_length_code_count: assign,
+        code_array_size: assign,
+        length_code: assign,
+        code_list: assign_array,
+        code_length_tree_code_length: assign,
+        deflate64: assign,
+        code_length_tree: assign,
+        options: assign,
+        current_inflated_count: assign,
+        output_crc32: assign,
+        error: assign,
+        warnings: assign,
+        warning_count: assign,
+        stats: assign,
+        filled_history: assign,
+        total_input_loaded: assign,
+        total_output_consumed: assign,
+    }
+    #[cfg(feature = "checkpoint")] {
+        checkpoint_input_bits: assign,
+        checkpoint_history_len: assign,
+        checkpoint_bit_buffer: assign,
+        checkpoint_bfinal_block_type: assign,
+        boundary_checkpoint_requested: assign,
+        checkpoint_metadata: assign_array,
+        checkpoint_metadata_len: assign,
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<T: CheckpointTracking> InflaterManaged<T> {
+    // Checks the invariants which decoding relies on instead of checking them again, so
+    // that a deserialized state which no inflater could have reached is rejected rather
+    // than indexing out of bounds. Trees which failed to build are only left behind by a
+    // data error, after which they are rebuilt before they are used again.
+    fn validate_deserialized(&mut self) -> Result<(), &'static str> {
+        if !self.output.is_valid() {
+            return Err("output window position out of range");
+        }
+        if !self.bits.is_valid() {
+            return Err("bit buffer out of range");
+        }
+        if self.warning_count > MAX_WARNINGS {
+            return Err("warning count out of range");
+        }
+        if self.code_list.iter().any(|&len| len > 15)
+            || self.code_length_tree_code_length.iter().any(|&len| len > 7)
+        {
+            return Err("code length out of range");
+        }
+        for tree in [
+            &mut self.literal_length_tree,
+            &mut self.distance_tree,
+            &mut self.code_length_tree,
+        ] {
+            if !tree.is_valid() {
+                if self.state != InflaterState::DataErrored {
+                    return Err("Huffman tree doesn't match its code lengths");
+                }
+                *tree = HuffmanTree::invalid();
+            }
+        }
+        if self.extra_bits < 0 || self.extra_bits > 16 || self.distance_code >= 32 {
+            return Err("match state out of range");
+        }
+
+        let dynamic = self.block_type == BlockType::Dynamic;
+        let consistent = match self.state {
+            InflaterState::ReadingNumLitCodes
+            | InflaterState::ReadingNumDistCodes
+            | InflaterState::ReadingNumCodeLengthCodes => dynamic,
+            InflaterState::ReadingCodeLengthCodes => {
+                dynamic
+                    && self.code_length_code_count as usize <= CODE_ORDER.len()
+                    && self.loop_counter <= self.code_length_code_count
+            }
+            InflaterState::ReadingTreeCodesBefore | InflaterState::ReadingTreeCodesAfter => {
+                dynamic
+                    && self.code_length_tree.code_lengths().len()
+                        == HuffmanTree::NUMBER_OF_CODE_LENGTH_TREE_ELEMENTS
+                    && self.literal_length_code_count as usize
+                        <= HuffmanTree::MAX_LITERAL_TREE_ELEMENTS
+                    && self.distance_code_count as usize <= HuffmanTree::MAX_DIST_TREE_ELEMENTS
+                    && self.code_array_size
+                        == self.literal_length_code_count + self.distance_code_count
+                    && self.loop_counter <= self.code_array_size
+            }
+            InflaterState::DecodeTop
+            | InflaterState::HaveInitialLength
+            | InflaterState::HaveFullLength
+            | InflaterState::HaveDistCode => {
+                self.block_type != BlockType::Uncompressed
+                    && self.literal_length_tree.code_lengths().len()
+                        == HuffmanTree::MAX_LITERAL_TREE_ELEMENTS
+                    && self.distance_tree.code_lengths().len()
+                        == HuffmanTree::MAX_DIST_TREE_ELEMENTS
+            }
+            InflaterState::UncompressedAligning => self.block_type == BlockType::Uncompressed,
+            InflaterState::UncompressedByte1
+            | InflaterState::UncompressedByte2
+            | InflaterState::UncompressedByte3
+            | InflaterState::UncompressedByte4
+            | InflaterState::DecodingUncompressed => {
+                self.block_type == BlockType::Uncompressed && self.bits.bits_in_buffer % 8 == 0
+            }
+            InflaterState::ReadingBFinal
+            | InflaterState::ReadingBType
+            | InflaterState::Done
+            | InflaterState::DataErrored => true,
+        };
+        if !consistent {
+            return Err("decoder state inconsistent with the block");
+        }
+        Ok(())
+    }
+}
+
+/// Deserializes the inflater into a new box, since the inflater is too large to be
+/// deserialized by value. Fails if the state is one no inflater could have reached.
+#[cfg(feature = "serde")]
+impl<'de, T: CheckpointTracking> serde::Deserialize<'de> for Box<InflaterManaged<T>> {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let mut inflater = Box::new(InflaterManaged::init(InflaterOptions::new()));
+        crate::serde_in_place::deserialize_in_place(&mut *inflater, deserializer)?;
+        inflater.validate_deserialized().map_err(|reason| {
+            serde::de::Error::custom(format_args!("invalid inflater state: {reason}"))
+        })?;
+        Ok(inflater)
+    }
+}
+
 #[cfg(feature = "checkpoint")]
 include!("inflater_checkpoint_impl.rs");
+
+#[cfg(feature = "debug-dump")]
+include!("inflater_dump_impl.rs");
@@ -2,8 +2,9 @@ use crate::InternalErr;
 use std::cmp::min;
 
 #[derive(Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub(crate) struct BitsBuffer {
-    pub(crate) bit_buffer: u32,
+    pub(crate) bit_buffer: u64,
     pub(crate) bits_in_buffer: i32,
 }
 
@@ -20,10 +21,16 @@ impl BitsBuffer {
         debug_assert!((0..=32).contains(&num_bits));
         let mask = (!0u32).unbounded_shr(num_bits as u32);
         Self {
-            bit_buffer: bits & mask,
+            bit_buffer: (bits & mask) as u64,
             bits_in_buffer: num_bits,
         }
     }
+
+    // whether the bit count is one the reader can reach, with no bits set above it
+    #[cfg(feature = "serde")]
+    pub(crate) fn is_valid(&self) -> bool {
+        (0..64).contains(&self.bits_in_buffer) && self.bit_buffer >> self.bits_in_buffer == 0
+    }
 }
 
 #[derive(Clone)]
@@ -47,7 +54,7 @@ impl<'a> InputBuffer<'a> {
     }
 
     #[allow(dead_code)]
-    pub fn peek_available_bits(&self) -> u32 {
+    pub fn peek_available_bits(&self) -> u64 {
         self.bits.bit_buffer
     }
 
@@ -65,7 +72,7 @@ impl<'a> InputBuffer<'a> {
             }
 
             // insert a byte to bitbuffer
-            self.bits.bit_buffer |= (self.buffer[0] as u32) << self.bits.bits_in_buffer;
+            self.bits.bit_buffer |= (self.buffer[0] as u64) << self.bits.bits_in_buffer;
             self.advance(1);
             self.bits.bits_in_buffer += 8;
 
@@ -74,7 +81,7 @@ impl<'a> InputBuffer<'a> {
                     return false;
                 }
                 // insert a byte to bitbuffer
-                self.bits.bit_buffer |= (self.buffer[0] as u32) << self.bits.bits_in_buffer;
+                self.bits.bit_buffer |= (self.buffer[0] as u64) << self.bits.bits_in_buffer;
                 self.advance(1);
                 self.bits.bits_in_buffer += 8;
             }
@@ -83,20 +90,20 @@ impl<'a> InputBuffer<'a> {
         true
     }
 
-    pub fn try_load_16bits(&mut self) -> u32 {
+    pub fn try_load_16bits(&mut self) -> u64 {
         if self.bits.bits_in_buffer < 8 {
             if self.buffer.len() > 1 {
-                self.bits.bit_buffer |= (self.buffer[0] as u32) << self.bits.bits_in_buffer;
-                self.bits.bit_buffer |= (self.buffer[1] as u32) << (self.bits.bits_in_buffer + 8);
+                self.bits.bit_buffer |= (self.buffer[0] as u64) << self.bits.bits_in_buffer;
+                self.bits.bit_buffer |= (self.buffer[1] as u64) << (self.bits.bits_in_buffer + 8);
                 self.advance(2);
                 self.bits.bits_in_buffer += 16;
             } else if !self.buffer.is_empty() {
-                self.bits.bit_buffer |= (self.buffer[0] as u32) << self.bits.bits_in_buffer;
+                self.bits.bit_buffer |= (self.buffer[0] as u64) << self.bits.bits_in_buffer;
                 self.advance(1);
                 self.bits.bits_in_buffer += 8;
             }
         } else if self.bits.bits_in_buffer < 16 && !self.buffer.is_empty() {
-            self.bits.bit_buffer |= (self.buffer[0] as u32) << self.bits.bits_in_buffer;
+            self.bits.bit_buffer |= (self.buffer[0] as u64) << self.bits.bits_in_buffer;
             self.advance(1);
             self.bits.bits_in_buffer += 8;
         }
@@ -104,7 +111,7 @@ impl<'a> InputBuffer<'a> {
         self.bits.bit_buffer
     }
 
-    fn get_bit_mask(&self, count: i32) -> u32 {
+    fn get_bit_mask(&self, count: i32) -> u64 {
         (1 << count) - 1
     }
 
@@ -121,16 +128,31 @@ impl<'a> InputBuffer<'a> {
         Ok(result)
     }
 
+    // refill refills the bit buffer to at least 56 bits with one unaligned 8-byte load if
+    // 8 input bytes are left, so that several symbols with their extra bits can be decoded
+    // before the next refill. Only the whole bytes which fit are consumed, and the bits of
+    // the next byte are masked off again.
+    #[inline(always)]
+    pub fn refill(&mut self) {
+        if let Some(head) = self.buffer.first_chunk::<8>() {
+            let bytes = (63 - self.bits.bits_in_buffer as usize) / 8;
+            self.bits.bit_buffer |= u64::from_le_bytes(*head) << self.bits.bits_in_buffer;
+            self.bits.bits_in_buffer += bytes as i32 * 8;
+            self.bits.bit_buffer &= self.get_bit_mask(self.bits.bits_in_buffer);
+            self.advance(bytes);
+        }
+    }
+
     // load_16bits_assume_input is an optimization of try_load_16bits when the caller knows
     // that 16 bits are in the bit buffer or available as remaining input bytes. If this
     // precondition is false, the call will assert in debug builds and fill with zeros in
     // release builds (likely resulting in data error).
     #[inline(always)]
-    pub fn load_16bits_assume_input(&mut self) -> u32 {
+    pub fn load_16bits_assume_input(&mut self) -> u64 {
         if self.bits.bits_in_buffer < 16 {
             if let Some((head, tail)) = self.buffer.split_at_checked(2) {
                 let word = u16::from_le_bytes(head.try_into().unwrap());
-                self.bits.bit_buffer |= (word as u32) << self.bits.bits_in_buffer;
+                self.bits.bit_buffer |= (word as u64) << self.bits.bits_in_buffer;
                 self.buffer = tail;
                 self.read_bytes += 2;
             } else {
@@ -148,7 +170,7 @@ impl<'a> InputBuffer<'a> {
     #[inline(always)]
     pub fn get_bits_assume_input(&mut self, count: i32) -> u32 {
         debug_assert!(0 < count && count <= 16, "count is invalid.");
-        let result = self.load_16bits_assume_input() & self.get_bit_mask(count);
+        let result = (self.load_16bits_assume_input() & self.get_bit_mask(count)) as u32;
         self.bits.bit_buffer >>= count;
         self.bits.bits_in_buffer -= count;
         result
@@ -177,6 +199,16 @@ impl<'a> InputBuffer<'a> {
         bytes_from_bit_buffer + length
     }
 
+    /// Gives back the whole bytes in the bit buffer which were read from this buffer.
+    pub fn unread_whole_bytes(&mut self) {
+        let bytes = min(self.bits.bits_in_buffer as usize / 8, self.read_bytes);
+        if bytes > 0 {
+            self.bits.bits_in_buffer -= bytes as i32 * 8;
+            self.bits.bit_buffer &= self.get_bit_mask(self.bits.bits_in_buffer);
+            self.read_bytes -= bytes;
+        }
+    }
+
     pub fn needs_input(&self) -> bool {
         self.buffer.is_empty()
     }
@@ -192,9 +224,13 @@ impl<'a> InputBuffer<'a> {
     }
 
     /// <summary>Skips to the next byte boundary.</summary>
-    pub fn skip_to_byte_boundary(&mut self) {
-        self.bits.bit_buffer >>= self.bits.bits_in_buffer % 8;
-        self.bits.bits_in_buffer -= self.bits.bits_in_buffer % 8;
+    /// Returns the skipped padding bits.
+    pub fn skip_to_byte_boundary(&mut self) -> u32 {
+        let padding_bits = self.bits.bits_in_buffer % 8;
+        let padding = self.bits.bit_buffer & self.get_bit_mask(padding_bits);
+        self.bits.bit_buffer >>= padding_bits;
+        self.bits.bits_in_buffer -= padding_bits;
+        padding as u32
     }
 
     fn advance(&mut self, buf: usize) {
@@ -8,25 +8,153 @@
 #![deny(rust_2018_idioms, nonstandard_style, future_incompatible)]
 #![cfg_attr(docsrs, feature(doc_cfg))]
 
+#[cfg(all(feature = "checkpoint", any(feature = "futures-io", feature = "tokio")))]
+mod async_random_access;
+#[cfg(any(feature = "futures-io", feature = "tokio"))]
+mod async_stream;
 mod buffer;
+#[cfg(feature = "stream")]
+mod byte_stream;
+mod carve;
+#[cfg(feature = "checkpoint")]
+mod checkpoint_policy;
+#[cfg(feature = "checkpoint")]
+mod checkpoint_transform;
+mod chunks;
+#[cfg(feature = "tokio-util")]
+mod codec;
+#[cfg(feature = "test-util")]
+#[cfg_attr(docsrs, doc(cfg(feature = "test-util")))]
+pub mod conformance;
+mod crc32;
+mod error;
+pub mod flate2_compat;
 mod huffman_tree;
+#[cfg(feature = "checkpoint")]
+mod index;
 mod inflater_managed;
 mod input_buffer;
+mod observer;
+mod options;
 mod output_window;
+mod pipeline;
+#[cfg(feature = "pool")]
+mod pool;
+#[cfg(feature = "checkpoint")]
+mod random_access;
+#[cfg(feature = "checkpoint")]
+mod seekable;
+#[cfg(feature = "serde")]
+mod serde_array;
+#[cfg(feature = "serde")]
+mod serde_in_place;
+mod sink;
+mod stats;
 mod stream;
+mod warning;
 
+#[cfg(all(feature = "checkpoint", any(feature = "futures-io", feature = "tokio")))]
+#[cfg_attr(
+    docsrs,
+    doc(cfg(all(feature = "checkpoint", any(feature = "futures-io", feature = "tokio"))))
+)]
+pub use async_random_access::{AsyncRandomAccessReader, AsyncReadAt};
+#[cfg(any(feature = "futures-io", feature = "tokio"))]
+#[cfg_attr(docsrs, doc(cfg(any(feature = "futures-io", feature = "tokio"))))]
+pub use async_stream::AsyncDeflate64Decoder;
+#[cfg(feature = "stream")]
+#[cfg_attr(docsrs, doc(cfg(feature = "stream")))]
+pub use byte_stream::Deflate64Stream;
+pub use carve::{CarveConfidence, CarvedStream, StreamCarver};
+#[cfg(feature = "checkpoint")]
+#[cfg_attr(docsrs, doc(cfg(feature = "checkpoint")))]
+pub use checkpoint_policy::CheckpointPolicy;
+#[cfg(feature = "checkpoint")]
+#[cfg_attr(docsrs, doc(cfg(feature = "checkpoint")))]
+pub use checkpoint_transform::CheckpointTransform;
+pub use chunks::InflateChunks;
+#[cfg(feature = "tokio-util")]
+#[cfg_attr(docsrs, doc(cfg(feature = "tokio-util")))]
+pub use codec::Deflate64Codec;
+pub use error::{InflateError, InflateErrorKind};
+#[cfg(feature = "checkpoint")]
+#[cfg_attr(docsrs, doc(cfg(feature = "checkpoint")))]
+pub use index::{Deflate64Index, Deflate64IndexEntry};
 pub use inflater_managed::InflaterManaged;
-pub use stream::Deflate64Decoder;
+#[cfg(feature = "checkpoint")]
+#[cfg_attr(docsrs, doc(cfg(feature = "checkpoint")))]
+pub use inflater_managed::{
+    validate_checkpoint, verify_checkpoint_chain, MAX_CHECKPOINT_METADATA_LEN, MAX_CHECKPOINT_SIZE,
+};
+pub use observer::{BlockInfo, BlockRecorder, InflateObserver};
+pub use options::InflaterOptions;
+pub use pipeline::PipelinedDecoder;
+#[cfg(feature = "pool")]
+#[cfg_attr(docsrs, doc(cfg(feature = "pool")))]
+pub use pool::{InflaterPool, PooledInflater};
+#[cfg(feature = "checkpoint")]
+#[cfg_attr(docsrs, doc(cfg(feature = "checkpoint")))]
+pub use random_access::RandomAccessInflater;
+#[cfg(feature = "checkpoint")]
+#[cfg_attr(docsrs, doc(cfg(feature = "checkpoint")))]
+pub use seekable::{Deflate64Cursor, SeekableDeflate64Reader};
+pub use sink::{FnSink, OutputSink, SinkError, WriteSink};
+pub use stats::DecodeStats;
+pub use stream::{Deflate64Decoder, Deflate64WriteDecoder};
+pub use warning::{InflateWarning, InflateWarningKind};
 
-#[derive(Debug, Copy, Clone, Eq, PartialEq)]
-enum BlockType {
+mod sealed {
+    pub trait Sealed {}
+}
+
+/// Selects whether an [`InflaterManaged`] keeps track of checkpoints.
+///
+/// Tracking checkpoints costs time for every decoded symbol, so inflaters only track them
+/// when created with `Checkpointing`. Enabling the `checkpoint` feature alone does not
+/// slow down inflaters created with [`NoCheckpoint`], the default.
+///
+/// This trait is sealed and can't be implemented outside of this crate.
+pub trait CheckpointTracking: sealed::Sealed {
+    #[doc(hidden)]
+    const ENABLED: bool;
+}
+
+/// Checkpoint tracking mode which does not track checkpoints.
+#[derive(Debug, Copy, Clone)]
+pub struct NoCheckpoint;
+
+impl sealed::Sealed for NoCheckpoint {}
+impl CheckpointTracking for NoCheckpoint {
+    const ENABLED: bool = false;
+}
+
+/// Checkpoint tracking mode which tracks checkpoints, see [`InflaterManaged::with_checkpoints`].
+#[cfg(feature = "checkpoint")]
+#[cfg_attr(docsrs, doc(cfg(feature = "checkpoint")))]
+#[derive(Debug, Copy, Clone)]
+pub struct Checkpointing;
+
+#[cfg(feature = "checkpoint")]
+impl sealed::Sealed for Checkpointing {}
+#[cfg(feature = "checkpoint")]
+impl CheckpointTracking for Checkpointing {
+    const ENABLED: bool = true;
+}
+
+/// The type of a deflate64 block.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum BlockType {
+    /// Stored block (BTYPE `00`) without compression.
     Uncompressed = 0,
+    /// Block compressed with the fixed Huffman codes (BTYPE `01`).
     Static = 1,
+    /// Block compressed with Huffman codes described in the block header (BTYPE `10`).
     Dynamic = 2,
 }
 
 impl BlockType {
-    pub fn from_int(int: u16) -> Option<BlockType> {
+    pub(crate) fn from_int(int: u16) -> Option<BlockType> {
         match int {
             0 => Some(Self::Uncompressed),
             1 => Some(Self::Static),
@@ -37,6 +165,7 @@ impl BlockType {
 }
 
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 enum InflaterState {
     //ReadingHeader = 0,           // Only applies to GZIP
     ReadingBFinal = 2,
@@ -104,7 +233,10 @@ fn array_copy1<T: Copy>(
 }
 
 /// A structure containing result of streaming inflate.
+///
+/// Fields may be added in minor releases, so create it with [`InflateResult::new`].
 #[derive(Debug)]
+#[non_exhaustive]
 pub struct InflateResult {
     /// The number of bytes consumed from the input slice.
     pub bytes_consumed: usize,
@@ -112,6 +244,11 @@ pub struct InflateResult {
     pub bytes_written: usize,
     /// true if there is error in input buffer
     pub data_error: bool,
+    /// true if zeros were copied for missing history while decoding during this call, see
+    /// [`InflaterOptions::fill_missing_history`].
+    pub history_filled: bool,
+    /// Why the call stopped, which tells what the next call needs to make progress.
+    pub status: InflateStatus,
 }
 
 impl InflateResult {
@@ -122,14 +259,149 @@ impl InflateResult {
             bytes_consumed: 0,
             bytes_written: 0,
             data_error: false,
+            history_filled: false,
+            status: InflateStatus::NeedsInput,
         }
     }
+
+    /// Returns true if the call made no progress: it neither consumed input nor wrote
+    /// output.
+    ///
+    /// Calling again with the same buffers makes no progress either, so loops around
+    /// [`InflaterManaged::inflate`] should stop, or provide what [`Self::status`] tells is
+    /// missing: more input for [`InflateStatus::NeedsInput`], and more output space for
+    /// [`InflateStatus::NeedsOutput`].
+    pub fn is_stalled(&self) -> bool {
+        self.bytes_consumed == 0 && self.bytes_written == 0
+    }
+}
+
+/// The reason an inflate call returned, see [`InflateResult::status`].
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum InflateStatus {
+    /// All input was consumed, and more is needed to continue the stream.
+    NeedsInput,
+    /// The output buffer is full; call again with more output space, even if all input
+    /// was consumed.
+    NeedsOutput,
+    /// The end of the stream was reached and all output was returned.
+    Finished,
+    /// The stream is invalid, see [`InflaterManaged::error`].
+    DataError,
+    /// The end of a block was reached, see [`InflaterManaged::inflate_until_block_boundary`],
+    /// or the block boundary checkpoint requested with
+    /// `InflaterManaged::request_boundary_checkpoint` is ready.
+    BlockBoundary,
+}
+
+/// The progress of a successful [`InflaterManaged::inflate_checked`] call.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[non_exhaustive]
+pub struct InflateSummary {
+    /// The number of bytes consumed from the input slice.
+    pub bytes_consumed: usize,
+    /// The number of bytes written to the output slice.
+    pub bytes_written: usize,
+    /// Why the call stopped; never [`InflateStatus::DataError`].
+    pub status: InflateStatus,
+}
+
+/// Sizes and checksum of a deflate64 stream, as recorded in the zip local file header.
+///
+/// See [`InflaterManaged::with_entry_metadata`].
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EntryMetadata {
+    /// The size of the compressed deflate64 stream.
+    pub compressed_size: u64,
+    /// The size of the decompressed data.
+    pub uncompressed_size: u64,
+    /// The CRC-32 of the decompressed data.
+    pub crc32: u32,
+}
+
+/// Sizes and block count of a deflate64 stream.
+///
+/// See [`InflaterManaged::measure`].
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[non_exhaustive]
+pub struct StreamMeasurements {
+    /// The length of the deflate64 stream.
+    pub compressed_size: u64,
+    /// The size of the decompressed data.
+    pub uncompressed_size: u64,
+    /// The count of deflate64 blocks in the stream.
+    pub block_count: u64,
+}
+
+/// Sizes and checksum of a deflate64 stream read from a reader.
+///
+/// See [`InflaterManaged::scan_uncompressed_size`].
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[non_exhaustive]
+pub struct StreamScan {
+    /// The length of the deflate64 stream.
+    pub compressed_size: u64,
+    /// The size of the decompressed data.
+    pub uncompressed_size: u64,
+    /// The CRC-32 of the decompressed data, if it was requested.
+    pub crc32: Option<u32>,
+}
+
+/// The memory used by an inflater, in bytes.
+///
+/// See [`InflaterManaged::memory_usage`].
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[non_exhaustive]
+pub struct MemoryUsage {
+    /// The total memory used, including the parts below.
+    pub total: usize,
+    /// The history window.
+    pub window: usize,
+    /// The Huffman decoding tables of the current block and of the code lengths.
+    pub huffman_trees: usize,
+    /// The memory allocated on the heap. The inflater keeps all of its state inline, so
+    /// this is currently always 0.
+    pub heap: usize,
+}
+
+/// Damaged input skipped by [`InflaterManaged::resync`].
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[non_exhaustive]
+pub struct DataGap {
+    /// The count of bytes of the input passed to `resync` which were consumed. Decoding
+    /// continues with the input after them.
+    pub bytes_consumed: usize,
+    /// The input bits skipped, from the position of the error to the start of the block
+    /// decoding continues with.
+    pub input_bits: std::ops::Range<u64>,
+    /// The output offset of the gap: output before it was decoded before the error, and
+    /// output after it is decoded from the block after the gap.
+    pub output_offset: u64,
+}
+
+/// Position of the end of a deflate64 block.
+///
+/// See [`InflaterManaged::inflate_until_block_boundary`].
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[non_exhaustive]
+pub struct BlockBoundary {
+    /// Count of input bits before the boundary.
+    ///
+    /// Blocks are not byte aligned, so the boundary can be in the middle of an input byte.
+    pub input_bits: u64,
+    /// Count of output bytes before the boundary.
+    pub output_bytes: u64,
+    /// true if the block before the boundary is the final block of the stream.
+    pub end_of_stream: bool,
 }
 
 /// Input and output stream positions corresponding to an inflater checkpoint.
 #[cfg(feature = "checkpoint")]
 #[cfg_attr(docsrs, doc(cfg(feature = "checkpoint")))]
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct CheckpointStreamPositions {
     /// Count of input bytes already consumed before checkpoint.
     pub input_bytes_to_skip: u64,
@@ -137,8 +409,119 @@ pub struct CheckpointStreamPositions {
     pub output_bytes_already_returned: u64,
 }
 
+/// The header of a serialized inflater checkpoint, see [`CheckpointInfo::parse`].
+#[cfg(feature = "checkpoint")]
+#[cfg_attr(docsrs, doc(cfg(feature = "checkpoint")))]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct CheckpointInfo {
+    /// The version of the checkpoint format.
+    pub version: u16,
+    /// The flags of the checkpoint format, which are always 0 in version 1 checkpoints.
+    pub flags: u16,
+    /// Count of input bits consumed before the checkpoint.
+    pub input_bits: u64,
+    /// Count of output bytes written before the checkpoint, including unread ones.
+    pub output_bytes_written: u64,
+    /// Count of output bytes written but not yet returned before the checkpoint.
+    pub output_bytes_unread: u32,
+    /// The type of the block the checkpoint is in. A checkpoint between blocks is in an
+    /// uncompressed block with no bytes remaining.
+    pub block_type: BlockType,
+    /// Whether the block the checkpoint is in is the final block.
+    pub final_block: bool,
+    /// The checksum which protects the checkpoint.
+    pub checksum: CheckpointChecksum,
+    /// Whether the checkpoint is a delta, which is restored with
+    /// [`InflaterManaged::restore_from_delta`].
+    pub delta: bool,
+    /// The CRC-32 of the output returned before the checkpoint, if the inflater computed
+    /// it, see [`InflaterOptions::track_crc32`].
+    pub output_crc32: Option<u32>,
+}
+
+/// The checksum which protects serialized checkpoints against corruption, see
+/// [`InflaterOptions::checkpoint_checksum`].
+#[cfg(feature = "checkpoint")]
+#[cfg_attr(docsrs, doc(cfg(feature = "checkpoint")))]
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub enum CheckpointChecksum {
+    /// Fletcher-32, the only checksum of version 1 checkpoints.
+    #[default]
+    Fletcher32,
+    /// CRC-32 (ISO-HDLC), as used by zip and gzip.
+    Crc32,
+    /// CRC-32C (Castagnoli), as used by iSCSI, ext4 and many storage systems.
+    Crc32c,
+}
+
+/// The state of a stream at a block boundary which zlib needs to continue decoding it,
+/// see [`ZlibHandoff::from_checkpoint`] and [`InflaterManaged::zlib_handoff`].
+///
+/// To continue with zlib, create a raw inflate stream with `inflateInit2(strm, -15)`, call
+/// `inflatePrime(strm, prime_bits, prime_value)` and
+/// `inflateSetDictionary(strm, dictionary, len)`, and pass the input from
+/// `input_byte_offset`. zlib only decodes plain deflate, so the rest of the stream must not
+/// use the extensions of deflate64: distances over 32KB, and the 16 extra bits of length
+/// code 285. zlib only uses the last 32KB of the dictionary.
+#[cfg(feature = "checkpoint")]
+#[cfg_attr(docsrs, doc(cfg(feature = "checkpoint")))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct ZlibHandoff {
+    /// The offset of the first input byte to pass to zlib.
+    pub input_byte_offset: u64,
+    /// The count of bits of the byte before `input_byte_offset` which are not consumed
+    /// yet, from 0 to 7.
+    pub prime_bits: u8,
+    /// The bits not consumed yet, in the low `prime_bits` bits.
+    pub prime_value: u8,
+    /// The count of output bytes before the output of zlib.
+    pub output_offset: u64,
+    /// The up to 64KB of output before `output_offset`.
+    pub dictionary: Vec<u8>,
+}
+
+/// The reason [`verify_checkpoint_chain`] rejected a series of checkpoints, with the index
+/// of the checkpoint at fault.
+#[cfg(feature = "checkpoint")]
+#[cfg_attr(docsrs, doc(cfg(feature = "checkpoint")))]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum CheckpointChainError {
+    /// The checkpoint can't be restored, or its offsets are before those of the previous
+    /// checkpoint.
+    Invalid(usize),
+    /// Decoding the stream from the previous checkpoint, or from the start of the stream
+    /// for the first checkpoint, does not reproduce the checkpoint: the decoding fails
+    /// before reaching its offsets, or its history or output CRC-32 differs.
+    Mismatch(usize),
+    /// Decoding the stream from the last checkpoint fails before the end of the stream.
+    /// Without checkpoints, the index is 0.
+    Incomplete(usize),
+}
+
+#[cfg(feature = "checkpoint")]
+impl std::fmt::Display for CheckpointChainError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Invalid(index) => write!(f, "checkpoint {index} is invalid"),
+            Self::Mismatch(index) => write!(f, "checkpoint {index} does not match the stream"),
+            Self::Incomplete(index) => {
+                write!(f, "stream ends early after checkpoint {index}")
+            }
+        }
+    }
+}
+
+#[cfg(feature = "checkpoint")]
+impl std::error::Error for CheckpointChainError {}
+
 #[derive(Debug)]
 enum InternalErr {
     DataNeeded,
-    DataError,
+    DataError(InflateErrorKind),
 }
@@ -0,0 +1,124 @@
+use crate::{BlockType, InflateError};
+use std::ops::Range;
+
+/// Receives the lifecycle events of decoding, see [`InflaterManaged::inflate_observed`].
+///
+/// All methods do nothing by default, so implementations only override the events they
+/// need. Offsets are counted from the start of the stream, in bits for the input and in
+/// bytes for the output.
+///
+/// [`InflaterManaged::inflate_observed`]: crate::InflaterManaged::inflate_observed
+pub trait InflateObserver {
+    /// Called after the header bits of a block were read. `input_bit_offset` is the
+    /// position of the BFINAL bit of the block.
+    fn on_block_start(&mut self, block_type: BlockType, bfinal: bool, input_bit_offset: u64) {
+        let _ = (block_type, bfinal, input_bit_offset);
+    }
+
+    /// Called at the end of a block, with the input position after its last bit and the
+    /// count of bytes decompressed up to its end. The output of the block may not have
+    /// been returned yet.
+    fn on_block_end(&mut self, input_bit_offset: u64, output_offset: u64) {
+        let _ = (input_bit_offset, output_offset);
+    }
+
+    /// Called once when an error stops decompression.
+    fn on_error(&mut self, error: &InflateError) {
+        let _ = error;
+    }
+
+    /// Called when the output returned so far reaches another multiple of
+    /// [`InflaterOptions::progress_interval`](crate::InflaterOptions::progress_interval),
+    /// with the input position and the count of bytes returned. Output is returned from the
+    /// history window in steps, even within a single call, and a step which passes several
+    /// multiples calls this once.
+    fn on_progress(&mut self, input_bit_offset: u64, output_offset: u64) {
+        let _ = (input_bit_offset, output_offset);
+    }
+}
+
+impl<O: InflateObserver + ?Sized> InflateObserver for &mut O {
+    fn on_block_start(&mut self, block_type: BlockType, bfinal: bool, input_bit_offset: u64) {
+        (**self).on_block_start(block_type, bfinal, input_bit_offset)
+    }
+
+    fn on_block_end(&mut self, input_bit_offset: u64, output_offset: u64) {
+        (**self).on_block_end(input_bit_offset, output_offset)
+    }
+
+    fn on_error(&mut self, error: &InflateError) {
+        (**self).on_error(error)
+    }
+
+    fn on_progress(&mut self, input_bit_offset: u64, output_offset: u64) {
+        (**self).on_progress(input_bit_offset, output_offset)
+    }
+}
+
+/// The position of a deflate64 block in the compressed and the decompressed stream.
+///
+/// See [`InflaterManaged::enumerate_blocks`](crate::InflaterManaged::enumerate_blocks) and
+/// [`BlockRecorder`].
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct BlockInfo {
+    /// The type of the block.
+    pub block_type: BlockType,
+    /// true if the block is the final block of the stream.
+    pub is_final: bool,
+    /// The input bits of the block, from its BFINAL bit to its last bit.
+    pub input_bits: Range<u64>,
+    /// The output bytes decompressed from the block.
+    pub output_bytes: Range<u64>,
+}
+
+/// An [`InflateObserver`] which records the [`BlockInfo`] of each finished block.
+///
+/// Pass the same recorder to every call from the start of the stream. Blocks found before
+/// an error stay available, so this is also useful to locate the damage in a corrupt stream.
+#[derive(Debug, Clone, Default)]
+pub struct BlockRecorder {
+    blocks: Vec<BlockInfo>,
+    current: Option<(BlockType, bool, u64)>, // the block started but not finished yet
+}
+
+impl BlockRecorder {
+    /// Creates BlockRecorder without any blocks
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the blocks finished so far
+    pub fn blocks(&self) -> &[BlockInfo] {
+        &self.blocks
+    }
+
+    /// Returns the blocks finished so far
+    pub fn into_blocks(self) -> Vec<BlockInfo> {
+        self.blocks
+    }
+}
+
+impl InflateObserver for BlockRecorder {
+    fn on_block_start(&mut self, block_type: BlockType, bfinal: bool, input_bit_offset: u64) {
+        self.current = Some((block_type, bfinal, input_bit_offset));
+    }
+
+    fn on_block_end(&mut self, input_bit_offset: u64, output_offset: u64) {
+        let Some((block_type, is_final, input_start)) = self.current.take() else {
+            return;
+        };
+        // each block's output starts where the previous one ended
+        let output_start = self.blocks.last().map_or(0, |block| block.output_bytes.end);
+        self.blocks.push(BlockInfo {
+            block_type,
+            is_final,
+            input_bits: input_start..input_bit_offset,
+            output_bytes: output_start..output_offset,
+        });
+    }
+}
+
+// the observer of the inflate methods without one
+pub(crate) struct NoObserver;
+
+impl InflateObserver for NoObserver {}
@@ -0,0 +1,282 @@
+#[cfg(feature = "checkpoint")]
+use crate::CheckpointChecksum;
+use crate::{EntryMetadata, InflateErrorKind};
+use std::cmp::min_by_key;
+
+/// Construction-time configuration of an inflater.
+///
+/// Start from [`InflaterOptions::new`], which is what [`InflaterManaged::new`] uses, and
+/// change the options as needed:
+///
+/// ```
+/// use deflate64::{InflaterManaged, InflaterOptions};
+///
+/// let options = InflaterOptions::new()
+///     .max_output(1024 * 1024 * 1024)
+///     .track_crc32(true);
+/// let inflater = InflaterManaged::boxed_with_options(options);
+/// ```
+///
+/// [`InflaterManaged::new`]: crate::InflaterManaged::new
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct InflaterOptions {
+    pub(crate) uncompressed_size: usize,
+    pub(crate) entry_metadata: Option<EntryMetadata>,
+    pub(crate) max_output: Option<u64>,
+    pub(crate) max_expansion_ratio: Option<u64>,
+    pub(crate) track_crc32: bool,
+    pub(crate) collect_stats: bool,
+    pub(crate) strict: bool,
+    pub(crate) fill_missing_history: bool,
+    pub(crate) exact_uncompressed_size: bool,
+    pub(crate) progress_interval: u64,
+    #[cfg(feature = "checkpoint")]
+    pub(crate) compress_checkpoints: bool,
+    #[cfg(feature = "checkpoint")]
+    pub(crate) checkpoint_checksum: CheckpointChecksum,
+    #[cfg(feature = "checkpoint")]
+    pub(crate) checkpoints_at_block_boundaries_only: bool,
+}
+
+impl InflaterOptions {
+    /// Creates the default options: no size limits, no verification, and no CRC-32.
+    pub const fn new() -> Self {
+        Self {
+            uncompressed_size: usize::MAX,
+            entry_metadata: None,
+            max_output: None,
+            max_expansion_ratio: None,
+            track_crc32: false,
+            collect_stats: false,
+            strict: false,
+            fill_missing_history: false,
+            exact_uncompressed_size: false,
+            progress_interval: 0,
+            #[cfg(feature = "checkpoint")]
+            compress_checkpoints: false,
+            #[cfg(feature = "checkpoint")]
+            checkpoint_checksum: CheckpointChecksum::Fletcher32,
+            #[cfg(feature = "checkpoint")]
+            checkpoints_at_block_boundaries_only: false,
+        }
+    }
+
+    /// Sets the expected uncompressed size.
+    ///
+    /// Output after `uncompressed_size` bytes is silently dropped, and the stream is
+    /// treated as finished. See [`Self::exact_uncompressed_size`], [`Self::entry_metadata`]
+    /// and [`Self::max_output`] to reject such streams instead.
+    pub const fn uncompressed_size(mut self, uncompressed_size: usize) -> Self {
+        self.uncompressed_size = uncompressed_size;
+        self
+    }
+
+    /// Sets whether [`Self::uncompressed_size`] is verified instead of truncating the output.
+    ///
+    /// Producing more output than `uncompressed_size` is then an
+    /// [`InflateErrorKind::UncompressedSizeMismatch`] error, and so is a stream which ends
+    /// with less output when [`InflaterManaged::finish`](crate::InflaterManaged::finish)
+    /// is called. Use this to detect mismatches between a header and its stream when only
+    /// the uncompressed size is known; see [`Self::entry_metadata`] to verify all sizes
+    /// and the CRC-32 of a zip entry.
+    pub const fn exact_uncompressed_size(mut self, exact: bool) -> Self {
+        self.exact_uncompressed_size = exact;
+        self
+    }
+
+    /// Sets the sizes and CRC-32 of a zip entry.
+    ///
+    /// The inflater consumes at most `compressed_size` bytes of input, and producing more
+    /// than `uncompressed_size` bytes of output is a data error. Call
+    /// [`InflaterManaged::finish`](crate::InflaterManaged::finish) after the end of the
+    /// stream to verify both sizes and the CRC-32 of the output.
+    pub const fn entry_metadata(mut self, metadata: EntryMetadata) -> Self {
+        self.entry_metadata = Some(metadata);
+        self
+    }
+
+    /// Sets the maximum count of output bytes.
+    ///
+    /// Producing more output is an [`InflateErrorKind::OutputLimitExceeded`] error. Unlike
+    /// the sizes of [`Self::entry_metadata`], this limit doesn't come from the archive, so it
+    /// protects against streams which decompress to much more than their headers declare.
+    pub const fn max_output(mut self, max_output: u64) -> Self {
+        self.max_output = Some(max_output);
+        self
+    }
+
+    /// Sets the maximum ratio of the output size to the size of the input consumed.
+    ///
+    /// Producing more output is an [`InflateErrorKind::ExpansionRatioExceeded`] error. The
+    /// ratio is checked continuously against the input consumed so far, so a stream is
+    /// rejected as soon as its output runs ahead, but the first
+    /// [`RATIO_GRACE_OUTPUT`](Self::RATIO_GRACE_OUTPUT) bytes of output are always allowed
+    /// so that small, highly compressible streams are not rejected.
+    pub const fn max_expansion_ratio(mut self, ratio: u64) -> Self {
+        self.max_expansion_ratio = Some(ratio);
+        self
+    }
+
+    /// The count of output bytes which is always allowed by [`Self::max_expansion_ratio`].
+    pub const RATIO_GRACE_OUTPUT: u64 = 1024 * 1024;
+
+    /// Sets whether the CRC-32 of the output is computed, see
+    /// [`InflaterManaged::output_crc32`](crate::InflaterManaged::output_crc32).
+    ///
+    /// The CRC-32 is always computed if [`Self::entry_metadata`] is set.
+    pub const fn track_crc32(mut self, track_crc32: bool) -> Self {
+        self.track_crc32 = track_crc32;
+        self
+    }
+
+    /// Sets whether [`DecodeStats`](crate::DecodeStats) are collected, see
+    /// [`InflaterManaged::stats`](crate::InflaterManaged::stats).
+    ///
+    /// Collecting statistics slightly slows down decoding of compressed blocks.
+    pub const fn collect_stats(mut self, collect_stats: bool) -> Self {
+        self.collect_stats = collect_stats;
+        self
+    }
+
+    /// Sets whether streams which deviate from RFC 1951 are rejected.
+    ///
+    /// By default, the inflater decodes such streams as well as it can, and reports the
+    /// deviations it tolerated as [warnings](crate::InflaterManaged::warnings). In strict
+    /// mode, these are errors instead:
+    ///
+    /// - incomplete or over-subscribed Huffman codes in dynamic block headers, reported as
+    ///   the invalid tree error of the code,
+    /// - more than 286 literal/length codes in a dynamic block header,
+    /// - non-zero padding before the length of an uncompressed block.
+    ///
+    /// Empty uncompressed blocks are valid and still only a warning.
+    pub const fn strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    /// Sets whether matches which reach back before the start of the stream copy zeros
+    /// for the missing history, instead of failing with
+    /// [`InflateErrorKind::DistanceTooFarBack`].
+    ///
+    /// This salvages the rest of streams whose start is lost, such as a truncated zip
+    /// entry or data found by [`StreamCarver`](crate::StreamCarver). Each such match is
+    /// reported as a [warning](crate::InflaterManaged::warnings), and in
+    /// [`InflateResult::history_filled`](crate::InflateResult::history_filled). This has
+    /// no effect in [strict mode](Self::strict).
+    pub const fn fill_missing_history(mut self, fill_missing_history: bool) -> Self {
+        self.fill_missing_history = fill_missing_history;
+        self
+    }
+
+    /// Sets the count of output bytes between calls of
+    /// [`InflateObserver::on_progress`](crate::InflateObserver::on_progress), or 0 to never
+    /// call it.
+    ///
+    /// Progress is reported to the observer of
+    /// [`InflaterManaged::inflate_observed`](crate::InflaterManaged::inflate_observed), so
+    /// large output buffers can be used while still showing the progress of long streams.
+    pub const fn progress_interval(mut self, progress_interval: u64) -> Self {
+        self.progress_interval = progress_interval;
+        self
+    }
+
+    /// Sets whether the history window in checkpoints is run-length encoded, see
+    /// [`InflaterManaged::checkpoint`](crate::InflaterManaged::checkpoint).
+    ///
+    /// This shrinks checkpoints of output with long runs of repeated bytes, such as
+    /// zero-filled regions of disk images, at the cost of encoding the window when the
+    /// checkpoint is taken. The window is kept as-is when encoding it doesn't make it
+    /// smaller. Checkpoints are restored whether or not this is set.
+    #[cfg(feature = "checkpoint")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "checkpoint")))]
+    pub const fn compress_checkpoints(mut self, compress_checkpoints: bool) -> Self {
+        self.compress_checkpoints = compress_checkpoints;
+        self
+    }
+
+    /// Sets the checksum of checkpoints, see
+    /// [`InflaterManaged::checkpoint`](crate::InflaterManaged::checkpoint).
+    ///
+    /// Defaults to [`CheckpointChecksum::Fletcher32`]. Checkpoints are restored whatever
+    /// checksum they use, and [`CheckpointInfo::parse`](crate::CheckpointInfo::parse)
+    /// reports it. The CRC-32C is computed in software.
+    #[cfg(feature = "checkpoint")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "checkpoint")))]
+    pub const fn checkpoint_checksum(mut self, checksum: CheckpointChecksum) -> Self {
+        self.checkpoint_checksum = checksum;
+        self
+    }
+
+    /// Sets whether checkpoints are only tracked at the end of deflate64 blocks, instead of
+    /// after every write to the history window.
+    ///
+    /// This skips the checkpoint bookkeeping of the decoding loop, at the cost of
+    /// checkpoints only being available until the first output of the next block, such as
+    /// when stopped by
+    /// [`InflaterManaged::inflate_until_block_boundary`](crate::InflaterManaged::inflate_until_block_boundary)
+    /// or [`InflaterManaged::request_boundary_checkpoint`](crate::InflaterManaged::request_boundary_checkpoint),
+    /// or by a [`CheckpointPolicy`](crate::CheckpointPolicy) set to
+    /// [`at_block_boundaries_only`](crate::CheckpointPolicy::at_block_boundaries_only).
+    /// At other positions, [`InflaterManaged::checkpoint`](crate::InflaterManaged::checkpoint)
+    /// returns None.
+    #[cfg(feature = "checkpoint")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "checkpoint")))]
+    pub const fn checkpoints_at_block_boundaries_only(mut self, boundaries_only: bool) -> Self {
+        self.checkpoints_at_block_boundaries_only = boundaries_only;
+        self
+    }
+
+    pub(crate) fn tracks_crc32(&self) -> bool {
+        self.track_crc32 || self.entry_metadata.is_some()
+    }
+
+    // the lowest limit of the total output after consuming `input_bytes`, with the error of
+    // exceeding it
+    pub(crate) fn output_limit(&self, input_bytes: u64) -> Option<(u64, InflateErrorKind)> {
+        let ratio = self.max_expansion_ratio.map(|ratio| {
+            (
+                ratio
+                    .saturating_mul(input_bytes)
+                    .max(Self::RATIO_GRACE_OUTPUT),
+                InflateErrorKind::ExpansionRatioExceeded,
+            )
+        });
+        match (self.fixed_output_limit(), ratio) {
+            (Some(fixed), Some(ratio)) => Some(min_by_key(fixed, ratio, |limit| limit.0)),
+            (limit, None) | (None, limit) => limit,
+        }
+    }
+
+    // the lowest limit of the total output which doesn't depend on the input
+    pub(crate) fn fixed_output_limit(&self) -> Option<(u64, InflateErrorKind)> {
+        let metadata = self.entry_metadata.map(|metadata| {
+            (
+                metadata.uncompressed_size,
+                InflateErrorKind::UncompressedSizeMismatch,
+            )
+        });
+        let declared = self.enforces_uncompressed_size().then_some((
+            self.uncompressed_size as u64,
+            InflateErrorKind::UncompressedSizeMismatch,
+        ));
+        let max_output = self
+            .max_output
+            .map(|max_output| (max_output, InflateErrorKind::OutputLimitExceeded));
+        [metadata, declared, max_output]
+            .into_iter()
+            .flatten()
+            .min_by_key(|limit| limit.0)
+    }
+
+    pub(crate) fn enforces_uncompressed_size(&self) -> bool {
+        self.exact_uncompressed_size && self.uncompressed_size != usize::MAX
+    }
+}
+
+impl Default for InflaterOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
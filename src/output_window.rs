@@ -16,8 +16,13 @@ const WINDOW_MASK: usize = 131071;
 /// we need to look back in the output window and copy bytes from there.
 /// We use a byte array of WINDOW_SIZE circularly.
 /// </summary>
-#[derive(Debug)]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub(crate) struct OutputWindow {
+    #[cfg_attr(
+        feature = "serde",
+        serde(serialize_with = "crate::serde_array::serialize")
+    )]
     window: [u8; WINDOW_SIZE],
     end: usize,
     bytes_used: usize,
@@ -32,10 +37,23 @@ impl OutputWindow {
         }
     }
 
+    /// <summary>Clears the window for a new stream.</summary>
+    pub(crate) fn reset(&mut self) {
+        self.window.fill(0);
+        self.end = 0;
+        self.bytes_used = 0;
+    }
+
     pub(crate) fn clear_bytes_used(&mut self) {
         self.bytes_used = 0;
     }
 
+    /// <summary>Whether the positions are within the window, as deserialized ones must be.</summary>
+    #[cfg(feature = "serde")]
+    pub(crate) fn is_valid(&self) -> bool {
+        self.end < WINDOW_SIZE && self.bytes_used <= WINDOW_SIZE
+    }
+
     /// <summary>Add a byte to output window.</summary>
     #[inline(always)]
     pub fn write(&mut self, b: u8) {
@@ -113,6 +131,37 @@ impl OutputWindow {
         self.bytes_used
     }
 
+    /// <summary>
+    /// The `length` bytes most recently copied out by copy_to, which are still in the window.
+    /// Returned as two slices since the bytes may wrap around the end of the window.
+    /// </summary>
+    pub fn recently_copied(&self, length: usize) -> (&[u8], &[u8]) {
+        debug_assert!(self.bytes_used + length <= WINDOW_SIZE);
+        let start = self.end.wrapping_sub(self.bytes_used + length) & WINDOW_MASK;
+        if start + length <= WINDOW_SIZE {
+            (&self.window[start..][..length], &[])
+        } else {
+            let tail_len = WINDOW_SIZE - start;
+            (&self.window[start..], &self.window[..length - tail_len])
+        }
+    }
+
+    /// <summary>
+    /// The `length` bytes most recently written to the window, including those not copied out yet.
+    /// Returned as two slices since the bytes may wrap around the end of the window.
+    /// </summary>
+    #[cfg(feature = "debug-dump")]
+    pub fn last_written(&self, length: usize) -> (&[u8], &[u8]) {
+        debug_assert!(length <= WINDOW_SIZE);
+        let start = self.end.wrapping_sub(length) & WINDOW_MASK;
+        if start + length <= WINDOW_SIZE {
+            (&self.window[start..][..length], &[])
+        } else {
+            let tail_len = WINDOW_SIZE - start;
+            (&self.window[start..], &self.window[..length - tail_len])
+        }
+    }
+
     /// <summary>Copy the decompressed bytes to output buffer.</summary>
     pub fn copy_to(&mut self, output: Buffer<'_>) -> usize {
         let (copy_end, mut output) = if output.len() > self.bytes_used {
@@ -168,9 +217,19 @@ impl OutputWindow {
     }
 
     #[cfg(feature = "checkpoint")]
-    pub(crate) fn restore_from_checkpoint(&mut self, data: &[u8], bytes_used: usize) {
-        self.window[..data.len()].copy_from_slice(data);
-        self.end = data.len();
+    // returns the window, whose first `len` bytes are to be filled with checkpoint data
+    pub(crate) fn restore_from_checkpoint(&mut self, len: usize, bytes_used: usize) -> &mut [u8] {
+        self.end = len;
         self.bytes_used = bytes_used;
+        &mut self.window
+    }
+}
+
+#[cfg(feature = "serde")]
+crate::serde_in_place::impl_deserialize_in_place! {
+    impl DeserializeInPlace for OutputWindow as "OutputWindow" {
+        window: crate::serde_in_place::assign_array,
+        end: crate::serde_in_place::assign,
+        bytes_used: crate::serde_in_place::assign,
     }
 }
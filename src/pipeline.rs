@@ -0,0 +1,138 @@
+use crate::Deflate64Decoder;
+use std::io::{self, Read};
+use std::sync::mpsc::{sync_channel, Receiver};
+use std::thread;
+
+const DEFAULT_CHUNK_SIZE: usize = 64 * 1024;
+const DEFAULT_QUEUE_DEPTH: usize = 4;
+
+/// The reader that decompresses deflate64 on a background thread.
+///
+/// A worker thread reads the compressed data from the inner reader, decompresses it and
+/// sends the output in chunks over a bounded channel, so decompression overlaps with the
+/// work done on the decompressed data. When the channel is full, the worker waits until
+/// chunks are received, so at most `queue_depth` chunks are buffered.
+///
+/// Chunks can be received with [`Iterator::next`], or read as a byte stream with [`Read`].
+/// Dropping this decoder stops the worker after it finished its current chunk.
+pub struct PipelinedDecoder {
+    receiver: Receiver<io::Result<Vec<u8>>>,
+    chunk: Vec<u8>,
+    position: usize,
+    finished: bool,
+}
+
+impl PipelinedDecoder {
+    /// Creates PipelinedDecoder with Read, using 64 KiB chunks and a queue of 4 chunks
+    pub fn new<R: Read + Send + 'static>(inner: R) -> Self {
+        Self::with_chunk_size(inner, DEFAULT_CHUNK_SIZE, DEFAULT_QUEUE_DEPTH)
+    }
+
+    /// Creates PipelinedDecoder with Read, sending chunks of `chunk_size` bytes and
+    /// buffering at most `queue_depth` chunks
+    ///
+    /// # Panics
+    ///
+    /// Panics if `chunk_size` is zero.
+    pub fn with_chunk_size<R: Read + Send + 'static>(
+        inner: R,
+        chunk_size: usize,
+        queue_depth: usize,
+    ) -> Self {
+        assert!(chunk_size > 0, "chunk_size must not be zero");
+        let (sender, receiver) = sync_channel(queue_depth);
+        thread::spawn(move || {
+            let mut decoder = Deflate64Decoder::new(inner);
+            loop {
+                let chunk = read_chunk(&mut decoder, chunk_size);
+                // an empty chunk marks the end of the stream
+                let last = !matches!(&chunk, Ok(chunk) if !chunk.is_empty());
+                if sender.send(chunk).is_err() || last {
+                    // the receiver was dropped, or there is nothing more to send
+                    return;
+                }
+            }
+        });
+        Self {
+            receiver,
+            chunk: Vec::new(),
+            position: 0,
+            finished: false,
+        }
+    }
+
+    fn fill_chunk(&mut self) -> io::Result<bool> {
+        while self.position == self.chunk.len() {
+            if self.finished {
+                return Ok(false);
+            }
+            match self.receiver.recv() {
+                Ok(Ok(chunk)) => {
+                    self.finished = chunk.is_empty();
+                    self.chunk = chunk;
+                    self.position = 0;
+                }
+                Ok(Err(e)) => {
+                    self.finished = true;
+                    return Err(e);
+                }
+                Err(_) => {
+                    // the worker stopped without sending the end of the stream
+                    self.finished = true;
+                    return Err(io::Error::other("deflate64 decoder thread panicked"));
+                }
+            }
+        }
+        Ok(true)
+    }
+}
+
+fn read_chunk(decoder: &mut impl Read, chunk_size: usize) -> io::Result<Vec<u8>> {
+    let mut chunk = vec![0u8; chunk_size];
+    let mut filled = 0;
+    while filled < chunk_size {
+        match decoder.read(&mut chunk[filled..]) {
+            Ok(0) => break,
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    chunk.truncate(filled);
+    Ok(chunk)
+}
+
+impl Iterator for PipelinedDecoder {
+    type Item = io::Result<Vec<u8>>;
+
+    /// Receives the next chunk of decompressed data, or `None` at the end of the stream.
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.fill_chunk() {
+            Ok(true) => {
+                let chunk = if self.position == 0 {
+                    std::mem::take(&mut self.chunk)
+                } else {
+                    self.chunk[self.position..].to_vec()
+                };
+                self.chunk = Vec::new();
+                self.position = 0;
+                Some(Ok(chunk))
+            }
+            Ok(false) => None,
+            Err(e) => Some(Err(e)),
+        }
+    }
+}
+
+impl Read for PipelinedDecoder {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() || !self.fill_chunk()? {
+            return Ok(0);
+        }
+        let available = &self.chunk[self.position..];
+        let len = available.len().min(buf.len());
+        buf[..len].copy_from_slice(&available[..len]);
+        self.position += len;
+        Ok(len)
+    }
+}
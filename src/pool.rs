@@ -0,0 +1,99 @@
+use crate::{InflaterManaged, InflaterOptions};
+use std::ops::{Deref, DerefMut};
+use std::sync::{Arc, Mutex, PoisonError};
+
+const DEFAULT_MAX_IDLE: usize = 16;
+
+/// A pool of reusable inflaters, for services which decompress many streams concurrently.
+///
+/// Each inflater is more than 140 KiB large, so creating one per stream costs an allocation
+/// and clearing its history window. Inflaters checked out of the pool with [`Self::get`]
+/// are returned to it when dropped, and are reset in place on the next checkout, so no
+/// state of a previous stream is visible to the next one.
+///
+/// The pool is cheap to clone, and clones share the same inflaters. At most `max_idle`
+/// returned inflaters are kept, the others are freed.
+#[derive(Clone)]
+pub struct InflaterPool {
+    idle: Arc<Mutex<Vec<Box<InflaterManaged>>>>,
+    max_idle: usize,
+}
+
+impl InflaterPool {
+    /// Creates an empty pool which keeps at most 16 idle inflaters
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> Self {
+        Self::with_max_idle(DEFAULT_MAX_IDLE)
+    }
+
+    /// Creates an empty pool which keeps at most `max_idle` idle inflaters
+    pub fn with_max_idle(max_idle: usize) -> Self {
+        Self {
+            idle: Arc::new(Mutex::new(Vec::new())),
+            max_idle,
+        }
+    }
+
+    /// Returns the count of idle inflaters in the pool
+    pub fn idle_count(&self) -> usize {
+        self.lock().len()
+    }
+
+    /// Checks out an inflater for a new stream, as created with [`InflaterManaged::new`]
+    pub fn get(&self) -> PooledInflater {
+        self.get_with_options(InflaterOptions::new())
+    }
+
+    /// Checks out an inflater for a new stream, as created with
+    /// [`InflaterManaged::with_options`]
+    pub fn get_with_options(&self, options: InflaterOptions) -> PooledInflater {
+        let idle = self.lock().pop();
+        let inflater = match idle {
+            Some(mut inflater) => {
+                inflater.reset_with_options(options);
+                inflater
+            }
+            None => InflaterManaged::boxed_with_options(options),
+        };
+        PooledInflater {
+            inflater: Some(inflater),
+            pool: self.clone(),
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Vec<Box<InflaterManaged>>> {
+        // the list of inflaters is always consistent, even if a thread panicked with the lock
+        self.idle.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// An inflater checked out of an [`InflaterPool`], which returns it to the pool when dropped.
+pub struct PooledInflater {
+    inflater: Option<Box<InflaterManaged>>,
+    pool: InflaterPool,
+}
+
+impl Deref for PooledInflater {
+    type Target = InflaterManaged;
+
+    fn deref(&self) -> &InflaterManaged {
+        self.inflater.as_ref().unwrap()
+    }
+}
+
+impl DerefMut for PooledInflater {
+    fn deref_mut(&mut self) -> &mut InflaterManaged {
+        self.inflater.as_mut().unwrap()
+    }
+}
+
+impl Drop for PooledInflater {
+    fn drop(&mut self) {
+        if let Some(inflater) = self.inflater.take() {
+            let mut idle = self.pool.lock();
+            if idle.len() < self.pool.max_idle {
+                idle.push(inflater);
+            }
+        }
+    }
+}
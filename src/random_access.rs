@@ -0,0 +1,214 @@
+use crate::{Checkpointing, Deflate64Index, InflaterManaged};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::ops::Range;
+use std::sync::Arc;
+
+const BUFFER_SIZE: usize = 64 * 1024;
+
+/// The inflater that decompresses a deflate64 stream from arbitrary output offsets, using a
+/// [`Deflate64Index`] of the stream.
+///
+/// [`seek`](Self::seek) restores the nearest checkpoint before the target from the index,
+/// or starts over from the beginning of the stream, and decompresses and discards the data
+/// up to the target, which is less than the interval of the index. Seeking forward to an
+/// offset before the next checkpoint continues from the current position instead.
+/// [`Read`] then decompresses from the target.
+///
+/// The stream starts at the position of the inner reader when this inflater is created,
+/// and the inner reader must not be moved by anything else. The index is shared through an
+/// [`Arc`], so inflaters on other threads can serve other ranges of the same stream with
+/// their own readers.
+///
+/// ```
+/// # use deflate64::{Deflate64Index, RandomAccessInflater};
+/// # use std::io::{Cursor, Read};
+/// # let compressed: &[u8] = &[0x4b, 0x04, 0x02, 0x00];
+/// let index = Deflate64Index::build(compressed, 1024 * 1024)?;
+/// let mut inflater = RandomAccessInflater::new(index, Cursor::new(compressed))?;
+/// inflater.seek(2)?;
+/// let mut rest = Vec::new();
+/// inflater.read_to_end(&mut rest)?;
+/// assert_eq!(rest, b"aa");
+/// # Ok::<(), std::io::Error>(())
+/// ```
+#[cfg_attr(docsrs, doc(cfg(feature = "checkpoint")))]
+pub struct RandomAccessInflater<R> {
+    index: Arc<Deflate64Index>,
+    inner: R,
+    stream_start: u64,
+    inflater: Box<InflaterManaged<Checkpointing>>,
+    buffer: Box<[u8]>,
+    input: Range<usize>, // compressed bytes in buffer not consumed yet
+    eof: bool,
+    position: u64,
+}
+
+impl<R: Read + Seek> RandomAccessInflater<R> {
+    /// Creates RandomAccessInflater with the index of a deflate64 stream, which may be
+    /// shared with other inflaters, and a seekable reader positioned at the start of the
+    /// stream
+    pub fn new(index: impl Into<Arc<Deflate64Index>>, mut inner: R) -> io::Result<Self> {
+        let stream_start = inner.stream_position()?;
+        Ok(Self {
+            index: index.into(),
+            inner,
+            stream_start,
+            inflater: Box::new(InflaterManaged::with_checkpoints()),
+            buffer: vec![0u8; BUFFER_SIZE].into_boxed_slice(),
+            input: 0..0,
+            eof: false,
+            position: 0,
+        })
+    }
+
+    /// Returns inner reader
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    /// Returns reference to inner reader
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    /// Returns the index of the stream
+    pub fn index(&self) -> &Arc<Deflate64Index> {
+        &self.index
+    }
+
+    /// Returns the output offset the next read decompresses from
+    pub fn position(&self) -> u64 {
+        self.position
+    }
+
+    /// Moves to `output_offset` in the decompressed data, which is at most the
+    /// uncompressed size of the index.
+    ///
+    /// Returns an [`io::ErrorKind::InvalidInput`] error if `output_offset` is past the end
+    /// of the stream or the stream is invalid, and an [`io::ErrorKind::InvalidData`] error
+    /// if the index doesn't match the stream.
+    pub fn seek(&mut self, output_offset: u64) -> io::Result<()> {
+        if output_offset > self.index.uncompressed_size() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "seek past the end of the deflate64 stream",
+            ));
+        }
+        // the nearest checkpoint before the target, if it's closer than the current position
+        let entry_offset = self
+            .index
+            .entry_before(output_offset)
+            .map_or(0, |entry| entry.output_offset);
+        if output_offset < self.position || entry_offset > self.position {
+            self.restart(output_offset)?;
+        }
+        self.skip_to(output_offset)
+    }
+
+    /// Decompresses exactly the output bytes in `range`, seeking to its start as
+    /// [`seek`](Self::seek) does, and leaves the position at its end.
+    ///
+    /// Returns an [`io::ErrorKind::InvalidInput`] error if `range` ends past the end of the
+    /// stream or is reversed.
+    pub fn read_range(&mut self, range: Range<u64>) -> io::Result<Vec<u8>> {
+        let len = range.end.saturating_sub(range.start);
+        let mut data = Vec::with_capacity(usize::try_from(len).unwrap_or(0));
+        self.read_range_to_writer(range, &mut data)?;
+        Ok(data)
+    }
+
+    /// Same as [`read_range`](Self::read_range) but writes the output bytes in `range` to
+    /// `writer` as they are decompressed, and returns their count.
+    pub fn read_range_to_writer(
+        &mut self,
+        range: Range<u64>,
+        mut writer: impl Write,
+    ) -> io::Result<u64> {
+        if range.start > range.end {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "invalid range of the deflate64 stream",
+            ));
+        }
+        if range.end > self.index.uncompressed_size() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "range past the end of the deflate64 stream",
+            ));
+        }
+        self.seek(range.start)?;
+        let mut buffer = [0u8; 8 * 1024];
+        while self.position < range.end {
+            let len = (range.end - self.position).min(buffer.len() as u64) as usize;
+            let read = self.decode(&mut buffer[..len])?;
+            writer.write_all(&buffer[..read])?;
+        }
+        Ok(range.end - range.start)
+    }
+
+    // Moves the decompression to the nearest checkpoint before `output_offset`, or the
+    // start of the stream.
+    fn restart(&mut self, output_offset: u64) -> io::Result<()> {
+        let positions = self
+            .index
+            .restore_before(&mut self.inflater, output_offset)?;
+        self.inner.seek(SeekFrom::Start(
+            self.stream_start + positions.input_bytes_to_skip,
+        ))?;
+        self.input = 0..0;
+        self.eof = false;
+        self.position = positions.output_bytes_already_returned;
+        Ok(())
+    }
+
+    // Decompresses and discards output until `target`.
+    fn skip_to(&mut self, target: u64) -> io::Result<()> {
+        let mut scratch = [0u8; 8 * 1024];
+        while self.position < target {
+            let len = (target - self.position).min(scratch.len() as u64) as usize;
+            self.decode(&mut scratch[..len])?;
+        }
+        Ok(())
+    }
+
+    fn decode(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() || self.position == self.index.uncompressed_size() {
+            return Ok(0);
+        }
+
+        loop {
+            if self.input.is_empty() && !self.eof {
+                let read = self.inner.read(&mut self.buffer)?;
+                self.input = 0..read;
+                self.eof = read == 0;
+            }
+
+            let result = self.inflater.inflate(&self.buffer[self.input.clone()], buf);
+            self.input.start += result.bytes_consumed;
+            self.position += result.bytes_written as u64;
+
+            if let Some(&error) = self.inflater.error() {
+                return Err(error.into());
+            }
+
+            if result.bytes_written == 0 {
+                if self.inflater.finished() || self.eof {
+                    // the stream ended before the uncompressed size of the index
+                    return Err(io::Error::new(
+                        io::ErrorKind::UnexpectedEof,
+                        "incomplete deflate64",
+                    ));
+                }
+                continue;
+            }
+
+            return Ok(result.bytes_written);
+        }
+    }
+}
+
+impl<R: Read + Seek> Read for RandomAccessInflater<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.decode(buf)
+    }
+}
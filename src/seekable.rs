@@ -0,0 +1,282 @@
+use crate::{CheckpointStreamPositions, Checkpointing, InflaterManaged};
+use std::io::{self, Cursor, Read, Seek, SeekFrom};
+use std::ops::Range;
+
+const DEFAULT_CHECKPOINT_INTERVAL: u64 = 4 * 1024 * 1024;
+const BUFFER_SIZE: usize = 8 * 1024;
+
+/// The reader that decompresses deflate64 from a seekable reader and supports seeking in
+/// the decompressed data.
+///
+/// While reading, a checkpoint is retained every `checkpoint_interval` bytes of output.
+/// Seeking forward decompresses and discards the data in between, unless a checkpoint
+/// closer to the target was retained before. Seeking backward restores the nearest
+/// checkpoint before the target, or starts over from the beginning of the stream, and
+/// decompresses from there.
+///
+/// Each checkpoint takes up to [`MAX_CHECKPOINT_SIZE`](crate::MAX_CHECKPOINT_SIZE) bytes of
+/// memory, so smaller intervals make seeking faster at the cost of memory.
+///
+/// The stream starts at the position of the inner reader when this reader is created, and
+/// the inner reader must not be moved by anything else.
+pub struct SeekableDeflate64Reader<R> {
+    inner: R,
+    stream_start: u64,
+    inflater: Box<InflaterManaged<Checkpointing>>,
+    buffer: Box<[u8]>,
+    input: Range<usize>, // compressed bytes in buffer not consumed yet
+    eof: bool,
+    position: u64,
+    decoded: u64, // count of bytes decompressed, less than position after seeking past the end
+    len: Option<u64>,
+    checkpoints: Vec<(Vec<u8>, CheckpointStreamPositions)>,
+    checkpoint_interval: u64,
+}
+
+impl<R: Read + Seek> SeekableDeflate64Reader<R> {
+    /// Creates SeekableDeflate64Reader with a seekable reader positioned at the start of
+    /// the stream, retaining a checkpoint every 4 MiB of output
+    pub fn new(inner: R) -> io::Result<Self> {
+        Self::with_checkpoint_interval(inner, DEFAULT_CHECKPOINT_INTERVAL)
+    }
+
+    /// Creates SeekableDeflate64Reader with a seekable reader positioned at the start of
+    /// the stream, retaining a checkpoint every `checkpoint_interval` bytes of output
+    ///
+    /// # Panics
+    ///
+    /// Panics if `checkpoint_interval` is zero.
+    pub fn with_checkpoint_interval(mut inner: R, checkpoint_interval: u64) -> io::Result<Self> {
+        assert!(
+            checkpoint_interval > 0,
+            "checkpoint_interval must not be zero"
+        );
+        let stream_start = inner.stream_position()?;
+        Ok(Self {
+            inner,
+            stream_start,
+            inflater: Box::new(InflaterManaged::with_checkpoints()),
+            buffer: vec![0u8; BUFFER_SIZE].into_boxed_slice(),
+            input: 0..0,
+            eof: false,
+            position: 0,
+            decoded: 0,
+            len: None,
+            checkpoints: Vec::new(),
+            checkpoint_interval,
+        })
+    }
+
+    /// Returns inner reader
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    /// Returns reference to innner reader
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    /// Returns the count of checkpoints retained so far
+    pub fn checkpoint_count(&self) -> usize {
+        self.checkpoints.len()
+    }
+
+    fn retain_checkpoint(&mut self) {
+        let last = self
+            .checkpoints
+            .last()
+            .map_or(0, |(_, positions)| positions.output_bytes_already_returned);
+        if self.decoded < last + self.checkpoint_interval {
+            return;
+        }
+        if let Some((data, positions)) = self.inflater.checkpoint() {
+            if positions.output_bytes_already_returned > last {
+                self.checkpoints.push((data, positions));
+            }
+        }
+    }
+
+    // Moves the decompression to the start of the stream or the checkpoint at `index`.
+    fn restart(&mut self, checkpoint: Option<usize>) -> io::Result<()> {
+        self.inflater.reset();
+        let (input_offset, output_offset) = match checkpoint {
+            Some(index) => {
+                let (data, _) = &self.checkpoints[index];
+                let positions = self.inflater.restore_from_checkpoint(data).ok_or_else(|| {
+                    io::Error::new(io::ErrorKind::InvalidData, "invalid deflate64 checkpoint")
+                })?;
+                (
+                    positions.input_bytes_to_skip,
+                    positions.output_bytes_already_returned,
+                )
+            }
+            None => (0, 0),
+        };
+        self.inner
+            .seek(SeekFrom::Start(self.stream_start + input_offset))?;
+        self.input = 0..0;
+        self.eof = false;
+        self.decoded = output_offset;
+        Ok(())
+    }
+
+    // Decompresses and discards output until `target`, or the end of the stream.
+    fn skip_to(&mut self, target: u64) -> io::Result<()> {
+        let mut scratch = [0u8; BUFFER_SIZE];
+        while self.decoded < target {
+            let len = (target - self.decoded).min(scratch.len() as u64) as usize;
+            if self.decode(&mut scratch[..len])? == 0 {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    fn decode(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            // we received empty buffer, so it won't be possible to write anything
+            return Ok(0);
+        }
+
+        loop {
+            if self.input.is_empty() && !self.eof {
+                let read = self.inner.read(&mut self.buffer)?;
+                self.input = 0..read;
+                self.eof = read == 0;
+            }
+
+            let result = self.inflater.inflate(&self.buffer[self.input.clone()], buf);
+            self.input.start += result.bytes_consumed;
+            self.decoded += result.bytes_written as u64;
+
+            if let Some(&error) = self.inflater.error() {
+                return Err(error.into());
+            }
+
+            self.retain_checkpoint();
+
+            if self.inflater.finished() {
+                self.len = Some(self.decoded);
+            }
+
+            if result.bytes_written == 0 && !self.eof && !self.inflater.finished() {
+                // if we haven't ready any data and we haven't hit EOF yet,
+                // ask again. We must not return 0 in such case
+                continue;
+            }
+
+            return Ok(result.bytes_written);
+        }
+    }
+}
+
+impl<R: Read + Seek> Read for SeekableDeflate64Reader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.decoded != self.position {
+            // seeked past the end of the stream
+            return Ok(0);
+        }
+        let read = self.decode(buf)?;
+        self.position += read as u64;
+        Ok(read)
+    }
+}
+
+impl<R: Read + Seek> Seek for SeekableDeflate64Reader<R> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let (base, offset) = match pos {
+            SeekFrom::Start(offset) => (offset, 0),
+            SeekFrom::Current(offset) => (self.position, offset),
+            SeekFrom::End(offset) => {
+                if self.len.is_none() {
+                    self.skip_to(u64::MAX)?;
+                }
+                match self.len {
+                    Some(len) => (len, offset),
+                    None => {
+                        return Err(io::Error::new(
+                            io::ErrorKind::UnexpectedEof,
+                            "incomplete deflate64",
+                        ))
+                    }
+                }
+            }
+        };
+        let target = base.checked_add_signed(offset).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "invalid seek to a negative or overflowing position",
+            )
+        })?;
+
+        // the nearest checkpoint before the target, if it's closer than the current position
+        let checkpoint = self
+            .checkpoints
+            .partition_point(|(_, positions)| positions.output_bytes_already_returned <= target)
+            .checked_sub(1);
+        let checkpoint_offset = checkpoint.map_or(0, |index| {
+            self.checkpoints[index].1.output_bytes_already_returned
+        });
+        if target < self.decoded || checkpoint_offset > self.decoded {
+            self.restart(checkpoint)?;
+        }
+        self.skip_to(target)?;
+        self.position = target;
+        Ok(target)
+    }
+}
+
+/// The reader that decompresses an in-memory deflate64 stream and supports seeking in the
+/// decompressed data.
+///
+/// This is [`SeekableDeflate64Reader`] over a byte slice: checkpoints are retained as regions
+/// of the stream are visited for the first time, so later seeks to them are cheap.
+pub struct Deflate64Cursor<'a> {
+    reader: SeekableDeflate64Reader<Cursor<&'a [u8]>>,
+}
+
+impl<'a> Deflate64Cursor<'a> {
+    /// Creates Deflate64Cursor with a deflate64 stream, retaining a checkpoint every 4 MiB
+    /// of output
+    pub fn new(data: &'a [u8]) -> Self {
+        Self::with_checkpoint_interval(data, DEFAULT_CHECKPOINT_INTERVAL)
+    }
+
+    /// Creates Deflate64Cursor with a deflate64 stream, retaining a checkpoint every
+    /// `checkpoint_interval` bytes of output
+    ///
+    /// # Panics
+    ///
+    /// Panics if `checkpoint_interval` is zero.
+    pub fn with_checkpoint_interval(data: &'a [u8], checkpoint_interval: u64) -> Self {
+        let reader = SeekableDeflate64Reader::with_checkpoint_interval(
+            Cursor::new(data),
+            checkpoint_interval,
+        )
+        .expect("seeking in a byte slice never fails");
+        Self { reader }
+    }
+
+    /// Returns the deflate64 stream
+    pub fn get_ref(&self) -> &'a [u8] {
+        self.reader.get_ref().get_ref()
+    }
+
+    /// Returns the count of checkpoints retained so far
+    pub fn checkpoint_count(&self) -> usize {
+        self.reader.checkpoint_count()
+    }
+}
+
+impl Read for Deflate64Cursor<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.reader.read(buf)
+    }
+}
+
+impl Seek for Deflate64Cursor<'_> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        self.reader.seek(pos)
+    }
+}
@@ -0,0 +1,22 @@
+//! Serializes arrays of any length as sequences, since serde only supports arrays of up to
+//! 32 elements. Use with `#[serde(with = "crate::serde_array")]`.
+
+use serde::de::Error;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+pub(crate) fn serialize<S: Serializer, T: Serialize, const N: usize>(
+    array: &[T; N],
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    array.as_slice().serialize(serializer)
+}
+
+pub(crate) fn deserialize<'de, D: Deserializer<'de>, T: Deserialize<'de>, const N: usize>(
+    deserializer: D,
+) -> Result<[T; N], D::Error> {
+    let elements = Vec::<T>::deserialize(deserializer)?;
+    let len = elements.len();
+    elements
+        .try_into()
+        .map_err(|_| D::Error::invalid_length(len, &format!("an array of length {N}").as_str()))
+}
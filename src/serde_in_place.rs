@@ -0,0 +1,217 @@
+//! Deserializes structs in place, like the derived `Deserialize` would by value.
+//!
+//! Deserializing the inflater by value moves its 128 KiB window through many stack frames,
+//! which overflows the stack in debug builds, so it's deserialized into a boxed inflater.
+
+use serde::de::{self, DeserializeSeed, Deserializer, IgnoredAny, MapAccess, SeqAccess, Visitor};
+use serde::Deserialize;
+use std::fmt;
+
+/// A struct with the fields serialized by the derived `Serialize`, deserialized in place.
+pub(crate) trait DeserializeInPlace {
+    const NAME: &'static str;
+    const FIELDS: &'static [&'static str];
+
+    /// Deserializes the field `FIELDS[index]` into this.
+    fn deserialize_field<'de, D: Deserializer<'de>>(
+        &mut self,
+        index: usize,
+        deserializer: D,
+    ) -> Result<(), D::Error>;
+}
+
+/// Implements [`DeserializeInPlace`] with the functions deserializing each field into `&mut`
+/// of it. The fields must be listed in declaration order, followed by the fields which only
+/// exist with a cfg option.
+macro_rules! impl_deserialize_in_place {
+    (
+        impl$(<$param:ident: $bound:path>)? DeserializeInPlace for $ty:ty as $name:literal {
+            $($field:ident: $deserialize:path,)*
+        }
+    ) => {
+        $crate::serde_in_place::impl_deserialize_in_place! {
+            impl$(<$param: $bound>)? DeserializeInPlace for $ty as $name {
+                $($field: $deserialize,)*
+            }
+            #[cfg(any())] {}
+        }
+    };
+    (
+        impl$(<$param:ident: $bound:path>)? DeserializeInPlace for $ty:ty as $name:literal {
+            $($field:ident: $deserialize:path,)*
+        }
+        #[cfg($cfg:meta)] {
+            $($cfg_field:ident: $cfg_deserialize:path,)*
+        }
+    ) => {
+        impl$(<$param: $bound>)? $crate::serde_in_place::DeserializeInPlace for $ty {
+            const NAME: &'static str = $name;
+            #[cfg(not($cfg))]
+            const FIELDS: &'static [&'static str] = &[$(stringify!($field)),*];
+            #[cfg($cfg)]
+            const FIELDS: &'static [&'static str] =
+                &[$(stringify!($field),)* $(stringify!($cfg_field)),*];
+
+            fn deserialize_field<'de, D: serde::Deserializer<'de>>(
+                &mut self,
+                index: usize,
+                deserializer: D,
+            ) -> Result<(), D::Error> {
+                match Self::FIELDS[index] {
+                    $(stringify!($field) => $deserialize(&mut self.$field, deserializer),)*
+                    $(
+                    #[cfg($cfg)]
+                    stringify!($cfg_field) => $cfg_deserialize(&mut self.$cfg_field, deserializer),
+                    )*
+                    _ => unreachable!(),
+                }
+            }
+        }
+    };
+}
+pub(crate) use impl_deserialize_in_place;
+
+/// Deserializes a struct into `place`.
+pub(crate) fn deserialize_in_place<'de, T: DeserializeInPlace, D: Deserializer<'de>>(
+    place: &mut T,
+    deserializer: D,
+) -> Result<(), D::Error> {
+    debug_assert!(T::FIELDS.len() <= 64);
+    deserializer.deserialize_struct(T::NAME, T::FIELDS, StructVisitor(place))
+}
+
+/// Deserializes a value and assigns it to `place`.
+pub(crate) fn assign<'de, T: Deserialize<'de>, D: Deserializer<'de>>(
+    place: &mut T,
+    deserializer: D,
+) -> Result<(), D::Error> {
+    *place = T::deserialize(deserializer)?;
+    Ok(())
+}
+
+/// Deserializes an array serialized with [`crate::serde_array`] into `place`.
+pub(crate) fn assign_array<'de, T: Deserialize<'de>, D: Deserializer<'de>, const N: usize>(
+    place: &mut [T; N],
+    deserializer: D,
+) -> Result<(), D::Error> {
+    deserializer.deserialize_seq(ArrayVisitor(place))
+}
+
+struct ArrayVisitor<'a, T>(&'a mut [T]);
+
+impl<'de, T: Deserialize<'de>> Visitor<'de> for ArrayVisitor<'_, T> {
+    type Value = ();
+
+    fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(formatter, "an array of length {}", self.0.len())
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<(), A::Error> {
+        let len = self.0.len();
+        for index in 0..len {
+            match seq.next_element()? {
+                Some(element) => self.0[index] = element,
+                None => return Err(de::Error::invalid_length(index, &self)),
+            }
+        }
+        let mut extra = 0;
+        while seq.next_element::<IgnoredAny>()?.is_some() {
+            extra += 1;
+        }
+        if extra != 0 {
+            return Err(de::Error::invalid_length(len + extra, &self));
+        }
+        Ok(())
+    }
+}
+
+struct StructVisitor<'a, T>(&'a mut T);
+
+impl<'de, T: DeserializeInPlace> Visitor<'de> for StructVisitor<'_, T> {
+    type Value = ();
+
+    fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(formatter, "struct {}", T::NAME)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<(), A::Error> {
+        for index in 0..T::FIELDS.len() {
+            let field = FieldSeed {
+                place: &mut *self.0,
+                index,
+            };
+            if seq.next_element_seed(field)?.is_none() {
+                return Err(de::Error::invalid_length(index, &self));
+            }
+        }
+        Ok(())
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<(), A::Error> {
+        let mut seen = 0u64;
+        while let Some(index) = map.next_key_seed(FieldName(T::FIELDS))? {
+            let Some(index) = index else {
+                map.next_value::<IgnoredAny>()?;
+                continue;
+            };
+            if seen & (1 << index) != 0 {
+                return Err(de::Error::duplicate_field(T::FIELDS[index]));
+            }
+            seen |= 1 << index;
+            map.next_value_seed(FieldSeed {
+                place: &mut *self.0,
+                index,
+            })?;
+        }
+        match (0..T::FIELDS.len()).find(|index| seen & (1 << index) == 0) {
+            Some(index) => Err(de::Error::missing_field(T::FIELDS[index])),
+            None => Ok(()),
+        }
+    }
+}
+
+struct FieldSeed<'a, T> {
+    place: &'a mut T,
+    index: usize,
+}
+
+impl<'de, T: DeserializeInPlace> DeserializeSeed<'de> for FieldSeed<'_, T> {
+    type Value = ();
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        self.place.deserialize_field(self.index, deserializer)
+    }
+}
+
+// the index of a field, or None for unknown fields, which are ignored like the derive does
+struct FieldName(&'static [&'static str]);
+
+impl<'de> DeserializeSeed<'de> for FieldName {
+    type Value = Option<usize>;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_identifier(self)
+    }
+}
+
+impl<'de> Visitor<'de> for FieldName {
+    type Value = Option<usize>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.write_str("a field identifier")
+    }
+
+    fn visit_u64<E: de::Error>(self, value: u64) -> Result<Self::Value, E> {
+        Ok(usize::try_from(value)
+            .ok()
+            .filter(|&index| index < self.0.len()))
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<Self::Value, E> {
+        Ok(self.0.iter().position(|&field| field == value))
+    }
+
+    fn visit_bytes<E: de::Error>(self, value: &[u8]) -> Result<Self::Value, E> {
+        Ok(self.0.iter().position(|&field| field.as_bytes() == value))
+    }
+}
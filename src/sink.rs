@@ -0,0 +1,157 @@
+use crate::inflater_managed::SCRATCH_BUFFER_SIZE;
+use crate::InflateError;
+use std::fmt;
+use std::io::{self, Write};
+use std::mem;
+
+/// A destination of decompressed output, see [`InflaterManaged::inflate_into_sink`].
+///
+/// The inflater asks the sink for a buffer with [`Self::buffer`], decompresses into it, and
+/// then passes the count of bytes written with [`Self::commit`]. This is implemented for
+/// `&mut [u8]`, which is advanced past the output like [`Write`] for slices does,
+/// `Vec<u8>`, which grows without limit, [`WriteSink`], and [`FnSink`].
+///
+/// [`InflaterManaged::inflate_into_sink`]: crate::InflaterManaged::inflate_into_sink
+pub trait OutputSink {
+    /// The error returned when the sink fails to take output.
+    type Error;
+
+    /// Returns the buffer for the next output. An empty buffer means the sink is full.
+    fn buffer(&mut self) -> &mut [u8];
+
+    /// Takes the first `len` bytes of the buffer last returned by [`Self::buffer`].
+    fn commit(&mut self, len: usize) -> Result<(), Self::Error>;
+}
+
+impl OutputSink for &mut [u8] {
+    type Error = std::convert::Infallible;
+
+    fn buffer(&mut self) -> &mut [u8] {
+        self
+    }
+
+    fn commit(&mut self, len: usize) -> Result<(), Self::Error> {
+        *self = &mut mem::take(self)[len..];
+        Ok(())
+    }
+}
+
+impl OutputSink for Vec<u8> {
+    type Error = std::convert::Infallible;
+
+    fn buffer(&mut self) -> &mut [u8] {
+        let len = self.len();
+        self.resize(len + SCRATCH_BUFFER_SIZE, 0);
+        &mut self[len..]
+    }
+
+    fn commit(&mut self, len: usize) -> Result<(), Self::Error> {
+        self.truncate(self.len() - SCRATCH_BUFFER_SIZE + len);
+        Ok(())
+    }
+}
+
+/// The sink writing output to a [`Write`] through a 64 KiB buffer.
+pub struct WriteSink<W> {
+    writer: W,
+    buffer: Box<[u8]>,
+}
+
+impl<W: Write> WriteSink<W> {
+    /// Creates WriteSink with a writer
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer,
+            buffer: vec![0u8; SCRATCH_BUFFER_SIZE].into_boxed_slice(),
+        }
+    }
+
+    /// Returns the writer
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+
+    /// Returns reference to the writer
+    pub fn get_ref(&self) -> &W {
+        &self.writer
+    }
+
+    /// Returns mutable reference to the writer
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.writer
+    }
+}
+
+impl<W: Write> OutputSink for WriteSink<W> {
+    type Error = io::Error;
+
+    fn buffer(&mut self) -> &mut [u8] {
+        &mut self.buffer
+    }
+
+    fn commit(&mut self, len: usize) -> io::Result<()> {
+        self.writer.write_all(&self.buffer[..len])
+    }
+}
+
+/// The sink passing output to a function in chunks of at most 64 KiB.
+///
+/// The function returns an error to stop decompression.
+pub struct FnSink<F> {
+    function: F,
+    buffer: Box<[u8]>,
+}
+
+impl<E, F: FnMut(&[u8]) -> Result<(), E>> FnSink<F> {
+    /// Creates FnSink with a function
+    pub fn new(function: F) -> Self {
+        Self {
+            function,
+            buffer: vec![0u8; SCRATCH_BUFFER_SIZE].into_boxed_slice(),
+        }
+    }
+}
+
+impl<E, F: FnMut(&[u8]) -> Result<(), E>> OutputSink for FnSink<F> {
+    type Error = E;
+
+    fn buffer(&mut self) -> &mut [u8] {
+        &mut self.buffer
+    }
+
+    fn commit(&mut self, len: usize) -> Result<(), E> {
+        if len == 0 {
+            return Ok(());
+        }
+        (self.function)(&self.buffer[..len])
+    }
+}
+
+/// The error of [`InflaterManaged::inflate_into_sink`].
+///
+/// [`InflaterManaged::inflate_into_sink`]: crate::InflaterManaged::inflate_into_sink
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum SinkError<E> {
+    /// The deflate64 stream is invalid.
+    Inflate(InflateError),
+    /// The sink failed to take output.
+    Sink(E),
+}
+
+impl<E: fmt::Display> fmt::Display for SinkError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Inflate(error) => write!(f, "invalid deflate64: {error}"),
+            Self::Sink(error) => write!(f, "failed to write deflate64 output: {error}"),
+        }
+    }
+}
+
+impl<E: std::error::Error + 'static> std::error::Error for SinkError<E> {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Inflate(error) => Some(error),
+            Self::Sink(error) => Some(error),
+        }
+    }
+}
@@ -0,0 +1,125 @@
+use crate::BlockType;
+
+/// The count of length symbols, 257 to 285 of the literal/length alphabet.
+const LENGTH_CODES: usize = 29;
+/// The count of distance codes of deflate64.
+const DISTANCE_CODES: usize = 32;
+/// The longest match length of standard deflate.
+const DEFLATE_MAX_LENGTH: u32 = 258;
+/// The farthest match distance of standard deflate.
+const DEFLATE_MAX_DISTANCE: u32 = 32768;
+
+/// Statistics about the structure of a deflate64 stream, see
+/// [`InflaterManaged::stats`](crate::InflaterManaged::stats).
+///
+/// Statistics cover the part of the stream decoded since the inflater was created or reset.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DecodeStats {
+    block_counts: [u64; 3], // indexed by BlockType
+    literals: u64,
+    matches: u64,
+    match_bytes: u64,
+    length_codes: [u64; LENGTH_CODES],
+    distance_codes: [u64; DISTANCE_CODES],
+    largest_block_output: u64,
+    max_match_length: u32,
+    max_match_distance: u32,
+    block_start_output: u64, // output offset of the start of the current block
+}
+
+impl DecodeStats {
+    /// Returns the count of blocks of `block_type` started so far.
+    pub fn block_count(&self, block_type: BlockType) -> u64 {
+        self.block_counts[block_type as usize]
+    }
+
+    /// Returns the count of literal bytes decoded from compressed blocks.
+    pub fn literals(&self) -> u64 {
+        self.literals
+    }
+
+    /// Returns the count of length/distance pairs decoded from compressed blocks.
+    pub fn matches(&self) -> u64 {
+        self.matches
+    }
+
+    /// Returns the count of bytes copied by length/distance pairs.
+    pub fn match_bytes(&self) -> u64 {
+        self.match_bytes
+    }
+
+    /// Returns how often each length symbol was used; index 0 is symbol 257.
+    pub fn length_code_histogram(&self) -> &[u64; LENGTH_CODES] {
+        &self.length_codes
+    }
+
+    /// Returns how often each distance code was used.
+    pub fn distance_code_histogram(&self) -> &[u64; DISTANCE_CODES] {
+        &self.distance_codes
+    }
+
+    /// Returns the most bytes decompressed from a single finished block.
+    pub fn largest_block_output(&self) -> u64 {
+        self.largest_block_output
+    }
+
+    /// Returns the longest match length used, or 0 if there were no matches.
+    pub fn max_match_length(&self) -> u32 {
+        self.max_match_length
+    }
+
+    /// Returns the farthest match distance used, or 0 if there were no matches.
+    pub fn max_match_distance(&self) -> u32 {
+        self.max_match_distance
+    }
+
+    /// Returns true if a match is longer than 258 bytes or farther than 32768 bytes, which
+    /// only deflate64 can represent.
+    ///
+    /// If this is false, the data can be recompressed as standard deflate with the same
+    /// matches.
+    pub fn deflate64_features_used(&self) -> bool {
+        self.max_match_length > DEFLATE_MAX_LENGTH || self.max_match_distance > DEFLATE_MAX_DISTANCE
+    }
+
+    /// Returns true if the stream decodes to the same data as standard deflate, so that a
+    /// zip entry can be re-marked as deflate without recompressing it.
+    ///
+    /// This requires more than [`Self::deflate64_features_used`] being false: length
+    /// symbol 285 has a different meaning in deflate, and distance codes 30 and 31 are
+    /// invalid in deflate, even when they encode lengths and distances deflate allows.
+    pub fn decodes_as_deflate(&self) -> bool {
+        self.length_codes[LENGTH_CODES - 1] == 0
+            && self.distance_codes[DISTANCE_CODES - 2..] == [0, 0]
+    }
+
+    pub(crate) fn block_start(&mut self, block_type: BlockType, output_offset: u64) {
+        self.block_counts[block_type as usize] += 1;
+        self.block_start_output = output_offset;
+    }
+
+    pub(crate) fn block_end(&mut self, output_offset: u64) {
+        let block_output = output_offset - self.block_start_output;
+        self.largest_block_output = self.largest_block_output.max(block_output);
+    }
+
+    pub(crate) fn literal(&mut self) {
+        self.literals += 1;
+    }
+
+    pub(crate) fn length_code(&mut self, length_index: usize) {
+        self.matches += 1;
+        self.length_codes[length_index] += 1;
+    }
+
+    pub(crate) fn distance_code(&mut self, distance_code: usize) {
+        self.distance_codes[distance_code] += 1;
+    }
+
+    pub(crate) fn match_copied(&mut self, length: usize, distance: usize) {
+        self.match_bytes += length as u64;
+        self.max_match_length = self.max_match_length.max(length as u32);
+        self.max_match_distance = self.max_match_distance.max(distance as u32);
+    }
+}
@@ -1,9 +1,26 @@
 // TODO: move this module to deflate64 crate
 
 use crate::InflaterManaged;
-use std::io::{self, BufRead, BufReader, Read};
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::ops::Range;
 
 /// The reader the decompresses deflate64 from another BufRead.
+///
+/// This drives an [`InflaterManaged`] and keeps the compressed bytes it did not consume
+/// yet, so it can be used wherever a [`Read`] is expected.
+///
+/// ```
+/// use deflate64::Deflate64Decoder;
+/// use std::io::Read;
+///
+/// // a single stored block containing "abc"
+/// let compressed: &[u8] = &[0b001, 3, 0, !3, !0, b'a', b'b', b'c'];
+///
+/// let mut decoder = Deflate64Decoder::new(compressed);
+/// let mut output = String::new();
+/// decoder.read_to_string(&mut output).unwrap();
+/// assert_eq!(output, "abc");
+/// ```
 pub struct Deflate64Decoder<R> {
     inner: R,
     inflater: Box<InflaterManaged>,
@@ -14,20 +31,34 @@ impl<R: Read> Deflate64Decoder<BufReader<R>> {
     pub fn new(inner: R) -> Self {
         Self::with_buffer(BufReader::new(inner))
     }
+
+    /// Creates Deflate64Decoder with Read, buffering `capacity` bytes of compressed data
+    pub fn with_capacity(capacity: usize, inner: R) -> Self {
+        Self::with_buffer(BufReader::with_capacity(capacity, inner))
+    }
 }
 
 impl<R: BufRead> Deflate64Decoder<R> {
     /// Creates Deflate64Decoder with BufRead
+    ///
+    /// The slices returned by [`BufRead::fill_buf`] are passed to the inflater directly,
+    /// and only the bytes it used are consumed, so no extra buffer or copy is needed.
+    /// Prefer this over [`Self::new`] when the reader already buffers, for example a
+    /// [`BufReader`] over a file or a [`std::io::Cursor`].
     pub fn with_buffer(inner: R) -> Self {
         Self {
             inner,
-            inflater: Box::new(InflaterManaged::new()),
+            inflater: InflaterManaged::boxed(),
         }
     }
 }
 
 impl<R> Deflate64Decoder<R> {
     /// Returns inner BufRead instance
+    ///
+    /// Once this decoder returned end of file, the inner reader is positioned
+    /// right after the last byte of the deflate64 stream, so the data following
+    /// the stream can be read from it.
     pub fn into_inner(self) -> R {
         self.inner
     }
@@ -58,11 +89,8 @@ impl<R: BufRead> Read for Deflate64Decoder<R> {
 
             self.inner.consume(result.bytes_consumed);
 
-            if result.data_error {
-                return Err(io::Error::new(
-                    io::ErrorKind::InvalidInput,
-                    "invalid deflate64",
-                ));
+            if let Some(&error) = self.inflater.error() {
+                return Err(error.into());
             }
 
             if result.bytes_written == 0 && !eof && !self.inflater.finished() {
@@ -75,3 +103,106 @@ impl<R: BufRead> Read for Deflate64Decoder<R> {
         }
     }
 }
+
+const WRITE_DECODER_BUFFER_SIZE: usize = 32 * 1024;
+
+/// The writer that decompresses deflate64 written to it into another Write.
+///
+/// Compressed data is passed to [`Write::write`], and the decompressed data is written
+/// to the inner writer. Call [`Self::finish`] after writing the whole stream to flush the
+/// output and check that the stream is complete.
+///
+/// Bytes after the end of the deflate64 stream are not consumed, so writing them returns 0.
+pub struct Deflate64WriteDecoder<W: Write> {
+    inner: W,
+    inflater: Box<InflaterManaged>,
+    buffer: Box<[u8]>,
+    pending: Range<usize>, // decompressed bytes in buffer not written to inner yet
+}
+
+impl<W: Write> Deflate64WriteDecoder<W> {
+    /// Creates Deflate64WriteDecoder with Write
+    pub fn new(inner: W) -> Self {
+        Self {
+            inner,
+            inflater: InflaterManaged::boxed(),
+            buffer: vec![0u8; WRITE_DECODER_BUFFER_SIZE].into_boxed_slice(),
+            pending: 0..0,
+        }
+    }
+
+    /// Returns reference to inner Write instance
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    /// Returns mutable reference to inner Write instance
+    ///
+    /// Writing to the inner writer directly may corrupt the decompressed output.
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.inner
+    }
+
+    /// Writes all decompressed data to the inner writer and flushes it, then checks
+    /// that the end of the deflate64 stream was written.
+    pub fn try_finish(&mut self) -> io::Result<()> {
+        self.flush()?;
+        if !self.inflater.finished() {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "incomplete deflate64",
+            ));
+        }
+        Ok(())
+    }
+
+    /// Same as [`Self::try_finish`] but returns the inner Write instance
+    pub fn finish(mut self) -> io::Result<W> {
+        self.try_finish()?;
+        Ok(self.inner)
+    }
+
+    fn write_pending(&mut self) -> io::Result<()> {
+        while !self.pending.is_empty() {
+            match self.inner.write(&self.buffer[self.pending.clone()]) {
+                Ok(0) => return Err(io::ErrorKind::WriteZero.into()),
+                Ok(n) => self.pending.start += n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<W: Write> Write for Deflate64WriteDecoder<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.write_pending()?;
+
+        let mut consumed = 0;
+        loop {
+            let result = self.inflater.inflate(&buf[consumed..], &mut self.buffer);
+            consumed += result.bytes_consumed;
+            self.pending = 0..result.bytes_written;
+
+            if let Some(&error) = self.inflater.error() {
+                return Err(error.into());
+            }
+
+            if let Err(e) = self.write_pending() {
+                // the rest of the output is written by the next call
+                return if consumed > 0 { Ok(consumed) } else { Err(e) };
+            }
+
+            if result.bytes_written < self.buffer.len() {
+                // all input consumed, or the end of the stream was reached
+                return Ok(consumed);
+            }
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.write_pending()?;
+        self.inner.flush()
+    }
+}
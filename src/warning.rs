@@ -0,0 +1,106 @@
+use crate::InflateErrorKind;
+use std::fmt;
+
+/// The kind of a non-fatal anomaly found while decoding a deflate64 stream.
+///
+/// Streams with these anomalies decode fine, but well-behaved encoders don't produce them.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub enum InflateWarningKind {
+    /// An uncompressed block with zero length.
+    EmptyStoredBlock,
+    /// The bits skipped to align an uncompressed block to a byte boundary are not zero.
+    NonZeroStoredBlockPadding,
+    /// The literal/length code lengths of a dynamic block do not use the whole code space.
+    IncompleteLiteralLengthTree,
+    /// The literal/length code lengths of a dynamic block use more than the whole code space.
+    OverSubscribedLiteralLengthTree,
+    /// The distance code lengths of a dynamic block do not use the whole code space.
+    ///
+    /// A single distance code of length one, or no distance codes at all, is allowed by
+    /// RFC 1951 and is not reported.
+    IncompleteDistanceTree,
+    /// The distance code lengths of a dynamic block use more than the whole code space.
+    OverSubscribedDistanceTree,
+    /// A match reaches back before the start of the stream, and zeros were copied for the
+    /// missing history, see [`InflaterOptions::fill_missing_history`].
+    ///
+    /// [`InflaterOptions::fill_missing_history`]: crate::InflaterOptions::fill_missing_history
+    MissingHistory,
+}
+
+impl InflateWarningKind {
+    // the error reported instead of this warning in strict mode, if the anomaly violates RFC 1951
+    pub(crate) fn strict_error(self) -> Option<InflateErrorKind> {
+        match self {
+            Self::EmptyStoredBlock => None,
+            Self::NonZeroStoredBlockPadding => Some(InflateErrorKind::InvalidStoredBlockPadding),
+            Self::IncompleteLiteralLengthTree | Self::OverSubscribedLiteralLengthTree => {
+                Some(InflateErrorKind::InvalidLiteralLengthTree)
+            }
+            Self::IncompleteDistanceTree | Self::OverSubscribedDistanceTree => {
+                Some(InflateErrorKind::InvalidDistanceTree)
+            }
+            Self::MissingHistory => Some(InflateErrorKind::DistanceTooFarBack),
+        }
+    }
+}
+
+impl fmt::Display for InflateWarningKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::EmptyStoredBlock => "empty stored block",
+            Self::NonZeroStoredBlockPadding => "non-zero stored block padding",
+            Self::IncompleteLiteralLengthTree => "incomplete literal/length tree",
+            Self::OverSubscribedLiteralLengthTree => "over-subscribed literal/length tree",
+            Self::IncompleteDistanceTree => "incomplete distance tree",
+            Self::OverSubscribedDistanceTree => "over-subscribed distance tree",
+            Self::MissingHistory => "match before the start of the stream",
+        })
+    }
+}
+
+/// A non-fatal anomaly found while decoding a deflate64 stream.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct InflateWarning {
+    kind: InflateWarningKind,
+    block_index: u64,
+    input_offset: u64,
+}
+
+impl InflateWarning {
+    pub(crate) fn new(kind: InflateWarningKind, block_index: u64, input_offset: u64) -> Self {
+        Self {
+            kind,
+            block_index,
+            input_offset,
+        }
+    }
+
+    /// Returns the kind of this warning.
+    pub fn kind(&self) -> InflateWarningKind {
+        self.kind
+    }
+
+    /// Returns the zero-based index of the block in which the anomaly was found.
+    pub fn block_index(&self) -> u64 {
+        self.block_index
+    }
+
+    /// Returns the offset of the input byte at which the anomaly was found.
+    pub fn input_offset(&self) -> u64 {
+        self.input_offset
+    }
+}
+
+impl fmt::Display for InflateWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} in block {} at input byte {}",
+            self.kind, self.block_index, self.input_offset
+        )
+    }
+}
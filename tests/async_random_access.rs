@@ -0,0 +1,91 @@
+#![cfg(all(feature = "checkpoint", feature = "futures-io"))]
+
+use deflate64::{AsyncRandomAccessReader, AsyncReadAt, Deflate64Index};
+use futures::executor::block_on;
+use futures::io::{AsyncReadExt, AsyncSeekExt};
+use std::io::{self, SeekFrom};
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+mod common;
+
+use common::{compressed_data, BINARY_WAV_COMPRESSED_SIZE, BINARY_WAV_DATA};
+
+const INTERVAL: u64 = 256 * 1024;
+
+fn index() -> Arc<Deflate64Index> {
+    Arc::new(Deflate64Index::build(compressed_data(), INTERVAL).unwrap())
+}
+
+/// AsyncReadAt which returns Pending before every read, and reads at most `chunk` bytes
+struct PendingSource {
+    data: &'static [u8],
+    chunk: usize,
+    pending: bool,
+}
+
+impl AsyncReadAt for PendingSource {
+    fn poll_read_at(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        offset: u64,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        self.pending = !self.pending;
+        if self.pending {
+            cx.waker().wake_by_ref();
+            return Poll::Pending;
+        }
+        let data = self.data.get(offset as usize..).unwrap_or_default();
+        let len = buf.len().min(self.chunk).min(data.len());
+        buf[..len].copy_from_slice(&data[..len]);
+        Poll::Ready(Ok(len))
+    }
+}
+
+#[test]
+fn seek_and_read() {
+    let source = PendingSource {
+        data: compressed_data(),
+        chunk: 1000,
+        pending: false,
+    };
+    let mut reader = AsyncRandomAccessReader::new(index(), source);
+    block_on(async {
+        for offset in [1000000, 2000000, 10, INTERVAL, INTERVAL - 1, 2700000, 0] {
+            assert_eq!(reader.seek(SeekFrom::Start(offset)).await.unwrap(), offset);
+            let mut data = vec![0u8; 1000];
+            reader.read_exact(&mut data).await.unwrap();
+            let offset = offset as usize;
+            assert_eq!(data, BINARY_WAV_DATA[offset..][..1000]);
+        }
+
+        assert_eq!(reader.seek(SeekFrom::Current(-1000)).await.unwrap(), 0);
+        let end = BINARY_WAV_DATA.len() as u64;
+        assert_eq!(
+            reader.seek(SeekFrom::End(-100000)).await.unwrap(),
+            end - 100000
+        );
+        let mut data = vec![];
+        reader.read_to_end(&mut data).await.unwrap();
+        assert_eq!(data, BINARY_WAV_DATA[BINARY_WAV_DATA.len() - 100000..]);
+        assert_eq!(reader.position(), end);
+
+        let error = reader.seek(SeekFrom::End(1)).await.unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+        let error = reader.seek(SeekFrom::Current(-(end as i64) - 1)).await;
+        assert_eq!(error.unwrap_err().kind(), io::ErrorKind::InvalidInput);
+    });
+}
+
+#[test]
+fn truncated_source() {
+    let source = compressed_data()[..BINARY_WAV_COMPRESSED_SIZE / 2].to_vec();
+    let mut reader = AsyncRandomAccessReader::new(index(), source);
+    block_on(async {
+        reader.seek(SeekFrom::Start(2000000)).await.unwrap();
+        let error = reader.read(&mut [0u8; 10]).await.unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::UnexpectedEof);
+    });
+}
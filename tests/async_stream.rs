@@ -0,0 +1,50 @@
+#![cfg(feature = "futures-io")]
+
+use deflate64::AsyncDeflate64Decoder;
+use futures::executor::block_on;
+use futures::io::AsyncReadExt;
+use std::io;
+
+mod common;
+
+use common::{compressed_data, PendingReader, BINARY_WAV_DATA};
+
+#[test]
+fn decode_from_async_read() {
+    let mut decoder = AsyncDeflate64Decoder::new(compressed_data());
+
+    let mut uncompressed_data = vec![];
+    block_on(decoder.read_to_end(&mut uncompressed_data)).unwrap();
+
+    assert_eq!(&uncompressed_data[..], BINARY_WAV_DATA);
+}
+
+#[test]
+fn decode_from_pending_reader() {
+    let reader = PendingReader::new(compressed_data(), 1000);
+    let mut decoder = AsyncDeflate64Decoder::with_capacity(100, reader);
+
+    // read_to_end re-initializes the spare capacity on every poll, which is slow
+    // with a reader that is pending this often
+    let mut uncompressed_data = vec![];
+    let mut buf = [0u8; 4096];
+    loop {
+        let read = block_on(decoder.read(&mut buf)).unwrap();
+        if read == 0 {
+            break;
+        }
+        uncompressed_data.extend_from_slice(&buf[..read]);
+    }
+
+    assert_eq!(&uncompressed_data[..], BINARY_WAV_DATA);
+}
+
+#[test]
+fn decode_invalid_stream() {
+    // BFINAL=1, BTYPE=11
+    let mut decoder = AsyncDeflate64Decoder::new(&[0b111u8][..]);
+
+    let mut uncompressed_data = vec![];
+    let error = block_on(decoder.read_to_end(&mut uncompressed_data)).unwrap_err();
+    assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+}
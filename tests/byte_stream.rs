@@ -0,0 +1,106 @@
+#![cfg(feature = "stream")]
+
+use bytes::Bytes;
+use deflate64::Deflate64Stream;
+use futures::executor::block_on;
+use futures::stream::{self, StreamExt, TryStreamExt};
+use std::io;
+
+mod common;
+
+use common::{compressed_data, BINARY_WAV_DATA};
+
+fn chunked(
+    data: &'static [u8],
+    chunk: usize,
+) -> impl stream::Stream<Item = io::Result<Bytes>> + Unpin {
+    stream::iter(
+        data.chunks(chunk)
+            .map(|chunk| Ok(Bytes::from_static(chunk))),
+    )
+}
+
+fn decode_all(
+    decoder: impl stream::Stream<Item = io::Result<Bytes>> + Unpin,
+) -> io::Result<Vec<u8>> {
+    block_on(
+        decoder.try_fold(Vec::new(), |mut output, chunk| async move {
+            output.extend_from_slice(&chunk);
+            Ok(output)
+        }),
+    )
+}
+
+#[test]
+fn decode_single_chunk() {
+    let decoder = Deflate64Stream::new(chunked(compressed_data(), usize::MAX));
+    assert_eq!(decode_all(decoder).unwrap(), BINARY_WAV_DATA);
+}
+
+#[test]
+fn decode_small_chunks() {
+    for chunk in [1, 7, 4096] {
+        let decoder = Deflate64Stream::new(chunked(compressed_data(), chunk));
+        assert_eq!(
+            decode_all(decoder).unwrap(),
+            BINARY_WAV_DATA,
+            "chunk {chunk}"
+        );
+    }
+}
+
+#[test]
+fn output_chunk_size() {
+    let decoder = Deflate64Stream::with_chunk_size(1000, chunked(compressed_data(), 4096));
+    let chunks = block_on(decoder.try_collect::<Vec<_>>()).unwrap();
+    assert!(chunks
+        .iter()
+        .all(|chunk| !chunk.is_empty() && chunk.len() <= 1000));
+    assert_eq!(chunks.concat(), BINARY_WAV_DATA);
+}
+
+#[test]
+fn empty_input_chunks() {
+    let input = stream::iter([
+        Bytes::new(),
+        Bytes::from_static(compressed_data()),
+        Bytes::new(),
+    ])
+    .map(Ok);
+    assert_eq!(
+        decode_all(Deflate64Stream::new(input)).unwrap(),
+        BINARY_WAV_DATA
+    );
+}
+
+#[test]
+fn truncated_input() {
+    let decoder = Deflate64Stream::new(chunked(&compressed_data()[..100000], 4096));
+    let error = decode_all(decoder).unwrap_err();
+    assert_eq!(error.kind(), io::ErrorKind::UnexpectedEof);
+}
+
+#[test]
+fn invalid_input() {
+    let decoder = Deflate64Stream::new(chunked(&[0b111], 1));
+    let error = decode_all(decoder).unwrap_err();
+    assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+}
+
+#[test]
+fn inner_error() {
+    let input = chunked(&compressed_data()[..100000], 4096)
+        .chain(stream::iter([Err(io::Error::other("connection reset"))]));
+    let mut decoder = Deflate64Stream::new(input);
+    let error = block_on(async {
+        loop {
+            match decoder.next().await {
+                Some(Ok(_)) => {}
+                Some(Err(e)) => break e,
+                None => panic!("stream ended without error"),
+            }
+        }
+    });
+    assert_eq!(error.to_string(), "connection reset");
+    assert!(block_on(decoder.next()).is_none());
+}
@@ -0,0 +1,66 @@
+use deflate64::{CarveConfidence, StreamCarver};
+
+mod common;
+
+use common::{
+    BINARY_WAV_COMPRESSED_SIZE, BINARY_WAV_DATA_OFFSET, BINARY_WAV_UNCOMPRESSED_SIZE, ZIP_FILE_DATA,
+};
+
+fn noise(len: usize) -> Vec<u8> {
+    let mut seed = 0x2548_u32;
+    (0..len)
+        .map(|_| {
+            seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12345);
+            (seed >> 16) as u8
+        })
+        .collect()
+}
+
+#[test]
+fn zip_file() {
+    let found = StreamCarver::new(ZIP_FILE_DATA)
+        .max_trial_output(u64::MAX)
+        .next()
+        .unwrap();
+    assert_eq!(found.offset, BINARY_WAV_DATA_OFFSET);
+    assert_eq!(found.input_len, BINARY_WAV_COMPRESSED_SIZE);
+    assert_eq!(found.output_len, BINARY_WAV_UNCOMPRESSED_SIZE as u64);
+    assert_eq!(found.confidence, CarveConfidence::High);
+}
+
+#[test]
+fn streams_in_noise() {
+    let mut data = noise(4096);
+    let stored = data.len();
+    data.extend_from_slice(&[0b001, 100, 0, !100, !0]);
+    data.extend_from_slice(&[b'a'; 100]);
+    data.extend_from_slice(&noise(4096));
+    // truncated in the middle of the stream
+    let truncated = data.len();
+    data.extend_from_slice(&ZIP_FILE_DATA[BINARY_WAV_DATA_OFFSET..][..100_000]);
+
+    let found: Vec<_> = StreamCarver::new(&data)
+        .filter(|found| found.confidence > CarveConfidence::Low)
+        .collect();
+    assert_eq!(found.len(), 2, "{found:?}");
+    assert_eq!(found[0].offset, stored);
+    assert_eq!(found[0].input_len, 105);
+    assert_eq!(found[0].output_len, 100);
+    assert_eq!(found[0].confidence, CarveConfidence::High);
+    assert_eq!(found[1].offset, truncated);
+    assert_eq!(found[1].input_len, 100_000);
+    assert_eq!(found[1].confidence, CarveConfidence::Medium);
+}
+
+#[test]
+fn trial_limit() {
+    let mut carver = StreamCarver::new(&ZIP_FILE_DATA[BINARY_WAV_DATA_OFFSET..])
+        .max_trial_output(100_000)
+        .min_output(1000);
+    let found = carver.next().unwrap();
+    assert_eq!(found.offset, 0);
+    assert!(found.output_len >= 100_000);
+    assert_eq!(found.confidence, CarveConfidence::Medium);
+    // the scan continues after the input decoded by the trial
+    assert_eq!(carver.offset(), found.input_len);
+}
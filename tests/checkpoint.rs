@@ -1,17 +1,19 @@
 #![cfg(feature = "checkpoint")]
 
-use deflate64::{CheckpointStreamPositions, InflaterManaged};
+use deflate64::{
+    validate_checkpoint, verify_checkpoint_chain, BlockType, CheckpointChainError,
+    CheckpointChecksum, CheckpointInfo, CheckpointPolicy, CheckpointStreamPositions,
+    CheckpointTransform, Checkpointing, EntryMetadata, InflateErrorKind, InflateStatus,
+    InflaterManaged, InflaterOptions, ZlibHandoff, MAX_CHECKPOINT_METADATA_LEN,
+    MAX_CHECKPOINT_SIZE,
+};
 
-const BINARY_WAV_DATA_OFFSET: usize = 40;
-const BINARY_WAV_COMPRESSED_SIZE: usize = 2669743;
-const BINARY_WAV_UNCOMPRESSED_SIZE: usize = 2703788;
+mod common;
 
-static ZIP_FILE_DATA: &[u8] = include_bytes!("../test-assets/deflate64.zip");
-static BINARY_WAV_DATA: &[u8] = include_bytes!("../test-assets/folder/binary.wmv");
-
-fn compressed_data() -> &'static [u8] {
-    &ZIP_FILE_DATA[BINARY_WAV_DATA_OFFSET..][..BINARY_WAV_COMPRESSED_SIZE]
-}
+use common::{
+    bomb, compressed_data, fletcher32_checksum, BINARY_WAV_COMPRESSED_SIZE, BINARY_WAV_DATA,
+    BINARY_WAV_UNCOMPRESSED_SIZE,
+};
 
 fn assert_bytes_eq(actual: &[u8], expected: &[u8], msg: &str) {
     match actual.iter().zip(expected).position(|(a, b)| a != b) {
@@ -35,7 +37,7 @@ fn assert_bytes_eq(actual: &[u8], expected: &[u8], msg: &str) {
 }
 
 fn inflate_with_checkpoints(output_interval: usize) -> Vec<(Vec<u8>, CheckpointStreamPositions)> {
-    let mut inflater = Box::new(InflaterManaged::new());
+    let mut inflater = Box::new(InflaterManaged::with_checkpoints());
     let mut output = vec![0u8; BINARY_WAV_UNCOMPRESSED_SIZE + 100];
     let mut written = 0;
     let mut consumed = 0;
@@ -65,7 +67,7 @@ fn inflate_with_checkpoints(output_interval: usize) -> Vec<(Vec<u8>, CheckpointS
 }
 
 fn resume_from_checkpoint(
-    inflater: &mut InflaterManaged,
+    inflater: &mut InflaterManaged<Checkpointing>,
     compressed: &[u8],
     positions: &CheckpointStreamPositions,
 ) -> Vec<u8> {
@@ -73,6 +75,11 @@ fn resume_from_checkpoint(
     let mut output = vec![0u8; BINARY_WAV_UNCOMPRESSED_SIZE + 100];
     let mut written = 0;
     let mut consumed = input_skip;
+    assert_eq!(inflater.total_in(), positions.input_bytes_to_skip);
+    assert_eq!(
+        inflater.total_out(),
+        positions.output_bytes_already_returned
+    );
 
     while consumed < compressed.len() && !inflater.finished() {
         let r = inflater.inflate(&compressed[consumed..], &mut output[written..]);
@@ -80,6 +87,11 @@ fn resume_from_checkpoint(
         written += r.bytes_written;
         assert!(!r.data_error);
     }
+    assert_eq!(inflater.total_in(), consumed as u64);
+    assert_eq!(
+        inflater.total_out(),
+        positions.output_bytes_already_returned + written as u64
+    );
 
     output.truncate(written);
     output
@@ -96,15 +108,6 @@ fn build_uncompressed_deflate_stream(data: &[u8]) -> Vec<u8> {
     stream
 }
 
-fn fletcher32_checksum(data: &[u8]) -> u32 {
-    let (mut a, mut b) = (0u32, 0u32);
-    for &byte in data {
-        a = a.wrapping_add(byte as u32);
-        b = b.wrapping_add(a);
-    }
-    (b << 16) | (a & 0xFFFF)
-}
-
 fn build_synthetic_checkpoint(window_data: &[u8]) -> Vec<u8> {
     let mut cp = vec![0u8; 346 + window_data.len()];
     cp[0..2].copy_from_slice(&0x1001u16.to_le_bytes()); // version
@@ -132,7 +135,7 @@ fn rebuild_checkpoint_checksum(data: &mut [u8]) {
 #[test]
 fn checkpoint_availability_lifecycle() {
     // Before first inflate
-    let mut inflater = Box::new(InflaterManaged::new());
+    let mut inflater = Box::new(InflaterManaged::with_checkpoints());
     assert!(inflater.checkpoint().is_none());
 
     // Mid-stream
@@ -142,7 +145,7 @@ fn checkpoint_availability_lifecycle() {
     assert!(inflater.checkpoint().is_some());
 
     // After finished with output drained
-    let mut inflater2 = Box::new(InflaterManaged::new());
+    let mut inflater2 = Box::new(InflaterManaged::with_checkpoints());
     let mut output2 = vec![0u8; BINARY_WAV_UNCOMPRESSED_SIZE + 100];
     inflater2.inflate(compressed_data(), &mut output2);
     assert!(inflater2.finished());
@@ -154,7 +157,7 @@ fn failed_restore_preserves_inflater_state() {
     let checkpoints = inflate_with_checkpoints(10000);
     let (valid_cp, _) = &checkpoints[checkpoints.len() / 2];
 
-    let mut inflater = Box::new(InflaterManaged::new());
+    let mut inflater = Box::new(InflaterManaged::with_checkpoints());
     inflater.restore_from_checkpoint(valid_cp).unwrap();
     let (before, _) = inflater.checkpoint().unwrap();
 
@@ -171,11 +174,57 @@ fn failed_restore_preserves_inflater_state() {
     assert_bytes_eq(&before, &after, "state changed after failed restore");
 }
 
+#[test]
+fn restore_after_data_error() {
+    let checkpoints = inflate_with_checkpoints(10000);
+    let (cp_data, positions) = &checkpoints[checkpoints.len() / 2];
+
+    let mut inflater = Box::new(InflaterManaged::with_checkpoints_and_options(
+        InflaterOptions::new().collect_stats(true),
+    ));
+    // an empty stored block, a stored block of "abc" and BTYPE=11
+    let stream = [
+        0b000, 0, 0, !0, !0, 0b000, 3, 0, !3, !0, b'a', b'b', b'c', 0xff,
+    ];
+    let result = inflater.inflate(&stream, &mut [0u8; 1024]);
+    assert!(result.data_error);
+    assert!(!inflater.warnings().is_empty());
+    assert_eq!(
+        inflater
+            .stats()
+            .unwrap()
+            .block_count(BlockType::Uncompressed),
+        2
+    );
+
+    let restored = inflater.restore_from_checkpoint(cp_data).unwrap();
+    assert_eq!(&restored, positions);
+    assert!(!inflater.errored());
+    assert!(inflater.error().is_none());
+    assert!(inflater.warnings().is_empty());
+    assert_eq!(
+        inflater
+            .stats()
+            .unwrap()
+            .block_count(BlockType::Uncompressed),
+        0
+    );
+
+    let output = resume_from_checkpoint(&mut inflater, compressed_data(), positions);
+    let out_skip = positions.output_bytes_already_returned as usize;
+    assert_bytes_eq(
+        &output,
+        &BINARY_WAV_DATA[out_skip..],
+        "output after restore",
+    );
+    assert!(inflater.finished());
+}
+
 #[test]
 fn reject_bad_checkpoints() {
     let checkpoints = inflate_with_checkpoints(10000);
     let (cp_data, _) = &checkpoints[checkpoints.len() / 2];
-    let mut inflater = Box::new(InflaterManaged::new());
+    let mut inflater = Box::new(InflaterManaged::with_checkpoints());
 
     let mut bad_version = cp_data.clone();
     bad_version[0] = 0x11;
@@ -200,11 +249,120 @@ fn reject_bad_checkpoints() {
     assert!(inflater.restore_from_checkpoint(cp_data).is_some());
 }
 
+#[test]
+fn validate_checkpoints() {
+    let checkpoints = inflate_with_checkpoints(100000);
+    for (cp_data, positions) in &checkpoints {
+        assert_eq!(validate_checkpoint(cp_data).as_ref(), Some(positions));
+    }
+    let (cp_data, _) = checkpoints
+        .iter()
+        .find(|(cp_data, _)| {
+            CheckpointInfo::parse(cp_data).unwrap().block_type == BlockType::Dynamic
+        })
+        .unwrap();
+
+    let invalid = |change: &dyn Fn(&mut [u8])| {
+        let mut changed = cp_data.clone();
+        change(&mut changed);
+        rebuild_checkpoint_checksum(&mut changed);
+        assert!(validate_checkpoint(&changed).is_none());
+        let mut inflater = Box::new(InflaterManaged::with_checkpoints());
+        assert!(inflater.restore_from_checkpoint(&changed).is_none());
+    };
+    invalid(&|data| data[0] = 0x11); // version
+    invalid(&|data| data[2] = 0x40); // unknown required flag
+    invalid(&|data| data[15] = 0x03); // reserved block type
+    invalid(&|data| data[18] = 17); // code length
+    invalid(&|data| data[349] = 0x01); // unread output longer than the window
+
+    let mut corrupted = cp_data.clone();
+    corrupted[1000] ^= 0x01;
+    assert!(validate_checkpoint(&corrupted).is_none());
+    assert!(validate_checkpoint(&cp_data[..cp_data.len() - 1]).is_none());
+
+    // deltas can't be restored without their base
+    let mut inflater = Box::new(InflaterManaged::with_checkpoints());
+    inflater.restore_from_checkpoint(cp_data).unwrap();
+    let (delta, _) = inflater.checkpoint_delta(cp_data).unwrap();
+    assert!(CheckpointInfo::parse(&delta).is_some());
+    assert!(validate_checkpoint(&delta).is_none());
+}
+
+#[test]
+fn checkpoint_chain() {
+    let compressed = compressed_data();
+    let checkpoints: Vec<_> = inflate_with_checkpoints(500000)
+        .into_iter()
+        .map(|(cp_data, _)| cp_data)
+        .collect();
+    assert!(checkpoints.len() >= 4);
+    assert_eq!(verify_checkpoint_chain(compressed, &checkpoints), Ok(()));
+    assert_eq!(verify_checkpoint_chain::<Vec<u8>>(compressed, &[]), Ok(()));
+
+    let mut reordered = checkpoints.clone();
+    reordered.swap(1, 2);
+    assert_eq!(
+        verify_checkpoint_chain(compressed, &reordered),
+        Err(CheckpointChainError::Invalid(2))
+    );
+    let mut corrupted = checkpoints.clone();
+    corrupted[1][1000] ^= 0x01;
+    assert_eq!(
+        verify_checkpoint_chain(compressed, &corrupted),
+        Err(CheckpointChainError::Invalid(1))
+    );
+
+    // a checkpoint with a different history restores, but does not match the stream
+    let mut diverged = checkpoints.clone();
+    diverged[1][1000] ^= 0x01;
+    rebuild_checkpoint_checksum(&mut diverged[1]);
+    assert!(validate_checkpoint(&diverged[1]).is_some());
+    assert_eq!(
+        verify_checkpoint_chain(compressed, &diverged),
+        Err(CheckpointChainError::Mismatch(1))
+    );
+
+    let last = checkpoints.len() - 1;
+    assert_eq!(
+        verify_checkpoint_chain(&compressed[..compressed.len() - 1000], &checkpoints),
+        Err(CheckpointChainError::Incomplete(last))
+    );
+
+    // with output CRC-32s, stale checkpoints are found by their CRC-32 too
+    let options = InflaterOptions::new().track_crc32(true);
+    let mut checkpoints: Vec<_> = inflate_with_policy(options, &mut CheckpointPolicy::new(500000))
+        .into_iter()
+        .map(|(cp_data, _)| cp_data)
+        .collect();
+    assert!(CheckpointInfo::parse(&checkpoints[2])
+        .unwrap()
+        .output_crc32
+        .is_some());
+    assert_eq!(verify_checkpoint_chain(compressed, &checkpoints), Ok(()));
+    // the stream diverges between the second and the third checkpoint
+    let mut changed = compressed.to_vec();
+    let offset = validate_checkpoint(&checkpoints[1])
+        .unwrap()
+        .input_bytes_to_skip as usize;
+    changed[offset + 1000] ^= 0x10;
+    assert_eq!(
+        verify_checkpoint_chain(&changed, &checkpoints),
+        Err(CheckpointChainError::Mismatch(2))
+    );
+    checkpoints[2][350] ^= 0x01;
+    rebuild_checkpoint_checksum(&mut checkpoints[2]);
+    assert_eq!(
+        verify_checkpoint_chain(compressed, &checkpoints),
+        Err(CheckpointChainError::Mismatch(2))
+    );
+}
+
 #[test]
 fn restore_and_reserialize() {
     let checkpoints = inflate_with_checkpoints(10000);
     for (cp_data, cp_positions) in &checkpoints {
-        let mut restored = Box::new(InflaterManaged::new());
+        let mut restored = Box::new(InflaterManaged::with_checkpoints());
         let positions = restored.restore_from_checkpoint(cp_data).unwrap();
         assert_eq!(cp_positions, &positions);
 
@@ -229,10 +387,70 @@ fn restore_and_reserialize() {
     }
 }
 
+#[test]
+fn restore_past_output_limit() {
+    let checkpoints = inflate_with_checkpoints(100_000);
+    let (cp_data, positions) = &checkpoints[0];
+    assert_eq!(positions.output_bytes_already_returned, 100_000);
+
+    // the checkpoint already returned more output than these inflaters allow
+    let mut inflater = Box::new(InflaterManaged::with_checkpoints_and_options(
+        InflaterOptions::new().max_output(1000),
+    ));
+    assert_eq!(inflater.restore_from_checkpoint(cp_data), None);
+    let mut inflater = Box::new(InflaterManaged::with_checkpoints_and_options(
+        InflaterOptions::new().entry_metadata(EntryMetadata {
+            crc32: 0,
+            compressed_size: BINARY_WAV_COMPRESSED_SIZE as u64,
+            uncompressed_size: 1000,
+        }),
+    ));
+    assert_eq!(inflater.restore_from_checkpoint(cp_data), None);
+
+    // the limit applies to the output before the checkpoint too
+    let mut inflater = Box::new(InflaterManaged::with_checkpoints_and_options(
+        InflaterOptions::new().max_output(150_000),
+    ));
+    inflater.restore_from_checkpoint(cp_data).unwrap();
+    let skip = positions.input_bytes_to_skip as usize;
+    let mut output = vec![0u8; BINARY_WAV_UNCOMPRESSED_SIZE];
+    let result = inflater.inflate(&compressed_data()[skip..], &mut output);
+    assert!(result.data_error);
+    assert_eq!(result.bytes_written, 50_000);
+    assert_eq!(
+        inflater.error().unwrap().kind(),
+        InflateErrorKind::OutputLimitExceeded
+    );
+}
+
+#[test]
+fn restore_past_expansion_ratio() {
+    let stream = bomb(100);
+    let mut inflater = Box::new(InflaterManaged::with_checkpoints());
+    let mut output = vec![0u8; 2 << 20];
+    let result = inflater.inflate(&stream, &mut output);
+    assert_eq!(result.bytes_written, output.len());
+    let (cp_data, positions) = inflater.checkpoint().unwrap();
+
+    // the output before the checkpoint already exceeds the ratio for the input before it
+    let mut inflater = Box::new(InflaterManaged::with_checkpoints_and_options(
+        InflaterOptions::new().max_expansion_ratio(1000),
+    ));
+    inflater.restore_from_checkpoint(&cp_data).unwrap();
+    let skip = positions.input_bytes_to_skip as usize;
+    let result = inflater.inflate(&stream[skip..], &mut output);
+    assert!(result.data_error);
+    assert_eq!(result.bytes_written, 0);
+    assert_eq!(
+        inflater.error().unwrap().kind(),
+        InflateErrorKind::ExpansionRatioExceeded
+    );
+}
+
 #[test]
 fn restore_continue_restore() {
     let checkpoints = inflate_with_checkpoints(10000);
-    let mut inflater = Box::new(InflaterManaged::new());
+    let mut inflater = Box::new(InflaterManaged::with_checkpoints());
 
     // Restore, do work
     let (cp1, _) = &checkpoints[0];
@@ -245,7 +463,7 @@ fn restore_continue_restore() {
 
     // Take new checkpoint, restore it, verify output
     let (new_cp, new_pos) = inflater.checkpoint().unwrap();
-    let mut restored = Box::new(InflaterManaged::new());
+    let mut restored = Box::new(InflaterManaged::with_checkpoints());
     restored.restore_from_checkpoint(&new_cp).unwrap();
     let output = resume_from_checkpoint(&mut restored, compressed_data(), &new_pos);
     assert_bytes_eq(
@@ -261,13 +479,13 @@ fn checkpoint_uncompressed_block() {
     let compressed = build_uncompressed_deflate_stream(&original);
 
     // Decompress partway, take checkpoint
-    let mut inflater = Box::new(InflaterManaged::new());
+    let mut inflater = Box::new(InflaterManaged::with_checkpoints());
     let mut output = vec![0u8; 500];
     inflater.inflate(&compressed, &mut output);
     let (cp_data, positions) = inflater.checkpoint().unwrap();
 
     // Verify reserialization
-    let mut restored = Box::new(InflaterManaged::new());
+    let mut restored = Box::new(InflaterManaged::with_checkpoints());
     restored.restore_from_checkpoint(&cp_data).unwrap();
     let (reserialized, _) = restored.checkpoint().unwrap();
     assert_bytes_eq(&cp_data, &reserialized, "reserialized checkpoint");
@@ -291,7 +509,7 @@ fn checkpoint_with_large_unread_buffer() {
     ];
 
     // Fill internal inflater buffer
-    let mut inflater = InflaterManaged::new();
+    let mut inflater = InflaterManaged::with_checkpoints();
     let mut output = [0xff; 100];
     let _ = inflater.inflate(&DEFLATE64_ZERO_256KB, &mut output);
     assert!(!inflater.finished());
@@ -303,7 +521,7 @@ fn checkpoint_with_large_unread_buffer() {
     let (cp, _) = inflater.checkpoint().unwrap();
     assert!(cp.len() > 346 + MAX_HISTORY);
 
-    let mut restored = InflaterManaged::new();
+    let mut restored = InflaterManaged::with_checkpoints();
     restored.restore_from_checkpoint(&cp).unwrap();
     assert_eq!(avail_before, restored.available_output());
 
@@ -321,7 +539,7 @@ fn all_window_bytes_restored_properly() {
     let checkpoints = inflate_with_checkpoints(10000);
     let (real_cp, real_positions) = &checkpoints[checkpoints.len() / 2];
 
-    let mut inflater = Box::new(InflaterManaged::new());
+    let mut inflater = Box::new(InflaterManaged::with_checkpoints());
     let synthetic_cp = build_synthetic_checkpoint(&vec![0xFE; OUTPUT_BUFFER_SIZE]);
     inflater.restore_from_checkpoint(&synthetic_cp).unwrap();
     assert_eq!(inflater.available_output(), OUTPUT_BUFFER_SIZE);
@@ -344,3 +562,857 @@ fn all_window_bytes_restored_properly() {
         "output after real restore",
     );
 }
+
+#[test]
+fn checkpoint_into_buffer() {
+    let mut inflater = Box::new(InflaterManaged::with_checkpoints());
+    let mut output = vec![0u8; 100000];
+    inflater.inflate(&compressed_data()[..50000], &mut output);
+    let (expected, expected_positions) = inflater.checkpoint().unwrap();
+
+    let mut buffer = vec![0u8; MAX_CHECKPOINT_SIZE];
+    let (len, positions) = inflater.checkpoint_into(&mut buffer).unwrap();
+    assert_eq!(&buffer[..len], &expected[..]);
+    assert_eq!(positions, expected_positions);
+
+    assert!(inflater.checkpoint_into(&mut buffer[..len - 1]).is_none());
+    assert!(inflater.checkpoint_into(&mut buffer[..len]).is_some());
+}
+
+#[test]
+fn checkpoint_len() {
+    assert_eq!(InflaterManaged::checkpoint_max_len(), MAX_CHECKPOINT_SIZE);
+
+    let mut inflater = Box::new(InflaterManaged::with_checkpoints());
+    assert_eq!(inflater.checkpoint_len(), None);
+
+    let mut output = vec![0u8; 100];
+    inflater.inflate(&compressed_data()[..50000], &mut output);
+    let len = inflater.checkpoint_len().unwrap();
+    assert!(len < MAX_CHECKPOINT_SIZE);
+    let mut buffer = [0u8; MAX_CHECKPOINT_SIZE];
+    assert_eq!(inflater.checkpoint_into(&mut buffer).unwrap().0, len);
+    assert_eq!(inflater.checkpoint().unwrap().0.len(), len);
+}
+
+#[test]
+fn checkpoint_positions() {
+    let mut inflater = Box::new(InflaterManaged::with_checkpoints());
+    assert_eq!(inflater.checkpoint_positions(), None);
+
+    let mut output = vec![0u8; 100000];
+    let mut consumed = 0;
+    for chunk in compressed_data().chunks(30000) {
+        consumed += inflater.inflate(chunk, &mut output).bytes_consumed;
+        let positions = inflater.checkpoint_positions();
+        assert_eq!(
+            positions,
+            inflater.checkpoint().map(|(_, positions)| positions)
+        );
+        if let Some(positions) = positions {
+            assert!(positions.input_bytes_to_skip <= consumed as u64);
+        }
+    }
+}
+
+#[test]
+fn parse_checkpoint_info() {
+    let mut inflater = Box::new(InflaterManaged::with_checkpoints());
+    let mut output = vec![0u8; 100000];
+    inflater.inflate(&compressed_data()[..54321], &mut output);
+    let (checkpoint, positions) = inflater.checkpoint().unwrap();
+
+    let info = CheckpointInfo::parse(&checkpoint).unwrap();
+    assert_eq!(info.version, 0x1002);
+    assert_eq!(info.flags, 0);
+    assert_eq!(info.positions(), positions);
+    assert_eq!(info.input_bits.div_ceil(8), positions.input_bytes_to_skip);
+    assert_eq!(
+        info.output_bytes_written - info.output_bytes_unread as u64,
+        inflater.total_out()
+    );
+    assert_eq!(info.block_type, BlockType::Dynamic);
+    assert!(!info.final_block);
+
+    let mut corrupted = checkpoint.clone();
+    corrupted[5] ^= 1;
+    assert_eq!(CheckpointInfo::parse(&corrupted), None);
+    assert_eq!(CheckpointInfo::parse(&checkpoint[..100]), None);
+}
+
+#[test]
+fn restore_version_1_checkpoint() {
+    let checkpoints = inflate_with_checkpoints(10000);
+    for (cp_data, positions) in &checkpoints {
+        // version 1 has no flags and header_len fields
+        let mut v1 = Vec::new();
+        v1.extend_from_slice(&0x1001u16.to_le_bytes());
+        v1.extend_from_slice(&cp_data[6..]);
+        rebuild_checkpoint_checksum(&mut v1);
+
+        assert_eq!(CheckpointInfo::parse(&v1).unwrap().version, 0x1001);
+        let mut restored = Box::new(InflaterManaged::with_checkpoints());
+        assert_eq!(
+            restored.restore_from_checkpoint(&v1).as_ref(),
+            Some(positions)
+        );
+        // restored checkpoints are written in the current version
+        assert_eq!(&restored.checkpoint().unwrap().0, cp_data);
+    }
+}
+
+#[test]
+fn checkpoint_version_2_extensions() {
+    let checkpoints = inflate_with_checkpoints(10000);
+    let (cp_data, positions) = &checkpoints[checkpoints.len() / 2];
+    let mut inflater = Box::new(InflaterManaged::with_checkpoints());
+
+    // unknown header fields are skipped
+    let mut extended = cp_data.clone();
+    extended.splice(350..350, [0xAB; 10]);
+    extended[4..6].copy_from_slice(&360u16.to_le_bytes());
+    rebuild_checkpoint_checksum(&mut extended);
+    assert_eq!(
+        inflater.restore_from_checkpoint(&extended).as_ref(),
+        Some(positions)
+    );
+
+    // unknown flags in the high byte are ignored
+    let mut hinted = cp_data.clone();
+    hinted[3] = 0x80;
+    rebuild_checkpoint_checksum(&mut hinted);
+    assert_eq!(CheckpointInfo::parse(&hinted).unwrap().flags, 0x8000);
+    assert_eq!(
+        inflater.restore_from_checkpoint(&hinted).as_ref(),
+        Some(positions)
+    );
+
+    // unknown flags in the low byte are rejected
+    let mut required = cp_data.clone();
+    required[2] = 0x80;
+    rebuild_checkpoint_checksum(&mut required);
+    assert_eq!(CheckpointInfo::parse(&required), None);
+    assert!(inflater.restore_from_checkpoint(&required).is_none());
+
+    // header_len must cover the known fields
+    let mut short_header = cp_data.clone();
+    short_header[4..6].copy_from_slice(&346u16.to_le_bytes());
+    rebuild_checkpoint_checksum(&mut short_header);
+    assert!(inflater.restore_from_checkpoint(&short_header).is_none());
+}
+
+#[test]
+fn compressed_checkpoints() {
+    let options = InflaterOptions::new().compress_checkpoints(true);
+
+    // long runs of zeros compress well
+    let mut data = vec![0u8; 60000];
+    data[30000..30100].copy_from_slice(&BINARY_WAV_DATA[..100]);
+    let stream = build_uncompressed_deflate_stream(&data);
+    let mut output = vec![0u8; 40000];
+    let mut inflater = Box::new(InflaterManaged::with_checkpoints_and_options(options));
+    inflater.inflate(&stream, &mut output);
+    let (compressed, positions) = inflater.checkpoint().unwrap();
+    assert!(compressed.len() < 2000, "{}", compressed.len());
+    assert_eq!(CheckpointInfo::parse(&compressed).unwrap().flags, 0x0001);
+
+    let mut restored = Box::new(InflaterManaged::with_checkpoints());
+    assert_eq!(
+        restored.restore_from_checkpoint(&compressed).as_ref(),
+        Some(&positions)
+    );
+    let skip = positions.input_bytes_to_skip as usize;
+    let result = restored.inflate(&stream[skip..], &mut output);
+    let returned = positions.output_bytes_already_returned as usize;
+    assert_bytes_eq(
+        &output[..result.bytes_written],
+        &data[returned..],
+        "output after restore",
+    );
+
+    // restoring the checkpoints of incompressible data gives the same state
+    let checkpoints = inflate_with_checkpoints(100000);
+    for (cp_data, positions) in &checkpoints {
+        let mut inflater = Box::new(InflaterManaged::with_checkpoints_and_options(options));
+        assert_eq!(
+            inflater.restore_from_checkpoint(cp_data).as_ref(),
+            Some(positions)
+        );
+        let (recompressed, _) = inflater.checkpoint().unwrap();
+        assert!(recompressed.len() <= cp_data.len());
+
+        let mut restored = Box::new(InflaterManaged::with_checkpoints());
+        assert_eq!(
+            restored.restore_from_checkpoint(&recompressed).as_ref(),
+            Some(positions)
+        );
+        assert_eq!(&restored.checkpoint().unwrap().0, cp_data);
+    }
+
+    // truncated run-length encoded window data is rejected
+    let mut truncated = compressed.clone();
+    truncated.remove(truncated.len() - 5);
+    rebuild_checkpoint_checksum(&mut truncated);
+    assert!(restored.restore_from_checkpoint(&truncated).is_none());
+}
+
+fn crc32c(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0x82F63B78
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+#[test]
+fn checkpoint_checksums() {
+    assert_eq!(crc32c(b"123456789"), 0xE3069283);
+
+    let mut checkpoints = Vec::new();
+    for checksum in [
+        CheckpointChecksum::Fletcher32,
+        CheckpointChecksum::Crc32,
+        CheckpointChecksum::Crc32c,
+    ] {
+        let options = InflaterOptions::new().checkpoint_checksum(checksum);
+        let mut inflater = Box::new(InflaterManaged::with_checkpoints_and_options(options));
+        let mut output = vec![0u8; 100000];
+        inflater.inflate(&compressed_data()[..54321], &mut output);
+        let (checkpoint, positions) = inflater.checkpoint().unwrap();
+        assert_eq!(
+            CheckpointInfo::parse(&checkpoint).unwrap().checksum,
+            checksum
+        );
+
+        let mut restored = Box::new(InflaterManaged::with_checkpoints());
+        assert_eq!(
+            restored.restore_from_checkpoint(&checkpoint).as_ref(),
+            Some(&positions)
+        );
+        let mut corrupted = checkpoint.clone();
+        corrupted[1000] ^= 1;
+        assert!(restored.restore_from_checkpoint(&corrupted).is_none());
+        checkpoints.push(checkpoint);
+    }
+
+    // only the flags and the checksum differ
+    let (fletcher, crc32c_checkpoint) = (&checkpoints[0], &checkpoints[2]);
+    let len = fletcher.len();
+    assert_eq!(fletcher[4..len - 4], crc32c_checkpoint[4..len - 4]);
+    assert_eq!(
+        crc32c_checkpoint[len - 4..],
+        crc32c(&crc32c_checkpoint[..len - 4]).to_le_bytes()
+    );
+
+    // both CRC flags are invalid
+    let mut both = checkpoints[1].clone();
+    both[2] |= 0x04;
+    rebuild_checkpoint_checksum(&mut both);
+    assert_eq!(CheckpointInfo::parse(&both), None);
+}
+
+#[test]
+fn delta_checkpoints() {
+    let mut inflater = Box::new(InflaterManaged::with_checkpoints());
+    let mut output = vec![0u8; 10000];
+    let mut consumed = 0;
+    let mut base: Option<Vec<u8>> = None;
+    let mut deltas = 0;
+    while !inflater.finished() {
+        let result = inflater.inflate(&compressed_data()[consumed..], &mut output);
+        consumed += result.bytes_consumed;
+        let Some((full, positions)) = inflater.checkpoint() else {
+            continue;
+        };
+        if let Some(base) = &base {
+            let (delta, delta_positions) = inflater.checkpoint_delta(base).unwrap();
+            assert_eq!(delta_positions, positions);
+            assert!(CheckpointInfo::parse(&delta).unwrap().delta);
+            // about 10000 bytes of output since the base
+            assert!(delta.len() < 20000, "{}", delta.len());
+
+            let mut restored = Box::new(InflaterManaged::with_checkpoints());
+            assert_eq!(
+                restored.restore_from_delta(base, &delta).as_ref(),
+                Some(&positions)
+            );
+            assert_bytes_eq(&restored.checkpoint().unwrap().0, &full, "restored delta");
+
+            // deltas need their base
+            assert!(restored.restore_from_checkpoint(&delta).is_none());
+            assert!(restored.restore_from_delta(&full, &delta).is_none());
+            assert!(restored.restore_from_delta(&delta, &delta).is_none());
+            assert!(inflater.checkpoint_delta(&delta).is_none());
+            deltas += 1;
+        }
+        base = Some(full);
+    }
+    assert!(deltas > 100);
+
+    // a base from another stream is rejected
+    let stream = build_uncompressed_deflate_stream(&[b'x'; 1000]);
+    let mut other = Box::new(InflaterManaged::with_checkpoints());
+    other.inflate(&stream[..500], &mut output);
+    let (other_base, _) = other.checkpoint().unwrap();
+    let mut inflater = Box::new(InflaterManaged::with_checkpoints());
+    inflater.inflate(&compressed_data()[..400], &mut output);
+    assert!(inflater.checkpoint_delta(&other_base).is_none());
+}
+
+#[test]
+fn checkpoint_output_crc32() {
+    let data = &BINARY_WAV_DATA[..60000];
+    let stream = build_uncompressed_deflate_stream(data);
+    let tracking = InflaterOptions::new().track_crc32(true);
+    let mut output = vec![0u8; 60000];
+    let mut inflater = Box::new(InflaterManaged::with_checkpoints_and_options(tracking));
+    inflater.inflate(&stream, &mut output);
+    let metadata = EntryMetadata {
+        compressed_size: stream.len() as u64,
+        uncompressed_size: data.len() as u64,
+        crc32: inflater.output_crc32().unwrap(),
+    };
+
+    // the rest of the stream isn't in the checkpoint
+    inflater.reset();
+    let consumed = inflater
+        .inflate(&stream[..30005], &mut output)
+        .bytes_consumed;
+    assert_eq!(inflater.inflate(&[], &mut output).bytes_written, 30000);
+    let (cp_data, positions) = inflater.checkpoint().unwrap();
+    let info = CheckpointInfo::parse(&cp_data).unwrap();
+    assert_eq!(info.flags, 0x0100);
+    assert_eq!(info.output_crc32, inflater.output_crc32());
+    assert_eq!(cp_data.len(), inflater.checkpoint_len().unwrap());
+
+    // continuing after restoring verifies the CRC-32 of the whole output
+    let resume = |stream: &[u8]| {
+        let options = InflaterOptions::new().entry_metadata(metadata);
+        let mut restored = Box::new(InflaterManaged::with_checkpoints_and_options(options));
+        assert_eq!(
+            restored.restore_from_checkpoint(&cp_data).as_ref(),
+            Some(&positions)
+        );
+        let skip = positions.input_bytes_to_skip as usize;
+        let result = restored.inflate(&stream[skip..], &mut vec![0u8; 60000]);
+        assert_eq!(result.bytes_written, 30000);
+        restored.finish()
+    };
+    resume(&stream).unwrap();
+    // the stream changed after the checkpoint
+    let mut changed = data.to_vec();
+    changed[50000] ^= 1;
+    let error = resume(&build_uncompressed_deflate_stream(&changed)).unwrap_err();
+    assert_eq!(error.kind(), InflateErrorKind::Crc32Mismatch);
+
+    // deltas store it too
+    inflater.inflate(&stream[consumed..], &mut output[..100]);
+    let (delta, _) = inflater.checkpoint_delta(&cp_data).unwrap();
+    let info = CheckpointInfo::parse(&delta).unwrap();
+    assert_eq!(info.output_crc32, inflater.output_crc32());
+    let mut restored = Box::new(InflaterManaged::with_checkpoints_and_options(tracking));
+    assert!(restored.restore_from_delta(&cp_data, &delta).is_some());
+    assert_eq!(restored.output_crc32(), inflater.output_crc32());
+
+    // without it, the CRC-32 starts over
+    let mut untracked = Box::new(InflaterManaged::with_checkpoints());
+    untracked.inflate(&stream, &mut output[..30000]);
+    let (cp_data, _) = untracked.checkpoint().unwrap();
+    assert_eq!(CheckpointInfo::parse(&cp_data).unwrap().output_crc32, None);
+    assert!(restored.restore_from_checkpoint(&cp_data).is_some());
+    assert_eq!(restored.output_crc32(), Some(0));
+}
+
+#[test]
+fn checkpoint_metadata() {
+    let mut inflater = Box::new(InflaterManaged::with_checkpoints());
+    let mut output = vec![0u8; 200000];
+    let result = inflater.inflate(&compressed_data()[..100000], &mut output);
+    assert!(inflater.checkpoint_metadata().is_empty());
+    let (plain, _) = inflater.checkpoint().unwrap();
+    inflater.set_checkpoint_metadata(b"source file 42");
+    assert_eq!(inflater.checkpoint_metadata(), b"source file 42");
+    let (cp_data, positions) = inflater.checkpoint().unwrap();
+    assert_eq!(CheckpointInfo::parse(&cp_data).unwrap().flags, 0x0200);
+    assert_eq!(cp_data.len(), plain.len() + 15);
+    assert_eq!(cp_data.len(), inflater.checkpoint_len().unwrap());
+
+    let mut restored = Box::new(InflaterManaged::with_checkpoints());
+    assert_eq!(
+        restored.restore_from_checkpoint(&cp_data).as_ref(),
+        Some(&positions)
+    );
+    assert_eq!(restored.checkpoint_metadata(), b"source file 42");
+    assert_eq!(restored.checkpoint().unwrap().0, cp_data);
+    assert_eq!(validate_checkpoint(&cp_data).as_ref(), Some(&positions));
+
+    // the payload is covered by the checksum
+    let mut corrupted = cp_data.clone();
+    corrupted[351] ^= 0x01;
+    assert!(CheckpointInfo::parse(&corrupted).is_none());
+
+    // restoring a checkpoint without it clears it, as does reset()
+    assert!(restored.restore_from_checkpoint(&plain).is_some());
+    assert!(restored.checkpoint_metadata().is_empty());
+    let mut reader = &cp_data[..];
+    assert!(restored.restore_from_reader(&mut reader).unwrap().is_some());
+    assert_eq!(restored.checkpoint_metadata(), b"source file 42");
+    restored.reset();
+    assert!(restored.checkpoint_metadata().is_empty());
+
+    // deltas store it too
+    inflater.inflate(
+        &compressed_data()[result.bytes_consumed..][..1000],
+        &mut output,
+    );
+    inflater.set_checkpoint_metadata(b"delta");
+    let (delta, _) = inflater.checkpoint_delta(&cp_data).unwrap();
+    assert!(restored.restore_from_delta(&cp_data, &delta).is_some());
+    assert_eq!(restored.checkpoint_metadata(), b"delta");
+
+    // the longest payload fits in MAX_CHECKPOINT_SIZE
+    let metadata = [0xA5; MAX_CHECKPOINT_METADATA_LEN];
+    let mut inflater = Box::new(InflaterManaged::with_checkpoints_and_options(
+        InflaterOptions::new().track_crc32(true),
+    ));
+    inflater.set_checkpoint_metadata(&metadata);
+    inflater.inflate(compressed_data(), &mut [0u8; 200000]);
+    let mut out = vec![0u8; MAX_CHECKPOINT_SIZE];
+    let (len, _) = inflater.checkpoint_into(&mut out).unwrap();
+    assert!(restored.restore_from_checkpoint(&out[..len]).is_some());
+    assert_eq!(restored.checkpoint_metadata(), metadata);
+}
+
+#[test]
+#[should_panic]
+fn checkpoint_metadata_too_long() {
+    let mut inflater = Box::new(InflaterManaged::with_checkpoints());
+    inflater.set_checkpoint_metadata(&[0; MAX_CHECKPOINT_METADATA_LEN + 1]);
+}
+
+/// Encodes checkpoints by xoring them with a key, after a tag which decoding checks
+struct XorTransform(u8);
+
+impl CheckpointTransform for XorTransform {
+    fn encode(&mut self, checkpoint: &[u8]) -> std::io::Result<Vec<u8>> {
+        let mut encoded = b"XOR:".to_vec();
+        encoded.extend(checkpoint.iter().map(|byte| byte ^ self.0));
+        Ok(encoded)
+    }
+
+    fn decode(&mut self, encoded: &[u8]) -> std::io::Result<Vec<u8>> {
+        match encoded.strip_prefix(b"XOR:") {
+            Some(data) => Ok(data.iter().map(|byte| byte ^ self.0).collect()),
+            None => Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "not encoded",
+            )),
+        }
+    }
+}
+
+#[test]
+fn checkpoint_transform() {
+    let checkpoints = inflate_with_checkpoints(500000);
+    let (cp_data, positions) = &checkpoints[2];
+    let mut inflater = Box::new(InflaterManaged::with_checkpoints());
+    assert!(inflater.restore_from_checkpoint(cp_data).is_some());
+
+    let mut transform = XorTransform(0x5A);
+    let (encoded, encoded_positions) = inflater
+        .checkpoint_transformed(&mut transform)
+        .unwrap()
+        .unwrap();
+    assert_eq!(&encoded_positions, positions);
+    assert_eq!(encoded.len(), cp_data.len() + 4);
+    // the window isn't stored in plaintext
+    let window = &cp_data[cp_data.len() - 1004..cp_data.len() - 4];
+    assert!(!encoded.windows(window.len()).any(|part| part == window));
+
+    let mut restored = Box::new(InflaterManaged::with_checkpoints());
+    assert_eq!(
+        restored
+            .restore_transformed(&encoded, &mut transform)
+            .unwrap()
+            .as_ref(),
+        Some(positions)
+    );
+    assert_eq!(&restored.checkpoint().unwrap().0, cp_data);
+
+    // errors of the transform are returned without modifying the inflater
+    let error = restored
+        .restore_transformed(cp_data, &mut transform)
+        .unwrap_err();
+    assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+    assert_eq!(&restored.checkpoint().unwrap().0, cp_data);
+
+    // a wrong key decodes to a corrupt checkpoint
+    let result = restored.restore_transformed(&encoded, XorTransform(0x5B));
+    assert!(result.unwrap().is_none());
+
+    let fresh = Box::new(InflaterManaged::with_checkpoints());
+    assert!(fresh.checkpoint_transformed(transform).unwrap().is_none());
+}
+
+struct FailingWriter;
+
+impl std::io::Write for FailingWriter {
+    fn write(&mut self, _: &[u8]) -> std::io::Result<usize> {
+        Err(std::io::ErrorKind::BrokenPipe.into())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn checkpoint_writer_and_reader() {
+    let checkpoints = inflate_with_checkpoints(100000);
+    for (cp_data, positions) in &checkpoints {
+        let mut inflater = Box::new(InflaterManaged::with_checkpoints());
+        assert!(inflater.restore_from_checkpoint(cp_data).is_some());
+        let mut written = Vec::new();
+        let (len, written_positions) = inflater
+            .checkpoint_to_writer(&mut written)
+            .unwrap()
+            .unwrap();
+        assert_eq!(len, cp_data.len());
+        assert_eq!(&written_positions, positions);
+        assert_eq!(&written, cp_data);
+
+        // the reader is left after the checkpoint
+        written.extend_from_slice(b"trailer");
+        let mut reader = std::io::Cursor::new(&written);
+        let mut restored = Box::new(InflaterManaged::with_checkpoints());
+        assert_eq!(
+            restored.restore_from_reader(&mut reader).unwrap().as_ref(),
+            Some(positions)
+        );
+        assert_eq!(reader.position(), cp_data.len() as u64);
+        assert_eq!(&restored.checkpoint().unwrap().0, cp_data);
+    }
+    let (cp_data, _) = &checkpoints[checkpoints.len() / 2];
+
+    // version 1 checkpoints
+    let mut v1 = Vec::new();
+    v1.extend_from_slice(&0x1001u16.to_le_bytes());
+    v1.extend_from_slice(&cp_data[6..]);
+    rebuild_checkpoint_checksum(&mut v1);
+    let mut restored = Box::new(InflaterManaged::with_checkpoints());
+    assert!(restored.restore_from_reader(&v1[..]).unwrap().is_some());
+    assert_eq!(&restored.checkpoint().unwrap().0, cp_data);
+
+    // a corrupt window resets the inflater
+    let mut corrupt = cp_data.clone();
+    corrupt[1000] ^= 1;
+    assert_eq!(restored.restore_from_reader(&corrupt[..]).unwrap(), None);
+    assert!(restored.checkpoint().is_none());
+    assert_eq!(restored.total_out(), 0);
+
+    // a truncated checkpoint is an error
+    let error = restored
+        .restore_from_reader(&cp_data[..cp_data.len() - 1])
+        .unwrap_err();
+    assert_eq!(error.kind(), std::io::ErrorKind::UnexpectedEof);
+
+    // write errors are returned
+    let error = restored.checkpoint_to_writer(FailingWriter);
+    assert!(error.is_ok(), "nothing to write without a checkpoint");
+    assert!(restored.restore_from_checkpoint(cp_data).is_some());
+    let error = restored.checkpoint_to_writer(FailingWriter).unwrap_err();
+    assert_eq!(error.kind(), std::io::ErrorKind::BrokenPipe);
+
+    // run-length encoded checkpoints
+    let options = InflaterOptions::new().compress_checkpoints(true);
+    let mut data = vec![0u8; 60000];
+    data[30000..30100].copy_from_slice(&BINARY_WAV_DATA[..100]);
+    let stream = build_uncompressed_deflate_stream(&data);
+    let mut inflater = Box::new(InflaterManaged::with_checkpoints_and_options(options));
+    inflater.inflate(&stream, &mut vec![0u8; 40000]);
+    let (compressed, positions) = inflater.checkpoint().unwrap();
+    let mut written = Vec::new();
+    inflater.checkpoint_to_writer(&mut written).unwrap();
+    assert_eq!(written, compressed);
+    let reader = std::io::BufReader::new(&compressed[..]);
+    assert_eq!(
+        restored.restore_from_reader(reader).unwrap().as_ref(),
+        Some(&positions)
+    );
+    let mut output = vec![0u8; 60000];
+    let skip = positions.input_bytes_to_skip as usize;
+    let result = restored.inflate(&stream[skip..], &mut output);
+    let returned = positions.output_bytes_already_returned as usize;
+    assert_bytes_eq(
+        &output[..result.bytes_written],
+        &data[returned..],
+        "output after restore",
+    );
+
+    // delta checkpoints need their base
+    let (delta, _) = inflater.checkpoint_delta(&compressed).unwrap();
+    assert_eq!(restored.restore_from_reader(&delta[..]).unwrap(), None);
+}
+
+fn inflate_with_policy(
+    options: InflaterOptions,
+    policy: &mut CheckpointPolicy,
+) -> Vec<(Vec<u8>, CheckpointStreamPositions)> {
+    let mut inflater = Box::new(InflaterManaged::with_checkpoints_and_options(options));
+    let mut output = vec![0u8; BINARY_WAV_UNCOMPRESSED_SIZE];
+    let mut written = 0;
+    let mut checkpoints = Vec::new();
+    for chunk in compressed_data().chunks(77777) {
+        let mut consumed = 0;
+        while consumed < chunk.len() {
+            let output_end = (written + 250000).min(output.len());
+            let result = inflater.inflate_with_policy(
+                &chunk[consumed..],
+                &mut output[written..output_end],
+                policy,
+                |data, positions| checkpoints.push((data.to_vec(), positions)),
+            );
+            assert!(!result.data_error);
+            consumed += result.bytes_consumed;
+            written += result.bytes_written;
+        }
+    }
+    assert!(inflater.finished());
+    assert_bytes_eq(&output, BINARY_WAV_DATA, "output with policy");
+    checkpoints
+}
+
+#[test]
+fn checkpoint_policy() {
+    let mut policy = CheckpointPolicy::new(100000);
+    let checkpoints = inflate_with_policy(InflaterOptions::new(), &mut policy);
+    assert_eq!(checkpoints.len(), BINARY_WAV_UNCOMPRESSED_SIZE / 100000);
+    for (i, (data, positions)) in checkpoints.iter().enumerate() {
+        assert_eq!(
+            positions.output_bytes_already_returned,
+            (i as u64 + 1) * 100000
+        );
+        let mut restored = Box::new(InflaterManaged::with_checkpoints());
+        assert_eq!(
+            restored.restore_from_checkpoint(data).as_ref(),
+            Some(positions)
+        );
+        if i == checkpoints.len() / 2 {
+            let output = resume_from_checkpoint(&mut restored, compressed_data(), positions);
+            let skip = positions.output_bytes_already_returned as usize;
+            assert_bytes_eq(&output, &BINARY_WAV_DATA[skip..], "output after restore");
+        }
+    }
+}
+
+#[test]
+fn checkpoint_policy_retain_last() {
+    let mut policy = CheckpointPolicy::new(100000).retain_last(3);
+    assert!(policy.latest_checkpoint().is_none());
+    let checkpoints = inflate_with_policy(InflaterOptions::new(), &mut policy);
+    let kept = &checkpoints[checkpoints.len() - 3..];
+    let (data, positions) = policy.latest_checkpoint().unwrap();
+    assert_eq!((data, positions), (&kept[2].0[..], &kept[2].1));
+
+    let offset = kept[1].1.output_bytes_already_returned;
+    let (data, positions) = policy.checkpoint_at_or_before(offset + 99999).unwrap();
+    assert_eq!((data, positions), (&kept[1].0[..], &kept[1].1));
+    let (data, _) = policy.checkpoint_at_or_before(offset).unwrap();
+    assert_eq!(data, kept[1].0);
+    assert!(policy.checkpoint_at_or_before(offset - 100001).is_none());
+
+    // rewind to the oldest kept checkpoint
+    let (data, positions) = policy.checkpoint_at_or_before(offset - 1).unwrap();
+    let mut restored = Box::new(InflaterManaged::with_checkpoints());
+    assert_eq!(
+        restored.restore_from_checkpoint(data).as_ref(),
+        Some(positions)
+    );
+    let output = resume_from_checkpoint(&mut restored, compressed_data(), positions);
+    let skip = positions.output_bytes_already_returned as usize;
+    assert_bytes_eq(&output, &BINARY_WAV_DATA[skip..], "output after rewind");
+
+    let policy = policy.retain_last(1);
+    assert!(policy.checkpoint_at_or_before(offset).is_none());
+    assert_eq!(policy.latest_checkpoint().unwrap().0, kept[2].0);
+}
+
+#[test]
+fn checkpoint_policy_at_block_boundaries() {
+    let blocks = InflaterManaged::enumerate_blocks(compressed_data()).unwrap();
+    let mut policy = CheckpointPolicy::new(100000).at_block_boundaries_only(true);
+    let checkpoints = inflate_with_policy(InflaterOptions::new(), &mut policy);
+    assert!(!checkpoints.is_empty());
+    let mut previous = 0;
+    for (data, positions) in &checkpoints {
+        let output = positions.output_bytes_already_returned;
+        assert!(output >= previous + 100000);
+        assert!(blocks.iter().any(|block| block.output_bytes.end == output));
+        assert_eq!(
+            CheckpointInfo::parse(data).unwrap().block_type,
+            BlockType::Uncompressed
+        );
+        previous = output;
+    }
+}
+
+#[test]
+fn checkpoints_at_block_boundaries_only() {
+    let options = InflaterOptions::new().checkpoints_at_block_boundaries_only(true);
+    let policy = CheckpointPolicy::new(100000).at_block_boundaries_only(true);
+    let expected = inflate_with_policy(InflaterOptions::new(), &mut policy.clone());
+    let checkpoints = inflate_with_policy(options, &mut policy.clone());
+    assert_eq!(checkpoints, expected);
+
+    // checkpoints are not available within blocks
+    let mut inflater = Box::new(InflaterManaged::with_checkpoints_and_options(options));
+    let mut output = vec![0u8; 200000];
+    let result = inflater.inflate(&compressed_data()[..200000], &mut output);
+    assert!(!inflater.is_at_block_boundary());
+    assert!(inflater.checkpoint().is_none());
+    assert!(inflater.checkpoint_len().is_none());
+    assert!(inflater.checkpoint_positions().is_none());
+
+    let (_, boundary) = inflater
+        .inflate_until_block_boundary(&compressed_data()[result.bytes_consumed..], &mut output);
+    let boundary = boundary.unwrap();
+    let (cp_data, positions) = inflater.checkpoint().unwrap();
+    assert_eq!(
+        positions.output_bytes_already_returned,
+        boundary.output_bytes
+    );
+
+    // restored inflaters have the restored checkpoint until they write
+    let mut restored = Box::new(InflaterManaged::with_checkpoints_and_options(options));
+    assert!(restored.restore_from_checkpoint(&cp_data).is_some());
+    assert_eq!(restored.checkpoint().unwrap().0, cp_data);
+    let input = &compressed_data()[positions.input_bytes_to_skip as usize..];
+    restored.inflate(&input[..1000], &mut output[..1000]);
+    assert!(!restored.is_at_block_boundary());
+    assert!(restored.checkpoint().is_none());
+}
+
+/// Continues a stream as zlib would with `handoff`, by decoding stored blocks of the
+/// dictionary followed by the primed bits and the rest of the input
+fn resume_as_zlib(handoff: &ZlibHandoff, input: &[u8]) -> Vec<u8> {
+    let mut stream = Vec::new();
+    for chunk in handoff.dictionary.chunks(65535) {
+        let len = chunk.len() as u16;
+        stream.push(0); // BFINAL=0, BTYPE=00 (uncompressed)
+        stream.extend_from_slice(&len.to_le_bytes());
+        stream.extend_from_slice(&(!len).to_le_bytes());
+        stream.extend_from_slice(chunk);
+    }
+    let mut bits = handoff.prime_value as u32;
+    for &byte in &input[handoff.input_byte_offset as usize..] {
+        bits |= (byte as u32) << handoff.prime_bits;
+        stream.push(bits as u8);
+        bits >>= 8;
+    }
+    if handoff.prime_bits > 0 {
+        stream.push(bits as u8);
+    }
+
+    let mut inflater = Box::new(InflaterManaged::new());
+    let mut output = vec![0u8; handoff.dictionary.len() + BINARY_WAV_UNCOMPRESSED_SIZE];
+    let result = inflater.inflate(&stream, &mut output);
+    assert!(!result.data_error);
+    output.truncate(result.bytes_written);
+    output.split_off(handoff.dictionary.len())
+}
+
+#[test]
+fn zlib_handoff() {
+    let mut policy = CheckpointPolicy::new(500000).at_block_boundaries_only(true);
+    let checkpoints = inflate_with_policy(InflaterOptions::new(), &mut policy);
+    for (i, (cp_data, positions)) in checkpoints.iter().enumerate() {
+        let handoff = ZlibHandoff::from_checkpoint(cp_data).unwrap();
+        let info = CheckpointInfo::parse(cp_data).unwrap();
+        assert_eq!(handoff.input_byte_offset, positions.input_bytes_to_skip);
+        assert_eq!(handoff.prime_bits as u64, (8 - info.input_bits % 8) % 8);
+        assert_eq!(handoff.output_offset, info.output_bytes_written);
+        let offset = handoff.output_offset as usize;
+        assert_eq!(handoff.dictionary.len(), 65536);
+        assert_eq!(handoff.dictionary, BINARY_WAV_DATA[offset - 65536..offset]);
+        if i == 1 {
+            let output = resume_as_zlib(&handoff, compressed_data());
+            assert_bytes_eq(
+                &output,
+                &BINARY_WAV_DATA[offset..],
+                "output resumed by zlib",
+            );
+        }
+    }
+
+    // the live state while stopped at a block boundary
+    let mut inflater = Box::new(InflaterManaged::with_checkpoints());
+    let mut output = vec![0u8; 200000];
+    let result = inflater.inflate(&compressed_data()[..200000], &mut output);
+    assert!(!inflater.is_at_block_boundary());
+    assert!(inflater.zlib_handoff().is_none());
+    let (cp_data, _) = inflater.checkpoint().unwrap();
+    assert!(ZlibHandoff::from_checkpoint(&cp_data).is_none());
+    let (_, boundary) = inflater
+        .inflate_until_block_boundary(&compressed_data()[result.bytes_consumed..], &mut output);
+    let handoff = inflater.zlib_handoff().unwrap();
+    assert_eq!(handoff.output_offset, boundary.unwrap().output_bytes);
+    let (cp_data, _) = inflater.checkpoint().unwrap();
+    assert_eq!(ZlibHandoff::from_checkpoint(&cp_data), Some(handoff));
+
+    // not at the end of the stream
+    let mut inflater = Box::new(InflaterManaged::with_checkpoints());
+    let mut output = vec![0u8; 50];
+    inflater.inflate(&[0x4b, 0x04, 0x02, 0x00], &mut output);
+    assert!(inflater.zlib_handoff().is_none());
+}
+
+#[test]
+fn boundary_checkpoint_request() {
+    let blocks = InflaterManaged::enumerate_blocks(compressed_data()).unwrap();
+    let mut inflater = Box::new(InflaterManaged::with_checkpoints());
+    let mut output = vec![0u8; BINARY_WAV_UNCOMPRESSED_SIZE];
+    let mut consumed = 0;
+    let mut written = 0;
+
+    // without a request, inflate doesn't stop at block boundaries
+    let result = inflater.inflate(&compressed_data()[..200000], &mut output[..200000]);
+    consumed += result.bytes_consumed;
+    written += result.bytes_written;
+    assert_ne!(result.status, InflateStatus::BlockBoundary);
+
+    inflater.request_boundary_checkpoint();
+    assert!(inflater.boundary_checkpoint_requested());
+    let result = inflater.inflate(&compressed_data()[consumed..], &mut output[written..]);
+    consumed += result.bytes_consumed;
+    written += result.bytes_written;
+    assert_eq!(result.status, InflateStatus::BlockBoundary);
+    assert!(!inflater.boundary_checkpoint_requested());
+
+    let (checkpoint, positions) = inflater.checkpoint().unwrap();
+    assert_eq!(positions.output_bytes_already_returned, written as u64);
+    let block = blocks
+        .iter()
+        .find(|block| block.output_bytes.end == written as u64)
+        .unwrap();
+    assert_eq!(
+        positions.input_bytes_to_skip,
+        block.input_bits.end.div_ceil(8)
+    );
+    assert_eq!(
+        CheckpointInfo::parse(&checkpoint).unwrap().block_type,
+        BlockType::Uncompressed
+    );
+
+    // the request was cleared
+    let result = inflater.inflate(&compressed_data()[consumed..], &mut output[written..]);
+    written += result.bytes_written;
+    assert!(inflater.finished());
+    assert_bytes_eq(&output[..written], BINARY_WAV_DATA, "output");
+}
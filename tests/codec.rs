@@ -0,0 +1,73 @@
+#![cfg(feature = "tokio-util")]
+
+use bytes::BytesMut;
+use deflate64::Deflate64Codec;
+use futures::StreamExt;
+use std::io;
+use tokio_util::codec::{Decoder, FramedRead};
+
+mod common;
+
+use common::{compressed_data, BINARY_WAV_DATA};
+
+async fn decode_framed(input: &[u8], codec: Deflate64Codec) -> io::Result<Vec<u8>> {
+    let mut framed = FramedRead::new(input, codec);
+    let mut output = vec![];
+    while let Some(frame) = framed.next().await {
+        output.extend_from_slice(&frame?);
+    }
+    Ok(output)
+}
+
+#[tokio::test]
+async fn decode_with_framed_read() {
+    let output = decode_framed(compressed_data(), Deflate64Codec::new())
+        .await
+        .unwrap();
+    assert_eq!(output, BINARY_WAV_DATA);
+}
+
+#[tokio::test]
+async fn truncated_input() {
+    let error = decode_framed(&compressed_data()[..100000], Deflate64Codec::new())
+        .await
+        .unwrap_err();
+    assert_eq!(error.kind(), io::ErrorKind::UnexpectedEof);
+}
+
+#[tokio::test]
+async fn invalid_input() {
+    let error = decode_framed(&[0b111], Deflate64Codec::new())
+        .await
+        .unwrap_err();
+    assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+}
+
+#[test]
+fn max_frame_size() {
+    let mut codec = Deflate64Codec::with_max_frame_size(1000);
+    assert_eq!(codec.max_frame_size(), 1000);
+
+    let mut src = BytesMut::new();
+    let mut output = vec![];
+    for chunk in compressed_data().chunks(4096) {
+        src.extend_from_slice(chunk);
+        while let Some(frame) = codec.decode(&mut src).unwrap() {
+            assert!(!frame.is_empty() && frame.len() <= 1000);
+            output.extend_from_slice(&frame);
+        }
+    }
+    while let Some(frame) = codec.decode_eof(&mut src).unwrap() {
+        output.extend_from_slice(&frame);
+    }
+    assert_eq!(output, BINARY_WAV_DATA);
+}
+
+#[test]
+fn trailing_data_is_left_in_buffer() {
+    let mut codec = Deflate64Codec::new();
+    let mut src = BytesMut::from(&[0b011, 0x00, 0xAB, 0xCD][..]); // empty static block
+    assert!(codec.decode(&mut src).unwrap().is_none());
+    assert!(codec.decode_eof(&mut src).unwrap().is_none());
+    assert_eq!(&src[..], [0xAB, 0xCD]);
+}
@@ -0,0 +1,104 @@
+//! Test data shared by the integration tests. Each test crate uses a part of it.
+#![allow(dead_code)]
+
+use std::io;
+use std::pin::Pin;
+use std::task::{ready, Context, Poll};
+
+/// Offset of the deflate64 stream of binary.wmv in [`ZIP_FILE_DATA`].
+pub const BINARY_WAV_DATA_OFFSET: usize = 40;
+pub const BINARY_WAV_COMPRESSED_SIZE: usize = 2669743;
+pub const BINARY_WAV_UNCOMPRESSED_SIZE: usize = 2703788;
+
+pub static ZIP_FILE_DATA: &[u8] = include_bytes!("../../test-assets/deflate64.zip");
+pub static BINARY_WAV_DATA: &[u8] = include_bytes!("../../test-assets/folder/binary.wmv");
+
+/// Returns the deflate64 stream of binary.wmv.
+pub fn compressed_data() -> &'static [u8] {
+    &ZIP_FILE_DATA[BINARY_WAV_DATA_OFFSET..][..BINARY_WAV_COMPRESSED_SIZE]
+}
+
+/// Computes the checksum of checkpoints, for tests which modify them.
+pub fn fletcher32_checksum(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (0u32, 0u32);
+    for &byte in data {
+        a = a.wrapping_add(byte as u32);
+        b = b.wrapping_add(a);
+    }
+    (b << 16) | (a & 0xFFFF)
+}
+
+/// AsyncRead which returns Pending before every read, and reads at most `chunk` bytes.
+/// Implements the traits of both futures and tokio.
+pub struct PendingReader {
+    data: &'static [u8],
+    chunk: usize,
+    pending: bool,
+}
+
+impl PendingReader {
+    pub fn new(data: &'static [u8], chunk: usize) -> Self {
+        Self {
+            data,
+            chunk,
+            pending: false,
+        }
+    }
+
+    // returns Pending on every other call, and the next `len` bytes at most otherwise
+    fn poll_data(&mut self, cx: &mut Context<'_>, len: usize) -> Poll<&'static [u8]> {
+        self.pending = !self.pending;
+        if self.pending {
+            cx.waker().wake_by_ref();
+            return Poll::Pending;
+        }
+        let (data, rest) = self.data.split_at(len.min(self.chunk).min(self.data.len()));
+        self.data = rest;
+        Poll::Ready(data)
+    }
+}
+
+impl futures::io::AsyncRead for PendingReader {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let data = ready!(self.get_mut().poll_data(cx, buf.len()));
+        buf[..data.len()].copy_from_slice(data);
+        Poll::Ready(Ok(data.len()))
+    }
+}
+
+impl tokio::io::AsyncRead for PendingReader {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut tokio::io::ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let data = ready!(self.get_mut().poll_data(cx, buf.remaining()));
+        buf.put_slice(data);
+        Poll::Ready(Ok(()))
+    }
+}
+
+/// Returns a deflate64 stream of a static block of 'a' and `matches` matches of 65536
+/// bytes at distance 1.
+pub fn bomb(matches: usize) -> Vec<u8> {
+    let mut bits: Vec<bool> = vec![true, true, false]; // BFINAL=1, BTYPE=01
+    let mut code = |value: u32, len: u32| bits.extend((0..len).rev().map(|i| value >> i & 1 != 0));
+    code(0x30 + u32::from(b'a'), 8);
+    for _ in 0..matches {
+        code(0xc5, 8); // length code 285
+        code(0xfffd_u32.reverse_bits() >> 16, 16); // extra bits 65533, written LSB first
+        code(0, 5); // distance code 0
+    }
+    code(0, 7); // end of block
+    bits.chunks(8)
+        .map(|byte| {
+            byte.iter()
+                .rev()
+                .fold(0, |acc, &bit| acc << 1 | u8::from(bit))
+        })
+        .collect()
+}
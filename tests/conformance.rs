@@ -0,0 +1,56 @@
+#![cfg(feature = "test-util")]
+
+use deflate64::conformance::{test_vectors, Expected};
+use deflate64::{Deflate64Decoder, InflaterManaged};
+use std::io::Read;
+
+#[test]
+fn inflater_managed() {
+    for vector in test_vectors() {
+        let mut inflater = Box::new(InflaterManaged::new());
+        let mut output = vec![0u8; 4 * 1024 * 1024];
+        let result = inflater.inflate(&vector.input, &mut output);
+        match vector.expected {
+            Expected::Output { .. } => {
+                assert!(
+                    !result.data_error,
+                    "{}: {:?}",
+                    vector.name,
+                    inflater.error()
+                );
+                assert!(inflater.finished(), "{}", vector.name);
+                assert!(
+                    vector.output_matches(&output[..result.bytes_written]),
+                    "{}",
+                    vector.name
+                );
+            }
+            Expected::Error(kind) => {
+                assert!(result.data_error, "{}", vector.name);
+                assert_eq!(
+                    inflater.error().map(|e| e.kind()),
+                    Some(kind),
+                    "{}",
+                    vector.name
+                );
+            }
+            _ => unreachable!(),
+        }
+    }
+}
+
+#[test]
+fn decoder() {
+    for vector in test_vectors() {
+        let mut decoder = Deflate64Decoder::new(&vector.input[..]);
+        let mut output = vec![];
+        let result = decoder.read_to_end(&mut output);
+        match vector.expected {
+            Expected::Output { .. } => {
+                result.unwrap();
+                assert!(vector.output_matches(&output), "{}", vector.name);
+            }
+            _ => assert!(result.is_err(), "{}", vector.name),
+        }
+    }
+}
@@ -0,0 +1,48 @@
+#![cfg(feature = "debug-dump")]
+
+use deflate64::InflaterManaged;
+
+#[test]
+fn dump_state() {
+    let mut inflater = InflaterManaged::boxed();
+    let dump = inflater.dump_state();
+    assert!(dump.contains("state: ReadingBFinal"), "{dump}");
+    assert!(dump.contains("last 0 bytes:\n"), "{dump}");
+
+    // BFINAL=0, BTYPE=00, LEN=3, "abc", then the next header is missing
+    let stream = [0b000, 3, 0, !3, !0, b'a', b'b', b'c'];
+    let result = inflater.inflate(&stream, &mut [0u8; 2]);
+    assert_eq!(result.bytes_written, 2);
+    let dump = inflater.dump_state();
+    assert!(dump.contains("block: 1 (Uncompressed)"), "{dump}");
+    assert!(dump.contains("input: bit 64 (byte 8 + 0 bits)"), "{dump}");
+    assert!(
+        dump.contains("window: 3 bytes decoded, 2 returned, 1 pending"),
+        "{dump}"
+    );
+    assert!(dump.contains("last 3 bytes: 61 62 63\n"), "{dump}");
+
+    inflater.inflate(&[0b111], &mut [0u8; 16]);
+    let dump = inflater.dump_state();
+    assert!(dump.contains("state: DataErrored"), "{dump}");
+    assert!(dump.contains("error: "), "{dump}");
+}
+
+#[test]
+fn dump_code_lengths() {
+    // BFINAL=1, BTYPE=01, 'a', end of block
+    let stream = [0x4b, 0x04, 0x00];
+    let mut inflater = InflaterManaged::boxed();
+    let mut output = [0u8; 16];
+    let result = inflater.inflate(&stream, &mut output);
+    assert_eq!(&output[..result.bytes_written], b"a");
+    let dump = inflater.dump_state();
+    assert!(
+        dump.contains("literal/length code lengths (288):\n   8  8"),
+        "{dump}"
+    );
+    assert!(
+        dump.contains("distance code lengths (32):\n   5  5"),
+        "{dump}"
+    );
+}
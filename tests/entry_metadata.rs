@@ -0,0 +1,145 @@
+use deflate64::{EntryMetadata, InflateErrorKind, InflateStatus, InflaterManaged, InflaterOptions};
+
+mod common;
+
+use common::{
+    BINARY_WAV_COMPRESSED_SIZE, BINARY_WAV_DATA, BINARY_WAV_DATA_OFFSET,
+    BINARY_WAV_UNCOMPRESSED_SIZE, ZIP_FILE_DATA,
+};
+
+fn local_header_metadata() -> EntryMetadata {
+    let u32_at =
+        |offset: usize| u32::from_le_bytes(ZIP_FILE_DATA[offset..][..4].try_into().unwrap());
+    EntryMetadata {
+        crc32: u32_at(14),
+        compressed_size: u32_at(18) as u64,
+        uncompressed_size: u32_at(22) as u64,
+    }
+}
+
+fn inflate_entry(metadata: EntryMetadata, input: &[u8]) -> (InflaterManaged, Vec<u8>) {
+    let mut inflater = InflaterManaged::with_entry_metadata(metadata);
+    let mut output = vec![0u8; BINARY_WAV_UNCOMPRESSED_SIZE + 100];
+    let mut consumed = 0;
+    let mut written = 0;
+    loop {
+        let result = inflater.inflate(&input[consumed..], &mut output[written..]);
+        consumed += result.bytes_consumed;
+        written += result.bytes_written;
+        if result.data_error || (result.bytes_consumed == 0 && result.bytes_written == 0) {
+            break;
+        }
+    }
+    output.truncate(written);
+    (inflater, output)
+}
+
+#[test]
+fn check_test_data() {
+    let metadata = local_header_metadata();
+    assert_eq!(metadata.compressed_size, BINARY_WAV_COMPRESSED_SIZE as u64);
+    assert_eq!(
+        metadata.uncompressed_size,
+        BINARY_WAV_UNCOMPRESSED_SIZE as u64
+    );
+}
+
+#[test]
+fn verify_entry() {
+    // the input cap stops at the end of the entry even if more zip data follows
+    let (inflater, output) = inflate_entry(
+        local_header_metadata(),
+        &ZIP_FILE_DATA[BINARY_WAV_DATA_OFFSET..],
+    );
+    assert_eq!(output, BINARY_WAV_DATA);
+    inflater.finish().unwrap();
+}
+
+#[test]
+fn crc32_mismatch() {
+    let metadata = EntryMetadata {
+        crc32: local_header_metadata().crc32 ^ 1,
+        ..local_header_metadata()
+    };
+    let (inflater, output) = inflate_entry(metadata, &ZIP_FILE_DATA[BINARY_WAV_DATA_OFFSET..]);
+    assert_eq!(output, BINARY_WAV_DATA);
+    let error = inflater.finish().unwrap_err();
+    assert_eq!(error.kind(), InflateErrorKind::Crc32Mismatch);
+}
+
+#[test]
+fn uncompressed_size_too_small() {
+    let metadata = EntryMetadata {
+        uncompressed_size: BINARY_WAV_UNCOMPRESSED_SIZE as u64 - 1,
+        ..local_header_metadata()
+    };
+    let (inflater, output) = inflate_entry(metadata, &ZIP_FILE_DATA[BINARY_WAV_DATA_OFFSET..]);
+    assert_eq!(output, BINARY_WAV_DATA[..BINARY_WAV_UNCOMPRESSED_SIZE - 1]);
+    let error = inflater.finish().unwrap_err();
+    assert_eq!(error.kind(), InflateErrorKind::UncompressedSizeMismatch);
+}
+
+#[test]
+fn uncompressed_size_too_large() {
+    let metadata = EntryMetadata {
+        uncompressed_size: BINARY_WAV_UNCOMPRESSED_SIZE as u64 + 1,
+        ..local_header_metadata()
+    };
+    let (inflater, _) = inflate_entry(metadata, &ZIP_FILE_DATA[BINARY_WAV_DATA_OFFSET..]);
+    let error = inflater.finish().unwrap_err();
+    assert_eq!(error.kind(), InflateErrorKind::UncompressedSizeMismatch);
+}
+
+#[test]
+fn compressed_size_too_large() {
+    let metadata = EntryMetadata {
+        compressed_size: BINARY_WAV_COMPRESSED_SIZE as u64 + 1,
+        ..local_header_metadata()
+    };
+    let (inflater, output) = inflate_entry(metadata, &ZIP_FILE_DATA[BINARY_WAV_DATA_OFFSET..]);
+    assert_eq!(output, BINARY_WAV_DATA);
+    let error = inflater.finish().unwrap_err();
+    assert_eq!(error.kind(), InflateErrorKind::CompressedSizeMismatch);
+}
+
+#[test]
+fn compressed_size_too_small() {
+    let metadata = EntryMetadata {
+        compressed_size: BINARY_WAV_COMPRESSED_SIZE as u64 - 1,
+        ..local_header_metadata()
+    };
+    let (inflater, _) = inflate_entry(metadata, &ZIP_FILE_DATA[BINARY_WAV_DATA_OFFSET..]);
+    let error = inflater.finish().unwrap_err();
+    assert_eq!(error.kind(), InflateErrorKind::IncompleteStream);
+}
+
+fn validate_entry(metadata: EntryMetadata) -> Box<InflaterManaged> {
+    let mut inflater =
+        InflaterManaged::boxed_with_options(InflaterOptions::new().entry_metadata(metadata));
+    let mut written = 0;
+    for chunk in ZIP_FILE_DATA[BINARY_WAV_DATA_OFFSET..].chunks(100_000) {
+        let summary = inflater.validate(chunk).unwrap();
+        written += summary.bytes_written;
+        if summary.status == InflateStatus::Finished {
+            break;
+        }
+    }
+    assert_eq!(written, BINARY_WAV_UNCOMPRESSED_SIZE);
+    inflater
+}
+
+#[test]
+fn validate() {
+    validate_entry(local_header_metadata()).finish().unwrap();
+
+    let metadata = EntryMetadata {
+        crc32: local_header_metadata().crc32 ^ 1,
+        ..local_header_metadata()
+    };
+    let error = validate_entry(metadata).finish().unwrap_err();
+    assert_eq!(error.kind(), InflateErrorKind::Crc32Mismatch);
+
+    let mut inflater = InflaterManaged::boxed();
+    let error = inflater.validate(&[0b111]).unwrap_err();
+    assert_eq!(error.kind(), InflateErrorKind::InvalidBlockType);
+}
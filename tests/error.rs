@@ -0,0 +1,452 @@
+use deflate64::{
+    BlockType, InflateErrorKind, InflateWarningKind, InflaterManaged, InflaterOptions,
+};
+
+#[test]
+fn error_codes_are_stable() {
+    let expected = [
+        (InflateErrorKind::InvalidBlockType, 1),
+        (InflateErrorKind::InvalidStoredBlockLength, 2),
+        (InflateErrorKind::InvalidHuffmanCode, 3),
+        (InflateErrorKind::InvalidCodeLengthRepeat, 4),
+        (InflateErrorKind::MissingEndOfBlockCode, 5),
+        (InflateErrorKind::InvalidSymbol, 6),
+        (InflateErrorKind::InvalidLength, 7),
+        (InflateErrorKind::InvalidDistance, 8),
+        (InflateErrorKind::CodeLengthRepeatOverflow, 9),
+        (InflateErrorKind::InvalidCodeLengthTree, 10),
+        (InflateErrorKind::InvalidLiteralLengthTree, 11),
+        (InflateErrorKind::InvalidDistanceTree, 12),
+        (InflateErrorKind::IncompleteStream, 13),
+        (InflateErrorKind::CompressedSizeMismatch, 14),
+        (InflateErrorKind::UncompressedSizeMismatch, 15),
+        (InflateErrorKind::Crc32Mismatch, 16),
+        (InflateErrorKind::OutputLimitExceeded, 17),
+        (InflateErrorKind::DistanceTooFarBack, 18),
+        (InflateErrorKind::InvalidStoredBlockPadding, 19),
+        (InflateErrorKind::ExpansionRatioExceeded, 20),
+        (InflateErrorKind::InvalidState, 21),
+    ];
+    for (kind, code) in expected {
+        assert_eq!(kind.code(), code);
+        assert_eq!(InflateErrorKind::from_code(code), Some(kind));
+    }
+    assert_eq!(InflateErrorKind::from_code(0), None);
+}
+
+#[test]
+fn invalid_block_type() {
+    // BFINAL=1, BTYPE=11
+    let mut inflater = Box::new(InflaterManaged::new());
+    assert!(inflater.error().is_none());
+    let result = inflater.inflate(&[0b111], &mut [0u8; 16]);
+    assert!(result.data_error);
+    let error = inflater.error().expect("error should be recorded");
+    assert_eq!(error.kind(), InflateErrorKind::InvalidBlockType);
+    assert_eq!(error.code(), 1);
+}
+
+#[test]
+fn invalid_stored_block_length() {
+    // BFINAL=1, BTYPE=00, LEN=5, NLEN=5
+    let mut inflater = Box::new(InflaterManaged::new());
+    let result = inflater.inflate(&[0b001, 5, 0, 5, 0], &mut [0u8; 16]);
+    assert!(result.data_error);
+    assert_eq!(
+        inflater.error().map(|e| e.kind()),
+        Some(InflateErrorKind::InvalidStoredBlockLength)
+    );
+}
+
+#[test]
+fn error_context() {
+    // block 0: BFINAL=0, BTYPE=00, LEN=3, data "abc"
+    // block 1: BFINAL=1, BTYPE=00, LEN=1, NLEN=1 (mismatch)
+    let stream = [0b000, 3, 0, !3, !0, b'a', b'b', b'c', 0b001, 1, 0, 1, 0];
+    let mut inflater = Box::new(InflaterManaged::new());
+    let mut output = [0u8; 16];
+    let result = inflater.inflate(&stream, &mut output);
+    assert!(result.data_error);
+    assert_eq!(&output[..result.bytes_written], b"abc");
+
+    let error = inflater.error().unwrap();
+    assert_eq!(error.kind(), InflateErrorKind::InvalidStoredBlockLength);
+    assert_eq!(error.block_index(), 1);
+    assert_eq!(error.block_type(), Some(BlockType::Uncompressed));
+    assert_eq!(error.input_offset(), 13);
+    assert_eq!(error.input_bit_offset(), 104);
+    assert_eq!(error.output_offset(), 3);
+    assert_eq!(
+        error.to_string(),
+        "stored block length does not match its complement in block 1 (uncompressed) \
+         at input byte 13, output byte 3"
+    );
+}
+
+#[test]
+fn error_context_without_block_type() {
+    let mut inflater = Box::new(InflaterManaged::new());
+    inflater.inflate(&[0b111], &mut [0u8; 16]);
+    let error = inflater.error().unwrap();
+    assert_eq!(error.block_index(), 0);
+    assert_eq!(error.block_type(), None);
+    assert_eq!(error.input_offset(), 0);
+    assert_eq!(error.input_bit_offset(), 3);
+    assert_eq!(
+        error.to_string(),
+        "invalid block type in block 0 at input byte 0, output byte 0"
+    );
+}
+
+/// Writes bits in deflate order: values LSB first, Huffman codes MSB first.
+struct BitWriter {
+    bytes: Vec<u8>,
+    bits: usize,
+}
+
+impl BitWriter {
+    fn new() -> Self {
+        Self {
+            bytes: Vec::new(),
+            bits: 0,
+        }
+    }
+
+    fn write_bits(&mut self, value: u32, count: usize) {
+        for i in 0..count {
+            if self.bits.is_multiple_of(8) {
+                self.bytes.push(0);
+            }
+            let bit = ((value >> i) & 1) as u8;
+            *self.bytes.last_mut().unwrap() |= bit << (self.bits % 8);
+            self.bits += 1;
+        }
+    }
+
+    fn write_code(&mut self, code: u32, length: usize) {
+        for i in (0..length).rev() {
+            self.write_bits(code >> i, 1);
+        }
+    }
+}
+
+const CODE_ORDER: [usize; 19] = [
+    16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15,
+];
+
+/// Starts a final dynamic block with the given counts and code length code lengths.
+fn dynamic_header(
+    literal_count: u32,
+    distance_count: u32,
+    code_lengths: &[(usize, u32)],
+) -> BitWriter {
+    let mut lengths = [0; 19];
+    for &(symbol, length) in code_lengths {
+        lengths[symbol] = length;
+    }
+    let code_length_count = CODE_ORDER
+        .iter()
+        .rposition(|&symbol| lengths[symbol] != 0)
+        .unwrap()
+        .max(3)
+        + 1;
+
+    let mut writer = BitWriter::new();
+    writer.write_bits(1, 1); // BFINAL
+    writer.write_bits(2, 2); // BTYPE=10
+    writer.write_bits(literal_count - 257, 5);
+    writer.write_bits(distance_count - 1, 5);
+    writer.write_bits(code_length_count as u32 - 4, 4);
+    for &symbol in &CODE_ORDER[..code_length_count] {
+        writer.write_bits(lengths[symbol], 3);
+    }
+    writer
+}
+
+fn inflate_error_kind(stream: &[u8]) -> InflateErrorKind {
+    let mut inflater = Box::new(InflaterManaged::new());
+    let result = inflater.inflate(stream, &mut [0u8; 1024]);
+    assert!(result.data_error);
+    let error = inflater.error().unwrap();
+    assert_eq!(error.block_type(), Some(BlockType::Dynamic));
+    error.kind()
+}
+
+#[test]
+fn code_length_repeat_without_previous() {
+    // codes: 0 => '0', 16 => '1'
+    let mut writer = dynamic_header(257, 1, &[(0, 1), (16, 1)]);
+    writer.write_code(1, 1);
+    writer.write_bits(0, 2);
+    assert_eq!(
+        inflate_error_kind(&writer.bytes),
+        InflateErrorKind::InvalidCodeLengthRepeat
+    );
+}
+
+#[test]
+fn code_length_repeat_overflow() {
+    // codes: 0 => '0', 18 => '1'; 2 * 138 zeros for 258 code lengths
+    let mut writer = dynamic_header(257, 1, &[(0, 1), (18, 1)]);
+    for _ in 0..2 {
+        writer.write_code(1, 1);
+        writer.write_bits(127, 7);
+    }
+    assert_eq!(
+        inflate_error_kind(&writer.bytes),
+        InflateErrorKind::CodeLengthRepeatOverflow
+    );
+}
+
+#[test]
+fn missing_end_of_block_code() {
+    // codes: 0 => '0', 18 => '1'; 138 + 120 zeros for 258 code lengths
+    let mut writer = dynamic_header(257, 1, &[(0, 1), (18, 1)]);
+    writer.write_code(1, 1);
+    writer.write_bits(127, 7);
+    writer.write_code(1, 1);
+    writer.write_bits(109, 7);
+    assert_eq!(
+        inflate_error_kind(&writer.bytes),
+        InflateErrorKind::MissingEndOfBlockCode
+    );
+}
+
+// Code length codes: 0 => '00', 1 => '01', 10 => '10', 18 => '11'.
+// A 10 bit code after two 1 bit codes is over-subscribed and collides with the short codes.
+const COLLIDING_CODE_LENGTHS: [(usize, u32); 4] = [(0, 2), (1, 2), (10, 2), (18, 2)];
+
+fn write_zeros(writer: &mut BitWriter, mut count: u32) {
+    while count > 0 {
+        let repeat = count.min(138);
+        writer.write_code(3, 2);
+        writer.write_bits(repeat - 11, 7);
+        count -= repeat;
+    }
+}
+
+#[test]
+fn invalid_literal_length_tree() {
+    let mut writer = dynamic_header(257, 1, &COLLIDING_CODE_LENGTHS);
+    // literals 0 and 1 have length 1, end of block has length 10
+    writer.write_code(1, 2);
+    writer.write_code(1, 2);
+    write_zeros(&mut writer, 254);
+    writer.write_code(2, 2);
+    // single distance code of length 1
+    writer.write_code(1, 2);
+    assert_eq!(
+        inflate_error_kind(&writer.bytes),
+        InflateErrorKind::InvalidLiteralLengthTree
+    );
+}
+
+#[test]
+fn invalid_distance_tree() {
+    let mut writer = dynamic_header(257, 3, &COLLIDING_CODE_LENGTHS);
+    // literal 0 and end of block have length 1
+    writer.write_code(1, 2);
+    write_zeros(&mut writer, 255);
+    writer.write_code(1, 2);
+    // distance codes 0 and 1 have length 1, distance code 2 has length 10
+    writer.write_code(1, 2);
+    writer.write_code(1, 2);
+    writer.write_code(2, 2);
+    assert_eq!(
+        inflate_error_kind(&writer.bytes),
+        InflateErrorKind::InvalidDistanceTree
+    );
+}
+
+#[test]
+fn error_traits() {
+    fn assert_error<E: std::error::Error + Send + Sync + 'static>() {}
+    assert_error::<deflate64::InflateError>();
+    assert_error::<deflate64::SinkError<std::io::Error>>();
+    assert_error::<deflate64::flate2_compat::DecompressError>();
+
+    let mut inflater = Box::new(InflaterManaged::new());
+    let error = inflater
+        .inflate_checked(&[0b111], &mut [0u8; 16])
+        .unwrap_err();
+    let io_error = std::io::Error::from(error);
+    assert_eq!(io_error.kind(), std::io::ErrorKind::InvalidInput);
+    assert_eq!(io_error.to_string(), error.to_string());
+
+    let error = InflaterManaged::measure(&[]).unwrap_err();
+    assert_eq!(error.kind(), InflateErrorKind::IncompleteStream);
+    let io_error = std::io::Error::from(error);
+    assert_eq!(io_error.kind(), std::io::ErrorKind::UnexpectedEof);
+}
+
+/// A final static block with the literal 'a' and a match of length 3 at `distance`.
+fn static_block_with_match(distance_code: u32) -> BitWriter {
+    let mut writer = BitWriter::new();
+    writer.write_bits(1, 1); // BFINAL
+    writer.write_bits(1, 2); // BTYPE=01
+    writer.write_code(0x30 + u32::from(b'a'), 8);
+    writer.write_code(1, 7); // length 3
+    writer.write_code(distance_code, 5);
+    writer.write_code(0, 7); // end of block
+    writer
+}
+
+#[test]
+fn distance_too_far_back() {
+    // distance code 0 is distance 1, which is valid after one byte
+    let mut inflater = Box::new(InflaterManaged::new());
+    let mut output = [0u8; 16];
+    let summary = inflater
+        .inflate_checked(&static_block_with_match(0).bytes, &mut output)
+        .unwrap();
+    assert_eq!(&output[..summary.bytes_written], b"aaaa");
+
+    // distance code 1 is distance 2; the padding lets the fast decoding loop run
+    for padding in [0, 16] {
+        let mut stream = static_block_with_match(1).bytes;
+        stream.resize(stream.len() + padding, 0);
+        let mut inflater = Box::new(InflaterManaged::new());
+        let error = inflater.inflate_checked(&stream, &mut output).unwrap_err();
+        assert_eq!(error.kind(), InflateErrorKind::DistanceTooFarBack);
+        assert_eq!(error.block_type(), Some(BlockType::Static));
+    }
+}
+
+/// Decodes `stream` leniently, expecting `output` and `warning`, and then strictly,
+/// expecting the error `kind`.
+fn check_strict(
+    stream: &[u8],
+    output: &[u8],
+    warning: Option<InflateWarningKind>,
+    kind: InflateErrorKind,
+) {
+    let mut inflater = InflaterManaged::boxed();
+    let mut buffer = [0u8; 16];
+    let summary = inflater.inflate_checked(stream, &mut buffer).unwrap();
+    assert_eq!(&buffer[..summary.bytes_written], output);
+    assert!(inflater.finished());
+    let warnings: Vec<_> = inflater.warnings().iter().map(|w| w.kind()).collect();
+    assert_eq!(warnings, Vec::from_iter(warning));
+
+    let mut inflater = InflaterManaged::boxed_with_options(InflaterOptions::new().strict(true));
+    let error = inflater.inflate_checked(stream, &mut buffer).unwrap_err();
+    assert_eq!(error.kind(), kind);
+}
+
+#[test]
+fn strict_incomplete_literal_length_tree() {
+    // code length codes: 0 => '00', 1 => '01', 2 => '10', 18 => '11'
+    let mut writer = dynamic_header(257, 1, &[(0, 2), (1, 2), (2, 2), (18, 2)]);
+    // literal 0 has length 1 and end of block length 2, which leaves a code unused
+    writer.write_code(1, 2);
+    write_zeros(&mut writer, 255);
+    writer.write_code(2, 2);
+    // single distance code of length 1
+    writer.write_code(1, 2);
+    // literal 0, end of block
+    writer.write_code(0, 1);
+    writer.write_code(2, 2);
+    check_strict(
+        &writer.bytes,
+        &[0],
+        Some(InflateWarningKind::IncompleteLiteralLengthTree),
+        InflateErrorKind::InvalidLiteralLengthTree,
+    );
+}
+
+#[test]
+fn strict_incomplete_code_length_tree() {
+    // code length codes: 0 => '00', 1 => '01', 18 => '10', and '11' is unused
+    let mut writer = dynamic_header(257, 1, &[(0, 2), (1, 2), (18, 2)]);
+    // literal 0 and end of block have length 1
+    writer.write_code(1, 2);
+    for count in [138, 117] {
+        writer.write_code(2, 2);
+        writer.write_bits(count - 11, 7);
+    }
+    writer.write_code(1, 2);
+    // single distance code of length 1
+    writer.write_code(1, 2);
+    // literal 0, end of block
+    writer.write_code(0, 1);
+    writer.write_code(1, 1);
+    check_strict(
+        &writer.bytes,
+        &[0],
+        None,
+        InflateErrorKind::InvalidCodeLengthTree,
+    );
+}
+
+#[test]
+fn strict_too_many_literal_length_codes() {
+    // code length codes: 0 => '0', 1 => '1'
+    let mut writer = dynamic_header(287, 1, &[(0, 1), (1, 1)]);
+    // literal 0 and end of block have length 1
+    writer.write_code(1, 1);
+    for _ in 0..255 {
+        writer.write_code(0, 1);
+    }
+    writer.write_code(1, 1);
+    for _ in 0..30 {
+        writer.write_code(0, 1);
+    }
+    // single distance code of length 1
+    writer.write_code(1, 1);
+    // literal 0, end of block
+    writer.write_code(0, 1);
+    writer.write_code(1, 1);
+    check_strict(
+        &writer.bytes,
+        &[0],
+        None,
+        InflateErrorKind::InvalidLiteralLengthTree,
+    );
+}
+
+#[test]
+fn strict_stored_block_padding() {
+    // BFINAL=1, BTYPE=00, padding 11111, LEN=1, "x"
+    check_strict(
+        &[0b1111_1001, 1, 0, !1, 0xff, b'x'],
+        b"x",
+        Some(InflateWarningKind::NonZeroStoredBlockPadding),
+        InflateErrorKind::InvalidStoredBlockPadding,
+    );
+    // empty stored blocks are valid
+    let stream = [0b000, 0, 0, 0xff, 0xff, 0b001, 1, 0, !1, 0xff, b'x'];
+    let mut inflater = InflaterManaged::boxed_with_options(InflaterOptions::new().strict(true));
+    let summary = inflater.inflate_checked(&stream, &mut [0u8; 16]).unwrap();
+    assert_eq!(summary.bytes_written, 1);
+}
+
+#[test]
+fn fill_missing_history() {
+    let options = InflaterOptions::new().fill_missing_history(true);
+    // distance code 1 is distance 2 after one byte, so the first byte of the match is
+    // missing; the padding lets the fast decoding loop run
+    for padding in [0, 16] {
+        let mut stream = static_block_with_match(1).bytes;
+        stream.resize(stream.len() + padding, 0);
+        let mut inflater = InflaterManaged::boxed_with_options(options);
+        let mut output = [0u8; 16];
+        let result = inflater.inflate(&stream, &mut output);
+        assert!(!result.data_error);
+        assert!(result.history_filled);
+        assert_eq!(&output[..result.bytes_written], b"a\0a\0");
+        assert!(inflater.finished());
+        assert_eq!(inflater.filled_history_bytes(), 1);
+        let warnings: Vec<_> = inflater.warnings().iter().map(|w| w.kind()).collect();
+        assert_eq!(warnings, [InflateWarningKind::MissingHistory]);
+
+        let mut inflater = InflaterManaged::boxed_with_options(options.strict(true));
+        let error = inflater.inflate_checked(&stream, &mut output).unwrap_err();
+        assert_eq!(error.kind(), InflateErrorKind::DistanceTooFarBack);
+    }
+
+    // valid matches are not flagged
+    let mut inflater = InflaterManaged::boxed_with_options(options);
+    let result = inflater.inflate(&static_block_with_match(0).bytes, &mut [0u8; 16]);
+    assert!(!result.history_filled);
+    assert_eq!(inflater.filled_history_bytes(), 0);
+}
@@ -0,0 +1,72 @@
+use deflate64::flate2_compat::{Decompress, FlushDecompress, Status};
+use deflate64::InflateErrorKind;
+
+mod common;
+
+use common::{compressed_data, BINARY_WAV_COMPRESSED_SIZE, BINARY_WAV_DATA};
+
+#[test]
+fn decompress_loop() {
+    let input = compressed_data();
+    let mut decompress = Decompress::new();
+    let mut output = vec![];
+    let mut buffer = [0u8; 8192];
+    loop {
+        let total_in = decompress.total_in() as usize;
+        let total_out = decompress.total_out();
+        let end = (total_in + 10000).min(input.len());
+        let status = decompress
+            .decompress(&input[total_in..end], &mut buffer, FlushDecompress::None)
+            .unwrap();
+        let written = (decompress.total_out() - total_out) as usize;
+        output.extend_from_slice(&buffer[..written]);
+        match status {
+            Status::Ok => {}
+            Status::StreamEnd => break,
+            Status::BufError => panic!("no progress"),
+        }
+    }
+    assert_eq!(decompress.total_in(), BINARY_WAV_COMPRESSED_SIZE as u64);
+    assert_eq!(decompress.total_out(), BINARY_WAV_DATA.len() as u64);
+    assert_eq!(output, BINARY_WAV_DATA);
+}
+
+#[test]
+fn decompress_vec() {
+    let mut decompress = Decompress::new();
+    let mut output = Vec::with_capacity(BINARY_WAV_DATA.len());
+    let status = decompress
+        .decompress_vec(compressed_data(), &mut output, FlushDecompress::Finish)
+        .unwrap();
+    assert_eq!(status, Status::StreamEnd);
+    assert_eq!(output, BINARY_WAV_DATA);
+}
+
+#[test]
+fn buf_error_without_input() {
+    let mut decompress = Decompress::new();
+    let status = decompress
+        .decompress(&[], &mut [0u8; 16], FlushDecompress::Finish)
+        .unwrap();
+    assert_eq!(status, Status::BufError);
+}
+
+#[test]
+fn invalid_stream() {
+    let mut decompress = Decompress::new();
+    let error = decompress
+        .decompress(&[0b111], &mut [0u8; 16], FlushDecompress::None)
+        .unwrap_err();
+    assert_eq!(
+        error.inflate_error().kind(),
+        InflateErrorKind::InvalidBlockType
+    );
+
+    decompress.reset();
+    assert_eq!(decompress.total_in(), 0);
+    let mut output = Vec::with_capacity(BINARY_WAV_DATA.len());
+    decompress
+        .decompress_vec(compressed_data(), &mut output, FlushDecompress::Finish)
+        .unwrap();
+    assert_eq!(output, BINARY_WAV_DATA);
+}
@@ -0,0 +1,159 @@
+//! Decodes mutated and random streams through the public API, which must never panic.
+//!
+//! This replays the same kind of inputs as the `inflate` target in `fuzz/`, with a fixed
+//! seed. Set `DEFLATE64_FUZZ_ITERATIONS` to run more iterations.
+
+use deflate64::{InflaterManaged, InflaterOptions, StreamCarver};
+use std::io::BufReader;
+
+mod common;
+
+use common::{BINARY_WAV_DATA_OFFSET, ZIP_FILE_DATA};
+
+static ISSUE_23_DATA: &[u8] =
+    include_bytes!("../test-assets/issue-23/raw_deflate64_index_out_of_bounds");
+
+struct XorShift(u64);
+
+impl XorShift {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn below(&mut self, bound: usize) -> usize {
+        (self.next() % bound as u64) as usize
+    }
+}
+
+fn seeds() -> Vec<Vec<u8>> {
+    vec![
+        // stored blocks
+        vec![
+            0b000, 3, 0, !3, !0, b'a', b'b', b'c', 0b001, 1, 0, !1, !0, b'd',
+        ],
+        // static block: 'a', length 3 at distance 1
+        vec![0x4b, 0x04, 0x02, 0x00],
+        // the start of a stream of dynamic blocks
+        ZIP_FILE_DATA[BINARY_WAV_DATA_OFFSET..][..4096].to_vec(),
+        ISSUE_23_DATA.to_vec(),
+    ]
+}
+
+fn mutate(rng: &mut XorShift, seed: &[u8]) -> Vec<u8> {
+    let mut data = seed.to_vec();
+    match rng.below(5) {
+        0 => {
+            // random bytes
+            let len = rng.below(512);
+            return (0..len).map(|_| rng.next() as u8).collect();
+        }
+        1 => data.truncate(rng.below(data.len() + 1)),
+        _ => {}
+    }
+    for _ in 0..1 + rng.below(8) {
+        if data.is_empty() {
+            break;
+        }
+        let index = rng.below(data.len());
+        match rng.below(3) {
+            0 => data[index] ^= 1 << rng.below(8),
+            1 => data[index] = rng.next() as u8,
+            _ => data.insert(index, rng.next() as u8),
+        }
+    }
+    data
+}
+
+fn exercise(data: &[u8], rng: &mut XorShift) {
+    let options = [
+        InflaterOptions::new(),
+        InflaterOptions::new().strict(true),
+        InflaterOptions::new().fill_missing_history(true),
+        InflaterOptions::new()
+            .uncompressed_size(rng.below(10000))
+            .exact_uncompressed_size(true)
+            .max_expansion_ratio(2)
+            .collect_stats(true),
+    ];
+    let mut output = vec![0u8; 1 << 16];
+    for options in options {
+        // random input and output chunks, recovering from errors
+        let mut inflater = InflaterManaged::boxed_with_options(options);
+        let mut consumed = 0;
+        for _ in 0..10000 {
+            let input_end = (consumed + rng.below(64)).min(data.len());
+            let output_len = rng.below(output.len());
+            let result = inflater.inflate(&data[consumed..input_end], &mut output[..output_len]);
+            consumed += result.bytes_consumed;
+            inflater.min_input_hint();
+            if result.data_error {
+                match inflater.resync(&data[consumed..]) {
+                    Some(gap) => consumed += gap.bytes_consumed,
+                    None => break,
+                }
+            }
+            if inflater.finished() || (result.is_stalled() && input_end == data.len()) {
+                break;
+            }
+        }
+        let _ = inflater.finish();
+
+        let mut inflater = InflaterManaged::boxed_with_options(options);
+        let _ = inflater.validate(data);
+        let mut inflater = InflaterManaged::boxed_with_options(options);
+        let _ = inflater.inflate_until_block_boundary(data, &mut output);
+    }
+
+    #[cfg(feature = "checkpoint")]
+    exercise_checkpoint(data, rng);
+
+    let _ = InflaterManaged::measure(data);
+    let _ = InflaterManaged::enumerate_blocks(data);
+    let _ = InflaterManaged::scan_uncompressed_size(&mut BufReader::new(data), true);
+    let _ = InflaterManaged::decompress_into(data, &mut output);
+    for _ in StreamCarver::new(data).min_output(1).take(4) {}
+}
+
+/// Restores a checkpoint with mutated fields but a valid checksum, and continues.
+#[cfg(feature = "checkpoint")]
+fn exercise_checkpoint(data: &[u8], rng: &mut XorShift) {
+    let options = InflaterOptions::new().compress_checkpoints(rng.below(2) == 0);
+    let mut inflater = Box::new(InflaterManaged::with_checkpoints_and_options(options));
+    let mut output = vec![0u8; rng.below(1 << 16)];
+    inflater.inflate(&data[..rng.below(data.len() + 1)], &mut output);
+    let Some((mut checkpoint, _)) = inflater.checkpoint() else {
+        return;
+    };
+    checkpoint.truncate(checkpoint.len() - 4);
+    for _ in 0..1 + rng.below(4) {
+        let index = rng.below(checkpoint.len());
+        checkpoint[index] ^= 1 << rng.below(8);
+    }
+    let checksum = common::fletcher32_checksum(&checkpoint);
+    checkpoint.extend_from_slice(&checksum.to_le_bytes());
+
+    let mut restored = Box::new(InflaterManaged::with_checkpoints());
+    if let Some(positions) = restored.restore_from_checkpoint(&checkpoint) {
+        let skip = (positions.input_bytes_to_skip as usize).min(data.len());
+        restored.inflate(&data[skip..], &mut output);
+        let _ = restored.checkpoint();
+        let _ = restored.finish();
+    }
+}
+
+#[test]
+fn mutated_streams() {
+    let iterations = std::env::var("DEFLATE64_FUZZ_ITERATIONS")
+        .map(|iterations| iterations.parse().unwrap())
+        .unwrap_or(200);
+    let seeds = seeds();
+    let mut rng = XorShift(0x2560);
+    for _ in 0..iterations {
+        let seed = &seeds[rng.below(seeds.len())];
+        let data = mutate(&mut rng, seed);
+        exercise(&data, &mut rng);
+    }
+}
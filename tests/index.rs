@@ -0,0 +1,171 @@
+#![cfg(feature = "checkpoint")]
+
+use deflate64::{Deflate64Index, InflaterManaged};
+use std::io::{self, Read};
+
+mod common;
+
+use common::{
+    compressed_data, BINARY_WAV_COMPRESSED_SIZE, BINARY_WAV_DATA, BINARY_WAV_DATA_OFFSET,
+    ZIP_FILE_DATA,
+};
+
+/// A reader which returns at most `chunk` bytes per read
+struct ChunkedReader<'a> {
+    data: &'a [u8],
+    chunk: usize,
+}
+
+impl Read for ChunkedReader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let len = buf.len().min(self.chunk).min(self.data.len());
+        buf[..len].copy_from_slice(&self.data[..len]);
+        self.data = &self.data[len..];
+        Ok(len)
+    }
+}
+
+#[test]
+fn build_index() {
+    let interval = 256 * 1024;
+    // the reader continues past the end of the stream
+    let index = Deflate64Index::build(&ZIP_FILE_DATA[BINARY_WAV_DATA_OFFSET..], interval).unwrap();
+    assert_eq!(index.interval(), interval);
+    assert_eq!(index.compressed_size(), BINARY_WAV_COMPRESSED_SIZE as u64);
+    assert_eq!(index.uncompressed_size(), BINARY_WAV_DATA.len() as u64);
+    assert_eq!(
+        index.entries().len() as u64,
+        BINARY_WAV_DATA.len() as u64 / interval
+    );
+
+    let mut output = vec![0u8; 1000];
+    for (i, entry) in index.entries().iter().enumerate() {
+        assert_eq!(entry.output_offset, (i as u64 + 1) * interval);
+        let mut inflater = Box::new(InflaterManaged::with_checkpoints());
+        let positions = inflater.restore_from_checkpoint(&entry.checkpoint).unwrap();
+        assert_eq!(positions.output_bytes_already_returned, entry.output_offset);
+        assert_eq!(positions.input_bytes_to_skip, entry.input_offset);
+
+        let input = &compressed_data()[entry.input_offset as usize..];
+        let result = inflater.inflate(input, &mut output);
+        let offset = entry.output_offset as usize;
+        assert_eq!(
+            &output[..result.bytes_written],
+            &BINARY_WAV_DATA[offset..][..result.bytes_written]
+        );
+    }
+
+    // the nearest preceding checkpoint
+    assert!(index.entry_before(interval - 1).is_none());
+    assert_eq!(
+        index.entry_before(interval).unwrap().output_offset,
+        interval
+    );
+    assert_eq!(
+        index.entry_before(3 * interval - 1).unwrap().output_offset,
+        2 * interval
+    );
+    assert_eq!(index.entry_before(u64::MAX), index.entries().last());
+
+    // the size of reads doesn't change the output offsets
+    let reader = ChunkedReader {
+        data: compressed_data(),
+        chunk: 1000,
+    };
+    let chunked = Deflate64Index::build(reader, interval).unwrap();
+    assert_eq!(chunked.compressed_size(), index.compressed_size());
+    assert_eq!(chunked.uncompressed_size(), index.uncompressed_size());
+    let offsets = |index: &Deflate64Index| -> Vec<u64> {
+        index
+            .entries()
+            .iter()
+            .map(|entry| entry.output_offset)
+            .collect()
+    };
+    assert_eq!(offsets(&chunked), offsets(&index));
+}
+
+#[test]
+fn build_index_errors() {
+    let error = Deflate64Index::build(&compressed_data()[..100000], 4096).unwrap_err();
+    assert_eq!(error.kind(), io::ErrorKind::UnexpectedEof);
+
+    let mut corrupt = compressed_data().to_vec();
+    corrupt[0] |= 0x06; // reserved block type
+    let error = Deflate64Index::build(&corrupt[..], 4096).unwrap_err();
+    assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0xEDB88320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+fn rebuild_index_crc(saved: &mut [u8]) {
+    let len = saved.len();
+    let crc = crc32(&saved[..len - 4]);
+    saved[len - 4..].copy_from_slice(&crc.to_le_bytes());
+}
+
+#[test]
+fn save_and_load_index() {
+    let index = Deflate64Index::build(compressed_data(), 512 * 1024).unwrap();
+    let mut saved = Vec::new();
+    index.save(&mut saved).unwrap();
+    let entries_len: usize = index
+        .entries()
+        .iter()
+        .map(|entry| 20 + entry.checkpoint.len())
+        .sum();
+    assert_eq!(saved.len(), 44 + entries_len + 4);
+    assert_eq!(&saved[..10], b"D64INDEX\x01\x00");
+
+    // the reader is left after the index
+    let mut data = saved.clone();
+    data.extend_from_slice(b"trailer");
+    let mut reader = io::Cursor::new(&data);
+    assert_eq!(Deflate64Index::load(&mut reader).unwrap(), index);
+    assert_eq!(reader.position(), saved.len() as u64);
+
+    // flags in the high byte are ignored
+    let mut hinted = saved.clone();
+    hinted[11] = 0x80;
+    rebuild_index_crc(&mut hinted);
+    assert_eq!(Deflate64Index::load(&hinted[..]).unwrap(), index);
+
+    let invalid_data = |data: &[u8]| {
+        let error = Deflate64Index::load(data).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    };
+    // unknown versions and required flags
+    for (offset, value) in [(0, b'X'), (8, 2), (10, 0x01)] {
+        let mut changed = saved.clone();
+        changed[offset] = value;
+        rebuild_index_crc(&mut changed);
+        invalid_data(&changed);
+    }
+    // checkpoint offsets which don't match the entry
+    let mut changed = saved.clone();
+    changed[44] ^= 1;
+    rebuild_index_crc(&mut changed);
+    invalid_data(&changed);
+    // corruption
+    for offset in [12, 44, 1000, saved.len() - 1] {
+        let mut corrupt = saved.clone();
+        corrupt[offset] ^= 1;
+        invalid_data(&corrupt);
+    }
+
+    let error = Deflate64Index::load(&saved[..saved.len() - 1]).unwrap_err();
+    assert_eq!(error.kind(), io::ErrorKind::UnexpectedEof);
+}
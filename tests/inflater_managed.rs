@@ -1,13 +1,16 @@
-use deflate64::InflaterManaged;
+use deflate64::{BlockType, InflateErrorKind, InflateStatus, InflaterManaged};
 use std::cmp::min;
+use std::io::{BufReader, ErrorKind, IoSliceMut, Read};
+use std::ops::ControlFlow;
 
-const BINARY_WAV_DATA_OFFSET: usize = 40;
-const BINARY_WAV_COMPRESSED_SIZE: usize = 2669743;
-const BINARY_WAV_UNCOMPRESSED_SIZE: usize = 2703788;
-const BINARY_WAV_UNCOMPRESSED_BUFFER_SIZE: usize = 2703788 + 10;
+mod common;
+
+use common::{
+    compressed_data, BINARY_WAV_COMPRESSED_SIZE, BINARY_WAV_DATA, BINARY_WAV_DATA_OFFSET,
+    BINARY_WAV_UNCOMPRESSED_SIZE, ZIP_FILE_DATA,
+};
 
-static ZIP_FILE_DATA: &[u8] = include_bytes!("../test-assets/deflate64.zip");
-static BINARY_WAV_DATA: &[u8] = include_bytes!("../test-assets/folder/binary.wmv");
+const BINARY_WAV_UNCOMPRESSED_BUFFER_SIZE: usize = 2703788 + 10;
 
 #[test]
 fn check_test_data() {
@@ -16,8 +19,7 @@ fn check_test_data() {
 
 #[test]
 fn binary_wav() {
-    let binary_wav_compressed =
-        &ZIP_FILE_DATA[BINARY_WAV_DATA_OFFSET..][..BINARY_WAV_COMPRESSED_SIZE];
+    let binary_wav_compressed = compressed_data();
     let mut uncompressed_data = vec![0u8; BINARY_WAV_UNCOMPRESSED_BUFFER_SIZE];
 
     let mut inflater = Box::new(InflaterManaged::new());
@@ -34,8 +36,7 @@ fn binary_wav() {
 
 #[test]
 fn binary_wav_with_size() {
-    let binary_wav_compressed =
-        &ZIP_FILE_DATA[BINARY_WAV_DATA_OFFSET..][..BINARY_WAV_COMPRESSED_SIZE];
+    let binary_wav_compressed = compressed_data();
     let mut uncompressed_data = vec![0u8; BINARY_WAV_UNCOMPRESSED_BUFFER_SIZE];
 
     let mut inflater = Box::new(InflaterManaged::with_uncompressed_size(
@@ -68,8 +69,7 @@ fn binary_wav_shredded_100() {
 }
 
 fn binary_wav_shredded(chunk: usize) {
-    let binary_wav_compressed =
-        &ZIP_FILE_DATA[BINARY_WAV_DATA_OFFSET..][..BINARY_WAV_COMPRESSED_SIZE];
+    let binary_wav_compressed = compressed_data();
     let mut uncompressed_data = vec![0u8; BINARY_WAV_UNCOMPRESSED_BUFFER_SIZE];
 
     let mut inflater = Box::new(InflaterManaged::new());
@@ -88,9 +88,771 @@ fn binary_wav_shredded(chunk: usize) {
     }
 
     assert_eq!(written, BINARY_WAV_UNCOMPRESSED_SIZE);
+    assert_eq!(inflater.total_in(), BINARY_WAV_COMPRESSED_SIZE as u64);
+    assert_eq!(inflater.total_out(), BINARY_WAV_UNCOMPRESSED_SIZE as u64);
+
+    assert_eq!(
+        &uncompressed_data[..BINARY_WAV_UNCOMPRESSED_SIZE],
+        BINARY_WAV_DATA
+    );
+}
+
+#[test]
+fn binary_wav_inflate_all() {
+    let binary_wav_compressed = compressed_data();
+    let mut uncompressed_data = vec![0u8; BINARY_WAV_UNCOMPRESSED_BUFFER_SIZE];
+
+    let mut inflater = Box::new(InflaterManaged::new());
+
+    // small output chunks, all input at once
+    let mut consumed = 0;
+    let mut written = 0;
+    while !inflater.finished() {
+        let end = min(written + 100_000, uncompressed_data.len());
+        let (c, w) = inflater
+            .inflate_all(
+                &binary_wav_compressed[consumed..],
+                &mut uncompressed_data[written..end],
+            )
+            .unwrap();
+        consumed += c;
+        written += w;
+    }
 
+    assert_eq!(consumed, BINARY_WAV_COMPRESSED_SIZE);
+    assert_eq!(written, BINARY_WAV_UNCOMPRESSED_SIZE);
     assert_eq!(
         &uncompressed_data[..BINARY_WAV_UNCOMPRESSED_SIZE],
         BINARY_WAV_DATA
     );
 }
+
+#[test]
+fn inflate_all_error() {
+    let mut inflater = Box::new(InflaterManaged::new());
+    let error = inflater.inflate_all(&[0b111], &mut [0u8; 16]).unwrap_err();
+    assert_eq!(error.kind(), deflate64::InflateErrorKind::InvalidBlockType);
+}
+
+#[test]
+fn binary_wav_block_boundaries() {
+    let binary_wav_compressed = compressed_data();
+    let mut uncompressed_data = vec![0u8; BINARY_WAV_UNCOMPRESSED_BUFFER_SIZE];
+
+    let mut inflater = Box::new(InflaterManaged::new());
+    let mut consumed = 0;
+    let mut written = 0;
+    let mut boundaries = vec![];
+    while !inflater.finished() {
+        let end = min(written + 100_000, uncompressed_data.len());
+        let (result, boundary) = inflater.inflate_until_block_boundary(
+            &binary_wav_compressed[consumed..],
+            &mut uncompressed_data[written..end],
+        );
+        assert!(!result.data_error, "unexpected error");
+        consumed += result.bytes_consumed;
+        written += result.bytes_written;
+        if let Some(boundary) = boundary {
+            assert_eq!(boundary.output_bytes, written as u64);
+            assert_eq!(boundary.input_bits.div_ceil(8), consumed as u64);
+            boundaries.push(boundary);
+        }
+    }
+
+    assert!(boundaries.len() > 1);
+    assert!(boundaries
+        .windows(2)
+        .all(|w| w[0].input_bits < w[1].input_bits));
+    let (last, rest) = boundaries.split_last().unwrap();
+    assert!(last.end_of_stream);
+    assert!(rest.iter().all(|boundary| !boundary.end_of_stream));
+    assert_eq!(consumed, BINARY_WAV_COMPRESSED_SIZE);
+    assert_eq!(written, BINARY_WAV_UNCOMPRESSED_SIZE);
+    assert_eq!(
+        &uncompressed_data[..BINARY_WAV_UNCOMPRESSED_SIZE],
+        BINARY_WAV_DATA
+    );
+}
+
+#[test]
+fn stored_block_boundaries() {
+    // block 0: BFINAL=0, BTYPE=00, LEN=3, data "abc"
+    // block 1: BFINAL=1, BTYPE=00, LEN=1, data "d"
+    let stream = [
+        0b000, 3, 0, !3, !0, b'a', b'b', b'c', 0b001, 1, 0, !1, !0, b'd',
+    ];
+    let mut inflater = Box::new(InflaterManaged::new());
+    let mut output = [0u8; 16];
+
+    let (result, boundary) = inflater.inflate_until_block_boundary(&stream, &mut output);
+    assert_eq!(result.bytes_consumed, 8);
+    assert_eq!(&output[..result.bytes_written], b"abc");
+    let boundary = boundary.unwrap();
+    assert_eq!((boundary.input_bits, boundary.output_bytes), (64, 3));
+    assert!(!boundary.end_of_stream);
+
+    let (result, boundary) = inflater.inflate_until_block_boundary(&stream[8..], &mut output);
+    assert_eq!(result.bytes_consumed, 6);
+    assert_eq!(&output[..result.bytes_written], b"d");
+    let boundary = boundary.unwrap();
+    assert_eq!((boundary.input_bits, boundary.output_bytes), (112, 4));
+    assert!(boundary.end_of_stream);
+    assert!(inflater.finished());
+}
+
+#[test]
+fn binary_wav_measure() {
+    // the rest of the zip file after the stream is ignored
+    let measurements = InflaterManaged::measure(&ZIP_FILE_DATA[BINARY_WAV_DATA_OFFSET..]).unwrap();
+    assert_eq!(
+        measurements.compressed_size,
+        BINARY_WAV_COMPRESSED_SIZE as u64
+    );
+    assert_eq!(
+        measurements.uncompressed_size,
+        BINARY_WAV_UNCOMPRESSED_SIZE as u64
+    );
+    assert!(measurements.block_count > 1);
+}
+
+#[test]
+fn measure_stored_blocks() {
+    let stream = [
+        0b000, 3, 0, !3, !0, b'a', b'b', b'c', 0b001, 1, 0, !1, !0, b'd',
+    ];
+    let measurements = InflaterManaged::measure(&stream).unwrap();
+    assert_eq!(measurements.compressed_size, 14);
+    assert_eq!(measurements.uncompressed_size, 4);
+    assert_eq!(measurements.block_count, 2);
+}
+
+#[test]
+fn measure_errors() {
+    let truncated = &ZIP_FILE_DATA[BINARY_WAV_DATA_OFFSET..][..BINARY_WAV_COMPRESSED_SIZE - 1];
+    let error = InflaterManaged::measure(truncated).unwrap_err();
+    assert_eq!(error.kind(), deflate64::InflateErrorKind::IncompleteStream);
+
+    let error = InflaterManaged::measure(&[0b111]).unwrap_err();
+    assert_eq!(error.kind(), deflate64::InflateErrorKind::InvalidBlockType);
+}
+
+#[test]
+fn reset() {
+    let binary_wav_compressed = compressed_data();
+    let mut uncompressed_data = vec![0u8; BINARY_WAV_UNCOMPRESSED_BUFFER_SIZE];
+
+    let mut inflater = Box::new(InflaterManaged::new());
+    let result = inflater.inflate(&[0b111], &mut uncompressed_data);
+    assert!(result.data_error);
+
+    inflater.reset();
+    assert!(!inflater.errored());
+    assert!(inflater.error().is_none());
+    let output = inflater.inflate(binary_wav_compressed, &mut uncompressed_data);
+    assert_eq!(output.bytes_consumed, BINARY_WAV_COMPRESSED_SIZE);
+    assert_eq!(output.bytes_written, BINARY_WAV_UNCOMPRESSED_SIZE);
+    assert!(!output.data_error, "unexpected error");
+    assert_eq!(
+        &uncompressed_data[..BINARY_WAV_UNCOMPRESSED_SIZE],
+        BINARY_WAV_DATA
+    );
+}
+
+#[test]
+#[cfg_attr(
+    debug_assertions,
+    ignore = "debug builds may construct the inflater on the stack"
+)]
+fn boxed_on_small_stack() {
+    let binary_wav_compressed = compressed_data();
+    let thread = std::thread::Builder::new().stack_size(64 * 1024);
+    let output = thread
+        .spawn(|| {
+            let mut inflater = InflaterManaged::boxed();
+            let mut output = vec![0u8; BINARY_WAV_UNCOMPRESSED_SIZE];
+            let result = inflater.inflate(binary_wav_compressed, &mut output);
+            assert!(inflater.finished());
+            output.truncate(result.bytes_written);
+            output
+        })
+        .unwrap()
+        .join()
+        .unwrap();
+    assert_eq!(output, BINARY_WAV_DATA);
+}
+
+#[test]
+fn reset_matches_new_inflater() {
+    let binary_wav_compressed = compressed_data();
+    let mut uncompressed_data = vec![0u8; 100000];
+
+    let mut inflater = Box::new(InflaterManaged::new());
+    inflater.inflate(binary_wav_compressed, &mut uncompressed_data);
+    inflater.reset_with_uncompressed_size(1000);
+    let fresh = Box::new(InflaterManaged::with_uncompressed_size(1000));
+    assert_eq!(format!("{inflater:?}"), format!("{fresh:?}"));
+
+    let output = inflater.inflate(binary_wav_compressed, &mut uncompressed_data);
+    assert_eq!(output.bytes_written, 1000);
+    assert_eq!(&uncompressed_data[..1000], &BINARY_WAV_DATA[..1000]);
+}
+
+#[test]
+fn chunks() {
+    let binary_wav_compressed = compressed_data();
+
+    let mut inflater = Box::new(InflaterManaged::new());
+    let mut chunks = inflater.chunks(binary_wav_compressed, 100000);
+    let mut output = vec![];
+    for chunk in &mut chunks {
+        let chunk = chunk.unwrap();
+        assert!(!chunk.is_empty() && chunk.len() <= 100000);
+        output.extend_from_slice(&chunk);
+    }
+    assert!(chunks.remaining_input().is_empty());
+    assert_eq!(output, BINARY_WAV_DATA);
+}
+
+#[test]
+fn chunks_truncated_input() {
+    let binary_wav_compressed = &ZIP_FILE_DATA[BINARY_WAV_DATA_OFFSET..][..100000];
+
+    let mut inflater = Box::new(InflaterManaged::new());
+    let mut output = vec![];
+    let mut chunks = inflater.chunks(binary_wav_compressed, 65536);
+    let error = loop {
+        match chunks.next().unwrap() {
+            Ok(chunk) => output.extend_from_slice(&chunk),
+            Err(error) => break error,
+        }
+    };
+    assert_eq!(error.kind(), deflate64::InflateErrorKind::IncompleteStream);
+    assert!(chunks.next().is_none());
+    assert!(!output.is_empty());
+    assert_eq!(output, BINARY_WAV_DATA[..output.len()]);
+}
+
+#[test]
+fn chunks_invalid_input() {
+    let mut inflater = Box::new(InflaterManaged::new());
+    let mut chunks = inflater.chunks(&[0b111], 1024);
+    let error = chunks.next().unwrap().unwrap_err();
+    assert_eq!(error.kind(), deflate64::InflateErrorKind::InvalidBlockType);
+    assert!(chunks.next().is_none());
+}
+
+#[test]
+fn decompress_into() {
+    let mut input = compressed_data().to_vec();
+    input.extend_from_slice(b"trailing data");
+
+    let mut output = vec![0u8; BINARY_WAV_UNCOMPRESSED_SIZE];
+    let consumed = InflaterManaged::decompress_into(&input, &mut output).unwrap();
+    assert_eq!(consumed, BINARY_WAV_COMPRESSED_SIZE);
+    assert_eq!(output, BINARY_WAV_DATA);
+}
+
+#[test]
+fn decompress_into_wrong_size() {
+    let binary_wav_compressed = compressed_data();
+
+    for size in [
+        BINARY_WAV_UNCOMPRESSED_SIZE - 1,
+        BINARY_WAV_UNCOMPRESSED_SIZE + 1,
+        0,
+    ] {
+        let mut output = vec![0u8; size];
+        let error =
+            InflaterManaged::decompress_into(binary_wav_compressed, &mut output).unwrap_err();
+        assert_eq!(
+            error.kind(),
+            deflate64::InflateErrorKind::UncompressedSizeMismatch,
+            "size {size}"
+        );
+    }
+}
+
+#[test]
+fn decompress_into_truncated_input() {
+    let binary_wav_compressed = &ZIP_FILE_DATA[BINARY_WAV_DATA_OFFSET..][..100000];
+
+    let mut output = vec![0u8; BINARY_WAV_UNCOMPRESSED_SIZE];
+    let error = InflaterManaged::decompress_into(binary_wav_compressed, &mut output).unwrap_err();
+    assert_eq!(error.kind(), deflate64::InflateErrorKind::IncompleteStream);
+}
+
+#[test]
+fn inflate_to_writer() {
+    let mut input = compressed_data().to_vec();
+    input.extend_from_slice(b"trailing data");
+
+    let mut inflater = Box::new(InflaterManaged::new());
+    let mut output = vec![];
+    let mut consumed = 0;
+    let mut written = 0;
+    for chunk in input.chunks(100000) {
+        let (chunk_consumed, chunk_written) =
+            inflater.inflate_to_writer(chunk, &mut output).unwrap();
+        consumed += chunk_consumed;
+        written += chunk_written;
+    }
+    assert!(inflater.finished());
+    assert_eq!(consumed, BINARY_WAV_COMPRESSED_SIZE);
+    assert_eq!(written, BINARY_WAV_UNCOMPRESSED_SIZE as u64);
+    assert_eq!(output, BINARY_WAV_DATA);
+}
+
+#[test]
+fn inflate_to_writer_invalid_input() {
+    let mut inflater = Box::new(InflaterManaged::new());
+    let error = inflater
+        .inflate_to_writer(&[0b111], &mut vec![])
+        .unwrap_err();
+    assert_eq!(error.kind(), std::io::ErrorKind::InvalidInput);
+    let error = error.downcast::<deflate64::InflateError>().unwrap();
+    assert_eq!(error.kind(), InflateErrorKind::InvalidBlockType);
+}
+
+#[test]
+fn inflate_with_sink() {
+    let binary_wav_compressed = compressed_data();
+
+    let mut inflater = Box::new(InflaterManaged::new());
+    let mut output = vec![];
+    let result = inflater.inflate_with_sink(binary_wav_compressed, |chunk| {
+        assert!(!chunk.is_empty());
+        output.extend_from_slice(chunk);
+        ControlFlow::<()>::Continue(())
+    });
+    assert_eq!(
+        result.unwrap(),
+        ControlFlow::Continue(BINARY_WAV_COMPRESSED_SIZE)
+    );
+    assert!(inflater.finished());
+    assert_eq!(output, BINARY_WAV_DATA);
+}
+
+#[test]
+fn inflate_with_sink_break() {
+    let binary_wav_compressed = compressed_data();
+
+    let mut inflater = Box::new(InflaterManaged::new());
+    let mut output = vec![];
+    let result = inflater.inflate_with_sink(binary_wav_compressed, |chunk| {
+        output.extend_from_slice(chunk);
+        if output.len() >= 200000 {
+            ControlFlow::Break(output.len())
+        } else {
+            ControlFlow::Continue(())
+        }
+    });
+    let ControlFlow::Break(len) = result.unwrap() else {
+        panic!("sink did not stop decompression");
+    };
+    assert!((200000..BINARY_WAV_UNCOMPRESSED_SIZE).contains(&len));
+    assert!(!inflater.finished());
+    assert_eq!(output, BINARY_WAV_DATA[..len]);
+}
+
+#[test]
+fn inflate_with_sink_invalid_input() {
+    let mut inflater = Box::new(InflaterManaged::new());
+    let error = inflater
+        .inflate_with_sink(&[0b111], |_| ControlFlow::<()>::Continue(()))
+        .unwrap_err();
+    assert_eq!(error.kind(), deflate64::InflateErrorKind::InvalidBlockType);
+}
+
+#[test]
+fn inflate_vectored() {
+    let binary_wav_compressed = compressed_data();
+
+    // a ring buffer whose free space wraps around
+    let mut ring = vec![0u8; 100000];
+    let mut inflater = Box::new(InflaterManaged::new());
+    let mut output = vec![];
+    let mut consumed = 0;
+    let mut position = 70000;
+    loop {
+        let (head, tail) = ring.split_at_mut(position);
+        let mut buffers = [
+            IoSliceMut::new(tail),
+            IoSliceMut::new(&mut [][..]),
+            IoSliceMut::new(head),
+        ];
+        let result = inflater.inflate_vectored(&binary_wav_compressed[consumed..], &mut buffers);
+        assert!(!result.data_error);
+        consumed += result.bytes_consumed;
+
+        let tail_len = ring.len() - position;
+        let written = result.bytes_written;
+        output.extend_from_slice(&ring[position..][..written.min(tail_len)]);
+        output.extend_from_slice(&ring[..written.saturating_sub(tail_len)]);
+        position = (position + written) % ring.len();
+        if written == 0 {
+            break;
+        }
+    }
+    assert!(inflater.finished());
+    assert_eq!(consumed, BINARY_WAV_COMPRESSED_SIZE);
+    assert_eq!(output, BINARY_WAV_DATA);
+}
+
+#[cfg(feature = "bytes")]
+#[test]
+fn inflate_buf() {
+    use bytes::{Buf, Bytes};
+
+    let binary_wav_compressed = compressed_data();
+    let (first, second) = binary_wav_compressed.split_at(1000000);
+    let mut input = Bytes::from_static(first).chain(Bytes::from_static(second));
+
+    let mut inflater = Box::new(InflaterManaged::new());
+    let mut output = vec![];
+    let mut buffer = vec![0u8; 100000];
+    loop {
+        let result = inflater.inflate_buf(&mut input, &mut buffer);
+        assert!(!result.data_error);
+        output.extend_from_slice(&buffer[..result.bytes_written]);
+        if result.bytes_written == 0 {
+            break;
+        }
+    }
+    assert!(inflater.finished());
+    assert!(!input.has_remaining());
+    assert_eq!(output, BINARY_WAV_DATA);
+}
+
+#[test]
+fn clone_and_decode_ahead() {
+    let binary_wav_compressed = compressed_data();
+
+    let mut inflater = Box::new(InflaterManaged::new());
+    let mut output = vec![0u8; 1000];
+    let result = inflater.inflate(binary_wav_compressed, &mut output);
+    let consumed = result.bytes_consumed;
+
+    // the clone decodes the rest of the stream without affecting the original
+    let mut snapshot = inflater.clone();
+    let mut rest = vec![0u8; BINARY_WAV_UNCOMPRESSED_BUFFER_SIZE];
+    let snapshot_result = snapshot.inflate(&binary_wav_compressed[consumed..], &mut rest);
+    assert!(snapshot.finished());
+    assert_eq!(
+        &rest[..snapshot_result.bytes_written],
+        &BINARY_WAV_DATA[1000..]
+    );
+
+    let mut rest = vec![0u8; 1000];
+    inflater.inflate(&binary_wav_compressed[consumed..], &mut rest);
+    assert_eq!(rest, BINARY_WAV_DATA[1000..2000]);
+}
+
+#[test]
+fn inflate_checked() {
+    let binary_wav_compressed = compressed_data();
+    let mut output = vec![0u8; BINARY_WAV_UNCOMPRESSED_BUFFER_SIZE];
+
+    let mut inflater = InflaterManaged::boxed();
+    let summary = inflater
+        .inflate_checked(binary_wav_compressed, &mut output)
+        .unwrap();
+    assert_eq!(summary.bytes_consumed, BINARY_WAV_COMPRESSED_SIZE);
+    assert_eq!(summary.bytes_written, BINARY_WAV_UNCOMPRESSED_SIZE);
+    assert_eq!(&output[..BINARY_WAV_UNCOMPRESSED_SIZE], BINARY_WAV_DATA);
+
+    let mut inflater = InflaterManaged::boxed();
+    let error = inflater.inflate_checked(&[0b111], &mut output).unwrap_err();
+    assert_eq!(error.kind(), InflateErrorKind::InvalidBlockType);
+}
+
+#[test]
+fn inflate_status() {
+    let binary_wav_compressed = compressed_data();
+    let mut output = vec![0u8; BINARY_WAV_UNCOMPRESSED_SIZE];
+
+    // small input and output chunks, driven only by the status
+    let mut inflater = InflaterManaged::boxed();
+    let mut consumed = 0;
+    let mut written = 0;
+    let mut input_end = 0;
+    let mut output_end = 0;
+    loop {
+        let result = inflater.inflate(
+            &binary_wav_compressed[consumed..input_end],
+            &mut output[written..output_end],
+        );
+        consumed += result.bytes_consumed;
+        written += result.bytes_written;
+        match result.status {
+            InflateStatus::NeedsInput => {
+                assert_eq!(consumed, input_end);
+                input_end = min(input_end + 1000, BINARY_WAV_COMPRESSED_SIZE);
+            }
+            InflateStatus::NeedsOutput => {
+                assert_eq!(written, output_end);
+                output_end = min(output_end + 1000, BINARY_WAV_UNCOMPRESSED_SIZE);
+            }
+            InflateStatus::Finished => break,
+            status => panic!("unexpected status {status:?}"),
+        }
+    }
+    assert_eq!(consumed, BINARY_WAV_COMPRESSED_SIZE);
+    assert_eq!(output, BINARY_WAV_DATA);
+
+    let mut inflater = InflaterManaged::boxed();
+    let result = inflater.inflate(&[0b111], &mut output);
+    assert!(result.data_error);
+    assert_eq!(result.status, InflateStatus::DataError);
+}
+
+#[test]
+fn min_input_hint() {
+    // block 0: BFINAL=0, BTYPE=00, LEN=3, data "abc"
+    // block 1: BFINAL=1, BTYPE=00, LEN=1, data "d"
+    let stream = [
+        0b000, 3, 0, !3, !0, b'a', b'b', b'c', 0b001, 1, 0, !1, !0, b'd',
+    ];
+    let mut inflater = InflaterManaged::boxed();
+    let mut output = [0u8; 16];
+    assert_eq!(inflater.min_input_hint(), 1);
+    let result = inflater.inflate(&stream[..5], &mut output);
+    assert_eq!(result.bytes_consumed, 5);
+    assert_eq!(inflater.min_input_hint(), 3);
+    assert_eq!(
+        inflate_by_hint(&mut inflater, &stream[5..], &mut output),
+        (9, 4)
+    );
+    assert!(inflater.finished());
+    assert_eq!(inflater.min_input_hint(), 0);
+
+    // feeding only the hinted input always makes progress
+    let binary_wav_compressed = compressed_data();
+    let mut output = vec![0u8; 100_000];
+    let mut inflater = InflaterManaged::boxed();
+    let (_, written) = inflate_by_hint(&mut inflater, binary_wav_compressed, &mut output);
+    assert_eq!(written, output.len());
+    assert_eq!(output, BINARY_WAV_DATA[..output.len()]);
+}
+
+fn inflate_by_hint(
+    inflater: &mut InflaterManaged,
+    input: &[u8],
+    output: &mut [u8],
+) -> (usize, usize) {
+    let mut consumed = 0;
+    let mut written = 0;
+    while !inflater.finished() && written < output.len() {
+        let hint = inflater.min_input_hint();
+        let result = inflater.inflate(&input[consumed..consumed + hint], &mut output[written..]);
+        assert!(!result.data_error);
+        assert!(result.bytes_consumed > 0 || result.bytes_written > 0);
+        consumed += result.bytes_consumed;
+        written += result.bytes_written;
+    }
+    (consumed, written)
+}
+
+#[test]
+fn scan_uncompressed_size() {
+    let mut reader = BufReader::with_capacity(1000, &ZIP_FILE_DATA[BINARY_WAV_DATA_OFFSET..]);
+    let scan = InflaterManaged::scan_uncompressed_size(&mut reader, true).unwrap();
+    assert_eq!(scan.compressed_size, BINARY_WAV_COMPRESSED_SIZE as u64);
+    assert_eq!(scan.uncompressed_size, BINARY_WAV_UNCOMPRESSED_SIZE as u64);
+    assert_eq!(scan.crc32, Some(0x2c1e_17a6));
+    // the reader is positioned right after the stream
+    let mut rest = vec![];
+    reader.read_to_end(&mut rest).unwrap();
+    assert_eq!(
+        rest,
+        &ZIP_FILE_DATA[BINARY_WAV_DATA_OFFSET + BINARY_WAV_COMPRESSED_SIZE..]
+    );
+
+    let mut reader = compressed_data();
+    let scan = InflaterManaged::scan_uncompressed_size(&mut reader, false).unwrap();
+    assert_eq!(scan.crc32, None);
+
+    let mut truncated = &ZIP_FILE_DATA[BINARY_WAV_DATA_OFFSET..][..BINARY_WAV_COMPRESSED_SIZE - 1];
+    let error = InflaterManaged::scan_uncompressed_size(&mut truncated, false).unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::UnexpectedEof);
+}
+
+#[test]
+fn stream_end() {
+    // the stream ends after 3 bits of its last byte, followed by trailing data
+    let stream = [0b011, 0x00, b'x', b'y'];
+    let mut inflater = InflaterManaged::boxed();
+    assert_eq!(inflater.stream_end_bits(), None);
+    let result = inflater.inflate(&stream, &mut [0u8; 16]);
+    assert_eq!(result.bytes_written, 0);
+    assert_eq!(inflater.stream_end_bits(), Some(10));
+    assert_eq!(inflater.stream_len(), Some(2));
+    assert_eq!(&stream[result.bytes_consumed..], b"xy");
+
+    let mut inflater = InflaterManaged::boxed();
+    let mut output = vec![0u8; BINARY_WAV_UNCOMPRESSED_SIZE];
+    let result = inflater.inflate(&ZIP_FILE_DATA[BINARY_WAV_DATA_OFFSET..], &mut output);
+    assert_eq!(result.bytes_consumed, BINARY_WAV_COMPRESSED_SIZE);
+    assert_eq!(
+        inflater.stream_len(),
+        Some(BINARY_WAV_COMPRESSED_SIZE as u64)
+    );
+    let bits = inflater.stream_end_bits().unwrap();
+    assert_eq!(bits.div_ceil(8), BINARY_WAV_COMPRESSED_SIZE as u64);
+
+    let mut inflater = InflaterManaged::boxed();
+    inflater.inflate(&[0b111], &mut output);
+    assert_eq!(inflater.stream_end_bits(), None);
+}
+
+#[test]
+fn block_introspection() {
+    // block 0: BFINAL=0, BTYPE=00, LEN=3, data "abc"
+    // block 1: BFINAL=1, BTYPE=00, LEN=1, data "d"
+    let stream = [
+        0b000, 3, 0, !3, !0, b'a', b'b', b'c', 0b001, 1, 0, !1, !0, b'd',
+    ];
+    let mut inflater = InflaterManaged::boxed();
+    let mut output = [0u8; 16];
+    assert!(inflater.is_at_block_boundary());
+    assert_eq!(inflater.current_block_type(), None);
+    assert!(!inflater.is_final_block());
+
+    // in the middle of block 0
+    let result = inflater.inflate(&stream[..6], &mut output);
+    assert_eq!(result.bytes_consumed, 6);
+    assert!(!inflater.is_at_block_boundary());
+    assert_eq!(inflater.current_block_type(), Some(BlockType::Uncompressed));
+    assert!(!inflater.is_final_block());
+
+    // between the blocks
+    let (result, _) = inflater.inflate_until_block_boundary(&stream[6..], &mut output);
+    assert_eq!(result.bytes_consumed, 2);
+    assert!(inflater.is_at_block_boundary());
+    assert_eq!(inflater.current_block_type(), None);
+    assert!(!inflater.is_final_block());
+
+    // in the header of block 1
+    inflater.inflate(&stream[8..10], &mut output);
+    assert!(!inflater.is_at_block_boundary());
+    assert_eq!(inflater.current_block_type(), Some(BlockType::Uncompressed));
+    assert!(inflater.is_final_block());
+
+    // at the end of the stream
+    inflater.inflate(&stream[10..], &mut output);
+    assert!(inflater.finished());
+    assert!(inflater.is_at_block_boundary());
+    assert_eq!(inflater.current_block_type(), None);
+    assert!(inflater.is_final_block());
+}
+
+#[test]
+fn stalled() {
+    // BFINAL=1, BTYPE=00, LEN=3, data "abc"
+    let stream = [0b001, 3, 0, !3, !0, b'a', b'b', b'c'];
+    let mut inflater = InflaterManaged::boxed();
+
+    // no output space
+    let result = inflater.inflate(&stream, &mut []);
+    assert!(result.is_stalled());
+    assert_eq!(result.status, InflateStatus::NeedsOutput);
+
+    // no input
+    let mut output = [0u8; 16];
+    let result = inflater.inflate(&[], &mut output);
+    assert!(result.is_stalled());
+    assert_eq!(result.status, InflateStatus::NeedsInput);
+
+    let result = inflater.inflate(&stream[..6], &mut output);
+    assert!(!result.is_stalled());
+    let result = inflater.inflate(&stream[6..], &mut output);
+    assert!(!result.is_stalled());
+    assert_eq!(&output[..result.bytes_written], b"abc");
+
+    // nothing more to do after the end of the stream
+    let result = inflater.inflate(&[], &mut output);
+    assert!(result.is_stalled());
+    assert_eq!(result.status, InflateStatus::Finished);
+}
+
+#[test]
+fn remaining_expected_output() {
+    let binary_wav_compressed = compressed_data();
+    assert_eq!(InflaterManaged::boxed().remaining_expected_output(), None);
+
+    // the stream is 10 bytes shorter than declared
+    let mut inflater = Box::new(InflaterManaged::with_uncompressed_size(
+        BINARY_WAV_UNCOMPRESSED_SIZE + 10,
+    ));
+    let expected = BINARY_WAV_UNCOMPRESSED_SIZE as u64 + 10;
+    assert_eq!(inflater.remaining_expected_output(), Some(expected));
+
+    let mut output = vec![0u8; 100_000];
+    let result = inflater.inflate(binary_wav_compressed, &mut output);
+    assert_eq!(result.bytes_written, 100_000);
+    assert_eq!(
+        inflater.remaining_expected_output(),
+        Some(expected - 100_000)
+    );
+
+    let mut output = vec![0u8; BINARY_WAV_UNCOMPRESSED_BUFFER_SIZE];
+    inflater.inflate(&binary_wav_compressed[result.bytes_consumed..], &mut output);
+    assert!(inflater.finished());
+    assert_eq!(inflater.remaining_expected_output(), Some(10));
+}
+
+#[test]
+fn memory_usage() {
+    let inflater = InflaterManaged::boxed();
+    let usage = inflater.memory_usage();
+    assert_eq!(usage.total, std::mem::size_of::<InflaterManaged>());
+    assert!(usage.window >= 128 * 1024);
+    assert!(usage.huffman_trees > 0);
+    assert!(usage.window + usage.huffman_trees < usage.total);
+    assert_eq!(usage.heap, 0);
+}
+
+// 600 bytes of `a`, `b`, `c`, `d` and `e` compressed by zlib into a dynamic block, whose
+// literal codes are short enough that two of them fit in the primary table
+static SHORT_LITERAL_CODES_COMPRESSED: &[u8] = &[
+    0x45, 0x51, 0x01, 0x12, 0xc3, 0x30, 0x08, 0x7a, 0x2b, 0x22, 0xff, 0xff, 0xc2, 0x00, 0xd3, 0xdb,
+    0x7a, 0xb3, 0x8d, 0x22, 0x12, 0x24, 0x30, 0x22, 0x67, 0xc0, 0x59, 0xf9, 0x40, 0x42, 0x23, 0xb0,
+    0xcf, 0xf4, 0x91, 0x16, 0x06, 0xec, 0x24, 0x22, 0x58, 0x03, 0xb1, 0x62, 0x9a, 0x9c, 0x1b, 0xe6,
+    0xe8, 0x64, 0x9a, 0x69, 0x5c, 0xca, 0x4c, 0x32, 0x71, 0x42, 0x81, 0xfe, 0x93, 0xe8, 0x77, 0x7e,
+    0xa1, 0x73, 0x57, 0xa9, 0xcd, 0x39, 0x9a, 0x2b, 0xcf, 0x8d, 0x45, 0x68, 0xf3, 0xa9, 0xf6, 0xcc,
+    0x1e, 0xc5, 0x1e, 0x57, 0x40, 0xfb, 0x28, 0x27, 0xdd, 0xe9, 0x2d, 0x38, 0x27, 0x37, 0xac, 0x42,
+    0xcb, 0xd2, 0x0f, 0xb7, 0xa3, 0x95, 0x6b, 0xb5, 0xc1, 0x41, 0x19, 0x69, 0x12, 0xf5, 0x0a, 0xc6,
+    0xd1, 0xea, 0xab, 0x16, 0xad, 0x1e, 0xd2, 0x2f, 0x79, 0xe6, 0x9e, 0xda, 0xbb, 0x4a, 0x5d, 0xca,
+    0xb4, 0xc3, 0x26, 0x4e, 0x25, 0xe2, 0xd3, 0xd8, 0xb4, 0x87, 0x71, 0x17, 0xaf, 0x69, 0x62, 0xcc,
+    0x8d, 0x77, 0x90, 0xaa, 0xe9, 0xe1, 0x7b, 0xb9, 0xff, 0xcb, 0x03, 0x74, 0xb4, 0x88, 0x27, 0xa3,
+    0xb3, 0xbb, 0x8a, 0xcf, 0x3a, 0xbe, 0xf9, 0x98, 0x6e, 0xea, 0x69, 0xad, 0xda, 0xb7, 0xb8, 0x4e,
+    0xe1, 0x49, 0x2e, 0x05, 0xce, 0xa1, 0x27, 0x17, 0x4f, 0x65, 0xb7, 0x5b, 0x57, 0xc5, 0xff, 0x65,
+    0xf0, 0x36, 0xa6, 0x7a, 0xe5, 0x8a, 0x6b, 0xb1, 0x13, 0x1f, 0x4d, 0xec, 0x7d, 0xeb, 0xc4, 0x5b,
+    0x2e, 0x3f, 0x67, 0xa2, 0x7e, 0xc9, 0x1f,
+];
+
+#[test]
+fn short_literal_codes() {
+    let mut x = 12345u32;
+    let expected: Vec<u8> = (0..600)
+        .map(|_| {
+            x = x.wrapping_mul(1103515245).wrapping_add(12345) & 0x7fffffff;
+            b"aaaaaaaabbbbccde"[(x >> 16) as usize % 16]
+        })
+        .collect();
+
+    let mut inflater = InflaterManaged::boxed();
+    let mut output = vec![0u8; 1000];
+    let result = inflater.inflate(SHORT_LITERAL_CODES_COMPRESSED, &mut output);
+    assert!(inflater.finished());
+    assert_eq!(&output[..result.bytes_written], &expected[..]);
+
+    // byte by byte, the symbols are decoded one at a time
+    let mut inflater = InflaterManaged::boxed();
+    let mut written = 0;
+    for byte in SHORT_LITERAL_CODES_COMPRESSED.chunks(1) {
+        written += inflater.inflate(byte, &mut output[written..]).bytes_written;
+    }
+    written += inflater.inflate(&[], &mut output[written..]).bytes_written;
+    assert!(inflater.finished());
+    assert_eq!(&output[..written], &expected[..]);
+}
@@ -0,0 +1,81 @@
+//! Checks that the inflater does not allocate after it was created.
+
+use deflate64::InflaterManaged;
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+mod common;
+
+use common::{compressed_data, BINARY_WAV_DATA};
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+struct CountingAllocator;
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.with(|count| count.set(count.get() + 1));
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+fn count_allocations(f: impl FnOnce()) -> usize {
+    let before = ALLOCATIONS.with(Cell::get);
+    f();
+    ALLOCATIONS.with(Cell::get) - before
+}
+
+#[test]
+fn inflate_does_not_allocate() {
+    let mut inflater = Box::new(InflaterManaged::new());
+    let mut output = vec![0u8; BINARY_WAV_DATA.len()];
+    let mut chunk = [0u8; 4096];
+    let mut written = 0;
+
+    let allocations = count_allocations(|| {
+        for input in compressed_data().chunks(1000) {
+            let mut consumed = 0;
+            loop {
+                let result = inflater.inflate(&input[consumed..], &mut chunk);
+                assert!(!result.data_error);
+                consumed += result.bytes_consumed;
+                output[written..][..result.bytes_written]
+                    .copy_from_slice(&chunk[..result.bytes_written]);
+                written += result.bytes_written;
+                if result.bytes_written < chunk.len() {
+                    break;
+                }
+            }
+        }
+        inflater.finish().unwrap();
+    });
+    assert_eq!(allocations, 0);
+    assert_eq!(output, BINARY_WAV_DATA);
+}
+
+#[cfg(feature = "checkpoint")]
+#[test]
+fn checkpoint_does_not_allocate() {
+    let mut inflater = Box::new(InflaterManaged::with_checkpoints());
+    let mut restored = Box::new(InflaterManaged::with_checkpoints());
+    let mut checkpoint = vec![0u8; deflate64::MAX_CHECKPOINT_SIZE];
+    let mut output = vec![0u8; 100000];
+
+    let allocations = count_allocations(|| {
+        inflater.inflate(&compressed_data()[..50000], &mut output);
+        let (len, _) = inflater.checkpoint_into(&mut checkpoint).unwrap();
+        restored
+            .restore_from_checkpoint(&checkpoint[..len])
+            .unwrap();
+    });
+    assert_eq!(allocations, 0);
+}
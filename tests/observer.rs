@@ -0,0 +1,208 @@
+use deflate64::{
+    BlockInfo, BlockRecorder, BlockType, InflateError, InflateErrorKind, InflateObserver,
+    InflaterManaged, InflaterOptions,
+};
+
+mod common;
+
+use common::{compressed_data, BINARY_WAV_COMPRESSED_SIZE, BINARY_WAV_UNCOMPRESSED_SIZE};
+
+#[derive(Debug, Eq, PartialEq)]
+enum Event {
+    BlockStart(BlockType, bool, u64),
+    BlockEnd(u64, u64),
+    Error(InflateErrorKind),
+    Progress(u64),
+}
+
+#[derive(Default)]
+struct Recorder(Vec<Event>);
+
+impl InflateObserver for Recorder {
+    fn on_block_start(&mut self, block_type: BlockType, bfinal: bool, input_bit_offset: u64) {
+        self.0
+            .push(Event::BlockStart(block_type, bfinal, input_bit_offset));
+    }
+
+    fn on_block_end(&mut self, input_bit_offset: u64, output_offset: u64) {
+        self.0
+            .push(Event::BlockEnd(input_bit_offset, output_offset));
+    }
+
+    fn on_error(&mut self, error: &InflateError) {
+        self.0.push(Event::Error(error.kind()));
+    }
+
+    fn on_progress(&mut self, _input_bit_offset: u64, output_offset: u64) {
+        self.0.push(Event::Progress(output_offset));
+    }
+}
+
+#[test]
+fn stored_blocks() {
+    // block 0: BFINAL=0, BTYPE=00, LEN=3, data "abc"
+    // block 1: BFINAL=1, BTYPE=00, LEN=1, data "d"
+    let stream = [
+        0b000, 3, 0, !3, !0, b'a', b'b', b'c', 0b001, 1, 0, !1, !0, b'd',
+    ];
+    let mut inflater = InflaterManaged::boxed();
+    let mut recorder = Recorder::default();
+    let mut output = [0u8; 16];
+    let result = inflater.inflate_observed(&stream, &mut output, &mut recorder);
+    assert_eq!(result.bytes_written, 4);
+    assert_eq!(
+        recorder.0,
+        [
+            Event::BlockStart(BlockType::Uncompressed, false, 0),
+            Event::BlockEnd(64, 3),
+            Event::BlockStart(BlockType::Uncompressed, true, 64),
+            Event::BlockEnd(112, 4),
+        ]
+    );
+}
+
+#[test]
+fn binary_wav_blocks() {
+    let binary_wav_compressed = compressed_data();
+    let mut output = vec![0u8; 100_000];
+
+    let mut inflater = InflaterManaged::boxed();
+    let mut recorder = Recorder::default();
+    let mut consumed = 0;
+    let mut written = 0;
+    while !inflater.finished() {
+        let result = inflater.inflate_observed(
+            &binary_wav_compressed[consumed..],
+            &mut output,
+            &mut recorder,
+        );
+        assert!(!result.data_error);
+        consumed += result.bytes_consumed;
+        written += result.bytes_written;
+    }
+    assert_eq!(written, BINARY_WAV_UNCOMPRESSED_SIZE);
+
+    // blocks start where the previous one ended, and only the last one is final
+    assert!(recorder.0.len() > 2);
+    let block_count = recorder.0.len() / 2;
+    let mut block_start = 0;
+    for (index, pair) in recorder.0.chunks(2).enumerate() {
+        let [Event::BlockStart(_, bfinal, start), Event::BlockEnd(end, _)] = pair else {
+            panic!("unexpected events {pair:?}");
+        };
+        assert_eq!(*start, block_start);
+        assert!(end > start);
+        assert_eq!(*bfinal, index == block_count - 1);
+        block_start = *end;
+    }
+    assert_eq!(block_start.div_ceil(8), BINARY_WAV_COMPRESSED_SIZE as u64);
+    assert_eq!(
+        recorder.0.last(),
+        Some(&Event::BlockEnd(
+            block_start,
+            BINARY_WAV_UNCOMPRESSED_SIZE as u64
+        ))
+    );
+}
+
+#[test]
+fn error() {
+    let mut inflater = InflaterManaged::boxed();
+    let mut recorder = Recorder::default();
+    let mut output = [0u8; 16];
+    inflater.inflate_observed(&[0b111], &mut output, &mut recorder);
+    inflater.inflate_observed(&[0b111], &mut output, &mut recorder);
+    assert_eq!(
+        recorder.0,
+        [Event::Error(InflateErrorKind::InvalidBlockType)]
+    );
+}
+
+#[test]
+fn enumerate_stored_blocks() {
+    let stream = [
+        0b000, 3, 0, !3, !0, b'a', b'b', b'c', 0b001, 1, 0, !1, !0, b'd',
+    ];
+    assert_eq!(
+        InflaterManaged::enumerate_blocks(&stream).unwrap(),
+        [
+            BlockInfo {
+                block_type: BlockType::Uncompressed,
+                is_final: false,
+                input_bits: 0..64,
+                output_bytes: 0..3,
+            },
+            BlockInfo {
+                block_type: BlockType::Uncompressed,
+                is_final: true,
+                input_bits: 64..112,
+                output_bytes: 3..4,
+            },
+        ]
+    );
+}
+
+#[test]
+fn enumerate_binary_wav_blocks() {
+    let binary_wav_compressed = compressed_data();
+    let blocks = InflaterManaged::enumerate_blocks(binary_wav_compressed).unwrap();
+    let measurements = InflaterManaged::measure(binary_wav_compressed).unwrap();
+    assert_eq!(blocks.len() as u64, measurements.block_count);
+    assert!(blocks
+        .windows(2)
+        .all(|w| w[0].input_bits.end == w[1].input_bits.start
+            && w[0].output_bytes.end == w[1].output_bytes.start));
+    let last = blocks.last().unwrap();
+    assert!(last.is_final);
+    assert_eq!(last.output_bytes.end, BINARY_WAV_UNCOMPRESSED_SIZE as u64);
+    assert_eq!(
+        last.input_bits.end.div_ceil(8),
+        BINARY_WAV_COMPRESSED_SIZE as u64
+    );
+
+    // a truncated stream is an error, but the recorder keeps the finished blocks
+    let truncated = &binary_wav_compressed[..BINARY_WAV_COMPRESSED_SIZE / 2];
+    let error = InflaterManaged::enumerate_blocks(truncated).unwrap_err();
+    assert_eq!(error.kind(), InflateErrorKind::IncompleteStream);
+
+    let mut inflater = InflaterManaged::boxed();
+    let mut recorder = BlockRecorder::new();
+    let mut output = vec![0u8; BINARY_WAV_UNCOMPRESSED_SIZE];
+    inflater.inflate_observed(truncated, &mut output, &mut recorder);
+    assert!(!recorder.blocks().is_empty());
+    assert_eq!(recorder.blocks(), &blocks[..recorder.blocks().len()]);
+}
+
+#[test]
+fn progress() {
+    let binary_wav_compressed = compressed_data();
+    // progress is also reported during a single call which returns all output
+    for output_len in [100_000, BINARY_WAV_UNCOMPRESSED_SIZE] {
+        let mut output = vec![0u8; output_len];
+        let mut inflater = InflaterManaged::boxed_with_options(
+            InflaterOptions::new().progress_interval(1_000_000),
+        );
+        let mut recorder = Recorder::default();
+        let mut consumed = 0;
+        while !inflater.finished() {
+            let result = inflater.inflate_observed(
+                &binary_wav_compressed[consumed..],
+                &mut output,
+                &mut recorder,
+            );
+            assert!(!result.data_error);
+            consumed += result.bytes_consumed;
+        }
+        let progress: Vec<_> = recorder
+            .0
+            .iter()
+            .filter_map(|event| match event {
+                Event::Progress(output_offset) => Some(*output_offset),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(progress.len(), 2, "{progress:?}");
+        assert_eq!(progress[0] / 1_000_000, 1);
+        assert_eq!(progress[1] / 1_000_000, 2);
+    }
+}
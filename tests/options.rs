@@ -0,0 +1,149 @@
+use deflate64::{EntryMetadata, InflateErrorKind, InflaterManaged, InflaterOptions};
+
+mod common;
+
+use common::{bomb, compressed_data, BINARY_WAV_COMPRESSED_SIZE, BINARY_WAV_DATA};
+
+const BINARY_WAV_CRC32: u32 = 0x2c1e_17a6;
+
+fn inflate(options: InflaterOptions) -> (Box<InflaterManaged>, Vec<u8>) {
+    let mut inflater = Box::new(InflaterManaged::with_options(options));
+    let mut output = vec![0u8; BINARY_WAV_DATA.len() + 100];
+    let (_, written) = inflater
+        .inflate_all(compressed_data(), &mut output)
+        .unwrap_or((0, 0));
+    output.truncate(written);
+    (inflater, output)
+}
+
+#[test]
+fn default_options() {
+    let (inflater, output) = inflate(InflaterOptions::default());
+    assert_eq!(output, BINARY_WAV_DATA);
+    assert_eq!(inflater.output_crc32(), None);
+    inflater.finish().unwrap();
+}
+
+#[test]
+fn uncompressed_size_truncates() {
+    let (inflater, output) = inflate(InflaterOptions::new().uncompressed_size(1000));
+    assert_eq!(output, BINARY_WAV_DATA[..1000]);
+    inflater.finish().unwrap();
+}
+
+#[test]
+fn track_crc32() {
+    let (inflater, output) = inflate(InflaterOptions::new().track_crc32(true));
+    assert_eq!(output, BINARY_WAV_DATA);
+    assert_eq!(inflater.output_crc32(), Some(BINARY_WAV_CRC32));
+}
+
+#[test]
+fn max_output() {
+    let options = InflaterOptions::new().max_output(BINARY_WAV_DATA.len() as u64);
+    let (inflater, output) = inflate(options);
+    assert_eq!(output, BINARY_WAV_DATA);
+    inflater.finish().unwrap();
+
+    let mut inflater = Box::new(InflaterManaged::with_options(
+        InflaterOptions::new().max_output(1000),
+    ));
+    let mut output = vec![0u8; 2000];
+    let error = inflater
+        .inflate_all(compressed_data(), &mut output)
+        .unwrap_err();
+    assert_eq!(error.kind(), InflateErrorKind::OutputLimitExceeded);
+    assert_eq!(output[..1000], BINARY_WAV_DATA[..1000]);
+}
+
+#[test]
+fn max_output_below_entry_size() {
+    let options = InflaterOptions::new()
+        .entry_metadata(EntryMetadata {
+            compressed_size: BINARY_WAV_COMPRESSED_SIZE as u64,
+            uncompressed_size: BINARY_WAV_DATA.len() as u64,
+            crc32: BINARY_WAV_CRC32,
+        })
+        .max_output(1000);
+    let (inflater, _) = inflate(options);
+    let error = inflater.error().unwrap();
+    assert_eq!(error.kind(), InflateErrorKind::OutputLimitExceeded);
+}
+
+#[test]
+fn reset_keeps_options() {
+    let (mut inflater, _) = inflate(InflaterOptions::new().max_output(1000));
+    inflater.reset();
+    let mut output = vec![0u8; 2000];
+    let error = inflater
+        .inflate_all(compressed_data(), &mut output)
+        .unwrap_err();
+    assert_eq!(error.kind(), InflateErrorKind::OutputLimitExceeded);
+}
+
+#[test]
+fn strict() {
+    let (inflater, output) = inflate(InflaterOptions::new().strict(true));
+    assert_eq!(output, BINARY_WAV_DATA);
+    inflater.finish().unwrap();
+}
+
+#[test]
+fn max_expansion_ratio() {
+    let (inflater, output) = inflate(InflaterOptions::new().max_expansion_ratio(2));
+    assert_eq!(output, BINARY_WAV_DATA);
+    inflater.finish().unwrap();
+
+    let stream = bomb(100);
+    let mut output = vec![0u8; 100 * 65536 + 1];
+    let mut inflater = InflaterManaged::boxed();
+    let (_, written) = inflater.inflate_all(&stream, &mut output).unwrap();
+    assert_eq!(written, output.len());
+
+    let mut inflater =
+        InflaterManaged::boxed_with_options(InflaterOptions::new().max_expansion_ratio(1000));
+    let result = inflater.inflate(&stream, &mut output);
+    assert!(result.data_error);
+    assert_eq!(
+        result.bytes_written as u64,
+        InflaterOptions::RATIO_GRACE_OUTPUT
+    );
+    let error = inflater.error().unwrap();
+    assert_eq!(error.kind(), InflateErrorKind::ExpansionRatioExceeded);
+}
+
+#[test]
+fn exact_uncompressed_size() {
+    let size = BINARY_WAV_DATA.len();
+    let (inflater, output) = inflate(
+        InflaterOptions::new()
+            .uncompressed_size(size)
+            .exact_uncompressed_size(true),
+    );
+    assert_eq!(output, BINARY_WAV_DATA);
+    inflater.finish().unwrap();
+
+    // more output than declared
+    let (inflater, output) = inflate(
+        InflaterOptions::new()
+            .uncompressed_size(1000)
+            .exact_uncompressed_size(true),
+    );
+    assert!(output.is_empty());
+    let error = inflater.error().unwrap();
+    assert_eq!(error.kind(), InflateErrorKind::UncompressedSizeMismatch);
+
+    // less output than declared
+    let (inflater, output) = inflate(
+        InflaterOptions::new()
+            .uncompressed_size(size + 1)
+            .exact_uncompressed_size(true),
+    );
+    assert_eq!(output, BINARY_WAV_DATA);
+    let error = inflater.finish().unwrap_err();
+    assert_eq!(error.kind(), InflateErrorKind::UncompressedSizeMismatch);
+
+    // without a declared size, nothing is verified
+    let (inflater, _) = inflate(InflaterOptions::new().exact_uncompressed_size(true));
+    inflater.finish().unwrap();
+}
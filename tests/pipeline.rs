@@ -0,0 +1,48 @@
+use deflate64::PipelinedDecoder;
+use std::io::{self, Read};
+
+mod common;
+
+use common::{compressed_data, BINARY_WAV_DATA};
+
+#[test]
+fn decode_with_read() {
+    let mut decoder = PipelinedDecoder::new(compressed_data());
+
+    let mut uncompressed_data = vec![];
+    decoder.read_to_end(&mut uncompressed_data).unwrap();
+
+    assert_eq!(&uncompressed_data[..], BINARY_WAV_DATA);
+}
+
+#[test]
+fn decode_chunks() {
+    let decoder = PipelinedDecoder::with_chunk_size(compressed_data(), 10_000, 1);
+
+    let chunks = decoder.collect::<io::Result<Vec<_>>>().unwrap();
+    let (last, rest) = chunks.split_last().unwrap();
+    assert!(rest.iter().all(|chunk| chunk.len() == 10_000));
+    assert!(!last.is_empty() && last.len() <= 10_000);
+
+    assert_eq!(&chunks.concat()[..], BINARY_WAV_DATA);
+}
+
+#[test]
+fn decode_error() {
+    // BFINAL=1, BTYPE=11
+    let mut decoder = PipelinedDecoder::new(&[0b111u8][..]);
+
+    let mut uncompressed_data = vec![];
+    let error = decoder.read_to_end(&mut uncompressed_data).unwrap_err();
+    assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+    assert_eq!(decoder.read(&mut [0u8; 16]).unwrap(), 0);
+}
+
+#[test]
+fn drop_before_end() {
+    let mut decoder = PipelinedDecoder::with_chunk_size(compressed_data(), 1000, 1);
+    let mut buf = [0u8; 100];
+    decoder.read_exact(&mut buf).unwrap();
+    assert_eq!(&buf[..], &BINARY_WAV_DATA[..100]);
+    drop(decoder);
+}
@@ -0,0 +1,73 @@
+#![cfg(feature = "pool")]
+
+use deflate64::{InflaterOptions, InflaterPool};
+use std::thread;
+
+mod common;
+
+use common::{compressed_data, BINARY_WAV_DATA};
+
+fn decompress(pool: &InflaterPool) -> Vec<u8> {
+    let mut inflater = pool.get();
+    let mut output = vec![0u8; BINARY_WAV_DATA.len()];
+    let (_, written) = inflater
+        .inflate_all(compressed_data(), &mut output)
+        .unwrap();
+    inflater.finish().unwrap();
+    output.truncate(written);
+    output
+}
+
+#[test]
+fn reuse() {
+    let pool = InflaterPool::new();
+    assert_eq!(pool.idle_count(), 0);
+
+    let mut inflater = pool.get();
+    assert!(inflater.inflate(&[0b111], &mut [0u8; 16]).data_error);
+    drop(inflater);
+    assert_eq!(pool.idle_count(), 1);
+
+    // the errored inflater is reset before it is used again
+    assert_eq!(decompress(&pool), BINARY_WAV_DATA);
+    assert_eq!(pool.idle_count(), 1);
+}
+
+#[test]
+fn options() {
+    let pool = InflaterPool::new();
+    drop(pool.get());
+    let mut inflater = pool.get_with_options(InflaterOptions::new().uncompressed_size(1000));
+    let mut output = vec![0u8; 2000];
+    let (_, written) = inflater
+        .inflate_all(compressed_data(), &mut output)
+        .unwrap();
+    assert_eq!(output[..written], BINARY_WAV_DATA[..1000]);
+}
+
+#[test]
+fn max_idle() {
+    let pool = InflaterPool::with_max_idle(2);
+    let inflaters = [pool.get(), pool.get(), pool.get()];
+    drop(inflaters);
+    assert_eq!(pool.idle_count(), 2);
+}
+
+#[test]
+fn concurrent() {
+    let pool = InflaterPool::new();
+    let threads = (0..4)
+        .map(|_| {
+            let pool = pool.clone();
+            thread::spawn(move || {
+                for _ in 0..2 {
+                    assert_eq!(decompress(&pool), BINARY_WAV_DATA);
+                }
+            })
+        })
+        .collect::<Vec<_>>();
+    for thread in threads {
+        thread.join().unwrap();
+    }
+    assert!((1..=4).contains(&pool.idle_count()));
+}
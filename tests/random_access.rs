@@ -0,0 +1,165 @@
+#![cfg(feature = "checkpoint")]
+
+use deflate64::{Deflate64Index, RandomAccessInflater};
+use std::io::{self, Cursor, Read, Seek, SeekFrom};
+use std::sync::Arc;
+use std::thread;
+
+mod common;
+
+use common::{
+    compressed_data, BINARY_WAV_COMPRESSED_SIZE, BINARY_WAV_DATA, BINARY_WAV_DATA_OFFSET,
+    ZIP_FILE_DATA,
+};
+
+const INTERVAL: u64 = 256 * 1024;
+
+/// The zip file positioned at the start of the deflate64 stream
+fn zip_file() -> Cursor<&'static [u8]> {
+    let mut file = Cursor::new(ZIP_FILE_DATA);
+    file.seek(SeekFrom::Start(BINARY_WAV_DATA_OFFSET as u64))
+        .unwrap();
+    file
+}
+
+fn index() -> Deflate64Index {
+    Deflate64Index::build(compressed_data(), INTERVAL).unwrap()
+}
+
+fn read_at(reader: &mut impl Read, len: usize) -> Vec<u8> {
+    let mut data = vec![0u8; len];
+    reader.read_exact(&mut data).unwrap();
+    data
+}
+
+#[test]
+fn seek_to_output_offsets() {
+    let mut inflater = RandomAccessInflater::new(index(), zip_file()).unwrap();
+    for offset in [
+        1000000,
+        2000000,
+        10,
+        1500000,
+        INTERVAL,
+        INTERVAL - 1,
+        2700000,
+        0,
+        999999,
+    ] {
+        inflater.seek(offset).unwrap();
+        assert_eq!(inflater.position(), offset);
+        let offset = offset as usize;
+        assert_eq!(
+            read_at(&mut inflater, 1000),
+            BINARY_WAV_DATA[offset..][..1000]
+        );
+        assert_eq!(inflater.position(), offset as u64 + 1000);
+    }
+}
+
+#[test]
+fn read_to_end_after_seek() {
+    let mut inflater = RandomAccessInflater::new(index(), zip_file()).unwrap();
+    let end = BINARY_WAV_DATA.len() as u64;
+    inflater.seek(end - 100000).unwrap();
+    let mut data = vec![];
+    inflater.read_to_end(&mut data).unwrap();
+    assert_eq!(data, BINARY_WAV_DATA[BINARY_WAV_DATA.len() - 100000..]);
+
+    inflater.seek(end).unwrap();
+    assert_eq!(inflater.read(&mut [0u8; 10]).unwrap(), 0);
+    let error = inflater.seek(end + 1).unwrap_err();
+    assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+}
+
+#[test]
+fn truncated_stream() {
+    let data = &compressed_data()[..BINARY_WAV_COMPRESSED_SIZE / 2];
+    let mut inflater = RandomAccessInflater::new(index(), Cursor::new(data)).unwrap();
+    let error = inflater.seek(2000000).unwrap_err();
+    assert_eq!(error.kind(), io::ErrorKind::UnexpectedEof);
+}
+
+#[test]
+fn read_ranges() {
+    let mut inflater = RandomAccessInflater::new(index(), zip_file()).unwrap();
+    let end = BINARY_WAV_DATA.len() as u64;
+    for range in [
+        1000000..1100000,
+        10..20,
+        INTERVAL - 100..INTERVAL + 100,
+        0..2 * INTERVAL + 1,
+        end - 1000..end,
+        500..500,
+        5..6,
+    ] {
+        let data = inflater.read_range(range.clone()).unwrap();
+        assert_eq!(
+            data,
+            BINARY_WAV_DATA[range.start as usize..range.end as usize]
+        );
+        assert_eq!(inflater.position(), range.end);
+
+        let mut written = Vec::new();
+        let len = inflater
+            .read_range_to_writer(range.clone(), &mut written)
+            .unwrap();
+        assert_eq!(len, range.end - range.start);
+        assert_eq!(written, data);
+    }
+
+    #[allow(clippy::reversed_empty_ranges)]
+    for range in [end - 10..end + 1, 20..10] {
+        let error = inflater.read_range(range).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+    }
+}
+
+#[test]
+fn shared_index() {
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<Deflate64Index>();
+    assert_send_sync::<RandomAccessInflater<Cursor<&[u8]>>>();
+
+    let index = Arc::new(index());
+    let chunk = BINARY_WAV_DATA.len() as u64 / 4;
+    let threads: Vec<_> = (0..4)
+        .map(|i| {
+            let index = Arc::clone(&index);
+            thread::spawn(move || {
+                let mut inflater = RandomAccessInflater::new(index, zip_file()).unwrap();
+                let range = i * chunk..(i + 1) * chunk;
+                let data = inflater.read_range(range.clone()).unwrap();
+                assert_eq!(
+                    data,
+                    BINARY_WAV_DATA[range.start as usize..range.end as usize]
+                );
+            })
+        })
+        .collect();
+    for thread in threads {
+        thread.join().unwrap();
+    }
+    assert_eq!(Arc::strong_count(&index), 1);
+}
+
+#[test]
+fn spawn_inflater() {
+    let index = index();
+    for offset in [0, INTERVAL - 1, INTERVAL, 1000000] {
+        let (mut inflater, positions) = index.spawn_inflater(offset).unwrap();
+        let expected = index
+            .entry_before(offset)
+            .map_or(0, |entry| entry.output_offset);
+        assert_eq!(positions.output_bytes_already_returned, expected);
+
+        let input = &compressed_data()[positions.input_bytes_to_skip as usize..];
+        let mut output = vec![0u8; 1000];
+        let result = inflater.inflate(input, &mut output);
+        let returned = positions.output_bytes_already_returned as usize;
+        assert_eq!(
+            output[..result.bytes_written],
+            BINARY_WAV_DATA[returned..][..result.bytes_written]
+        );
+    }
+}
@@ -0,0 +1,115 @@
+use deflate64::{DataGap, EntryMetadata, InflateErrorKind, InflaterManaged};
+
+mod common;
+
+use common::{compressed_data, BINARY_WAV_COMPRESSED_SIZE, BINARY_WAV_UNCOMPRESSED_SIZE};
+
+#[test]
+fn no_error() {
+    let mut inflater = InflaterManaged::boxed();
+    assert_eq!(inflater.resync(&[0b001, 1, 0, !1, !0, b'x']), None);
+}
+
+#[test]
+fn stored_blocks() {
+    // block 0: BFINAL=0, BTYPE=00, LEN=3, "abc"
+    // garbage starting with BTYPE=11
+    // block 1: BFINAL=1, BTYPE=00, LEN=3, "xyz"
+    let stream = [
+        0b000, 3, 0, !3, !0, b'a', b'b', b'c', 0xff, 0xff, 0b001, 3, 0, !3, !0, b'x', b'y', b'z',
+    ];
+    let mut inflater = InflaterManaged::boxed();
+    let mut output = [0u8; 16];
+    let result = inflater.inflate(&stream, &mut output);
+    assert_eq!(&output[..result.bytes_written], b"abc");
+    let error = inflater.error().unwrap();
+    assert_eq!(error.kind(), InflateErrorKind::InvalidBlockType);
+    assert_eq!(error.input_bit_offset(), 67);
+
+    let consumed = result.bytes_consumed;
+    let gap = inflater.resync(&stream[consumed..]).unwrap();
+    assert_eq!(consumed + gap.bytes_consumed, 11);
+    assert_eq!(gap.input_bits, 67..80);
+    assert_eq!(gap.output_offset, 3);
+    assert!(inflater.error().is_none());
+
+    let result = inflater.inflate(&stream[consumed + gap.bytes_consumed..], &mut output);
+    assert!(!result.data_error);
+    assert_eq!(&output[..result.bytes_written], b"xyz");
+    assert!(inflater.finished());
+}
+
+#[test]
+fn stays_within_entry() {
+    // block 0: BFINAL=0, BTYPE=00, LEN=3, "abc"
+    // garbage starting with BTYPE=11, the end of the entry
+    // block 1 of the next entry: BFINAL=1, BTYPE=00, LEN=3, "xyz"
+    let stream = [
+        0b000, 3, 0, !3, !0, b'a', b'b', b'c', 0xff, 0xff, 0b001, 3, 0, !3, !0, b'x', b'y', b'z',
+    ];
+    let mut inflater = InflaterManaged::with_entry_metadata(EntryMetadata {
+        crc32: 0,
+        compressed_size: 10,
+        uncompressed_size: 3,
+    });
+    let mut output = [0u8; 16];
+    let result = inflater.inflate(&stream, &mut output);
+    assert!(result.data_error);
+
+    let consumed = result.bytes_consumed;
+    assert_eq!(inflater.resync(&stream[consumed..]), None);
+    assert!(inflater.errored());
+    assert_eq!(inflater.min_input_hint(), 0);
+    let result = inflater.inflate(&stream[consumed..], &mut output);
+    assert_eq!(result.bytes_consumed, 0);
+}
+
+#[test]
+fn no_block_found() {
+    let stream = [0b111, 0xff, 0xff];
+    let mut inflater = InflaterManaged::boxed();
+    let result = inflater.inflate(&stream, &mut [0u8; 16]);
+    assert!(result.data_error);
+    assert_eq!(inflater.resync(&stream[result.bytes_consumed..]), None);
+    assert!(inflater.errored());
+}
+
+#[test]
+fn damaged_binary_wav() {
+    let mut compressed = compressed_data().to_vec();
+    let block_starts: Vec<u64> = InflaterManaged::enumerate_blocks(&compressed)
+        .unwrap()
+        .iter()
+        .map(|block| block.input_bits.start)
+        .collect();
+    // make the header of a block in the middle invalid with BTYPE=11
+    let damaged = block_starts[block_starts.len() / 2];
+    for bit in damaged + 1..damaged + 3 {
+        compressed[(bit / 8) as usize] |= 1 << (bit % 8);
+    }
+
+    let mut inflater = InflaterManaged::boxed();
+    let mut output = vec![0u8; BINARY_WAV_UNCOMPRESSED_SIZE * 2];
+    let mut consumed = 0;
+    let mut written = 0;
+    let mut gaps: Vec<DataGap> = vec![];
+    while !inflater.finished() || inflater.errored() {
+        let result = inflater.inflate(&compressed[consumed..], &mut output[written..]);
+        consumed += result.bytes_consumed;
+        written += result.bytes_written;
+        if result.data_error {
+            let gap = inflater
+                .resync(&compressed[consumed..])
+                .expect("no block found");
+            consumed += gap.bytes_consumed;
+            gaps.push(gap);
+        }
+    }
+
+    assert_eq!(consumed, BINARY_WAV_COMPRESSED_SIZE);
+    assert!(written < BINARY_WAV_UNCOMPRESSED_SIZE);
+    // decoding continued with the block after the damaged one
+    let next = block_starts[block_starts.len() / 2 + 1];
+    assert_eq!(gaps.len(), 1);
+    assert_eq!(gaps[0].input_bits, damaged + 3..next);
+}
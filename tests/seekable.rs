@@ -0,0 +1,118 @@
+#![cfg(feature = "checkpoint")]
+
+use deflate64::{Deflate64Cursor, SeekableDeflate64Reader};
+use std::io::{self, Cursor, Read, Seek, SeekFrom};
+
+mod common;
+
+use common::{
+    compressed_data, BINARY_WAV_COMPRESSED_SIZE, BINARY_WAV_DATA, BINARY_WAV_DATA_OFFSET,
+    ZIP_FILE_DATA,
+};
+
+/// The zip file positioned at the start of the deflate64 stream
+fn zip_file() -> Cursor<&'static [u8]> {
+    let mut file = Cursor::new(ZIP_FILE_DATA);
+    file.seek(SeekFrom::Start(BINARY_WAV_DATA_OFFSET as u64))
+        .unwrap();
+    file
+}
+
+fn read_at(reader: &mut impl Read, len: usize) -> Vec<u8> {
+    let mut data = vec![0u8; len];
+    reader.read_exact(&mut data).unwrap();
+    data
+}
+
+#[test]
+fn read_whole_stream() {
+    let mut reader = SeekableDeflate64Reader::new(zip_file()).unwrap();
+    let mut data = vec![];
+    reader.read_to_end(&mut data).unwrap();
+    assert_eq!(data, BINARY_WAV_DATA);
+}
+
+#[test]
+fn seek_forward_and_backward() {
+    let mut reader =
+        SeekableDeflate64Reader::with_checkpoint_interval(zip_file(), 256 * 1024).unwrap();
+    for offset in [1000000, 2000000, 10, 1500000, 2700000, 0, 999999] {
+        assert_eq!(reader.seek(SeekFrom::Start(offset)).unwrap(), offset);
+        let offset = offset as usize;
+        assert_eq!(
+            read_at(&mut reader, 1000),
+            BINARY_WAV_DATA[offset..][..1000],
+            "offset {offset}"
+        );
+    }
+    assert!(reader.checkpoint_count() >= 8);
+}
+
+#[test]
+fn seek_relative() {
+    let mut reader = SeekableDeflate64Reader::new(zip_file()).unwrap();
+    assert_eq!(reader.seek(SeekFrom::Current(5000)).unwrap(), 5000);
+    assert_eq!(reader.seek(SeekFrom::Current(-1000)).unwrap(), 4000);
+    assert_eq!(read_at(&mut reader, 100), BINARY_WAV_DATA[4000..4100]);
+
+    let end = BINARY_WAV_DATA.len() as u64;
+    assert_eq!(reader.seek(SeekFrom::End(-100)).unwrap(), end - 100);
+    assert_eq!(
+        read_at(&mut reader, 100),
+        BINARY_WAV_DATA[end as usize - 100..]
+    );
+    assert_eq!(reader.read(&mut [0u8; 10]).unwrap(), 0);
+
+    let error = reader
+        .seek(SeekFrom::Current(-(end as i64) - 1))
+        .unwrap_err();
+    assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+}
+
+#[test]
+fn seek_past_end() {
+    let mut reader = SeekableDeflate64Reader::new(zip_file()).unwrap();
+    let end = BINARY_WAV_DATA.len() as u64;
+    assert_eq!(reader.seek(SeekFrom::Start(end + 10)).unwrap(), end + 10);
+    assert_eq!(reader.read(&mut [0u8; 10]).unwrap(), 0);
+    assert_eq!(reader.seek(SeekFrom::End(-10)).unwrap(), end - 10);
+    assert_eq!(
+        read_at(&mut reader, 10),
+        BINARY_WAV_DATA[end as usize - 10..]
+    );
+}
+
+#[test]
+fn truncated_stream_end() {
+    let data = &ZIP_FILE_DATA[BINARY_WAV_DATA_OFFSET..][..BINARY_WAV_COMPRESSED_SIZE / 2];
+    let mut reader = SeekableDeflate64Reader::new(Cursor::new(data)).unwrap();
+    let error = reader.seek(SeekFrom::End(0)).unwrap_err();
+    assert_eq!(error.kind(), io::ErrorKind::UnexpectedEof);
+}
+
+#[test]
+fn cursor() {
+    let data = compressed_data();
+    let mut cursor = Deflate64Cursor::with_checkpoint_interval(data, 512 * 1024);
+    assert_eq!(cursor.checkpoint_count(), 0);
+
+    cursor.seek(SeekFrom::Start(2000000)).unwrap();
+    assert_eq!(
+        read_at(&mut cursor, 1000),
+        BINARY_WAV_DATA[2000000..][..1000]
+    );
+    let checkpoints = cursor.checkpoint_count();
+    assert!(checkpoints >= 3);
+
+    // revisiting the indexed region does not retain more checkpoints
+    for offset in [1000000, 100, 1900000] {
+        cursor.seek(SeekFrom::Start(offset)).unwrap();
+        let offset = offset as usize;
+        assert_eq!(
+            read_at(&mut cursor, 1000),
+            BINARY_WAV_DATA[offset..][..1000]
+        );
+    }
+    assert_eq!(cursor.checkpoint_count(), checkpoints);
+    assert_eq!(cursor.get_ref().len(), BINARY_WAV_COMPRESSED_SIZE);
+}
@@ -0,0 +1,161 @@
+#![cfg(feature = "serde")]
+
+use deflate64::{InflateErrorKind, InflaterManaged};
+
+mod common;
+
+use common::{compressed_data, BINARY_WAV_DATA};
+
+#[test]
+fn serialize_and_resume() {
+    let mut inflater = Box::new(InflaterManaged::new());
+    let mut output = vec![0u8; 100000];
+    let result = inflater.inflate(&compressed_data()[..123457], &mut output);
+    let consumed = result.bytes_consumed;
+    let written = result.bytes_written;
+
+    let state = serde_json::to_string(&inflater).unwrap();
+    drop(inflater);
+    let mut restored: Box<InflaterManaged> = serde_json::from_str(&state).unwrap();
+
+    let mut rest = vec![0u8; BINARY_WAV_DATA.len()];
+    let mut rest_written = 0;
+    let mut input = &compressed_data()[consumed..];
+    while !restored.finished() {
+        let result = restored.inflate(input, &mut rest[rest_written..]);
+        assert!(!result.data_error);
+        input = &input[result.bytes_consumed..];
+        rest_written += result.bytes_written;
+    }
+    assert_eq!(written + rest_written, BINARY_WAV_DATA.len());
+    assert_eq!(output[..written], BINARY_WAV_DATA[..written]);
+    assert_eq!(rest[..rest_written], BINARY_WAV_DATA[written..]);
+}
+
+#[test]
+fn roundtrip_preserves_state() {
+    let mut inflater = Box::new(InflaterManaged::new());
+    let mut output = vec![0u8; 100000];
+    inflater.inflate(&compressed_data()[..54321], &mut output);
+
+    let state = serde_json::to_string(&inflater).unwrap();
+    let restored: Box<InflaterManaged> = serde_json::from_str(&state).unwrap();
+    assert_eq!(format!("{restored:?}"), format!("{inflater:?}"));
+}
+
+#[cfg(feature = "checkpoint")]
+#[test]
+fn roundtrip_preserves_checkpoint_state() {
+    use deflate64::Checkpointing;
+
+    let mut inflater = Box::new(InflaterManaged::with_checkpoints());
+    let mut output = vec![0u8; 100000];
+    inflater.inflate(&compressed_data()[..54321], &mut output);
+
+    let state = serde_json::to_string(&inflater).unwrap();
+    let restored: Box<InflaterManaged<Checkpointing>> = serde_json::from_str(&state).unwrap();
+    assert_eq!(format!("{restored:?}"), format!("{inflater:?}"));
+}
+
+#[test]
+fn reject_wrong_array_length() {
+    let inflater = Box::new(InflaterManaged::new());
+    let state = serde_json::to_value(&inflater).unwrap();
+    let mut state = state.as_object().unwrap().clone();
+    state["code_list"].as_array_mut().unwrap().pop();
+    let error = serde_json::from_value::<Box<InflaterManaged>>(state.into()).unwrap_err();
+    assert!(error.to_string().contains("array of length"), "{error}");
+}
+
+#[test]
+fn reject_invalid_state() {
+    let mut inflater = Box::new(InflaterManaged::new());
+    let mut output = vec![0u8; 100000];
+    inflater.inflate(&compressed_data()[..54321], &mut output);
+    let state = serde_json::to_value(&inflater).unwrap();
+    assert_eq!(state["state"], "DecodeTop");
+
+    let mut corrupt = state.clone();
+    corrupt["output"]["end"] = 1_000_000_000.into();
+    let error = serde_json::from_value::<Box<InflaterManaged>>(corrupt).unwrap_err();
+    assert!(
+        error.to_string().contains("invalid inflater state"),
+        "{error}"
+    );
+
+    let mut corrupt = state.clone();
+    corrupt["literal_length_tree"]["table"][0] = (-32000).into();
+    let error = serde_json::from_value::<Box<InflaterManaged>>(corrupt).unwrap_err();
+    assert!(
+        error.to_string().contains("invalid inflater state"),
+        "{error}"
+    );
+
+    let mut corrupt = state.clone();
+    corrupt["state"] = "DecodingUncompressed".into();
+    let error = serde_json::from_value::<Box<InflaterManaged>>(corrupt).unwrap_err();
+    assert!(
+        error.to_string().contains("invalid inflater state"),
+        "{error}"
+    );
+}
+
+#[test]
+fn roundtrip_between_chunks() {
+    let mut inflater = Box::new(InflaterManaged::new());
+    let mut output = vec![0u8; BINARY_WAV_DATA.len()];
+    let mut written = 0;
+    // byte by byte through the header of the first block, to stop in each of its states
+    let (header, rest) = compressed_data()[..100000].split_at(48);
+    for chunk in header.chunks(1).chain(rest.chunks(7919)) {
+        let mut chunk = chunk;
+        loop {
+            let end = output.len().min(written + 5000);
+            let result = inflater.inflate(chunk, &mut output[written..end]);
+            assert!(!result.data_error);
+            chunk = &chunk[result.bytes_consumed..];
+            written += result.bytes_written;
+            let state = serde_json::to_string(&inflater).unwrap();
+            inflater = serde_json::from_str(&state).unwrap();
+            if chunk.is_empty() && result.bytes_written == 0 {
+                break;
+            }
+        }
+    }
+    assert!(written > 0);
+    assert_eq!(output[..written], BINARY_WAV_DATA[..written]);
+}
+
+#[test]
+fn roundtrip_after_data_error() {
+    let mut inflater = Box::new(InflaterManaged::new());
+    let mut output = [0u8; 16];
+    let result = inflater.inflate(&[0b111], &mut output);
+    assert!(result.data_error);
+    let mut state = serde_json::to_value(&inflater).unwrap();
+
+    // a tree which failed to build is not used again after a data error
+    state["literal_length_tree"]["table"][0] = (-32000).into();
+    let restored: Box<InflaterManaged> = serde_json::from_value(state).unwrap();
+    let kind = restored.error().unwrap().kind();
+    assert_eq!(kind, InflateErrorKind::InvalidBlockType);
+}
+
+#[cfg(feature = "checkpoint")]
+#[test]
+fn checkpoint_positions_roundtrip() {
+    use deflate64::{CheckpointInfo, CheckpointStreamPositions};
+
+    let mut inflater = Box::new(InflaterManaged::with_checkpoints());
+    let mut output = vec![0u8; 100000];
+    inflater.inflate(&compressed_data()[..54321], &mut output);
+    let (checkpoint, positions) = inflater.checkpoint().unwrap();
+
+    let json = serde_json::to_string(&positions).unwrap();
+    let restored: CheckpointStreamPositions = serde_json::from_str(&json).unwrap();
+    assert_eq!(restored, positions);
+
+    let info = CheckpointInfo::parse(&checkpoint).unwrap();
+    let json = serde_json::to_string(&info).unwrap();
+    assert_eq!(serde_json::from_str::<CheckpointInfo>(&json).unwrap(), info);
+}
@@ -0,0 +1,111 @@
+use deflate64::{FnSink, InflateErrorKind, InflaterManaged, OutputSink, SinkError, WriteSink};
+
+mod common;
+
+use common::{compressed_data, BINARY_WAV_COMPRESSED_SIZE, BINARY_WAV_DATA};
+
+#[test]
+fn slice_sink() {
+    let mut inflater = InflaterManaged::boxed();
+    let mut output = vec![0u8; BINARY_WAV_DATA.len()];
+    let mut sink = &mut output[..100000];
+    let (consumed, written) = inflater
+        .inflate_into_sink(compressed_data(), &mut sink)
+        .unwrap();
+    assert!(sink.is_empty());
+    assert_eq!(written, 100000);
+
+    let mut sink = &mut output[100000..];
+    let (_, written) = inflater
+        .inflate_into_sink(&compressed_data()[consumed..], &mut sink)
+        .unwrap();
+    assert_eq!(written as usize, BINARY_WAV_DATA.len() - 100000);
+    assert!(inflater.finished());
+    assert_eq!(output, BINARY_WAV_DATA);
+}
+
+#[test]
+fn vec_sink() {
+    let mut inflater = InflaterManaged::boxed();
+    let mut output = b"header".to_vec();
+    let (consumed, written) = inflater
+        .inflate_into_sink(compressed_data(), &mut output)
+        .unwrap();
+    assert_eq!(consumed, BINARY_WAV_COMPRESSED_SIZE);
+    assert_eq!(written as usize, BINARY_WAV_DATA.len());
+    assert_eq!(output[..6], *b"header");
+    assert_eq!(output[6..], *BINARY_WAV_DATA);
+}
+
+#[test]
+fn write_sink() {
+    let mut inflater = InflaterManaged::boxed();
+    let mut sink = WriteSink::new(Vec::new());
+    inflater
+        .inflate_into_sink(compressed_data(), &mut sink)
+        .unwrap();
+    assert_eq!(sink.into_inner(), BINARY_WAV_DATA);
+}
+
+#[test]
+fn fn_sink_error() {
+    let mut inflater = InflaterManaged::boxed();
+    let mut chunks = 0;
+    let mut sink = FnSink::new(|_: &[u8]| {
+        chunks += 1;
+        if chunks == 3 {
+            Err("enough")
+        } else {
+            Ok(())
+        }
+    });
+    let error = inflater
+        .inflate_into_sink(compressed_data(), &mut sink)
+        .unwrap_err();
+    assert_eq!(error, SinkError::Sink("enough"));
+    assert_eq!(chunks, 3);
+}
+
+#[test]
+fn inflate_error() {
+    let mut inflater = InflaterManaged::boxed();
+    let error = inflater
+        .inflate_into_sink(&[0b111], &mut Vec::new())
+        .unwrap_err();
+    // Vec never fails to take output
+    let SinkError::Inflate(error) = error;
+    assert_eq!(error.kind(), InflateErrorKind::InvalidBlockType);
+}
+
+// a sink of a downstream crate, which only counts the output
+struct CountingSink {
+    buffer: [u8; 1000],
+    count: u64,
+}
+
+impl OutputSink for CountingSink {
+    type Error = std::convert::Infallible;
+
+    fn buffer(&mut self) -> &mut [u8] {
+        &mut self.buffer
+    }
+
+    fn commit(&mut self, len: usize) -> Result<(), Self::Error> {
+        self.count += len as u64;
+        Ok(())
+    }
+}
+
+#[test]
+fn custom_sink() {
+    let mut inflater = InflaterManaged::boxed();
+    let mut sink = CountingSink {
+        buffer: [0; 1000],
+        count: 0,
+    };
+    inflater
+        .inflate_into_sink(compressed_data(), &mut sink)
+        .unwrap();
+    assert!(inflater.finished());
+    assert_eq!(sink.count as usize, BINARY_WAV_DATA.len());
+}
@@ -0,0 +1,99 @@
+use deflate64::{BlockType, DecodeStats, InflaterManaged, InflaterOptions};
+
+mod common;
+
+use common::{compressed_data, BINARY_WAV_UNCOMPRESSED_SIZE};
+
+// decompresses `input` given in chunks of `chunk` bytes, and returns the stats
+fn collect_stats(input: &[u8], chunk: usize) -> DecodeStats {
+    let mut inflater =
+        InflaterManaged::boxed_with_options(InflaterOptions::new().collect_stats(true));
+    let mut output = vec![0u8; 100_000];
+    let mut consumed = 0usize;
+    while !inflater.finished() {
+        let end = consumed.saturating_add(chunk).min(input.len());
+        let result = inflater.inflate(&input[consumed..end], &mut output);
+        assert!(!result.data_error);
+        consumed += result.bytes_consumed;
+    }
+    *inflater.stats().unwrap()
+}
+
+#[test]
+fn not_collected_by_default() {
+    let inflater = InflaterManaged::boxed();
+    assert!(inflater.stats().is_none());
+}
+
+#[test]
+fn binary_wav() {
+    let stats = collect_stats(compressed_data(), usize::MAX);
+    let block_count = InflaterManaged::measure(compressed_data())
+        .unwrap()
+        .block_count;
+    assert_eq!(
+        stats.block_count(BlockType::Uncompressed)
+            + stats.block_count(BlockType::Static)
+            + stats.block_count(BlockType::Dynamic),
+        block_count
+    );
+    assert_eq!(stats.block_count(BlockType::Uncompressed), 0);
+    assert_eq!(
+        stats.literals() + stats.match_bytes(),
+        BINARY_WAV_UNCOMPRESSED_SIZE as u64
+    );
+    assert_eq!(
+        stats.length_code_histogram().iter().sum::<u64>(),
+        stats.matches()
+    );
+    assert_eq!(
+        stats.distance_code_histogram().iter().sum::<u64>(),
+        stats.matches()
+    );
+    assert!(stats.largest_block_output() > 0);
+    assert!(stats.largest_block_output() <= BINARY_WAV_UNCOMPRESSED_SIZE as u64);
+
+    // the byte by byte slow path counts the same
+    assert_eq!(collect_stats(compressed_data(), 1), stats);
+}
+
+#[test]
+fn stored_blocks() {
+    // block 0: BFINAL=0, BTYPE=00, LEN=3, data "abc"
+    // block 1: BFINAL=1, BTYPE=00, LEN=1, data "d"
+    let stream = [
+        0b000, 3, 0, !3, !0, b'a', b'b', b'c', 0b001, 1, 0, !1, !0, b'd',
+    ];
+    let stats = collect_stats(&stream, usize::MAX);
+    assert_eq!(stats.block_count(BlockType::Uncompressed), 2);
+    assert_eq!(stats.literals(), 0);
+    assert_eq!(stats.matches(), 0);
+    assert_eq!(stats.largest_block_output(), 3);
+}
+
+#[test]
+fn reset_clears_stats() {
+    let mut inflater =
+        InflaterManaged::boxed_with_options(InflaterOptions::new().collect_stats(true));
+    let mut output = vec![0u8; 100_000];
+    inflater.inflate(&compressed_data()[..10_000], &mut output);
+    assert_ne!(inflater.stats(), Some(&DecodeStats::default()));
+    inflater.reset();
+    assert_eq!(inflater.stats(), Some(&DecodeStats::default()));
+}
+
+#[test]
+fn deflate64_features() {
+    let stats = collect_stats(compressed_data(), usize::MAX);
+    assert_eq!(stats.max_match_length(), 257);
+    assert_eq!(stats.max_match_distance(), 65512);
+    assert!(stats.deflate64_features_used());
+    assert!(!stats.decodes_as_deflate());
+
+    // BFINAL=1, BTYPE=01, 'a', length 3 at distance 1, end of block
+    let stats = collect_stats(&[0x4b, 0x04, 0x02, 0x00], usize::MAX);
+    assert_eq!(stats.max_match_length(), 3);
+    assert_eq!(stats.max_match_distance(), 1);
+    assert!(!stats.deflate64_features_used());
+    assert!(stats.decodes_as_deflate());
+}
@@ -1,25 +1,21 @@
-use deflate64::Deflate64Decoder;
-use std::io::{Cursor, Read};
+use deflate64::{Deflate64Decoder, Deflate64WriteDecoder, InflateError, InflateErrorKind};
+use std::io::{self, Cursor, Read, Write};
 
-const BINARY_WAV_DATA_OFFSET: usize = 40;
-const BINARY_WAV_COMPRESSED_SIZE: usize = 2669743;
-const BINARY_WAV_UNCOMPRESSED_SIZE: usize = 2703788;
+mod common;
 
-static ZIP_FILE_DATA: &[u8] = include_bytes!("../test-assets/deflate64.zip");
-static BINARY_WAV_DATA: &[u8] = include_bytes!("../test-assets/folder/binary.wmv");
+use common::{
+    compressed_data, BINARY_WAV_COMPRESSED_SIZE, BINARY_WAV_DATA, BINARY_WAV_DATA_OFFSET,
+    BINARY_WAV_UNCOMPRESSED_SIZE, ZIP_FILE_DATA,
+};
 
 #[test]
 fn check_test_data() {
     assert_eq!(BINARY_WAV_DATA.len(), BINARY_WAV_UNCOMPRESSED_SIZE);
 }
 
-fn source_stream() -> &'static [u8] {
-    &ZIP_FILE_DATA[BINARY_WAV_DATA_OFFSET..][..BINARY_WAV_COMPRESSED_SIZE]
-}
-
 #[test]
 fn decode_from_read() {
-    let mut decoder = Deflate64Decoder::new(Cursor::new(source_stream()));
+    let mut decoder = Deflate64Decoder::new(Cursor::new(compressed_data()));
 
     let mut uncompressed_data = vec![];
     decoder.read_to_end(&mut uncompressed_data).unwrap();
@@ -29,10 +25,82 @@ fn decode_from_read() {
 
 #[test]
 fn decode_from_buf_read() {
-    let mut decoder = Deflate64Decoder::with_buffer(Cursor::new(source_stream()));
+    let mut decoder = Deflate64Decoder::with_buffer(Cursor::new(compressed_data()));
 
     let mut uncompressed_data = vec![];
     decoder.read_to_end(&mut uncompressed_data).unwrap();
 
     assert_eq!(&uncompressed_data[..], BINARY_WAV_DATA);
 }
+
+#[test]
+fn decode_with_capacity() {
+    for capacity in [1, 7, 100, 64 * 1024] {
+        let mut decoder = Deflate64Decoder::with_capacity(capacity, compressed_data());
+
+        let mut uncompressed_data = vec![];
+        decoder.read_to_end(&mut uncompressed_data).unwrap();
+
+        assert_eq!(&uncompressed_data[..], BINARY_WAV_DATA);
+    }
+}
+
+#[test]
+fn into_inner_at_stream_end() {
+    let zip_data = &ZIP_FILE_DATA[BINARY_WAV_DATA_OFFSET..];
+    for capacity in [1, 7, 100, 8 * 1024] {
+        let mut decoder = Deflate64Decoder::with_capacity(capacity, zip_data);
+
+        let mut uncompressed_data = vec![];
+        decoder.read_to_end(&mut uncompressed_data).unwrap();
+        assert_eq!(&uncompressed_data[..], BINARY_WAV_DATA);
+
+        let mut rest = vec![];
+        decoder.into_inner().read_to_end(&mut rest).unwrap();
+        assert_eq!(&rest[..], &zip_data[BINARY_WAV_COMPRESSED_SIZE..]);
+    }
+}
+
+#[test]
+fn decode_with_write() {
+    for chunk_size in [1, 1000, BINARY_WAV_COMPRESSED_SIZE] {
+        let mut decoder = Deflate64WriteDecoder::new(vec![]);
+        for chunk in compressed_data().chunks(chunk_size) {
+            decoder.write_all(chunk).unwrap();
+        }
+        let uncompressed_data = decoder.finish().unwrap();
+
+        assert_eq!(&uncompressed_data[..], BINARY_WAV_DATA);
+    }
+}
+
+#[test]
+fn write_incomplete_stream() {
+    let mut decoder = Deflate64WriteDecoder::new(vec![]);
+    decoder
+        .write_all(&compressed_data()[..BINARY_WAV_COMPRESSED_SIZE / 2])
+        .unwrap();
+    let error = decoder.try_finish().unwrap_err();
+    assert_eq!(error.kind(), io::ErrorKind::UnexpectedEof);
+}
+
+#[test]
+fn write_invalid_stream() {
+    // BFINAL=1, BTYPE=11
+    let mut decoder = Deflate64WriteDecoder::new(vec![]);
+    let error = decoder.write_all(&[0b111]).unwrap_err();
+    assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+    let error = error.downcast::<InflateError>().unwrap();
+    assert_eq!(error.kind(), InflateErrorKind::InvalidBlockType);
+}
+
+#[test]
+fn read_invalid_stream() {
+    // BFINAL=1, BTYPE=11
+    let mut decoder = Deflate64Decoder::new(Cursor::new([0b111]));
+    let error = decoder.read_to_end(&mut vec![]).unwrap_err();
+    assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+    let error = error.downcast::<InflateError>().unwrap();
+    assert_eq!(error.kind(), InflateErrorKind::InvalidBlockType);
+    assert_eq!(error.input_bit_offset(), 3);
+}
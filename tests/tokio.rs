@@ -0,0 +1,77 @@
+#![cfg(feature = "tokio")]
+
+use deflate64::AsyncDeflate64Decoder;
+#[cfg(feature = "checkpoint")]
+use deflate64::{AsyncRandomAccessReader, Deflate64Index};
+use std::io;
+use tokio::io::AsyncReadExt;
+
+mod common;
+
+use common::{compressed_data, PendingReader, BINARY_WAV_DATA, BINARY_WAV_DATA_OFFSET};
+
+#[tokio::test]
+async fn decode_from_async_read() {
+    let mut decoder = AsyncDeflate64Decoder::new(compressed_data());
+
+    let mut uncompressed_data = vec![];
+    decoder.read_to_end(&mut uncompressed_data).await.unwrap();
+
+    assert_eq!(&uncompressed_data[..], BINARY_WAV_DATA);
+}
+
+#[tokio::test]
+async fn decode_from_file() {
+    let mut file = tokio::fs::File::open("test-assets/deflate64.zip")
+        .await
+        .unwrap();
+    let mut header = [0u8; BINARY_WAV_DATA_OFFSET];
+    file.read_exact(&mut header).await.unwrap();
+    let mut decoder = AsyncDeflate64Decoder::new(file);
+
+    let mut uncompressed_data = vec![];
+    decoder.read_to_end(&mut uncompressed_data).await.unwrap();
+
+    assert_eq!(&uncompressed_data[..], BINARY_WAV_DATA);
+}
+
+#[tokio::test]
+async fn decode_from_pending_reader() {
+    let reader = PendingReader::new(compressed_data(), 1000);
+    let mut decoder = AsyncDeflate64Decoder::with_capacity(100, reader);
+
+    let mut uncompressed_data = vec![];
+    decoder.read_to_end(&mut uncompressed_data).await.unwrap();
+
+    assert_eq!(&uncompressed_data[..], BINARY_WAV_DATA);
+}
+
+#[tokio::test]
+async fn decode_invalid_stream() {
+    // BFINAL=1, BTYPE=11
+    let mut decoder = AsyncDeflate64Decoder::new(&[0b111u8][..]);
+
+    let mut uncompressed_data = vec![];
+    let error = decoder
+        .read_to_end(&mut uncompressed_data)
+        .await
+        .unwrap_err();
+    assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+}
+
+#[cfg(feature = "checkpoint")]
+#[tokio::test]
+async fn random_access_reader() {
+    use tokio::io::AsyncSeekExt;
+
+    let index = Deflate64Index::build(compressed_data(), 256 * 1024).unwrap();
+    let mut reader = AsyncRandomAccessReader::new(index, compressed_data());
+    for offset in [2000000, 10, 1000000] {
+        let position = reader.seek(io::SeekFrom::Start(offset)).await.unwrap();
+        assert_eq!(position, offset);
+        let mut data = vec![0u8; 1000];
+        reader.read_exact(&mut data).await.unwrap();
+        let offset = offset as usize;
+        assert_eq!(data, BINARY_WAV_DATA[offset..][..1000]);
+    }
+}
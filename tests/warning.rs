@@ -0,0 +1,67 @@
+use deflate64::{InflateWarningKind, InflaterManaged};
+
+mod common;
+
+use common::{compressed_data, BINARY_WAV_UNCOMPRESSED_SIZE};
+
+#[test]
+fn no_warnings_for_well_formed_stream() {
+    let compressed = compressed_data();
+    let mut output = vec![0u8; BINARY_WAV_UNCOMPRESSED_SIZE];
+    let mut inflater = Box::new(InflaterManaged::new());
+    let result = inflater.inflate(compressed, &mut output);
+    assert!(!result.data_error);
+    assert!(inflater.finished());
+    assert_eq!(inflater.warnings(), &[]);
+}
+
+#[test]
+fn empty_stored_block() {
+    // block 0: BFINAL=0, BTYPE=00, LEN=0
+    // block 1: BFINAL=1, BTYPE=00, LEN=1, "x"
+    let stream = [0b000, 0, 0, 0xff, 0xff, 0b001, 1, 0, !1, 0xff, b'x'];
+    let mut inflater = Box::new(InflaterManaged::new());
+    let mut output = [0u8; 4];
+    let result = inflater.inflate(&stream, &mut output);
+    assert!(!result.data_error);
+    assert_eq!(&output[..result.bytes_written], b"x");
+
+    let [warning] = inflater.warnings() else {
+        panic!("expected one warning: {:?}", inflater.warnings());
+    };
+    assert_eq!(warning.kind(), InflateWarningKind::EmptyStoredBlock);
+    assert_eq!(warning.block_index(), 0);
+    assert_eq!(warning.input_offset(), 5);
+    assert_eq!(
+        warning.to_string(),
+        "empty stored block in block 0 at input byte 5"
+    );
+}
+
+#[test]
+fn non_zero_stored_block_padding() {
+    // BFINAL=1, BTYPE=00, padding 11111, LEN=1, "x"
+    let stream = [0b1111_1001, 1, 0, !1, 0xff, b'x'];
+    let mut inflater = Box::new(InflaterManaged::new());
+    let mut output = [0u8; 4];
+    let result = inflater.inflate(&stream, &mut output);
+    assert!(!result.data_error);
+    assert_eq!(&output[..result.bytes_written], b"x");
+
+    let kinds: Vec<_> = inflater.warnings().iter().map(|w| w.kind()).collect();
+    assert_eq!(kinds, [InflateWarningKind::NonZeroStoredBlockPadding]);
+}
+
+#[test]
+fn warnings_are_bounded() {
+    let mut stream = Vec::new();
+    for _ in 0..100 {
+        stream.extend_from_slice(&[0b000, 0, 0, 0xff, 0xff]);
+    }
+    stream.extend_from_slice(&[0b001, 0, 0, 0xff, 0xff]);
+    let mut inflater = Box::new(InflaterManaged::new());
+    let result = inflater.inflate(&stream, &mut [0u8; 4]);
+    assert!(!result.data_error);
+    assert!(inflater.finished());
+    assert_eq!(inflater.warnings().len(), 16);
+}
@@ -0,0 +1,111 @@
+//! JavaScript bindings for the [deflate64] crate, made with [wasm-bindgen].
+//!
+//! [`Inflater`] decompresses a deflate64 stream, such as a zip entry with compression
+//! method 9, incrementally: compressed chunks are pushed as they arrive, and decompressed
+//! output is pulled in chunks of bounded size.
+//!
+//! ```js
+//! const inflater = new Inflater();
+//! for await (const chunk of compressedChunks) {
+//!     inflater.push(chunk);
+//!     let output;
+//!     while ((output = inflater.pull()) !== undefined) {
+//!         consume(output);
+//!     }
+//! }
+//! inflater.finish();
+//! inflater.free();
+//! ```
+//!
+//! [deflate64]: https://crates.io/crates/deflate64
+//! [wasm-bindgen]: https://github.com/rustwasm/wasm-bindgen
+
+#![forbid(unsafe_code)]
+#![deny(rust_2018_idioms, nonstandard_style, future_incompatible)]
+
+use deflate64::InflaterManaged;
+use wasm_bindgen::prelude::*;
+
+const DEFAULT_MAX_OUTPUT_CHUNK: usize = 64 * 1024;
+
+/// An incremental deflate64 decompressor.
+///
+/// Each inflater allocates its 128 KiB history window once, and keeps at most the pushed
+/// input which was not decompressed yet and one output chunk. WebAssembly memory never
+/// shrinks, so reuse inflaters with `reset()` instead of creating one per entry when
+/// extracting many entries.
+#[wasm_bindgen]
+pub struct Inflater {
+    inflater: Box<InflaterManaged>,
+    input: Vec<u8>,
+    position: usize, // bytes of input already consumed
+    max_output_chunk: usize,
+}
+
+#[wasm_bindgen]
+impl Inflater {
+    /// Creates an inflater which returns output chunks of at most 64 KiB
+    #[wasm_bindgen(constructor)]
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> Inflater {
+        Self::with_max_output_chunk(DEFAULT_MAX_OUTPUT_CHUNK)
+    }
+
+    /// Creates an inflater which returns output chunks of at most `max_output_chunk` bytes
+    #[wasm_bindgen(js_name = withMaxOutputChunk)]
+    pub fn with_max_output_chunk(max_output_chunk: usize) -> Inflater {
+        Inflater {
+            inflater: InflaterManaged::boxed(),
+            input: Vec::new(),
+            position: 0,
+            max_output_chunk: max_output_chunk.max(1),
+        }
+    }
+
+    /// Adds a chunk of compressed data. Call `pull()` until it returns `undefined`
+    /// to receive the output.
+    pub fn push(&mut self, chunk: &[u8]) {
+        // drop the consumed input before it grows
+        self.input.drain(..self.position);
+        self.position = 0;
+        self.input.extend_from_slice(chunk);
+    }
+
+    /// Returns the next chunk of decompressed data, or `undefined` if more input is needed
+    /// or the stream ended. Throws if the stream is invalid.
+    pub fn pull(&mut self) -> Result<Option<Vec<u8>>, JsError> {
+        let mut output = vec![0u8; self.max_output_chunk];
+        let result = self
+            .inflater
+            .inflate(&self.input[self.position..], &mut output);
+        self.position += result.bytes_consumed;
+        if let Some(error) = self.inflater.error() {
+            return Err(JsError::new(&error.to_string()));
+        }
+        if result.bytes_written == 0 {
+            return Ok(None);
+        }
+        output.truncate(result.bytes_written);
+        Ok(Some(output))
+    }
+
+    /// Returns true if the end of the stream was reached and all output was pulled
+    #[wasm_bindgen(getter)]
+    pub fn finished(&self) -> bool {
+        self.inflater.finished()
+    }
+
+    /// Throws if the stream is invalid, or if it did not end yet or not all output was pulled
+    pub fn finish(&self) -> Result<(), JsError> {
+        self.inflater
+            .finish()
+            .map_err(|error| JsError::new(&error.to_string()))
+    }
+
+    /// Prepares this inflater for another stream, keeping its memory
+    pub fn reset(&mut self) {
+        self.inflater.reset();
+        self.input.clear();
+        self.position = 0;
+    }
+}
@@ -0,0 +1,57 @@
+use deflate64_wasm::Inflater;
+
+const BINARY_WAV_DATA_OFFSET: usize = 40;
+const BINARY_WAV_COMPRESSED_SIZE: usize = 2669743;
+
+static ZIP_FILE_DATA: &[u8] = include_bytes!("../../test-assets/deflate64.zip");
+static BINARY_WAV_DATA: &[u8] = include_bytes!("../../test-assets/folder/binary.wmv");
+
+fn compressed_data() -> &'static [u8] {
+    &ZIP_FILE_DATA[BINARY_WAV_DATA_OFFSET..][..BINARY_WAV_COMPRESSED_SIZE]
+}
+
+fn push_and_pull(inflater: &mut Inflater, chunk_size: usize) -> Vec<u8> {
+    let mut output = vec![];
+    for chunk in compressed_data().chunks(chunk_size) {
+        inflater.push(chunk);
+        while let Some(chunk) = inflater.pull().unwrap() {
+            output.extend_from_slice(&chunk);
+        }
+    }
+    output
+}
+
+#[test]
+fn push_and_pull_chunks() {
+    let mut inflater = Inflater::new();
+    let output = push_and_pull(&mut inflater, 65536);
+    assert!(inflater.finished());
+    assert!(inflater.finish().is_ok());
+    assert_eq!(output, BINARY_WAV_DATA);
+}
+
+#[test]
+fn max_output_chunk() {
+    let mut inflater = Inflater::with_max_output_chunk(1000);
+    inflater.push(compressed_data());
+    let mut output = vec![];
+    while let Some(chunk) = inflater.pull().unwrap() {
+        assert!(chunk.len() <= 1000);
+        output.extend_from_slice(&chunk);
+    }
+    assert!(inflater.finished());
+    assert_eq!(output, BINARY_WAV_DATA);
+}
+
+#[test]
+fn reset() {
+    let mut inflater = Inflater::new();
+    inflater.push(&compressed_data()[..100000]);
+    while inflater.pull().unwrap().is_some() {}
+    assert!(!inflater.finished());
+
+    inflater.reset();
+    let output = push_and_pull(&mut inflater, 1000000);
+    assert!(inflater.finished());
+    assert_eq!(output, BINARY_WAV_DATA);
+}